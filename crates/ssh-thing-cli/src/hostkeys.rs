@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use russh::keys;
+use russh::keys::PublicKeyBase64;
+use ssh_thing_core::hostkeys::{self, HostKeyCheck};
+use ssh_thing_core::{storage, HostKeyVerifier, KnownHost};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Checks keys against the app's known hosts. Unknown keys are confirmed on
+/// the terminal; without one they are rejected.
+pub struct TerminalHostKeyVerifier {
+    pub app_dir: PathBuf,
+    /// The server's pinned fingerprints; known hosts are skipped when set.
+    pub pinned_fingerprints: Vec<String>,
+}
+
+fn confirm_new_host(host: &str, port: u16, key_type: &str, fingerprint: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        eprintln!(
+            "Host key for {}:{} is not trusted yet; connect once interactively to accept it.",
+            host, port
+        );
+        return false;
+    }
+
+    eprintln!(
+        "The authenticity of host {}:{} can't be established.",
+        host, port
+    );
+    eprintln!("{} key fingerprint is {}.", key_type, fingerprint);
+    eprint!("Trust this host? [y/N] ");
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[async_trait]
+impl HostKeyVerifier for TerminalHostKeyVerifier {
+    async fn verify(&self, host: &str, port: u16, key: &keys::key::PublicKey) -> bool {
+        let key_type = key.name().to_string();
+        let fingerprint = key.fingerprint();
+
+        let mut known_hosts = match storage::load_known_hosts(&self.app_dir) {
+            Ok(hosts) => hosts,
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
+            }
+        };
+
+        match hostkeys::check_server_key(
+            &self.pinned_fingerprints,
+            &known_hosts,
+            host,
+            port,
+            &key_type,
+            &fingerprint,
+        ) {
+            HostKeyCheck::Trusted => return true,
+            HostKeyCheck::Changed { stored_fingerprint } => {
+                eprintln!(
+                    "Host key for {}:{} has changed (was {}, now {}). Refusing to connect.",
+                    host, port, stored_fingerprint, fingerprint
+                );
+                return false;
+            }
+            HostKeyCheck::Unknown => {}
+            HostKeyCheck::NewKeyTypeForKnownHost { known_key_types } => {
+                eprintln!(
+                    "{}:{} is already known by its {} key(s) and now presents a {} key.",
+                    host,
+                    port,
+                    known_key_types.join(", "),
+                    key_type
+                );
+            }
+        }
+
+        let prompt = (host.to_string(), key_type.clone(), fingerprint.clone());
+        let trusted = tokio::task::spawn_blocking(move || {
+            confirm_new_host(&prompt.0, port, &prompt.1, &prompt.2)
+        })
+        .await
+        .unwrap_or(false);
+        if !trusted {
+            return false;
+        }
+
+        let added_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        hostkeys::add_trusted_key(
+            &mut known_hosts,
+            KnownHost {
+                host: host.to_string(),
+                port,
+                key_type,
+                fingerprint,
+                public_key_base64: key.public_key_base64(),
+                added_at,
+            },
+        );
+        if let Err(e) = storage::save_known_hosts(&self.app_dir, &known_hosts) {
+            eprintln!("{}", e);
+        }
+        true
+    }
+}
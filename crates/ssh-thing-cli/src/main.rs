@@ -0,0 +1,124 @@
+//! `ssh-thing` on the command line: connect to saved servers and run saved
+//! snippets using the desktop app's servers.json and keyring.
+
+mod hostkeys;
+mod shell;
+mod store;
+
+use clap::{Parser, Subcommand};
+use ssh_thing_core::net::TrafficCounters;
+use ssh_thing_core::ssh::{self, ConnectOptions};
+use ssh_thing_core::{NullEventSink, ServerConnection, SshSession};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use hostkeys::TerminalHostKeyVerifier;
+use store::Store;
+
+#[derive(Parser)]
+#[command(name = "ssh-thing", version, about)]
+struct Cli {
+    /// Profile to read servers and secrets from (defaults to the app's active profile)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Open an interactive shell on a saved server
+    Connect {
+        /// Server nickname, host or id
+        server: String,
+    },
+    /// Run a saved snippet on every server with a tag
+    Run {
+        /// Snippet name or id
+        snippet: String,
+        /// Tag selecting the servers to run on
+        #[arg(long)]
+        on: String,
+    },
+}
+
+async fn open_session(store: &Store, server: &ServerConnection) -> Result<SshSession, String> {
+    let verifier = Arc::new(TerminalHostKeyVerifier {
+        app_dir: store.app_dir.clone(),
+        pinned_fingerprints: server.pinned_fingerprints.clone(),
+    });
+    ssh::connect(
+        &ConnectOptions::for_server(server),
+        &store.secrets,
+        &NullEventSink,
+        verifier,
+        Arc::new(TrafficCounters::default()),
+    )
+    .await
+}
+
+async fn connect(store: &Store, name: &str) -> Result<u8, String> {
+    let servers = store.servers()?;
+    let server = store::find_server(&servers, name)?;
+    let session = open_session(store, server).await?;
+    let result = shell::run_shell(&session).await;
+    ssh::disconnect(&session).await;
+    result.map(|status| status.min(u8::MAX as u32) as u8)
+}
+
+async fn run(store: &Store, snippet: &str, tag: &str) -> Result<u8, String> {
+    let snippets = store.snippets()?;
+    let snippet = store::find_snippet(&snippets, snippet)?;
+    let servers = store.servers()?;
+    let targets = store::servers_tagged(&servers, tag)?;
+
+    let mut failed = 0usize;
+    for server in &targets {
+        println!("==> {}", store::server_label(server));
+        let result = match open_session(store, server).await {
+            Ok(session) => {
+                let result = shell::run_command(&session, &snippet.command).await;
+                ssh::disconnect(&session).await;
+                result
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(0) => {}
+            Ok(status) => {
+                eprintln!("exited with status {}", status);
+                failed += 1;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} servers failed", failed, targets.len());
+        return Ok(1);
+    }
+    Ok(0)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match Store::open(cli.profile.as_deref()) {
+        Ok(store) => match &cli.command {
+            Command::Connect { server } => connect(&store, server).await,
+            Command::Run { snippet, on } => run(&store, snippet, on).await,
+        },
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(status) => ExitCode::from(status),
+        Err(e) => {
+            eprintln!("ssh-thing: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crossterm::terminal;
+use russh::ChannelMsg;
+use ssh_thing_core::SshSession;
+use std::io::{self, Read, Write};
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration};
+
+const DEFAULT_TERM: &str = "xterm-256color";
+
+/// Puts the local terminal back into cooked mode however the shell ends.
+struct RawModeGuard;
+
+impl RawModeGuard {
+    fn enable() -> Result<Self, String> {
+        terminal::enable_raw_mode().map_err(|e| format!("Failed to enter raw mode: {}", e))?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Reads stdin on a plain thread: a blocking read cannot be cancelled, so it
+// must not sit inside `select!`.
+fn spawn_stdin_reader() -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel(32);
+    std::thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 1024];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
+
+fn write_all(mut out: impl Write, data: &[u8]) -> Result<(), String> {
+    out.write_all(data)
+        .and_then(|_| out.flush())
+        .map_err(|e| format!("Failed to write output: {}", e))
+}
+
+/// Opens an interactive shell attached to the local terminal and returns the
+/// remote exit status.
+pub async fn run_shell(session: &SshSession) -> Result<u32, String> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+
+    let mut size = terminal::size().unwrap_or((80, 24));
+    let term = std::env::var("TERM").unwrap_or_else(|_| DEFAULT_TERM.to_string());
+    channel
+        .request_pty(false, &term, size.0 as u32, size.1 as u32, 0, 0, &[])
+        .await
+        .map_err(|e| format!("Failed to request PTY: {}", e))?;
+    channel
+        .request_shell(true)
+        .await
+        .map_err(|e| format!("Failed to request shell: {}", e))?;
+
+    let _raw_mode = RawModeGuard::enable()?;
+    let mut stdin = spawn_stdin_reader();
+    let mut stdin_open = true;
+    // Polled rather than signal driven so it works the same on Windows.
+    let mut resize_check = interval(Duration::from_millis(250));
+    let mut exit_status = 0;
+
+    loop {
+        tokio::select! {
+            input = stdin.recv(), if stdin_open => match input {
+                Some(bytes) => {
+                    channel
+                        .data(&bytes[..])
+                        .await
+                        .map_err(|e| format!("Failed to send input: {}", e))?;
+                }
+                None => {
+                    stdin_open = false;
+                    let _ = channel.eof().await;
+                }
+            },
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => write_all(io::stdout(), &data)?,
+                Some(ChannelMsg::ExtendedData { data, .. }) => write_all(io::stderr(), &data)?,
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = status,
+                Some(_) => {}
+                None => break,
+            },
+            _ = resize_check.tick() => {
+                if let Ok(current) = terminal::size() {
+                    if current != size {
+                        size = current;
+                        let _ = channel
+                            .window_change(size.0 as u32, size.1 as u32, 0, 0)
+                            .await;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(exit_status)
+}
+
+/// Runs `command` without a PTY, streaming its output to the local stdout and
+/// stderr, and returns the remote exit status.
+pub async fn run_command(session: &SshSession, command: &str) -> Result<u32, String> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let mut exit_status = None;
+    while let Some(message) = channel.wait().await {
+        match message {
+            ChannelMsg::Data { data } => write_all(io::stdout(), &data)?,
+            ChannelMsg::ExtendedData { data, .. } => write_all(io::stderr(), &data)?,
+            ChannelMsg::ExitStatus {
+                exit_status: status,
+            } => exit_status = Some(status),
+            ChannelMsg::ExitSignal {
+                signal_name,
+                error_message,
+                ..
+            } => {
+                return Err(format!(
+                    "Command terminated by signal {:?}: {}",
+                    signal_name, error_message
+                ));
+            }
+            ChannelMsg::Failure => {
+                return Err("Remote command request failed".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    exit_status.ok_or_else(|| "Command ended without an exit status".to_string())
+}
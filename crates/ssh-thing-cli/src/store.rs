@@ -0,0 +1,138 @@
+use ssh_thing_core::{profiles, storage, KeyringSecretStore, ServerConnection, Snippet};
+use std::path::PathBuf;
+
+// Must match `identifier` in src-tauri/tauri.conf.json; Tauri derives the
+// app data directory from it.
+const APP_IDENTIFIER: &str = "com.kentaylor.ssh-thing";
+
+/// The desktop app's data for one profile.
+pub struct Store {
+    pub app_dir: PathBuf,
+    pub secrets: KeyringSecretStore,
+}
+
+impl Store {
+    /// Opens `profile`, or whichever profile the app last had active.
+    pub fn open(profile: Option<&str>) -> Result<Self, String> {
+        let root = dirs::data_dir()
+            .ok_or_else(|| "Failed to get app data directory".to_string())?
+            .join(APP_IDENTIFIER);
+        let profile = profile
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| profiles::saved_active_profile(&root));
+        Ok(Self {
+            app_dir: profiles::profile_dir(&root, &profile),
+            secrets: KeyringSecretStore::new(profiles::keyring_service_name(&profile)),
+        })
+    }
+
+    pub fn servers(&self) -> Result<Vec<ServerConnection>, String> {
+        let mut servers = storage::load_servers(&self.app_dir)?;
+        storage::apply_identities(&mut servers, &storage::load_identities(&self.app_dir)?);
+        Ok(servers)
+    }
+
+    pub fn snippets(&self) -> Result<Vec<Snippet>, String> {
+        storage::load_snippets(&self.app_dir)
+    }
+}
+
+pub fn server_label(server: &ServerConnection) -> String {
+    match &server.nickname {
+        Some(nickname) => format!("{} ({}@{})", nickname, server.user, server.host),
+        None => format!("{}@{}", server.user, server.host),
+    }
+}
+
+/// Looks a server up by id, nickname or host, in that order.
+pub fn find_server<'a>(
+    servers: &'a [ServerConnection],
+    name: &str,
+) -> Result<&'a ServerConnection, String> {
+    servers
+        .iter()
+        .find(|server| server.id == name)
+        .or_else(|| {
+            servers.iter().find(|server| {
+                server
+                    .nickname
+                    .as_deref()
+                    .is_some_and(|nickname| nickname.eq_ignore_ascii_case(name))
+            })
+        })
+        .or_else(|| {
+            servers
+                .iter()
+                .find(|server| server.host.eq_ignore_ascii_case(name))
+        })
+        .ok_or_else(|| format!("No server named {}", name))
+}
+
+pub fn servers_tagged<'a>(
+    servers: &'a [ServerConnection],
+    tag: &str,
+) -> Result<Vec<&'a ServerConnection>, String> {
+    let tagged: Vec<_> = servers
+        .iter()
+        .filter(|server| server.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .collect();
+    if tagged.is_empty() {
+        return Err(format!("No servers are tagged {}", tag));
+    }
+    Ok(tagged)
+}
+
+pub fn find_snippet<'a>(snippets: &'a [Snippet], name: &str) -> Result<&'a Snippet, String> {
+    snippets
+        .iter()
+        .find(|snippet| snippet.id == name || snippet.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("No snippet named {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, nickname: Option<&str>, host: &str, tags: &[&str]) -> ServerConnection {
+        let mut value = serde_json::json!({
+            "id": id,
+            "nickname": nickname,
+            "host": host,
+            "port": 22,
+            "user": "ops",
+            "auth": {"type": "SecretRef", "secret_id": format!("server:{}:password", id)}
+        });
+        value["tags"] = serde_json::json!(tags);
+        serde_json::from_value(value).expect("server")
+    }
+
+    #[test]
+    fn test_find_server_prefers_nickname_over_host() {
+        let servers = vec![
+            server("1", Some("db"), "10.0.0.5", &[]),
+            server("2", None, "db", &[]),
+        ];
+
+        assert_eq!(find_server(&servers, "DB").expect("found").id, "1");
+        assert_eq!(find_server(&servers, "2").expect("found").id, "2");
+        assert_eq!(find_server(&servers, "10.0.0.5").expect("found").id, "1");
+        assert!(find_server(&servers, "web").is_err());
+    }
+
+    #[test]
+    fn test_servers_tagged_matches_case_insensitively() {
+        let servers = vec![
+            server("1", None, "a", &["prod", "web"]),
+            server("2", None, "b", &["staging"]),
+            server("3", None, "c", &["Prod"]),
+        ];
+
+        let ids: Vec<_> = servers_tagged(&servers, "prod")
+            .expect("tagged")
+            .iter()
+            .map(|server| server.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["1", "3"]);
+        assert!(servers_tagged(&servers, "db").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+use crate::model::{ConnectionState, ConnectionStateEvent};
+
+/// Receives the events the core raises. The desktop app forwards them to the
+/// webview; other front ends can log or ignore them.
+pub trait EventSink: Send + Sync {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String>;
+}
+
+impl dyn EventSink + '_ {
+    pub fn emit<T: Serialize>(&self, event: &str, payload: &T) -> Result<(), String> {
+        let value = serde_json::to_value(payload)
+            .map_err(|e| format!("Failed to serialize {} event: {}", event, e))?;
+        self.emit_value(event, value)
+    }
+
+    pub fn connection_state(
+        &self,
+        connection_id: Option<&str>,
+        server_id: Option<&str>,
+        shell_id: Option<&str>,
+        state: ConnectionState,
+    ) -> Result<(), String> {
+        let payload = ConnectionStateEvent {
+            connection_id: connection_id.map(|s| s.to_string()),
+            server_id: server_id.map(|s| s.to_string()),
+            shell_id: shell_id.map(|s| s.to_string()),
+            state,
+            badge: None,
+        };
+        self.emit("connection-state", &payload)
+    }
+}
+
+/// Drops every event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullEventSink;
+
+impl EventSink for NullEventSink {
+    fn emit_value(&self, _event: &str, _payload: serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<(String, serde_json::Value)>>);
+
+    impl EventSink for RecordingSink {
+        fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+            self.0.lock().unwrap().push((event.to_string(), payload));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_connection_state_event_payload() {
+        let sink = RecordingSink::default();
+        let events: &dyn EventSink = &sink;
+        events
+            .connection_state(Some("c1"), Some("s1"), None, ConnectionState::Connected)
+            .expect("emitted");
+
+        let recorded = sink.0.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].0, "connection-state");
+        assert_eq!(recorded[0].1["connection_id"], "c1");
+        assert_eq!(recorded[0].1["state"], "Connected");
+    }
+}
@@ -0,0 +1,291 @@
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::model::KnownHost;
+
+// ssh-keygen's randomart field and symbols, from fewest visits to most,
+// then the start and end marks.
+const RANDOMART_WIDTH: usize = 17;
+const RANDOMART_HEIGHT: usize = 9;
+const RANDOMART_SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+
+/// Outcome of looking a presented host key up in the known hosts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostKeyCheck {
+    Trusted,
+    /// No key is stored for the host at all.
+    Unknown,
+    /// The host is known, but by keys of other types. Servers offer
+    /// different key types depending on what the client negotiates, so this
+    /// is not treated as a change, but the user should see it is not a new
+    /// host.
+    NewKeyTypeForKnownHost {
+        known_key_types: Vec<String>,
+    },
+    /// A different key of the same type is stored.
+    Changed {
+        stored_fingerprint: String,
+    },
+}
+
+/// Matches a presented key against the server's pinned fingerprints, or
+/// without pins against the stored key of the same type for `host:port`.
+pub fn check_server_key(
+    pinned_fingerprints: &[String],
+    known_hosts: &[KnownHost],
+    host: &str,
+    port: u16,
+    key_type: &str,
+    fingerprint: &str,
+) -> HostKeyCheck {
+    if !pinned_fingerprints.is_empty() {
+        return if pinned_fingerprints.iter().any(|pin| pin == fingerprint) {
+            HostKeyCheck::Trusted
+        } else {
+            HostKeyCheck::Changed {
+                stored_fingerprint: pinned_fingerprints.join(", "),
+            }
+        };
+    }
+
+    let for_host: Vec<&KnownHost> = known_hosts
+        .iter()
+        .filter(|entry| entry.host == host && entry.port == port)
+        .collect();
+    match for_host.iter().find(|entry| entry.key_type == key_type) {
+        Some(known) if known.fingerprint == fingerprint => HostKeyCheck::Trusted,
+        Some(known) => HostKeyCheck::Changed {
+            stored_fingerprint: known.fingerprint.clone(),
+        },
+        None if for_host.is_empty() => HostKeyCheck::Unknown,
+        None => HostKeyCheck::NewKeyTypeForKnownHost {
+            known_key_types: for_host
+                .iter()
+                .map(|entry| entry.key_type.clone())
+                .collect(),
+        },
+    }
+}
+
+/// Stores `entry`, replacing the key of the same type for its host but
+/// keeping keys of other types.
+pub fn add_trusted_key(known_hosts: &mut Vec<KnownHost>, entry: KnownHost) {
+    known_hosts.retain(|known| {
+        !(known.host == entry.host && known.port == entry.port && known.key_type == entry.key_type)
+    });
+    known_hosts.push(entry);
+}
+
+/// `SHA256:...`, as `ssh-keygen -l` prints it.
+pub fn sha256_fingerprint(blob: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(blob)))
+}
+
+/// `MD5:aa:bb:...`, the form older tools and some provider consoles show.
+pub fn md5_fingerprint(blob: &[u8]) -> String {
+    let hex: Vec<String> = Md5::digest(blob)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("MD5:{}", hex.join(":"))
+}
+
+fn blob_strings(blob: &[u8]) -> Vec<&[u8]> {
+    let mut strings = Vec::new();
+    let mut rest = blob;
+    while rest.len() >= 4 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let Some(value) = rest.get(4..4 + length) else {
+            break;
+        };
+        strings.push(value);
+        rest = &rest[4 + length..];
+    }
+    strings
+}
+
+/// The short type name and size ssh-keygen puts in the randomart title,
+/// e.g. `ED25519 256`.
+fn key_title(blob: &[u8]) -> String {
+    let strings = blob_strings(blob);
+    let key_type = strings.first().copied().unwrap_or_default();
+    match key_type {
+        b"ssh-ed25519" => "ED25519 256".to_string(),
+        b"ssh-rsa" => {
+            let modulus = strings.get(2).copied().unwrap_or_default();
+            let modulus = match modulus.iter().position(|byte| *byte != 0) {
+                Some(start) => &modulus[start..],
+                None => &[],
+            };
+            let bits = modulus.first().map_or(0, |top| {
+                (modulus.len() as u32 - 1) * 8 + (8 - top.leading_zeros())
+            });
+            format!("RSA {}", bits)
+        }
+        name if name.starts_with(b"ecdsa-sha2-nistp") => {
+            let bits = String::from_utf8_lossy(&name[b"ecdsa-sha2-nistp".len()..]).to_string();
+            format!("ECDSA {}", bits)
+        }
+        name => String::from_utf8_lossy(name).to_string(),
+    }
+}
+
+fn randomart_border(label: &str) -> String {
+    let label = if label.len() > RANDOMART_WIDTH {
+        &label[..RANDOMART_WIDTH]
+    } else {
+        label
+    };
+    let left = (RANDOMART_WIDTH - label.len()) / 2;
+    let right = RANDOMART_WIDTH - label.len() - left;
+    format!("+{}{}{}+", "-".repeat(left), label, "-".repeat(right))
+}
+
+/// ssh-keygen's "drunken bishop" picture of the key's SHA-256 digest, so
+/// keys can be compared at a glance with what `ssh-keygen -lv` shows.
+pub fn randomart(blob: &[u8]) -> String {
+    let visits_cap = RANDOMART_SYMBOLS.len() - 3;
+    let mut field = [[0usize; RANDOMART_HEIGHT]; RANDOMART_WIDTH];
+    let (start_x, start_y) = (RANDOMART_WIDTH / 2, RANDOMART_HEIGHT / 2);
+    let (mut x, mut y) = (start_x, start_y);
+    for byte in Sha256::digest(blob) {
+        let mut input = byte;
+        for _ in 0..4 {
+            x = if input & 1 != 0 {
+                (x + 1).min(RANDOMART_WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if input & 2 != 0 {
+                (y + 1).min(RANDOMART_HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            if field[x][y] < visits_cap {
+                field[x][y] += 1;
+            }
+            input >>= 2;
+        }
+    }
+    field[start_x][start_y] = RANDOMART_SYMBOLS.len() - 2;
+    field[x][y] = RANDOMART_SYMBOLS.len() - 1;
+
+    let title = format!("[{}]", key_title(blob));
+    let title = if title.len() > RANDOMART_WIDTH {
+        format!("[{}]", title[1..].split(' ').next().unwrap_or_default())
+    } else {
+        title
+    };
+    let mut lines = vec![randomart_border(&title)];
+    for row in 0..RANDOMART_HEIGHT {
+        let cells: String = (0..RANDOMART_WIDTH)
+            .map(|column| RANDOMART_SYMBOLS[field[column][row]] as char)
+            .collect();
+        lines.push(format!("|{}|", cells));
+    }
+    lines.push(randomart_border("[SHA256]"));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(key_type: &str, fingerprint: &str) -> KnownHost {
+        KnownHost {
+            host: "example.com".to_string(),
+            port: 22,
+            key_type: key_type.to_string(),
+            fingerprint: fingerprint.to_string(),
+            public_key_base64: String::new(),
+            added_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_server_key_matches_by_key_type() {
+        let mut known_hosts = vec![known("ssh-ed25519", "SHA256:ed")];
+        add_trusted_key(&mut known_hosts, known("ssh-rsa", "SHA256:rsa"));
+
+        let check = |key_type, fingerprint| {
+            check_server_key(&[], &known_hosts, "example.com", 22, key_type, fingerprint)
+        };
+        assert_eq!(known_hosts.len(), 2);
+        assert_eq!(check("ssh-ed25519", "SHA256:ed"), HostKeyCheck::Trusted);
+        assert_eq!(check("ssh-rsa", "SHA256:rsa"), HostKeyCheck::Trusted);
+        assert_eq!(
+            check("ecdsa-sha2-nistp256", "SHA256:ec"),
+            HostKeyCheck::NewKeyTypeForKnownHost {
+                known_key_types: vec!["ssh-ed25519".to_string(), "ssh-rsa".to_string()]
+            }
+        );
+        assert_eq!(
+            check_server_key(&[], &known_hosts, "other.com", 22, "ssh-rsa", "SHA256:rsa"),
+            HostKeyCheck::Unknown
+        );
+        assert_eq!(
+            check("ssh-rsa", "SHA256:other"),
+            HostKeyCheck::Changed {
+                stored_fingerprint: "SHA256:rsa".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_pinned_fingerprints_override_known_hosts() {
+        let known_hosts = vec![known("ssh-ed25519", "SHA256:ed")];
+        let pins = vec!["SHA256:pinned".to_string()];
+
+        assert_eq!(
+            check_server_key(
+                &pins,
+                &known_hosts,
+                "example.com",
+                22,
+                "ssh-ed25519",
+                "SHA256:pinned"
+            ),
+            HostKeyCheck::Trusted
+        );
+        assert_eq!(
+            check_server_key(
+                &pins,
+                &known_hosts,
+                "example.com",
+                22,
+                "ssh-ed25519",
+                "SHA256:ed"
+            ),
+            HostKeyCheck::Changed {
+                stored_fingerprint: "SHA256:pinned".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_forms_and_randomart_frame() {
+        assert_eq!(
+            md5_fingerprint(b""),
+            "MD5:d4:1d:8c:d9:8f:00:b2:04:e9:80:09:98:ec:f8:42:7e"
+        );
+        assert_eq!(
+            sha256_fingerprint(b""),
+            "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+
+        let mut blob = Vec::new();
+        for field in [b"ssh-ed25519".as_slice(), &[7; 32]] {
+            blob.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            blob.extend_from_slice(field);
+        }
+        let art = randomart(&blob);
+        let lines: Vec<&str> = art.lines().collect();
+        assert_eq!(lines.len(), RANDOMART_HEIGHT + 2);
+        assert_eq!(lines[0], "+--[ED25519 256]--+");
+        assert_eq!(lines[RANDOMART_HEIGHT + 1], "+----[SHA256]-----+");
+        assert!(lines.iter().all(|line| line.len() == RANDOMART_WIDTH + 2));
+        let field = lines[1..=RANDOMART_HEIGHT].concat();
+        assert_eq!(field.matches('E').count(), 1);
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::{sleep, timeout, Duration};
+use tracing::debug;
+
+const DEFAULT_KNOCK_DELAY_MS: u64 = 200;
+const TCP_KNOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum KnockProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KnockPort {
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: KnockProtocol,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortKnockSequence {
+    pub ports: Vec<KnockPort>,
+    /// Pause between knocks, and after the last knock before connecting, so
+    /// the knock daemon has time to open the port.
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+}
+
+impl PortKnockSequence {
+    fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms.unwrap_or(DEFAULT_KNOCK_DELAY_MS))
+    }
+}
+
+async fn knock_once(addr: SocketAddr, protocol: KnockProtocol) -> Result<(), String> {
+    match protocol {
+        // Only the SYN matters; the port is expected to be closed.
+        KnockProtocol::Tcp => {
+            let _ = timeout(TCP_KNOCK_TIMEOUT, TcpStream::connect(addr)).await;
+            Ok(())
+        }
+        KnockProtocol::Udp => {
+            let bind_addr = if addr.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let socket = UdpSocket::bind(bind_addr)
+                .await
+                .map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+            socket
+                .send_to(&[0], addr)
+                .await
+                .map_err(|e| format!("Failed to send knock to {}: {}", addr, e))?;
+            Ok(())
+        }
+    }
+}
+
+/// Knocks on `ip`, which the caller resolves so that it can connect to the
+/// same address afterwards.
+pub async fn knock(ip: IpAddr, sequence: &PortKnockSequence) -> Result<(), String> {
+    for knock_port in &sequence.ports {
+        debug!(%ip, port = knock_port.port, protocol = ?knock_port.protocol, "Sending port knock");
+        knock_once(SocketAddr::new(ip, knock_port.port), knock_port.protocol).await?;
+        sleep(sequence.delay()).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knock_sequence_defaults() {
+        let sequence: PortKnockSequence =
+            serde_json::from_str(r#"{"ports":[{"port":7000},{"port":8000,"protocol":"Udp"}]}"#)
+                .expect("parse knock sequence");
+
+        assert_eq!(sequence.ports[0].protocol, KnockProtocol::Tcp);
+        assert_eq!(sequence.ports[1].protocol, KnockProtocol::Udp);
+        assert_eq!(
+            sequence.delay(),
+            Duration::from_millis(DEFAULT_KNOCK_DELAY_MS)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_knock_sends_udp_sequence_in_order() {
+        let listener = UdpSocket::bind("127.0.0.1:0").await.expect("bind listener");
+        let port = listener.local_addr().expect("local addr").port();
+        let sequence = PortKnockSequence {
+            ports: vec![
+                KnockPort {
+                    port,
+                    protocol: KnockProtocol::Udp,
+                },
+                KnockPort {
+                    port,
+                    protocol: KnockProtocol::Udp,
+                },
+            ],
+            delay_ms: Some(0),
+        };
+
+        knock("127.0.0.1".parse().expect("ip"), &sequence)
+            .await
+            .expect("knock");
+
+        let mut buf = [0u8; 8];
+        for _ in 0..2 {
+            let (len, _) = listener.recv_from(&mut buf).await.expect("receive knock");
+            assert_eq!(len, 1);
+        }
+    }
+}
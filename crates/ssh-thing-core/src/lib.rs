@@ -0,0 +1,29 @@
+//! SSH, session and storage logic shared by the ssh-thing front ends.
+//!
+//! Nothing here depends on Tauri: events go through [`events::EventSink`],
+//! credentials through [`secrets::SecretStore`] and host key decisions
+//! through [`ssh::HostKeyVerifier`].
+
+pub mod events;
+pub mod hostkeys;
+pub mod knock;
+pub mod model;
+pub mod net;
+pub mod ppk;
+pub mod profiles;
+pub mod secrets;
+pub mod ssh;
+pub mod ssm;
+pub mod storage;
+pub mod trace;
+pub mod wol;
+
+pub use events::{EventSink, NullEventSink};
+pub use model::{
+    AuthMethod, CloudSource, CompressionMode, ConnectionState, ConnectionStateEvent, Elevation,
+    ElevationMethod, Environment, ForwardDestination, ForwardProfile, HostKeyMismatch,
+    HostKeyPrompt, Identity, KnownHost, PortForward, ReconnectPolicy, SecretKind, ServerBadge,
+    ServerConnection, Snippet,
+};
+pub use secrets::{KeyringSecretStore, SecretStore};
+pub use ssh::{ChannelTuning, ConnectOptions, HostKeyVerifier, SshSession};
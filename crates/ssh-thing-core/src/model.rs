@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+
+use crate::knock::PortKnockSequence;
+use crate::ssm::SsmTarget;
+use crate::wol::WakeOnLanConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Disconnected,
+    Error(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionStateEvent {
+    pub connection_id: Option<String>,
+    pub server_id: Option<String>,
+    pub shell_id: Option<String>,
+    pub state: ConnectionState,
+    /// How the server is marked, so the terminal chrome can match it.
+    #[serde(default)]
+    pub badge: Option<ServerBadge>,
+}
+
+/// Which environment a server belongs to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Prod,
+    Staging,
+    Dev,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServerBadge {
+    pub environment: Option<Environment>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// Where a server entry was imported from, so a re-sync updates it in place
+/// instead of adding a duplicate.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CloudSource {
+    pub provider: String,
+    pub resource_id: String,
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+/// What to do when a shell loses its connection without the remote side
+/// exiting.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "mode")]
+pub enum ReconnectPolicy {
+    #[default]
+    Manual,
+    Automatic {
+        max_attempts: u32,
+        delay_seconds: u64,
+    },
+}
+
+/// Whether to negotiate zlib compression for a session.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionMode {
+    Off,
+    On,
+    /// On, except for hosts on the loopback or local network.
+    #[default]
+    Auto,
+}
+
+/// How to become another user once a shell opens.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationMethod {
+    /// `sudo -i`, asking for the login user's password.
+    Sudo,
+    /// `su -`, asking for the target user's password.
+    Su,
+}
+
+/// Elevation run in place of the login shell on connect.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Elevation {
+    pub method: ElevationMethod,
+    /// User to become; root when unset.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Keyring secret holding the password for the prompt. Without one the
+    /// prompt is left for the user to answer.
+    #[serde(default)]
+    pub secret_id: Option<String>,
+}
+
+/// A host a forward may connect to. `host` is an exact name or address,
+/// `*.example.com` for its subdomains, or `*` for any host; an empty
+/// `ports` list allows every port.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ForwardDestination {
+    pub host: String,
+    #[serde(default)]
+    pub ports: Vec<u16>,
+}
+
+impl ForwardDestination {
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let pattern = self.host.trim().to_ascii_lowercase();
+        let host = host.trim().to_ascii_lowercase();
+        let host_matches = if pattern == "*" {
+            true
+        } else if let Some(domain) = pattern.strip_prefix("*.") {
+            host.strip_suffix(domain)
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+        } else {
+            pattern == host
+        };
+        host_matches && (self.ports.is_empty() || self.ports.contains(&port))
+    }
+}
+
+/// A local port tunnelled to `remote_host:remote_port`, resolved on the
+/// server side. A dynamic forward is a SOCKS5 proxy instead, where each
+/// client picks its own destination.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortForward {
+    pub local_port: u16,
+    /// Unused by dynamic forwards.
+    #[serde(default)]
+    pub remote_host: String,
+    #[serde(default)]
+    pub remote_port: u16,
+    /// Local address to listen on; loopback when unset.
+    #[serde(default)]
+    pub bind_address: Option<String>,
+    /// Listen on the next free port when `local_port` is taken instead of
+    /// failing.
+    #[serde(default)]
+    pub reassign_if_busy: bool,
+    #[serde(default)]
+    pub dynamic: bool,
+    /// Destinations clients may reach through the forward; any when empty.
+    #[serde(default)]
+    pub allowed_destinations: Vec<ForwardDestination>,
+}
+
+impl PortForward {
+    pub fn allows(&self, host: &str, port: u16) -> bool {
+        self.allowed_destinations.is_empty()
+            || self
+                .allowed_destinations
+                .iter()
+                .any(|destination| destination.matches(host, port))
+    }
+}
+
+/// A named set of forwards started and stopped together, e.g. "dev DB set".
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ForwardProfile {
+    pub name: String,
+    pub forwards: Vec<PortForward>,
+    /// Start with every session to the server and stop when it disconnects.
+    #[serde(default)]
+    pub auto_start: bool,
+    /// Open as a shell-less, always-reconnecting tunnel when the app starts.
+    #[serde(default)]
+    pub start_on_launch: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConnection {
+    pub id: String,
+    #[serde(default)]
+    pub nickname: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub last_connected_at: Option<u64>,
+    #[serde(default)]
+    pub wake_on_lan: Option<WakeOnLanConfig>,
+    #[serde(default)]
+    pub port_knock: Option<PortKnockSequence>,
+    #[serde(default)]
+    pub cloud_source: Option<CloudSource>,
+    /// Connect through AWS SSM Session Manager instead of TCP to `host`.
+    #[serde(default)]
+    pub ssm: Option<SsmTarget>,
+    /// Leave from the local Tailscale address when `host` is on the tailnet.
+    #[serde(default)]
+    pub bind_tailnet: bool,
+    /// Free-form labels for picking groups of servers, e.g. `ssh-thing run --on`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Folder the server is listed under.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// `TERM` for shells on this server; the global default applies when unset.
+    #[serde(default)]
+    pub term: Option<String>,
+    /// Command to run in the PTY instead of the login shell, e.g. `zsh -l` or
+    /// `docker exec -it app bash`.
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Becomes root or another user right after connecting. Ignored when
+    /// `shell` is set.
+    #[serde(default)]
+    pub elevate: Option<Elevation>,
+    /// Seconds between keepalives, 0 to turn them off. The global default
+    /// applies when unset.
+    #[serde(default)]
+    pub keepalive_seconds: Option<u64>,
+    #[serde(default)]
+    pub reconnect: Option<ReconnectPolicy>,
+    #[serde(default)]
+    pub compression: CompressionMode,
+    /// Markdown runbook for the server: how to restart it, where the logs are.
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// CSS color for the server's tab and list entry, e.g. `#d73a49`.
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    /// Unix time the password, key or certificate stops working, for
+    /// reminders ahead of a rotation.
+    #[serde(default)]
+    pub credential_expires_at: Option<u64>,
+    /// Host key fingerprints (`SHA256:...`) the server must present. When
+    /// set, known hosts and the trust prompt are bypassed.
+    #[serde(default)]
+    pub pinned_fingerprints: Vec<String>,
+    #[serde(default)]
+    pub forward_profiles: Vec<ForwardProfile>,
+    /// Shared credential to log in with instead of `auth`.
+    #[serde(default)]
+    pub identity_id: Option<String>,
+    /// Most sessions the app keeps open to the server at once.
+    #[serde(default)]
+    pub max_sessions: Option<u32>,
+    /// Most shells open on the server across all its sessions.
+    #[serde(default)]
+    pub max_shells: Option<u32>,
+    pub auth: AuthMethod,
+}
+
+impl ServerConnection {
+    /// `None` when the server has no color, icon or environment set.
+    pub fn badge(&self) -> Option<ServerBadge> {
+        if self.environment.is_none() && self.color.is_none() && self.icon.is_none() {
+            return None;
+        }
+        Some(ServerBadge {
+            environment: self.environment,
+            color: self.color.clone(),
+            icon: self.icon.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SecretKind {
+    Password,
+    PrivateKey,
+}
+
+fn default_secret_kind() -> SecretKind {
+    SecretKind::Password
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AuthMethod {
+    SecretRef {
+        secret_id: String,
+        #[serde(default = "default_secret_kind")]
+        kind: SecretKind,
+    },
+    // Legacy shapes kept for migration
+    Password {
+        password: String,
+    },
+    Key {
+        private_key: String,
+    },
+}
+
+/// A named credential several servers can log in with, so changing its
+/// secret changes it for all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub id: String,
+    pub name: String,
+    /// Replaces the user of servers logging in as this identity.
+    #[serde(default)]
+    pub username: Option<String>,
+    pub auth: AuthMethod,
+    /// Given to servers saved without a credential of their own.
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub description: Option<String>,
+    /// Answer sudo's password prompt with the server's saved password when
+    /// run through `run_snippet`.
+    #[serde(default)]
+    pub requires_sudo: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHost {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub public_key_base64: String,
+    pub added_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostKeyPrompt {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub public_key_base64: String,
+    /// The key's fingerprints as `ssh-keygen -l` prints them, for
+    /// comparing with a provider's console.
+    #[serde(default)]
+    pub fingerprint_sha256: String,
+    #[serde(default)]
+    pub fingerprint_md5: String,
+    /// ssh-keygen's randomart for the key, one line per row.
+    #[serde(default)]
+    pub randomart: String,
+    /// Seconds until an unanswered prompt is rejected, if it ever is.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// Key types already trusted for the host. Empty for a new host;
+    /// otherwise the host now presents a key of a type not seen before.
+    #[serde(default)]
+    pub known_key_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostKeyMismatch {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub stored_fingerprint: String,
+}
@@ -0,0 +1,199 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+use tokio::process::Command;
+use tracing::debug;
+
+const TAILSCALE_CLI: &str = "tailscale";
+#[cfg(target_os = "macos")]
+const TAILSCALE_APP_CLI: &str = "/Applications/Tailscale.app/Contents/MacOS/Tailscale";
+
+#[derive(Debug, Default)]
+pub struct TrafficCounters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    peer_addr: OnceLock<SocketAddr>,
+}
+
+impl TrafficCounters {
+    /// The address the session's TCP connection went to; unset for SSM.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr.get().copied()
+    }
+
+    pub(crate) fn set_peer_addr(&self, addr: SocketAddr) {
+        let _ = self.peer_addr.set(addr);
+    }
+
+    pub fn bytes_in(&self) -> u64 {
+        self.bytes_in.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_out(&self) -> u64 {
+        self.bytes_out.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps the TCP stream under an SSH session and counts the bytes on the
+/// wire in each direction.
+pub struct CountingStream<S> {
+    inner: S,
+    counters: Arc<TrafficCounters>,
+}
+
+impl<S> CountingStream<S> {
+    pub fn new(inner: S, counters: Arc<TrafficCounters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len().saturating_sub(before) as u64;
+        self.counters.bytes_in.fetch_add(read, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = &result {
+            self.counters
+                .bytes_out
+                .fetch_add(*written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// 100.64.0.0/10, the CGNAT range Tailscale assigns node addresses from.
+pub fn is_tailnet_address(ip: Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    octets[0] == 100 && (octets[1] & 0xC0) == 64
+}
+
+/// Runs the Tailscale CLI, falling back to the app bundle on macOS where the
+/// CLI is often not on `PATH`.
+pub async fn tailscale_cli(args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = match Command::new(TAILSCALE_CLI).args(args).output().await {
+        Ok(output) => output,
+        #[cfg(target_os = "macos")]
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            Command::new(TAILSCALE_APP_CLI).args(args).output().await?
+        }
+        Err(e) => return Err(e),
+    };
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+async fn local_tailnet_ipv4() -> Option<Ipv4Addr> {
+    let stdout = tailscale_cli(&["ip", "-4"]).await.ok()?;
+    String::from_utf8_lossy(&stdout)
+        .lines()
+        .find_map(|line| line.trim().parse::<Ipv4Addr>().ok())
+        .filter(|ip| is_tailnet_address(*ip))
+}
+
+/// Opens the TCP connection for a session. With `bind_tailnet`, connections
+/// to tailnet addresses leave from the local Tailscale address so they stay
+/// on the tailnet even when another VPN owns the default route.
+pub async fn connect_tcp(host: &str, port: u16, bind_tailnet: bool) -> io::Result<TcpStream> {
+    if bind_tailnet {
+        let remote = lookup_host((host, port))
+            .await?
+            .find(|addr| is_tailnet_socket(*addr));
+        if let Some(remote) = remote {
+            return connect_addr(remote, true).await;
+        }
+    }
+    TcpStream::connect((host, port)).await
+}
+
+/// Like `connect_tcp`, for an address that was already resolved.
+pub async fn connect_addr(remote: SocketAddr, bind_tailnet: bool) -> io::Result<TcpStream> {
+    if bind_tailnet && is_tailnet_socket(remote) {
+        if let Some(local) = local_tailnet_ipv4().await {
+            debug!(%remote, %local, "Binding connection to tailnet address");
+            let socket = TcpSocket::new_v4()?;
+            socket.bind(SocketAddr::new(IpAddr::V4(local), 0))?;
+            return socket.connect(remote).await;
+        }
+    }
+    TcpStream::connect(remote).await
+}
+
+/// The address `connect_tcp` would pick for `host`, for callers that need
+/// to reach the same machine before connecting.
+pub async fn resolve(host: &str, port: u16, bind_tailnet: bool) -> io::Result<SocketAddr> {
+    let addrs: Vec<SocketAddr> = lookup_host((host, port)).await?.collect();
+    addrs
+        .iter()
+        .find(|addr| bind_tailnet && is_tailnet_socket(**addr))
+        .or(addrs.first())
+        .copied()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses found"))
+}
+
+fn is_tailnet_socket(addr: SocketAddr) -> bool {
+    matches!(addr, SocketAddr::V4(v4) if is_tailnet_address(*v4.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_counting_stream_counts_both_directions() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let counters = Arc::new(TrafficCounters::default());
+        let mut stream = CountingStream::new(client, counters.clone());
+
+        stream.write_all(b"hello").await.expect("write");
+        let mut received = [0u8; 5];
+        server.read_exact(&mut received).await.expect("server read");
+        server.write_all(b"hi").await.expect("server write");
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await.expect("read");
+
+        assert_eq!(counters.bytes_out(), 5);
+        assert_eq!(counters.bytes_in(), 2);
+    }
+
+    #[test]
+    fn test_is_tailnet_address() {
+        assert!(is_tailnet_address(Ipv4Addr::new(100, 64, 0, 1)));
+        assert!(is_tailnet_address(Ipv4Addr::new(100, 127, 255, 254)));
+        assert!(!is_tailnet_address(Ipv4Addr::new(100, 128, 0, 1)));
+        assert!(!is_tailnet_address(Ipv4Addr::new(10, 64, 0, 1)));
+    }
+}
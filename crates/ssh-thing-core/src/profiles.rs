@@ -0,0 +1,60 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+pub const PROFILES_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const KEYRING_SERVICE: &str = "com.ssh-thing";
+
+/// The default profile lives directly in the app data directory so data
+/// from before profiles existed keeps working without a migration.
+pub fn profile_dir(root: &Path, profile_id: &str) -> PathBuf {
+    if profile_id == DEFAULT_PROFILE_ID {
+        root.to_path_buf()
+    } else {
+        root.join(PROFILES_DIR).join(profile_id)
+    }
+}
+
+pub fn keyring_service_name(profile_id: &str) -> String {
+    if profile_id == DEFAULT_PROFILE_ID {
+        KEYRING_SERVICE.to_string()
+    } else {
+        format!("{}.{}", KEYRING_SERVICE, profile_id)
+    }
+}
+
+#[derive(Deserialize)]
+struct SavedProfiles {
+    #[serde(default)]
+    active_profile: Option<String>,
+}
+
+/// The profile the desktop app had active when it last saved its profile
+/// list, falling back to the default profile.
+pub fn saved_active_profile(root: &Path) -> String {
+    fs::read_to_string(root.join(PROFILES_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_str::<SavedProfiles>(&data).ok())
+        .and_then(|saved| saved.active_profile)
+        .unwrap_or_else(|| DEFAULT_PROFILE_ID.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_profile_uses_legacy_locations() {
+        let root = Path::new("/data/ssh-thing");
+
+        assert_eq!(profile_dir(root, DEFAULT_PROFILE_ID), root);
+        assert_eq!(
+            profile_dir(root, "work"),
+            Path::new("/data/ssh-thing/profiles/work")
+        );
+        assert_eq!(keyring_service_name(DEFAULT_PROFILE_ID), "com.ssh-thing");
+        assert_eq!(keyring_service_name("work"), "com.ssh-thing.work");
+    }
+}
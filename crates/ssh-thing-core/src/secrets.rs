@@ -0,0 +1,135 @@
+use keyring::Entry;
+
+use crate::model::{AuthMethod, SecretKind, ServerConnection};
+
+/// Where credentials referenced by [`AuthMethod::SecretRef`] live.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, secret_id: &str) -> Result<String, String>;
+    fn put(&self, secret_id: &str, secret: &str) -> Result<(), String>;
+    fn delete(&self, secret_id: &str) -> Result<(), String>;
+}
+
+/// The OS keyring, namespaced by service name.
+#[derive(Debug, Clone)]
+pub struct KeyringSecretStore {
+    service: String,
+}
+
+impl KeyringSecretStore {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    fn entry(&self, secret_id: &str) -> Result<Entry, String> {
+        Entry::new(&self.service, secret_id).map_err(|e| format!("keyring entry failed: {}", e))
+    }
+}
+
+impl SecretStore for KeyringSecretStore {
+    fn get(&self, secret_id: &str) -> Result<String, String> {
+        self.entry(secret_id)?
+            .get_password()
+            .map_err(|e| format!("keyring get failed: {}", e))
+    }
+
+    fn put(&self, secret_id: &str, secret: &str) -> Result<(), String> {
+        self.entry(secret_id)?
+            .set_password(secret)
+            .map_err(|e| format!("keyring set failed: {}", e))
+    }
+
+    fn delete(&self, secret_id: &str) -> Result<(), String> {
+        self.entry(secret_id)?
+            .delete_password()
+            .map_err(|e| format!("keyring delete failed: {}", e))
+    }
+}
+
+/// Moves legacy plaintext credentials into the store and replaces them with
+/// a reference. Returns whether the server changed.
+pub fn migrate_server_auth(
+    store: &dyn SecretStore,
+    server: &mut ServerConnection,
+) -> Result<bool, String> {
+    match &server.auth {
+        AuthMethod::SecretRef { .. } => Ok(false),
+        AuthMethod::Password { password } => {
+            let secret_id = format!("server:{}:password", server.id);
+            store.put(&secret_id, password)?;
+            server.auth = AuthMethod::SecretRef {
+                secret_id,
+                kind: SecretKind::Password,
+            };
+            Ok(true)
+        }
+        AuthMethod::Key { private_key } => {
+            let secret_id = format!("server:{}:private_key", server.id);
+            store.put(&secret_id, private_key)?;
+            server.auth = AuthMethod::SecretRef {
+                secret_id,
+                kind: SecretKind::PrivateKey,
+            };
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, secret_id: &str) -> Result<String, String> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(secret_id)
+                .cloned()
+                .ok_or_else(|| "missing".to_string())
+        }
+
+        fn put(&self, secret_id: &str, secret: &str) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(secret_id.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, secret_id: &str) -> Result<(), String> {
+            self.0.lock().unwrap().remove(secret_id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_migrate_server_auth_moves_plaintext_into_store() {
+        let store = MemoryStore::default();
+        let mut server: ServerConnection = serde_json::from_value(serde_json::json!({
+            "id": "s1",
+            "host": "example.com",
+            "port": 22,
+            "user": "ops",
+            "auth": {"type": "Password", "password": "hunter2"}
+        }))
+        .expect("server");
+
+        assert!(migrate_server_auth(&store, &mut server).expect("migrated"));
+        match &server.auth {
+            AuthMethod::SecretRef { secret_id, kind } => {
+                assert_eq!(secret_id, "server:s1:password");
+                assert!(matches!(kind, SecretKind::Password));
+                assert_eq!(store.get(secret_id).expect("stored"), "hunter2");
+            }
+            other => panic!("unexpected auth: {:?}", other),
+        }
+        assert!(!migrate_server_auth(&store, &mut server).expect("no-op"));
+    }
+}
@@ -0,0 +1,528 @@
+use async_trait::async_trait;
+use russh::client::{Config, Handle, Handler, Session};
+use russh::keys::PublicKeyBase64;
+use russh::{compression, keys, Preferred};
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::time::{timeout, Duration};
+
+#[cfg(debug_assertions)]
+use tracing::{debug, info};
+
+use crate::events::EventSink;
+use crate::hostkeys;
+use crate::knock::{self, PortKnockSequence};
+use crate::model::{AuthMethod, CompressionMode, ConnectionState, SecretKind, ServerConnection};
+use crate::net::{self, CountingStream, TrafficCounters};
+use crate::ppk;
+use crate::secrets::SecretStore;
+use crate::ssm::{self, SsmTarget};
+use crate::trace::{HandshakeTrace, TraceStage, TraceStream};
+
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 30;
+const UNREACHABLE_PREFIX: &str = "Failed to reach";
+pub const DEFAULT_KEEPALIVE_SECONDS: u64 = 15;
+// russh defaults to a 2 MiB window, which caps a single channel well below
+// the link speed once there is any latency.
+pub const DEFAULT_WINDOW_SIZE: u32 = 16 * 1024 * 1024;
+pub const DEFAULT_MAX_PACKET_SIZE: u32 = 32 * 1024;
+const MIN_MAX_PACKET_SIZE: u32 = 1024;
+// The largest packet OpenSSH accepts.
+const MAX_MAX_PACKET_SIZE: u32 = 256 * 1024;
+
+// zlib@openssh.com first: it only starts after authentication, so a failed
+// login never pays for the compressor.
+const COMPRESSION_PREFERRED: &[compression::Name] = &[
+    compression::ZLIB_LEGACY,
+    compression::ZLIB,
+    compression::NONE,
+];
+const COMPRESSION_OFF: &[compression::Name] = &[compression::NONE];
+
+/// Decides whether to trust the key a server presents during the handshake.
+#[async_trait]
+pub trait HostKeyVerifier: Send + Sync {
+    async fn verify(&self, host: &str, port: u16, key: &keys::key::PublicKey) -> bool;
+}
+
+pub struct ClientHandler {
+    host: String,
+    port: u16,
+    verifier: Arc<dyn HostKeyVerifier>,
+    trace: Option<Arc<HandshakeTrace>>,
+}
+
+#[async_trait]
+impl Handler for ClientHandler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &keys::key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let accepted = self
+            .verifier
+            .verify(&self.host, self.port, server_public_key)
+            .await;
+        if let Some(trace) = &self.trace {
+            trace.record(TraceStage::HostKey {
+                algorithm: server_public_key.name().to_string(),
+                fingerprint: hostkeys::sha256_fingerprint(&server_public_key.public_key_bytes()),
+                accepted,
+            });
+        }
+        Ok(accepted)
+    }
+
+    async fn auth_banner(
+        &mut self,
+        banner: &str,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(trace) = &self.trace {
+            trace.record(TraceStage::AuthBanner {
+                message: banner.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+pub type SshSession = Handle<ClientHandler>;
+
+/// Flow control for the channels of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelTuning {
+    /// Bytes the server may send on a channel before waiting for us to
+    /// acknowledge them.
+    pub window_size: u32,
+    /// Largest data packet the server may send.
+    pub maximum_packet_size: u32,
+}
+
+impl Default for ChannelTuning {
+    fn default() -> Self {
+        Self {
+            window_size: DEFAULT_WINDOW_SIZE,
+            maximum_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        }
+    }
+}
+
+impl ChannelTuning {
+    /// Keeps the packet size within what servers accept and the window
+    /// large enough to hold at least one packet.
+    pub fn clamped(self) -> Self {
+        let maximum_packet_size = self
+            .maximum_packet_size
+            .clamp(MIN_MAX_PACKET_SIZE, MAX_MAX_PACKET_SIZE);
+        Self {
+            window_size: self.window_size.max(maximum_packet_size),
+            maximum_packet_size,
+        }
+    }
+}
+
+/// Everything needed to open one authenticated session.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectOptions<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    pub user: &'a str,
+    pub auth: &'a AuthMethod,
+    pub timeout_seconds: Option<u64>,
+    /// Only used to tag the `connection-state` events.
+    pub connection_id: Option<&'a str>,
+    pub server_id: Option<&'a str>,
+    pub port_knock: Option<&'a PortKnockSequence>,
+    pub ssm: Option<&'a SsmTarget>,
+    pub bind_tailnet: bool,
+    /// 0 turns keepalives off.
+    pub keepalive_seconds: Option<u64>,
+    pub channel_tuning: ChannelTuning,
+    pub compression: CompressionMode,
+    /// Records the handshake step by step when set.
+    pub trace: Option<&'a Arc<HandshakeTrace>>,
+}
+
+impl<'a> ConnectOptions<'a> {
+    pub fn for_server(server: &'a ServerConnection) -> Self {
+        Self {
+            host: &server.host,
+            port: server.port,
+            user: &server.user,
+            auth: &server.auth,
+            timeout_seconds: server.timeout_seconds,
+            connection_id: None,
+            server_id: Some(&server.id),
+            port_knock: server.port_knock.as_ref(),
+            ssm: server.ssm.as_ref(),
+            bind_tailnet: server.bind_tailnet,
+            keepalive_seconds: server.keepalive_seconds,
+            channel_tuning: ChannelTuning::default(),
+            compression: server.compression,
+            trace: None,
+        }
+    }
+}
+
+fn is_local_host(host: &str) -> bool {
+    let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+    if host.eq_ignore_ascii_case("localhost") || host.to_ascii_lowercase().ends_with(".local") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        // fe80::/10 link-local and fc00::/7 unique local addresses.
+        Ok(IpAddr::V6(ip)) => {
+            ip.is_loopback()
+                || (ip.segments()[0] & 0xffc0) == 0xfe80
+                || (ip.segments()[0] & 0xfe00) == 0xfc00
+        }
+        Err(_) => false,
+    }
+}
+
+/// Compression algorithms to offer, most preferred first. Compression
+/// saves bandwidth on slow or distant links but costs CPU, which is not
+/// worth it on the local network.
+pub fn compression_algorithms(mode: CompressionMode, host: &str) -> &'static [compression::Name] {
+    let enabled = match mode {
+        CompressionMode::Off => false,
+        CompressionMode::On => true,
+        CompressionMode::Auto => !is_local_host(host),
+    };
+    if enabled {
+        COMPRESSION_PREFERRED
+    } else {
+        COMPRESSION_OFF
+    }
+}
+
+enum Credential {
+    Password(String),
+    PrivateKey(String),
+}
+
+fn resolve_credential(auth: &AuthMethod, secrets: &dyn SecretStore) -> Result<Credential, String> {
+    Ok(match auth {
+        AuthMethod::SecretRef { secret_id, kind } => match kind {
+            SecretKind::Password => Credential::Password(secrets.get(secret_id)?),
+            SecretKind::PrivateKey => Credential::PrivateKey(secrets.get(secret_id)?),
+        },
+        AuthMethod::Password { password } => Credential::Password(password.clone()),
+        AuthMethod::Key { private_key } => Credential::PrivateKey(private_key.clone()),
+    })
+}
+
+fn record_attempt(trace: Option<&Arc<HandshakeTrace>>, method: &str, user: &str, accepted: bool) {
+    if let Some(trace) = trace {
+        trace.record(TraceStage::AuthAttempt {
+            method: method.to_string(),
+            user: user.to_string(),
+            accepted,
+        });
+    }
+}
+
+async fn authenticate(
+    session: &mut SshSession,
+    user: &str,
+    credential: Credential,
+    trace: Option<&Arc<HandshakeTrace>>,
+) -> Result<(), String> {
+    match credential {
+        Credential::Password(password) => {
+            #[cfg(debug_assertions)]
+            debug!(user, "Authenticating with password");
+
+            let authenticated = session
+                .authenticate_password(user, &password)
+                .await
+                .map_err(|e| format!("Authentication failed: {}", e))?;
+            record_attempt(trace, "password", user, authenticated);
+            if !authenticated {
+                return Err("Password authentication failed".to_string());
+            }
+        }
+        Credential::PrivateKey(key_data) => {
+            #[cfg(debug_assertions)]
+            debug!(user, "Authenticating with key");
+
+            // Keys pasted from PuTTY are converted on the fly.
+            let key_data = if ppk::is_ppk(&key_data) {
+                ppk::to_openssh(&key_data, None)?
+            } else {
+                key_data
+            };
+            let key_pair = keys::decode_secret_key(&key_data, None)
+                .map_err(|e| format!("Failed to decode private key: {}", e))?;
+            let authenticated = session
+                .authenticate_publickey(user, Arc::new(key_pair))
+                .await
+                .map_err(|e| format!("Key authentication failed: {}", e))?;
+            record_attempt(trace, "publickey", user, authenticated);
+            if !authenticated {
+                return Err("Key authentication failed".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn open_session(
+    options: &ConnectOptions<'_>,
+    verifier: Arc<dyn HostKeyVerifier>,
+    counters: Arc<TrafficCounters>,
+) -> Result<SshSession, String> {
+    let keepalive_seconds = options
+        .keepalive_seconds
+        .unwrap_or(DEFAULT_KEEPALIVE_SECONDS);
+    let tuning = options.channel_tuning.clamped();
+    let config = Arc::new(Config {
+        window_size: tuning.window_size,
+        maximum_packet_size: tuning.maximum_packet_size,
+        preferred: Preferred {
+            compression: Cow::Borrowed(compression_algorithms(options.compression, options.host)),
+            ..Preferred::default()
+        },
+        keepalive_interval: (keepalive_seconds > 0)
+            .then_some(Duration::from_secs(keepalive_seconds)),
+        keepalive_max: 3,
+        ..Config::default()
+    });
+
+    let trace = options.trace.cloned();
+    // A name with several addresses could otherwise be knocked on one and
+    // connected to on another, so the knocked address is the one used.
+    let mut knocked = None;
+    if let Some(sequence) = options
+        .port_knock
+        .filter(|sequence| !sequence.ports.is_empty())
+    {
+        if let Some(trace) = &trace {
+            trace.record(TraceStage::PortKnock);
+        }
+        let remote = net::resolve(options.host, options.port, options.bind_tailnet)
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", options.host, e))?;
+        knock::knock(remote.ip(), sequence).await?;
+        knocked = Some(remote);
+    }
+    if let Some(trace) = &trace {
+        trace.record(TraceStage::Connecting {
+            host: options.host.to_string(),
+            port: options.port,
+            ssm: options.ssm.is_some(),
+        });
+    }
+
+    #[cfg(debug_assertions)]
+    debug!(
+        host = options.host,
+        port = options.port,
+        bind_tailnet = options.bind_tailnet,
+        "Establishing TCP connection"
+    );
+
+    let handler = ClientHandler {
+        host: options.host.to_string(),
+        port: options.port,
+        verifier,
+        trace: trace.clone(),
+    };
+    let connect_timeout = Duration::from_secs(
+        options
+            .timeout_seconds
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECONDS)
+            .max(1),
+    );
+    let (host, port, bind_tailnet) = (options.host, options.port, options.bind_tailnet);
+    let ssm = options.ssm.cloned();
+    // Set once the transport is up, so a timeout can tell a server that never
+    // answered from one that stalled during the handshake.
+    let mut reached = false;
+    let result = timeout(connect_timeout, async {
+        if let Some(target) = ssm {
+            let stream =
+                ssm::open_stream(&target, port).map_err(|e| format!("Failed to connect: {}", e))?;
+            reached = true;
+            return russh::client::connect_stream(
+                config,
+                CountingStream::new(TraceStream::new(stream, trace), counters),
+                handler,
+            )
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e));
+        }
+        let socket = match knocked {
+            Some(remote) => net::connect_addr(remote, bind_tailnet).await,
+            None => net::connect_tcp(host, port, bind_tailnet).await,
+        }
+        .map_err(|e| format!("{} {}:{}: {}", UNREACHABLE_PREFIX, host, port, e))?;
+        reached = true;
+        if let Ok(peer) = socket.peer_addr() {
+            counters.set_peer_addr(peer);
+        }
+        russh::client::connect_stream(
+            config,
+            CountingStream::new(TraceStream::new(socket, trace), counters),
+            handler,
+        )
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(_) if !reached => Err(format!(
+            "{} {}:{}: timed out after {} seconds",
+            UNREACHABLE_PREFIX,
+            host,
+            port,
+            connect_timeout.as_secs()
+        )),
+        Err(_) => Err(format!(
+            "Failed to connect: timed out after {} seconds",
+            connect_timeout.as_secs()
+        )),
+    }
+}
+
+/// Whether a `connect` error means nothing answered at the address, as
+/// opposed to a server that answered and refused the login or host key.
+/// Only the former is worth waking the machine or retrying for.
+pub fn is_unreachable(error: &str) -> bool {
+    error.starts_with(UNREACHABLE_PREFIX)
+}
+
+/// Opens and authenticates a session, reporting progress as
+/// `connection-state` events. Wire traffic is added to `counters`.
+pub async fn connect(
+    options: &ConnectOptions<'_>,
+    secrets: &dyn SecretStore,
+    events: &dyn EventSink,
+    verifier: Arc<dyn HostKeyVerifier>,
+    counters: Arc<TrafficCounters>,
+) -> Result<SshSession, String> {
+    #[cfg(debug_assertions)]
+    debug!(
+        host = options.host,
+        port = options.port,
+        user = options.user,
+        "Starting SSH connection"
+    );
+
+    events.connection_state(
+        options.connection_id,
+        options.server_id,
+        None,
+        ConnectionState::Connecting,
+    )?;
+
+    let result = async {
+        let mut session = open_session(options, verifier, counters).await?;
+        let credential = resolve_credential(options.auth, secrets)?;
+        authenticate(&mut session, options.user, credential, options.trace).await?;
+        Ok(session)
+    }
+    .await;
+
+    if let Some(trace) = options.trace {
+        trace.record(match &result {
+            Ok(_) => TraceStage::Connected,
+            Err(e) => TraceStage::Failed { error: e.clone() },
+        });
+    }
+
+    match result {
+        Ok(session) => {
+            #[cfg(debug_assertions)]
+            info!(
+                host = options.host,
+                port = options.port,
+                user = options.user,
+                "SSH connection established successfully"
+            );
+            events.connection_state(
+                options.connection_id,
+                options.server_id,
+                None,
+                ConnectionState::Connected,
+            )?;
+            Ok(session)
+        }
+        Err(e) => {
+            let _ = events.connection_state(
+                options.connection_id,
+                options.server_id,
+                None,
+                ConnectionState::Error(e.clone()),
+            );
+            Err(e)
+        }
+    }
+}
+
+pub async fn disconnect(session: &SshSession) {
+    let disconnect_result = timeout(
+        Duration::from_secs(2),
+        session.disconnect(russh::Disconnect::ByApplication, "disconnected", "en"),
+    )
+    .await;
+
+    if disconnect_result.is_err() {
+        #[cfg(debug_assertions)]
+        debug!("SSH disconnect timed out");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_tuning_clamps_packet_and_window() {
+        let tuning = ChannelTuning {
+            window_size: 4096,
+            maximum_packet_size: 1024 * 1024,
+        }
+        .clamped();
+
+        assert_eq!(tuning.maximum_packet_size, MAX_MAX_PACKET_SIZE);
+        assert_eq!(tuning.window_size, MAX_MAX_PACKET_SIZE);
+        assert_eq!(ChannelTuning::default().clamped(), ChannelTuning::default());
+    }
+
+    #[test]
+    fn test_auto_compression_skips_local_hosts() {
+        for host in [
+            "127.0.0.1",
+            "192.168.1.20",
+            "nas.local",
+            "[fe80::1]",
+            "fd12::3",
+        ] {
+            assert_eq!(
+                compression_algorithms(CompressionMode::Auto, host),
+                COMPRESSION_OFF,
+                "{}",
+                host
+            );
+        }
+        assert_eq!(
+            compression_algorithms(CompressionMode::Auto, "build.example.com"),
+            COMPRESSION_PREFERRED
+        );
+        assert_eq!(
+            compression_algorithms(CompressionMode::Off, "203.0.113.7"),
+            COMPRESSION_OFF
+        );
+        assert_eq!(
+            compression_algorithms(CompressionMode::On, "localhost"),
+            COMPRESSION_PREFERRED
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, Join, ReadBuf};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tracing::debug;
+
+const AWS_CLI: &str = "aws";
+const SSH_SESSION_DOCUMENT: &str = "AWS-StartSSHSession";
+
+/// Reaches an instance through AWS Systems Manager instead of a direct TCP
+/// connection. Needs the AWS CLI and the Session Manager plugin locally.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SsmTarget {
+    pub instance_id: String,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Path to the AWS CLI when it is not on `PATH`.
+    #[serde(default)]
+    pub aws_cli_path: Option<String>,
+}
+
+fn start_session_args(target: &SsmTarget, port: u16) -> Vec<String> {
+    let mut args = vec![
+        "ssm".to_string(),
+        "start-session".to_string(),
+        "--target".to_string(),
+        target.instance_id.clone(),
+        "--document-name".to_string(),
+        SSH_SESSION_DOCUMENT.to_string(),
+        "--parameters".to_string(),
+        format!("portNumber={}", port),
+    ];
+    if let Some(region) = &target.region {
+        args.push("--region".to_string());
+        args.push(region.clone());
+    }
+    if let Some(profile) = &target.profile {
+        args.push("--profile".to_string());
+        args.push(profile.clone());
+    }
+    args
+}
+
+/// The stdin/stdout pipe of an `aws ssm start-session` process, used as the
+/// transport for the SSH handshake. The process is killed when the stream is
+/// dropped.
+pub struct SsmStream {
+    _child: Child,
+    io: Join<ChildStdout, ChildStdin>,
+}
+
+impl AsyncRead for SsmStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SsmStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+pub fn open_stream(target: &SsmTarget, port: u16) -> io::Result<SsmStream> {
+    let program = target.aws_cli_path.as_deref().unwrap_or(AWS_CLI);
+    let mut child = Command::new(program)
+        .args(start_session_args(target, port))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                io::Error::new(
+                    e.kind(),
+                    format!("AWS CLI not found at '{}'; install it to use SSM", program),
+                )
+            } else {
+                e
+            }
+        })?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| io::Error::other("SSM session has no stdout"))?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other("SSM session has no stdin"))?;
+
+    // The CLI reports plugin and permission problems on stderr; surface them
+    // in the log since the SSH handshake only sees a closed pipe.
+    if let Some(stderr) = child.stderr.take() {
+        let instance_id = target.instance_id.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!(%instance_id, %line, "aws ssm start-session");
+            }
+        });
+    }
+
+    Ok(SsmStream {
+        _child: child,
+        io: tokio::io::join(stdout, stdin),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_session_args() {
+        let mut target = SsmTarget {
+            instance_id: "i-0abc".to_string(),
+            region: None,
+            profile: None,
+            aws_cli_path: None,
+        };
+        assert_eq!(
+            start_session_args(&target, 22),
+            vec![
+                "ssm",
+                "start-session",
+                "--target",
+                "i-0abc",
+                "--document-name",
+                "AWS-StartSSHSession",
+                "--parameters",
+                "portNumber=22",
+            ]
+        );
+
+        target.region = Some("us-east-1".to_string());
+        target.profile = Some("prod".to_string());
+        let args = start_session_args(&target, 2222);
+        assert!(args.contains(&"portNumber=2222".to_string()));
+        assert_eq!(&args[8..], &["--region", "us-east-1", "--profile", "prod"]);
+    }
+}
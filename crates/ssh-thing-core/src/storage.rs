@@ -0,0 +1,242 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::model::{Identity, KnownHost, ServerConnection, Snippet};
+
+pub const SERVERS_FILE: &str = "servers.json";
+pub const SNIPPETS_FILE: &str = "snippets.json";
+pub const KNOWN_HOSTS_FILE: &str = "known_hosts.json";
+pub const IDENTITIES_FILE: &str = "identities.json";
+
+pub fn servers_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SERVERS_FILE)
+}
+
+pub fn snippets_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SNIPPETS_FILE)
+}
+
+pub fn known_hosts_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(KNOWN_HOSTS_FILE)
+}
+
+pub fn identities_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(IDENTITIES_FILE)
+}
+
+/// Parses a JSON array, dropping records that no longer match `T` instead of
+/// failing the whole file, so one bad entry does not hide the rest.
+pub fn parse_json_array_lenient<T>(data: &str, label: &str) -> Result<Vec<T>, String>
+where
+    T: DeserializeOwned,
+{
+    match serde_json::from_str::<Vec<T>>(data) {
+        Ok(items) => Ok(items),
+        Err(primary_error) => {
+            let raw_items: Vec<serde_json::Value> = serde_json::from_str(data)
+                .map_err(|e| format!("Failed to parse {} file: {}", label, e))?;
+            let mut parsed = Vec::new();
+            let mut skipped = 0usize;
+            for item in raw_items {
+                match serde_json::from_value::<T>(item) {
+                    Ok(entry) => parsed.push(entry),
+                    Err(_) => skipped += 1,
+                }
+            }
+            if parsed.is_empty() {
+                Err(format!("Failed to parse {} file: {}", label, primary_error))
+            } else {
+                if skipped > 0 {
+                    debug!(
+                        label,
+                        skipped, "Skipped malformed records while loading data"
+                    );
+                }
+                Ok(parsed)
+            }
+        }
+    }
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T, label: &str) -> Result<(), String> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| format!("Invalid path for {} file", label))?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", label, e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {} file: {}", label, e))
+}
+
+/// Loads servers as stored. Entries may still carry legacy plaintext
+/// credentials; see [`crate::secrets::migrate_server_auth`].
+pub fn load_servers(app_dir: &Path) -> Result<Vec<ServerConnection>, String> {
+    let path = servers_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read servers file: {}", e))?;
+    parse_json_array_lenient(&data, "servers")
+}
+
+pub fn save_servers(app_dir: &Path, servers: &[ServerConnection]) -> Result<(), String> {
+    write_json(&servers_path(app_dir), servers, "servers")
+}
+
+pub fn load_snippets(app_dir: &Path) -> Result<Vec<Snippet>, String> {
+    let path = snippets_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read snippets file: {}", e))?;
+    parse_json_array_lenient(&data, "snippets")
+}
+
+pub fn save_snippets(app_dir: &Path, snippets: &[Snippet]) -> Result<(), String> {
+    write_json(&snippets_path(app_dir), snippets, "snippets")
+}
+
+pub fn load_known_hosts(app_dir: &Path) -> Result<Vec<KnownHost>, String> {
+    let path = known_hosts_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read known hosts file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse known hosts file: {}", e))
+}
+
+pub fn save_known_hosts(app_dir: &Path, hosts: &[KnownHost]) -> Result<(), String> {
+    write_json(&known_hosts_path(app_dir), hosts, "known hosts")
+}
+
+pub fn load_identities(app_dir: &Path) -> Result<Vec<Identity>, String> {
+    let path = identities_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read identities file: {}", e))?;
+    parse_json_array_lenient(&data, "identities")
+}
+
+pub fn save_identities(app_dir: &Path, identities: &[Identity]) -> Result<(), String> {
+    write_json(&identities_path(app_dir), identities, "identities")
+}
+
+/// Gives servers that use an identity its credential, and its user if it
+/// has one, in place of their own. A server whose identity is gone keeps
+/// its own. Meant for servers about to connect; the result is not saved.
+pub fn apply_identities(servers: &mut [ServerConnection], identities: &[Identity]) {
+    for server in servers.iter_mut() {
+        let Some(identity_id) = &server.identity_id else {
+            continue;
+        };
+        if let Some(identity) = identities
+            .iter()
+            .find(|identity| &identity.id == identity_id)
+        {
+            server.auth = identity.auth.clone();
+            if let Some(username) = &identity.username {
+                server.user = username.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::AuthMethod;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ssh-thing-core-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_parse_json_array_lenient_skips_bad_records() {
+        let snippets: Vec<Snippet> = parse_json_array_lenient(
+            r#"[
+                {"id": "1", "name": "ok", "command": "ls", "description": null},
+                {"id": "2"}
+            ]"#,
+            "snippets",
+        )
+        .expect("parsed");
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].id, "1");
+
+        assert!(parse_json_array_lenient::<Snippet>(r#"[{"id": "2"}]"#, "snippets").is_err());
+    }
+
+    #[test]
+    fn test_servers_round_trip() {
+        let dir = temp_dir("servers");
+        assert!(load_servers(&dir).expect("empty").is_empty());
+
+        let server: ServerConnection = serde_json::from_value(serde_json::json!({
+            "id": "s1",
+            "host": "example.com",
+            "port": 22,
+            "user": "ops",
+            "auth": {"type": "SecretRef", "secret_id": "server:s1:password"}
+        }))
+        .expect("server");
+        save_servers(&dir, &[server]).expect("saved");
+
+        let loaded = load_servers(&dir).expect("loaded");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].host, "example.com");
+        assert!(matches!(loaded[0].auth, AuthMethod::SecretRef { .. }));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_apply_identities_swaps_in_shared_credential() {
+        let mut servers: Vec<ServerConnection> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "s1", "host": "a.example.com", "port": 22, "user": "ops",
+                "identity_id": "ops",
+                "auth": {"type": "SecretRef", "secret_id": "server:s1:password"}
+            },
+            {
+                "id": "s2", "host": "b.example.com", "port": 22, "user": "ops",
+                "identity_id": "missing",
+                "auth": {"type": "SecretRef", "secret_id": "server:s2:password"}
+            }
+        ]))
+        .expect("servers");
+        let identities = vec![Identity {
+            id: "ops".to_string(),
+            name: "Ops password".to_string(),
+            username: Some("deploy".to_string()),
+            auth: AuthMethod::SecretRef {
+                secret_id: "identity:ops".to_string(),
+                kind: crate::model::SecretKind::Password,
+            },
+            is_default: false,
+        }];
+
+        apply_identities(&mut servers, &identities);
+
+        let secret_ids: Vec<&str> = servers
+            .iter()
+            .map(|server| match &server.auth {
+                AuthMethod::SecretRef { secret_id, .. } => secret_id.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(secret_ids, vec!["identity:ops", "server:s2:password"]);
+        assert_eq!(servers[0].user, "deploy");
+        assert_eq!(servers[1].user, "ops");
+    }
+}
@@ -0,0 +1,434 @@
+//! A step by step record of a connection's handshake, like `ssh -vvv` but
+//! as data: identification strings, the algorithms each side offered and
+//! which were agreed on, the host key and each authentication attempt.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const SSH_MSG_KEXINIT: u8 = 20;
+// Stop looking for the key exchange after this much traffic.
+const MAX_CAPTURE_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Client,
+    Server,
+}
+
+/// The name-lists of a `SSH_MSG_KEXINIT`, most preferred first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KexAlgorithms {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher_client_to_server: Vec<String>,
+    pub cipher_server_to_client: Vec<String>,
+    pub mac_client_to_server: Vec<String>,
+    pub mac_server_to_client: Vec<String>,
+    pub compression_client_to_server: Vec<String>,
+    pub compression_server_to_client: Vec<String>,
+}
+
+/// What both sides agreed on; `None` where they have nothing in common,
+/// which is why such a handshake fails.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NegotiatedAlgorithms {
+    pub kex: Option<String>,
+    pub host_key: Option<String>,
+    pub cipher_client_to_server: Option<String>,
+    pub cipher_server_to_client: Option<String>,
+    pub mac_client_to_server: Option<String>,
+    pub mac_server_to_client: Option<String>,
+    pub compression_client_to_server: Option<String>,
+    pub compression_server_to_client: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum TraceStage {
+    PortKnock,
+    Connecting {
+        host: String,
+        port: u16,
+        ssm: bool,
+    },
+    /// The `SSH-2.0-...` line a side sent.
+    Identification {
+        side: Side,
+        version: String,
+    },
+    KexInit {
+        side: Side,
+        algorithms: KexAlgorithms,
+    },
+    Negotiated {
+        algorithms: NegotiatedAlgorithms,
+    },
+    HostKey {
+        algorithm: String,
+        fingerprint: String,
+        accepted: bool,
+    },
+    /// Text the server shows before authentication.
+    AuthBanner {
+        message: String,
+    },
+    AuthAttempt {
+        method: String,
+        user: String,
+        accepted: bool,
+    },
+    Connected,
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Milliseconds since the connection attempt started.
+    pub elapsed_ms: u64,
+    #[serde(flatten)]
+    pub stage: TraceStage,
+}
+
+#[derive(Debug, Default)]
+enum CaptureState {
+    #[default]
+    Identification,
+    KexInit,
+    Done,
+}
+
+// Bytes one side has sent so far, until its KEXINIT has gone by.
+#[derive(Debug, Default)]
+struct Capture {
+    state: CaptureState,
+    buffer: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    events: Vec<TraceEvent>,
+    client: Capture,
+    server: Capture,
+    client_kex: Option<KexAlgorithms>,
+    server_kex: Option<KexAlgorithms>,
+}
+
+/// Collects the stages of one connection attempt.
+#[derive(Debug)]
+pub struct HandshakeTrace {
+    started: Instant,
+    state: Mutex<TraceState>,
+}
+
+impl Default for HandshakeTrace {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            state: Mutex::new(TraceState::default()),
+        }
+    }
+}
+
+fn name_list(reader: &mut &[u8]) -> Option<Vec<String>> {
+    let data = *reader;
+    let length = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let list = data.get(4..4 + length)?;
+    *reader = &data[4 + length..];
+    Some(
+        String::from_utf8_lossy(list)
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Reads the name-lists out of a KEXINIT payload, message number included.
+fn parse_kexinit(payload: &[u8]) -> Option<KexAlgorithms> {
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        return None;
+    }
+    // Message number and the 16 byte cookie.
+    let mut reader = payload.get(17..)?;
+    Some(KexAlgorithms {
+        kex: name_list(&mut reader)?,
+        host_key: name_list(&mut reader)?,
+        cipher_client_to_server: name_list(&mut reader)?,
+        cipher_server_to_client: name_list(&mut reader)?,
+        mac_client_to_server: name_list(&mut reader)?,
+        mac_server_to_client: name_list(&mut reader)?,
+        compression_client_to_server: name_list(&mut reader)?,
+        compression_server_to_client: name_list(&mut reader)?,
+    })
+}
+
+/// The first of the client's algorithms the server also offers (RFC 4253
+/// section 7.1).
+fn choose(client: &[String], server: &[String]) -> Option<String> {
+    client.iter().find(|name| server.contains(name)).cloned()
+}
+
+fn negotiate(client: &KexAlgorithms, server: &KexAlgorithms) -> NegotiatedAlgorithms {
+    NegotiatedAlgorithms {
+        kex: choose(&client.kex, &server.kex),
+        host_key: choose(&client.host_key, &server.host_key),
+        cipher_client_to_server: choose(
+            &client.cipher_client_to_server,
+            &server.cipher_client_to_server,
+        ),
+        cipher_server_to_client: choose(
+            &client.cipher_server_to_client,
+            &server.cipher_server_to_client,
+        ),
+        mac_client_to_server: choose(&client.mac_client_to_server, &server.mac_client_to_server),
+        mac_server_to_client: choose(&client.mac_server_to_client, &server.mac_server_to_client),
+        compression_client_to_server: choose(
+            &client.compression_client_to_server,
+            &server.compression_client_to_server,
+        ),
+        compression_server_to_client: choose(
+            &client.compression_server_to_client,
+            &server.compression_server_to_client,
+        ),
+    }
+}
+
+impl Capture {
+    /// Adds `bytes` and returns the stages they complete.
+    fn feed(&mut self, side: Side, bytes: &[u8]) -> Vec<TraceStage> {
+        let mut stages = Vec::new();
+        if matches!(self.state, CaptureState::Done) {
+            return stages;
+        }
+        self.buffer.extend_from_slice(bytes);
+        if matches!(self.state, CaptureState::Identification) {
+            // A server may send other lines before its identification.
+            while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                if line.starts_with("SSH-") {
+                    stages.push(TraceStage::Identification {
+                        side,
+                        version: line,
+                    });
+                    self.state = CaptureState::KexInit;
+                    break;
+                }
+            }
+        }
+        if matches!(self.state, CaptureState::KexInit) && self.buffer.len() >= 5 {
+            let length = u32::from_be_bytes([
+                self.buffer[0],
+                self.buffer[1],
+                self.buffer[2],
+                self.buffer[3],
+            ]) as usize;
+            if self.buffer.len() >= 4 + length {
+                let padding = self.buffer[4] as usize;
+                let payload = self
+                    .buffer
+                    .get(5..(4 + length).saturating_sub(padding))
+                    .unwrap_or_default();
+                if let Some(algorithms) = parse_kexinit(payload) {
+                    stages.push(TraceStage::KexInit { side, algorithms });
+                }
+                self.state = CaptureState::Done;
+            }
+        }
+        if matches!(self.state, CaptureState::Done) || self.buffer.len() > MAX_CAPTURE_BYTES {
+            self.state = CaptureState::Done;
+            self.buffer = Vec::new();
+        }
+        stages
+    }
+}
+
+impl HandshakeTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, stage: TraceStage) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        if let Ok(mut state) = self.state.lock() {
+            state.events.push(TraceEvent { elapsed_ms, stage });
+        }
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.state
+            .lock()
+            .map(|state| state.events.clone())
+            .unwrap_or_default()
+    }
+
+    fn observe(&self, side: Side, bytes: &[u8]) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let stages = match side {
+            Side::Client => state.client.feed(side, bytes),
+            Side::Server => state.server.feed(side, bytes),
+        };
+        for stage in stages {
+            if let TraceStage::KexInit { side, algorithms } = &stage {
+                match side {
+                    Side::Client => state.client_kex = Some(algorithms.clone()),
+                    Side::Server => state.server_kex = Some(algorithms.clone()),
+                }
+            }
+            let negotiated = match (&stage, &state.client_kex, &state.server_kex) {
+                (TraceStage::KexInit { .. }, Some(client), Some(server)) => {
+                    Some(negotiate(client, server))
+                }
+                _ => None,
+            };
+            state.events.push(TraceEvent { elapsed_ms, stage });
+            if let Some(algorithms) = negotiated {
+                state.events.push(TraceEvent {
+                    elapsed_ms,
+                    stage: TraceStage::Negotiated { algorithms },
+                });
+            }
+        }
+    }
+}
+
+/// Wraps the stream under a session and feeds the handshake traffic to a
+/// trace. Without a trace it only passes bytes through.
+pub struct TraceStream<S> {
+    inner: S,
+    trace: Option<Arc<HandshakeTrace>>,
+}
+
+impl<S> TraceStream<S> {
+    pub fn new(inner: S, trace: Option<Arc<HandshakeTrace>>) -> Self {
+        Self { inner, trace }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TraceStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Some(trace) = &self.trace {
+            trace.observe(Side::Server, &buf.filled()[before..]);
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TraceStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let (Some(trace), Poll::Ready(Ok(written))) = (&self.trace, &result) {
+            trace.observe(Side::Client, &buf[..*written]);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kexinit_packet(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![SSH_MSG_KEXINIT];
+        payload.extend_from_slice(&[0; 16]);
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.extend_from_slice(&[0; 5]);
+        let padding = 4;
+        let mut packet = ((payload.len() + 1 + padding) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(&payload);
+        packet.extend_from_slice(&[0; 4]);
+        packet
+    }
+
+    #[test]
+    fn test_trace_reads_identification_and_negotiates_split_kexinit() {
+        let trace = HandshakeTrace::new();
+        let client = kexinit_packet([
+            "curve25519-sha256,ext-info-c",
+            "ssh-ed25519,rsa-sha2-256",
+            "chacha20-poly1305@openssh.com,aes256-ctr",
+            "chacha20-poly1305@openssh.com,aes256-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256",
+            "none",
+            "none",
+            "",
+            "",
+        ]);
+        let server = kexinit_packet([
+            "diffie-hellman-group14-sha256,curve25519-sha256",
+            "rsa-sha2-256",
+            "aes128-ctr",
+            "aes256-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256",
+            "none,zlib@openssh.com",
+            "none,zlib@openssh.com",
+            "",
+            "",
+        ]);
+
+        trace.observe(Side::Client, b"SSH-2.0-russh_0.46\r\n");
+        trace.observe(Side::Client, &client);
+        trace.observe(Side::Server, b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n");
+        let (first, rest) = server.split_at(30);
+        trace.observe(Side::Server, first);
+        trace.observe(Side::Server, rest);
+        trace.observe(Side::Server, b"more traffic");
+
+        let stages: Vec<TraceStage> = trace.events().into_iter().map(|e| e.stage).collect();
+        assert_eq!(stages.len(), 5);
+        assert_eq!(
+            stages[2],
+            TraceStage::Identification {
+                side: Side::Server,
+                version: "SSH-2.0-OpenSSH_9.6".to_string(),
+            }
+        );
+        let TraceStage::Negotiated { algorithms } = &stages[4] else {
+            panic!("expected negotiated algorithms, got {:?}", stages[4]);
+        };
+        assert_eq!(algorithms.kex.as_deref(), Some("curve25519-sha256"));
+        assert_eq!(algorithms.host_key.as_deref(), Some("rsa-sha2-256"));
+        assert_eq!(algorithms.cipher_client_to_server, None);
+        assert_eq!(
+            algorithms.cipher_server_to_client.as_deref(),
+            Some("aes256-ctr")
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+const DEFAULT_BROADCAST_ADDRESS: &str = "255.255.255.255";
+const DEFAULT_WOL_PORT: u16 = 9;
+const DEFAULT_WAKE_RETRIES: u32 = 3;
+const DEFAULT_WAKE_WAIT_SECONDS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WakeOnLanConfig {
+    pub mac_address: String,
+    #[serde(default)]
+    pub broadcast_address: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Send a magic packet and keep retrying the connection while the machine
+    /// boots instead of failing on the first attempt.
+    #[serde(default)]
+    pub wake_before_connect: bool,
+    #[serde(default)]
+    pub wait_seconds: Option<u64>,
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl WakeOnLanConfig {
+    pub fn wait_seconds(&self) -> u64 {
+        self.wait_seconds.unwrap_or(DEFAULT_WAKE_WAIT_SECONDS)
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_WAKE_RETRIES)
+    }
+}
+
+pub fn parse_mac_address(mac: &str) -> Result<[u8; 6], String> {
+    let hex: String = mac
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address: {}", mac));
+    }
+
+    let mut bytes = [0u8; 6];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16)
+            .map_err(|_| format!("Invalid MAC address: {}", mac))?;
+    }
+    Ok(bytes)
+}
+
+/// Six 0xFF bytes followed by the MAC address repeated sixteen times.
+pub fn build_magic_packet(mac: [u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(&mac);
+    }
+    packet
+}
+
+pub async fn send_magic_packet(config: &WakeOnLanConfig) -> Result<(), String> {
+    let mac = parse_mac_address(&config.mac_address)?;
+    let target = format!(
+        "{}:{}",
+        config
+            .broadcast_address
+            .as_deref()
+            .unwrap_or(DEFAULT_BROADCAST_ADDRESS),
+        config.port.unwrap_or(DEFAULT_WOL_PORT)
+    );
+
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+    socket
+        .send_to(&build_magic_packet(mac), &target)
+        .await
+        .map_err(|e| format!("Failed to send magic packet to {}: {}", target, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac_address_formats() {
+        let expected = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+
+        assert_eq!(parse_mac_address("00:1A:2B:3C:4D:5E"), Ok(expected));
+        assert_eq!(parse_mac_address("00-1a-2b-3c-4d-5e"), Ok(expected));
+        assert_eq!(parse_mac_address("001a.2b3c.4d5e"), Ok(expected));
+        assert!(parse_mac_address("00:1a:2b:3c:4d").is_err());
+        assert!(parse_mac_address("zz:1a:2b:3c:4d:5e").is_err());
+    }
+
+    #[test]
+    fn test_build_magic_packet() {
+        let mac = [1, 2, 3, 4, 5, 6];
+        let packet = build_magic_packet(mac);
+
+        assert_eq!(packet.len(), 102);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+    }
+}
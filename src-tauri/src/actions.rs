@@ -2,14 +2,16 @@ use russh::ChannelMsg;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
 use tokio::time::{timeout, Duration};
 use tracing::debug;
 
+use crate::event_bus::Emitter;
+use crate::redaction::redactor;
 use crate::{
-    connect_ssh, disconnect_ssh, get_app_dir, load_servers, parse_json_array_lenient,
-    ServerConnection,
+    connect_ssh, disconnect_ssh, get_app_dir, identities, load_servers, parse_json_array_lenient,
+    settings, ServerConnection,
 };
 
 const ACTIONS_FILE: &str = "actions.json";
@@ -48,6 +50,12 @@ pub struct ActionHistoryEntry {
     pub exit_code: Option<u32>,
     #[serde(default)]
     pub output: Option<String>,
+    /// What the command wrote to stderr. Empty when a PTY was allocated,
+    /// since the server then merges both streams into `output`.
+    #[serde(default)]
+    pub stderr: Option<String>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
     #[serde(default)]
     pub error: Option<String>,
 }
@@ -65,7 +73,9 @@ pub struct ActionExecutionEvent {
 #[derive(Debug)]
 struct ActionCommandOutcome {
     output: String,
+    stderr: String,
     exit_code: Option<u32>,
+    duration_ms: u64,
 }
 
 fn unix_timestamp_now() -> Result<u64, String> {
@@ -107,7 +117,7 @@ pub fn save_actions(app_dir: &Path, actions: &[Action]) -> Result<(), String> {
     Ok(())
 }
 
-fn load_action_history(app_dir: &Path) -> Result<Vec<ActionHistoryEntry>, String> {
+pub(crate) fn load_action_history(app_dir: &Path) -> Result<Vec<ActionHistoryEntry>, String> {
     let path = get_action_history_path(app_dir);
     if !path.exists() {
         return Ok(Vec::new());
@@ -131,7 +141,16 @@ fn save_action_history(app_dir: &Path, entries: &[ActionHistoryEntry]) -> Result
     Ok(())
 }
 
-fn append_history_entry(app_dir: &Path, entry: ActionHistoryEntry) -> Result<(), String> {
+fn append_history_entry(app_dir: &Path, mut entry: ActionHistoryEntry) -> Result<(), String> {
+    let redactor = redactor(app_dir);
+    entry.command = redactor.redact(&entry.command);
+    for text in [&mut entry.output, &mut entry.stderr, &mut entry.error]
+        .into_iter()
+        .flatten()
+    {
+        *text = redactor.redact(text);
+    }
+
     let mut entries = load_action_history(app_dir)?;
     entries.push(entry);
     if entries.len() > MAX_HISTORY_ENTRIES {
@@ -201,7 +220,9 @@ fn push_output(target: &mut String, chunk: &str) {
 async fn collect_command_output(
     channel: &mut russh::Channel<russh::client::Msg>,
 ) -> Result<ActionCommandOutcome, String> {
+    let started = Instant::now();
     let mut output = String::new();
+    let mut stderr = String::new();
     let mut exit_code = None;
 
     loop {
@@ -210,10 +231,14 @@ async fn collect_command_output(
         };
 
         match message {
-            ChannelMsg::Data { data } | ChannelMsg::ExtendedData { data, .. } => {
+            ChannelMsg::Data { data } => {
                 let text = String::from_utf8_lossy(data.as_ref());
                 push_output(&mut output, &text);
             }
+            ChannelMsg::ExtendedData { data, .. } => {
+                let text = String::from_utf8_lossy(data.as_ref());
+                push_output(&mut stderr, &text);
+            }
             ChannelMsg::ExitStatus { exit_status } => {
                 exit_code = Some(exit_status);
             }
@@ -238,7 +263,12 @@ async fn collect_command_output(
         }
     }
 
-    Ok(ActionCommandOutcome { output, exit_code })
+    Ok(ActionCommandOutcome {
+        output,
+        stderr,
+        exit_code,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
 }
 
 async fn run_action_command(
@@ -248,17 +278,9 @@ async fn run_action_command(
     width: Option<u32>,
     height: Option<u32>,
 ) -> Result<ActionCommandOutcome, String> {
-    let session = connect_ssh(
-        app,
-        &server.host,
-        server.port,
-        &server.user,
-        &server.auth,
-        server.timeout_seconds,
-        None,
-        None,
-    )
-    .await?;
+    let server = &identities::with_identity(app, server)?;
+    let defaults = settings::connection_defaults(app);
+    let session = connect_ssh(app, server, None).await?;
 
     let action_result = async {
         let mut channel = session
@@ -270,7 +292,15 @@ async fn run_action_command(
         let pty_width = width.unwrap_or(80);
         let pty_height = height.unwrap_or(24);
         if channel
-            .request_pty(false, "xterm-256color", pty_width, pty_height, 0, 0, &[])
+            .request_pty(
+                false,
+                &defaults.term_for(server),
+                pty_width,
+                pty_height,
+                0,
+                0,
+                &[],
+            )
             .await
             .is_err()
         {
@@ -307,7 +337,7 @@ async fn run_action_command(
     }
     .await;
 
-    let _ = disconnect_ssh(app, Some(session), None, None).await;
+    let _ = disconnect_ssh(app, Some(&session), None, None).await;
     action_result
 }
 
@@ -371,7 +401,7 @@ pub async fn get_action_history(
 ) -> Result<Vec<ActionHistoryEntry>, String> {
     let app_dir = get_app_dir(&app)?;
     let mut history = load_action_history(&app_dir)?;
-    history.sort_by(|left, right| right.completed_at.cmp(&left.completed_at));
+    history.sort_by_key(|entry| std::cmp::Reverse(entry.completed_at));
 
     if let Some(target_id) = action_id {
         history.retain(|entry| entry.action_id == target_id);
@@ -426,7 +456,7 @@ pub async fn execute_action(
                     Some(format!("Command exited with status {}", code)),
                 ),
                 None => {
-                    if outcome.output.is_empty() {
+                    if outcome.output.is_empty() && outcome.stderr.is_empty() {
                         ("error", Some("Command produced no output and returned no exit status — the command may have failed to start or the SSH server closed the channel abnormally.".to_string()))
                     } else {
                         (
@@ -452,6 +482,12 @@ pub async fn execute_action(
                 } else {
                     Some(outcome.output)
                 },
+                stderr: if outcome.stderr.is_empty() {
+                    None
+                } else {
+                    Some(outcome.stderr)
+                },
+                duration_ms: Some(outcome.duration_ms),
                 error,
             };
 
@@ -495,6 +531,8 @@ pub async fn execute_action(
                 status: "error".to_string(),
                 exit_code: None,
                 output: None,
+                stderr: None,
+                duration_ms: None,
                 error: Some(error_message.clone()),
             };
 
@@ -559,6 +597,8 @@ mod tests {
             status: "success".to_string(),
             exit_code: Some(0),
             output: Some("done".to_string()),
+            stderr: Some("warning: unit file changed".to_string()),
+            duration_ms: Some(1_250),
             error: None,
         };
 
@@ -569,6 +609,8 @@ mod tests {
         assert_eq!(entry.action_id, deserialized.action_id);
         assert_eq!(entry.exit_code, deserialized.exit_code);
         assert_eq!(entry.output, deserialized.output);
+        assert_eq!(entry.stderr, deserialized.stderr);
+        assert_eq!(entry.duration_ms, deserialized.duration_ms);
     }
 
     #[test]
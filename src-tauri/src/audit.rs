@@ -0,0 +1,426 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use ssh_thing_core::secrets::{KeyringSecretStore, SecretStore};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::{get_app_dir, keyring_service_name};
+
+const AUDIT_FILE: &str = "security-audit.jsonl";
+const GENESIS_HASH: &str = "";
+// Kept in the profile's keyring, out of reach of whoever can edit the log:
+// the key the chain is signed with, and the seq and hash of the last entry
+// written, so cutting entries off the end shows.
+const KEY_SECRET_ID: &str = "audit:chain-key";
+const HEAD_SECRET_ID: &str = "audit:chain-head";
+
+/// Work on the audit log, done in order by one thread so recording never
+/// waits on the disk or keyring and two appends cannot fork the chain.
+enum AuditWork {
+    Record {
+        app_dir: PathBuf,
+        keyring: KeyringSecretStore,
+        event: AuditEvent,
+    },
+    Load {
+        app_dir: PathBuf,
+        keyring: KeyringSecretStore,
+        done: oneshot::Sender<Result<SecurityAudit, String>>,
+    },
+}
+
+static AUDIT_WRITER: OnceLock<mpsc::Sender<AuditWork>> = OnceLock::new();
+
+/// The end of a log as last written, so an append does not re-read it.
+struct Chain {
+    key: String,
+    last: Option<(u64, String)>,
+    file_len: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind")]
+pub enum AuditEvent {
+    HostKeyTrusted {
+        host: String,
+        port: u16,
+        fingerprint: String,
+    },
+    /// Accepted for one connection without being saved.
+    HostKeyAcceptedOnce {
+        host: String,
+        port: u16,
+        fingerprint: String,
+    },
+    HostKeyRejected {
+        host: String,
+        port: u16,
+        fingerprint: String,
+        reason: String,
+    },
+    HostKeyChanged {
+        host: String,
+        port: u16,
+        fingerprint: String,
+        stored_fingerprint: String,
+    },
+    SecretRead {
+        secret_id: String,
+    },
+    AuthFailed {
+        server_id: Option<String>,
+        host: String,
+        port: u16,
+        user: String,
+        error: String,
+    },
+}
+
+/// One line of the audit log. `hash` is keyed and covers the entry and the
+/// previous entry's hash, so editing or removing a line breaks every later
+/// one and the hashes cannot be recomputed without the key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub at: u64,
+    pub event: AuditEvent,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityAudit {
+    pub entries: Vec<AuditEntry>,
+    /// Sequence number of the first entry that does not match the chain.
+    pub tampered_at: Option<u64>,
+}
+
+fn get_audit_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(AUDIT_FILE)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+fn entry_hash(key: &str, seq: u64, at: u64, event: &AuditEvent, prev_hash: &str) -> String {
+    let event = serde_json::to_string(event).unwrap_or_default();
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}\n{}\n{}\n{}", prev_hash, seq, at, event).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn chain_entry(key: &str, previous: Option<(u64, &str)>, at: u64, event: AuditEvent) -> AuditEntry {
+    let seq = previous.map_or(0, |(seq, _)| seq + 1);
+    let prev_hash = previous.map_or(GENESIS_HASH, |(_, hash)| hash).to_string();
+    AuditEntry {
+        seq,
+        at,
+        hash: entry_hash(key, seq, at, &event, &prev_hash),
+        event,
+        prev_hash,
+    }
+}
+
+fn find_tampering(key: &str, entries: &[AuditEntry], head: Option<&(u64, String)>) -> Option<u64> {
+    let mut prev_hash = GENESIS_HASH;
+    for (index, entry) in entries.iter().enumerate() {
+        let intact = entry.seq == index as u64
+            && entry.prev_hash == prev_hash
+            && entry.hash == entry_hash(key, entry.seq, entry.at, &entry.event, &entry.prev_hash);
+        if !intact {
+            return Some(index as u64);
+        }
+        prev_hash = &entry.hash;
+    }
+    // Entries cut off the end leave an intact chain that stops short of the
+    // last entry written.
+    let (seq, hash) = head?;
+    match entries.get(*seq as usize) {
+        Some(entry) if entry.hash == *hash => None,
+        _ => Some((*seq).min(entries.len() as u64)),
+    }
+}
+
+fn parse_head(head: &str) -> Option<(u64, String)> {
+    let (seq, hash) = head.split_once(':')?;
+    Some((seq.parse().ok()?, hash.to_string()))
+}
+
+// Two v4 UUIDs give 244 bits from the OS random generator.
+fn new_key() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+// Lines that do not parse are kept as a break in the chain rather than
+// skipped, so truncating an entry cannot hide it.
+fn load_entries(app_dir: &Path) -> Result<(Vec<AuditEntry>, Option<u64>), String> {
+    let path = get_audit_path(app_dir);
+    if !path.exists() {
+        return Ok((Vec::new(), None));
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    let mut entries = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => {
+                let unreadable_at = entries.len() as u64;
+                return Ok((entries, Some(unreadable_at)));
+            }
+        }
+    }
+    Ok((entries, None))
+}
+
+// A log with entries but no key in the keyring was written before the chain
+// was keyed, or its key is gone; either way it cannot be vouched for, so it
+// is moved aside and a new chain started.
+fn open_chain(app_dir: &Path, keyring: &dyn SecretStore) -> Result<Chain, String> {
+    let path = get_audit_path(app_dir);
+    let key = match keyring.get(KEY_SECRET_ID) {
+        Ok(key) => key,
+        Err(_) => {
+            if file_len(&path) > 0 {
+                let aside = app_dir.join(format!("security-audit.unkeyed-{}.jsonl", now_secs()));
+                fs::rename(&path, &aside)
+                    .map_err(|e| format!("Failed to set aside unkeyed audit log: {}", e))?;
+            }
+            let key = new_key();
+            keyring.put(KEY_SECRET_ID, &key)?;
+            let _ = keyring.delete(HEAD_SECRET_ID);
+            return Ok(Chain {
+                key,
+                last: None,
+                file_len: 0,
+            });
+        }
+    };
+    // Numbering continues from the keyring's head rather than the file, so
+    // a truncated log stays broken instead of being written over.
+    let last = match keyring
+        .get(HEAD_SECRET_ID)
+        .ok()
+        .and_then(|head| parse_head(&head))
+    {
+        Some(head) => Some(head),
+        None => load_entries(app_dir)?
+            .0
+            .last()
+            .map(|entry| (entry.seq, entry.hash.clone())),
+    };
+    Ok(Chain {
+        key,
+        last,
+        file_len: file_len(&path),
+    })
+}
+
+fn append(
+    chains: &mut HashMap<PathBuf, Chain>,
+    app_dir: &Path,
+    keyring: &dyn SecretStore,
+    event: AuditEvent,
+) -> Result<(), String> {
+    let path = get_audit_path(app_dir);
+    // Reopened when the file changed under us, e.g. a profile was deleted.
+    let current = chains
+        .get(app_dir)
+        .is_some_and(|chain| chain.file_len == file_len(&path));
+    if !current {
+        chains.insert(app_dir.to_path_buf(), open_chain(app_dir, keyring)?);
+    }
+    let chain = chains
+        .get_mut(app_dir)
+        .ok_or_else(|| "Audit log is unavailable".to_string())?;
+
+    let previous = chain.last.as_ref().map(|(seq, hash)| (*seq, hash.as_str()));
+    let entry = chain_entry(&chain.key, previous, now_secs(), event);
+    let line = serde_json::to_string(&entry)
+        .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
+
+    chain.file_len = file_len(&path);
+    if let Err(e) = keyring.put(HEAD_SECRET_ID, &format!("{}:{}", entry.seq, entry.hash)) {
+        debug!(error = %e, "Failed to save audit chain head");
+    }
+    chain.last = Some((entry.seq, entry.hash));
+    Ok(())
+}
+
+fn audit_report(app_dir: &Path, keyring: &dyn SecretStore) -> Result<SecurityAudit, String> {
+    let (entries, unreadable_at) = load_entries(app_dir)?;
+    let head = keyring
+        .get(HEAD_SECRET_ID)
+        .ok()
+        .and_then(|head| parse_head(&head));
+    let tampered_at = match keyring.get(KEY_SECRET_ID) {
+        Ok(key) => find_tampering(&key, &entries, head.as_ref()).or(unreadable_at),
+        // Without its key the chain cannot be checked at all.
+        Err(_) if entries.is_empty() => unreadable_at,
+        Err(_) => Some(0),
+    };
+    Ok(SecurityAudit {
+        entries,
+        tampered_at,
+    })
+}
+
+fn audit_writer() -> &'static mpsc::Sender<AuditWork> {
+    AUDIT_WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut chains = HashMap::new();
+            for work in rx {
+                match work {
+                    AuditWork::Record {
+                        app_dir,
+                        keyring,
+                        event,
+                    } => {
+                        if let Err(e) = append(&mut chains, &app_dir, &keyring, event) {
+                            debug!(error = %e, "Failed to record audit event");
+                        }
+                    }
+                    AuditWork::Load {
+                        app_dir,
+                        keyring,
+                        done,
+                    } => {
+                        let _ = done.send(audit_report(&app_dir, &keyring));
+                    }
+                }
+            }
+        });
+        tx
+    })
+}
+
+fn profile_keyring(app: &AppHandle) -> KeyringSecretStore {
+    KeyringSecretStore::new(keyring_service_name(app))
+}
+
+/// Queues `event` for the audit log of the profile whose directory and
+/// keyring are given. Failures are logged and otherwise ignored so auditing
+/// never blocks a connection.
+pub fn record_in(app_dir: &Path, keyring: &KeyringSecretStore, event: AuditEvent) {
+    let work = AuditWork::Record {
+        app_dir: app_dir.to_path_buf(),
+        keyring: keyring.clone(),
+        event,
+    };
+    if audit_writer().send(work).is_err() {
+        debug!("Audit writer is not running");
+    }
+}
+
+pub fn record(app: &AppHandle, event: AuditEvent) {
+    match get_app_dir(app) {
+        Ok(app_dir) => record_in(&app_dir, &profile_keyring(app), event),
+        Err(e) => debug!(error = %e, "Failed to record audit event"),
+    }
+}
+
+/// Removes a deleted profile's chain key and head from its keyring.
+pub fn forget_keyring_state(keyring: &KeyringSecretStore) {
+    let _ = keyring.delete(KEY_SECRET_ID);
+    let _ = keyring.delete(HEAD_SECRET_ID);
+}
+
+pub fn is_auth_failure(error: &str) -> bool {
+    error.to_ascii_lowercase().contains("authentication failed")
+}
+
+#[tauri::command]
+pub async fn get_security_audit(app: AppHandle) -> Result<SecurityAudit, String> {
+    let (done, report) = oneshot::channel();
+    audit_writer()
+        .send(AuditWork::Load {
+            app_dir: get_app_dir(&app)?,
+            keyring: profile_keyring(&app),
+            done,
+        })
+        .map_err(|_| "Audit log is unavailable".to_string())?;
+    report
+        .await
+        .map_err(|_| "Audit log is unavailable".to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_tampering_detects_edited_entry() {
+        let key = "test-key";
+        let first = chain_entry(
+            key,
+            None,
+            10,
+            AuditEvent::SecretRead {
+                secret_id: "server:s1:password".to_string(),
+            },
+        );
+        let second = chain_entry(
+            key,
+            Some((first.seq, &first.hash)),
+            20,
+            AuditEvent::HostKeyTrusted {
+                host: "example.com".to_string(),
+                port: 22,
+                fingerprint: "SHA256:abc".to_string(),
+            },
+        );
+        let head = (second.seq, second.hash.clone());
+        let mut entries = vec![first, second];
+        assert_eq!(find_tampering(key, &entries, Some(&head)), None);
+        assert_eq!(find_tampering("other-key", &entries, Some(&head)), Some(0));
+
+        // Cutting the last entry leaves an intact chain that falls short of
+        // the head kept in the keyring.
+        let last = entries.pop().unwrap();
+        assert_eq!(find_tampering(key, &entries, None), None);
+        assert_eq!(find_tampering(key, &entries, Some(&head)), Some(1));
+        entries.push(last);
+
+        entries[0].at = 11;
+        assert_eq!(find_tampering(key, &entries, Some(&head)), Some(0));
+        entries.remove(0);
+        assert_eq!(find_tampering(key, &entries, Some(&head)), Some(0));
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+use crate::{
+    delete_secret, get_app_dir, identities, load_servers, save_servers, CompressionMode,
+    ReconnectPolicy, ServerConnection,
+};
+
+/// Changes applied to every server in `ids`. Unset fields are left alone; an
+/// empty `group`, `term` or `shell` clears it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerPatch {
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub term: Option<String>,
+    #[serde(default)]
+    pub shell: Option<String>,
+    #[serde(default)]
+    pub keepalive_seconds: Option<u64>,
+    #[serde(default)]
+    pub reconnect: Option<ReconnectPolicy>,
+    #[serde(default)]
+    pub compression: Option<CompressionMode>,
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl ServerPatch {
+    fn apply(&self, server: &mut ServerConnection) {
+        if let Some(user) = &self.user {
+            server.user = user.clone();
+        }
+        if let Some(port) = self.port {
+            server.port = port;
+        }
+        if let Some(group) = &self.group {
+            server.group = non_empty(group);
+        }
+        server.tags.retain(|tag| !self.remove_tags.contains(tag));
+        for tag in &self.add_tags {
+            if !server.tags.contains(tag) {
+                server.tags.push(tag.clone());
+            }
+        }
+        if let Some(timeout_seconds) = self.timeout_seconds {
+            server.timeout_seconds = Some(timeout_seconds);
+        }
+        if let Some(term) = &self.term {
+            server.term = non_empty(term);
+        }
+        if let Some(shell) = &self.shell {
+            server.shell = non_empty(shell);
+        }
+        if let Some(keepalive_seconds) = self.keepalive_seconds {
+            server.keepalive_seconds = Some(keepalive_seconds);
+        }
+        if let Some(reconnect) = self.reconnect {
+            server.reconnect = Some(reconnect);
+        }
+        if let Some(compression) = self.compression {
+            server.compression = compression;
+        }
+    }
+}
+
+// Checked up front so a typo in one id does not leave the batch half done.
+fn ensure_all_exist(servers: &[ServerConnection], ids: &[String]) -> Result<(), String> {
+    let missing: Vec<&str> = ids
+        .iter()
+        .filter(|id| !servers.iter().any(|server| &server.id == *id))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Servers not found: {}", missing.join(", ")))
+    }
+}
+
+#[tauri::command]
+pub async fn delete_servers(
+    app: AppHandle,
+    ids: Vec<String>,
+) -> Result<Vec<ServerConnection>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut servers = load_servers(&app_dir, &app)?;
+    ensure_all_exist(&servers, &ids)?;
+
+    let ids: HashSet<&String> = ids.iter().collect();
+    servers.retain(|server| {
+        if !ids.contains(&server.id) {
+            return true;
+        }
+        if let Some(secret_id) = identities::own_secret_id(server) {
+            let _ = delete_secret(&app, secret_id);
+        }
+        false
+    });
+    save_servers(&app_dir, &servers)?;
+    Ok(servers)
+}
+
+#[tauri::command]
+pub async fn move_servers_to_group(
+    app: AppHandle,
+    ids: Vec<String>,
+    group: Option<String>,
+) -> Result<Vec<ServerConnection>, String> {
+    update_servers_bulk(
+        app,
+        ServerPatch {
+            ids,
+            group: Some(group.unwrap_or_default()),
+            ..ServerPatch::default()
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn update_servers_bulk(
+    app: AppHandle,
+    patch: ServerPatch,
+) -> Result<Vec<ServerConnection>, String> {
+    if patch.port == Some(0) {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+
+    let app_dir = get_app_dir(&app)?;
+    let mut servers = load_servers(&app_dir, &app)?;
+    ensure_all_exist(&servers, &patch.ids)?;
+
+    for server in servers
+        .iter_mut()
+        .filter(|server| patch.ids.contains(&server.id))
+    {
+        patch.apply(server);
+    }
+    save_servers(&app_dir, &servers)?;
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuthMethod;
+
+    fn server(tags: &[&str]) -> ServerConnection {
+        ServerConnection {
+            id: "server-1".to_string(),
+            nickname: None,
+            host: "example.com".to_string(),
+            port: 22,
+            user: "root".to_string(),
+            timeout_seconds: None,
+            last_connected_at: None,
+            wake_on_lan: None,
+            port_knock: None,
+            cloud_source: None,
+            ssm: None,
+            bind_tailnet: false,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            group: Some("staging".to_string()),
+            term: None,
+            shell: None,
+            elevate: None,
+            keepalive_seconds: None,
+            reconnect: None,
+            compression: CompressionMode::Auto,
+            notes: None,
+            color: None,
+            icon: None,
+            environment: None,
+            credential_expires_at: None,
+            pinned_fingerprints: Vec::new(),
+            forward_profiles: Vec::new(),
+            identity_id: None,
+            max_sessions: None,
+            max_shells: None,
+            auth: AuthMethod::Password {
+                password: "pass".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_patch_only_touches_set_fields() {
+        let mut target = server(&["web", "old"]);
+        let patch = ServerPatch {
+            ids: vec!["server-1".to_string()],
+            user: Some("deploy".to_string()),
+            group: Some(" ".to_string()),
+            add_tags: vec!["web".to_string(), "prod".to_string()],
+            remove_tags: vec!["old".to_string()],
+            ..ServerPatch::default()
+        };
+
+        patch.apply(&mut target);
+
+        assert_eq!(target.user, "deploy");
+        assert_eq!(target.port, 22);
+        assert_eq!(target.group, None);
+        assert_eq!(target.tags, vec!["web".to_string(), "prod".to_string()]);
+    }
+}
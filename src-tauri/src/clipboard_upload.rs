@@ -0,0 +1,47 @@
+use arboard::Clipboard;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::sftp::{run_upload_paths, DirectoryTransferResult, TransferError};
+use crate::AppState;
+
+fn clipboard_file_paths() -> Result<Vec<PathBuf>, String> {
+    Clipboard::new()
+        .map_err(|e| format!("Failed to initialize clipboard: {}", e))?
+        .get()
+        .file_list()
+        .map_err(|e| format!("Failed to read files from clipboard: {}", e))
+}
+
+/// Uploads files into the directory the shell last reported through OSC 7,
+/// with progress as `transfer-progress`. Without `paths`, the files copied
+/// to the OS clipboard are used; dropped files pass their paths instead.
+#[tauri::command]
+pub async fn paste_clipboard_files(
+    app: AppHandle,
+    shell_id: String,
+    paths: Option<Vec<String>>,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let (server_id, cwd) = {
+        let state = app.state::<AppState>();
+        let shells = state.shells.lock().await;
+        let shell = shells
+            .get(&shell_id)
+            .ok_or_else(|| format!("Shell with id {} not found", shell_id))?;
+        let cwd = shell.cwd.clone().ok_or_else(|| {
+            "The shell has not reported its working directory (OSC 7)".to_string()
+        })?;
+        (shell.server_id.clone(), cwd)
+    };
+
+    let paths = match paths {
+        Some(paths) => paths.into_iter().map(PathBuf::from).collect(),
+        None => clipboard_file_paths()?,
+    };
+    if paths.is_empty() {
+        return Err("There are no files to upload".to_string().into());
+    }
+
+    let transfer_id = uuid::Uuid::new_v4().to_string();
+    run_upload_paths(&app, &server_id, &transfer_id, &paths, &cwd).await
+}
@@ -0,0 +1,840 @@
+use aws_sdk_ec2::types::{Filter, Instance};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::debug;
+
+use crate::tailscale::TAILSCALE_PROVIDER;
+use crate::{
+    dedupe, delete_secret, get_app_dir, get_secret, load_servers, put_secret, save_servers,
+    AuthMethod, CompressionMode, SecretKind, ServerConnection,
+};
+
+pub use ssh_thing_core::CloudSource;
+
+const EC2_PROVIDER: &str = "aws-ec2";
+const DEFAULT_EC2_USER: &str = "ec2-user";
+pub(crate) const DEFAULT_SSH_PORT: u16 = 22;
+const DIGITALOCEAN_PROVIDER: &str = "digitalocean";
+const DIGITALOCEAN_API: &str = "https://api.digitalocean.com/v2/droplets";
+const DIGITALOCEAN_PAGE_SIZE: u32 = 200;
+const HETZNER_PROVIDER: &str = "hetzner";
+const HETZNER_API: &str = "https://api.hetzner.cloud/v1/servers";
+const HETZNER_PAGE_SIZE: u32 = 50;
+// Droplets and Hetzner servers come up with root login unless the image says otherwise.
+const DEFAULT_ROOT_USER: &str = "root";
+// Instances can override the login user with one of these tags.
+const SSH_USER_TAGS: [&str; 2] = ["ssh-user", "SshUser"];
+
+fn default_ssh_port() -> u16 {
+    DEFAULT_SSH_PORT
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiscoveredServer {
+    pub source: CloudSource,
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub user: String,
+    /// Secret id the server's private key is expected under. Instances that
+    /// share a key pair share the secret.
+    #[serde(default)]
+    pub key_secret_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ec2DiscoveryOptions {
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Empty means the region configured for the profile.
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub use_private_ip: bool,
+    #[serde(default)]
+    pub default_user: Option<String>,
+}
+
+/// Options shared by the token-authenticated provider APIs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloudApiOptions {
+    #[serde(default)]
+    pub use_private_ip: bool,
+    #[serde(default)]
+    pub default_user: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CloudSyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudSyncResult {
+    pub summary: CloudSyncSummary,
+    pub servers: Vec<ServerConnection>,
+}
+
+fn ec2_tag<'a>(instance: &'a Instance, key: &str) -> Option<&'a str> {
+    instance
+        .tags()
+        .iter()
+        .find(|tag| tag.key() == Some(key))
+        .and_then(|tag| tag.value())
+        .filter(|value| !value.is_empty())
+}
+
+fn map_ec2_instance(
+    instance: &Instance,
+    region: Option<&str>,
+    options: &Ec2DiscoveryOptions,
+) -> Option<DiscoveredServer> {
+    let instance_id = instance.instance_id()?;
+    let public_host = instance
+        .public_ip_address()
+        .or(instance.public_dns_name())
+        .filter(|host| !host.is_empty());
+    let private_host = instance.private_ip_address();
+    let host = if options.use_private_ip {
+        private_host.or(public_host)
+    } else {
+        public_host.or(private_host)
+    }?;
+
+    let user = SSH_USER_TAGS
+        .iter()
+        .find_map(|key| ec2_tag(instance, key))
+        .or(options.default_user.as_deref())
+        .unwrap_or(DEFAULT_EC2_USER);
+
+    Some(DiscoveredServer {
+        source: CloudSource {
+            provider: EC2_PROVIDER.to_string(),
+            resource_id: instance_id.to_string(),
+            region: region.map(|r| r.to_string()),
+        },
+        name: ec2_tag(instance, "Name").unwrap_or(instance_id).to_string(),
+        host: host.to_string(),
+        port: DEFAULT_SSH_PORT,
+        user: user.to_string(),
+        key_secret_id: instance.key_name().map(|key| format!("ec2-key:{}", key)),
+    })
+}
+
+/// Instances running in `region`, or the profile's region, along with the
+/// name of the region that was listed.
+async fn discover_ec2_region(
+    options: &Ec2DiscoveryOptions,
+    region: Option<&str>,
+) -> Result<(Vec<DiscoveredServer>, Option<String>), String> {
+    let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+    if let Some(profile) = &options.profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = region {
+        loader = loader.region(aws_config::Region::new(region.to_string()));
+    }
+    let config = loader.load().await;
+    let region_name = config.region().map(|r| r.to_string());
+    let client = aws_sdk_ec2::Client::new(&config);
+
+    let running = Filter::builder()
+        .name("instance-state-name")
+        .values("running")
+        .build();
+    let mut pages = client
+        .describe_instances()
+        .filters(running)
+        .into_paginator()
+        .send();
+
+    let mut discovered = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|e| {
+            format!(
+                "Failed to list EC2 instances in {}: {}",
+                region_name.as_deref().unwrap_or("default region"),
+                aws_sdk_ec2::error::DisplayErrorContext(e)
+            )
+        })?;
+        for reservation in page.reservations() {
+            discovered.extend(reservation.instances().iter().filter_map(|instance| {
+                map_ec2_instance(instance, region_name.as_deref(), options)
+            }));
+        }
+    }
+    Ok((discovered, region_name))
+}
+
+/// Instances in every requested region, and the regions that were listed.
+async fn discover_ec2(
+    options: &Ec2DiscoveryOptions,
+) -> Result<(Vec<DiscoveredServer>, Vec<String>), String> {
+    let requested: Vec<Option<&str>> = if options.regions.is_empty() {
+        vec![None]
+    } else {
+        options
+            .regions
+            .iter()
+            .map(|region| Some(region.as_str()))
+            .collect()
+    };
+
+    let mut discovered = Vec::new();
+    let mut regions = Vec::new();
+    for region in requested {
+        let (servers, region_name) = discover_ec2_region(options, region).await?;
+        discovered.extend(servers);
+        regions.extend(region_name);
+    }
+    Ok((discovered, regions))
+}
+
+#[derive(Debug, Deserialize)]
+struct DigitalOceanPage {
+    #[serde(default)]
+    droplets: Vec<Droplet>,
+    #[serde(default)]
+    links: DigitalOceanLinks,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DigitalOceanLinks {
+    #[serde(default)]
+    pages: DigitalOceanPages,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DigitalOceanPages {
+    #[serde(default)]
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Droplet {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    networks: DropletNetworks,
+    #[serde(default)]
+    region: Option<DropletRegion>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DropletNetworks {
+    #[serde(default)]
+    v4: Vec<DropletAddress>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DropletAddress {
+    ip_address: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DropletRegion {
+    slug: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerPage {
+    #[serde(default)]
+    servers: Vec<HetznerServer>,
+    #[serde(default)]
+    meta: HetznerMeta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HetznerMeta {
+    #[serde(default)]
+    pagination: HetznerPagination,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HetznerPagination {
+    #[serde(default)]
+    next_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerServer {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    public_net: HetznerPublicNet,
+    #[serde(default)]
+    private_net: Vec<HetznerPrivateNet>,
+    #[serde(default)]
+    datacenter: Option<HetznerDatacenter>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HetznerPublicNet {
+    #[serde(default)]
+    ipv4: Option<HetznerIp>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerIp {
+    ip: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerPrivateNet {
+    ip: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerDatacenter {
+    location: HetznerLocation,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerLocation {
+    name: String,
+}
+
+fn pick_host<'a>(
+    public_host: Option<&'a str>,
+    private_host: Option<&'a str>,
+    use_private_ip: bool,
+) -> Option<&'a str> {
+    let public_host = public_host.filter(|host| !host.is_empty());
+    let private_host = private_host.filter(|host| !host.is_empty());
+    if use_private_ip {
+        private_host.or(public_host)
+    } else {
+        public_host.or(private_host)
+    }
+}
+
+fn api_discovered(
+    provider: &str,
+    id: u64,
+    name: String,
+    host: &str,
+    region: Option<String>,
+    options: &CloudApiOptions,
+) -> DiscoveredServer {
+    DiscoveredServer {
+        source: CloudSource {
+            provider: provider.to_string(),
+            resource_id: id.to_string(),
+            region,
+        },
+        name,
+        host: host.to_string(),
+        port: DEFAULT_SSH_PORT,
+        user: options
+            .default_user
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ROOT_USER.to_string()),
+        key_secret_id: None,
+    }
+}
+
+fn map_droplet(droplet: &Droplet, options: &CloudApiOptions) -> Option<DiscoveredServer> {
+    let address = |kind: &str| {
+        droplet
+            .networks
+            .v4
+            .iter()
+            .find(|address| address.kind == kind)
+            .map(|address| address.ip_address.as_str())
+    };
+    let host = pick_host(
+        address("public"),
+        address("private"),
+        options.use_private_ip,
+    )?;
+    Some(api_discovered(
+        DIGITALOCEAN_PROVIDER,
+        droplet.id,
+        droplet.name.clone(),
+        host,
+        droplet.region.as_ref().map(|region| region.slug.clone()),
+        options,
+    ))
+}
+
+fn map_hetzner_server(
+    server: &HetznerServer,
+    options: &CloudApiOptions,
+) -> Option<DiscoveredServer> {
+    let host = pick_host(
+        server.public_net.ipv4.as_ref().map(|ipv4| ipv4.ip.as_str()),
+        server.private_net.first().map(|net| net.ip.as_str()),
+        options.use_private_ip,
+    )?;
+    Some(api_discovered(
+        HETZNER_PROVIDER,
+        server.id,
+        server.name.clone(),
+        host,
+        server
+            .datacenter
+            .as_ref()
+            .map(|datacenter| datacenter.location.name.clone()),
+        options,
+    ))
+}
+
+fn api_token_secret_id(provider: &str) -> String {
+    format!("cloud:{}:api_token", provider)
+}
+
+fn check_api_provider(provider: &str) -> Result<(), String> {
+    match provider {
+        DIGITALOCEAN_PROVIDER | HETZNER_PROVIDER => Ok(()),
+        other => Err(format!("Unknown cloud provider: {}", other)),
+    }
+}
+
+fn api_token(app: &AppHandle, provider: &str) -> Result<String, String> {
+    get_secret(app, &api_token_secret_id(provider))
+        .map_err(|e| format!("No API token stored for {}: {}", provider, e))
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    token: &str,
+    provider: &str,
+) -> Result<T, String> {
+    let response = client
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {} API: {}", provider, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} API returned {}", provider, status));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse {} response: {}", provider, e))
+}
+
+async fn discover_digitalocean(
+    token: &str,
+    options: &CloudApiOptions,
+) -> Result<Vec<DiscoveredServer>, String> {
+    let client = reqwest::Client::new();
+    let mut url = Some(format!(
+        "{}?per_page={}",
+        DIGITALOCEAN_API, DIGITALOCEAN_PAGE_SIZE
+    ));
+    let mut discovered = Vec::new();
+    while let Some(next) = url {
+        let page: DigitalOceanPage =
+            fetch_json(&client, &next, token, DIGITALOCEAN_PROVIDER).await?;
+        discovered.extend(
+            page.droplets
+                .iter()
+                .filter_map(|droplet| map_droplet(droplet, options)),
+        );
+        url = page.links.pages.next;
+    }
+    Ok(discovered)
+}
+
+async fn discover_hetzner(
+    token: &str,
+    options: &CloudApiOptions,
+) -> Result<Vec<DiscoveredServer>, String> {
+    let client = reqwest::Client::new();
+    let mut page_number = Some(1);
+    let mut discovered = Vec::new();
+    while let Some(number) = page_number {
+        let url = format!(
+            "{}?page={}&per_page={}",
+            HETZNER_API, number, HETZNER_PAGE_SIZE
+        );
+        let page: HetznerPage = fetch_json(&client, &url, token, HETZNER_PROVIDER).await?;
+        discovered.extend(
+            page.servers
+                .iter()
+                .filter_map(|server| map_hetzner_server(server, options)),
+        );
+        page_number = page.meta.pagination.next_page;
+    }
+    Ok(discovered)
+}
+
+async fn discover_with_api(
+    app: &AppHandle,
+    provider: &str,
+    options: &CloudApiOptions,
+) -> Result<Vec<DiscoveredServer>, String> {
+    check_api_provider(provider)?;
+    let token = api_token(app, provider)?;
+    match provider {
+        DIGITALOCEAN_PROVIDER => discover_digitalocean(&token, options).await,
+        _ => discover_hetzner(&token, options).await,
+    }
+}
+
+fn new_server_from(discovered: &DiscoveredServer) -> ServerConnection {
+    let auth = AuthMethod::SecretRef {
+        secret_id: discovered.key_secret_id.clone().unwrap_or_else(|| {
+            format!(
+                "{}:{}",
+                discovered.source.provider, discovered.source.resource_id
+            )
+        }),
+        kind: if discovered.key_secret_id.is_some() {
+            SecretKind::PrivateKey
+        } else {
+            SecretKind::Password
+        },
+    };
+    ServerConnection {
+        id: uuid::Uuid::new_v4().to_string(),
+        nickname: Some(discovered.name.clone()),
+        host: discovered.host.clone(),
+        port: discovered.port,
+        user: discovered.user.clone(),
+        timeout_seconds: None,
+        last_connected_at: None,
+        wake_on_lan: None,
+        port_knock: None,
+        cloud_source: Some(discovered.source.clone()),
+        ssm: None,
+        // Tailnet peers are only reachable through the tailnet interface.
+        bind_tailnet: discovered.source.provider == TAILSCALE_PROVIDER,
+        tags: Vec::new(),
+        group: None,
+        term: None,
+        shell: None,
+        elevate: None,
+        keepalive_seconds: None,
+        reconnect: None,
+        compression: CompressionMode::Auto,
+        notes: None,
+        color: None,
+        icon: None,
+        environment: None,
+        credential_expires_at: None,
+        pinned_fingerprints: Vec::new(),
+        forward_profiles: Vec::new(),
+        identity_id: None,
+        max_sessions: None,
+        max_shells: None,
+        auth,
+    }
+}
+
+/// Applies a provider's discovery results to the saved servers. Existing
+/// entries keep their user, port and credentials; only the name and address
+/// follow the provider. With `regions`, only servers in those regions were
+/// listed, so only they can be removed as missing.
+pub fn merge_discovered(
+    servers: &mut Vec<ServerConnection>,
+    provider: &str,
+    discovered: &[DiscoveredServer],
+    remove_missing: bool,
+    regions: Option<&[String]>,
+) -> CloudSyncSummary {
+    let mut summary = CloudSyncSummary::default();
+
+    for item in discovered {
+        let existing = servers.iter_mut().find(|server| {
+            server.cloud_source.as_ref().is_some_and(|source| {
+                source.provider == provider && source.resource_id == item.source.resource_id
+            })
+        });
+        match existing {
+            Some(server) => {
+                let nickname = Some(item.name.clone());
+                if server.host != item.host || server.nickname != nickname {
+                    server.host = item.host.clone();
+                    server.nickname = nickname;
+                    summary.updated += 1;
+                }
+                server.cloud_source = Some(item.source.clone());
+            }
+            None => {
+                let server = new_server_from(item);
+                // A server added by hand before the provider was synced is
+                // adopted rather than listed twice.
+                let adoptable = dedupe::find_duplicate(servers, &server)
+                    .filter(|index| servers[*index].cloud_source.is_none());
+                match adoptable {
+                    Some(index) => {
+                        dedupe::merge_server(&mut servers[index], &server);
+                        summary.updated += 1;
+                    }
+                    None => {
+                        servers.push(server);
+                        summary.added += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    if remove_missing {
+        let before = servers.len();
+        let listed = |source: &CloudSource| {
+            regions.is_none_or(|regions| {
+                source
+                    .region
+                    .as_ref()
+                    .is_some_and(|region| regions.contains(region))
+            })
+        };
+        servers.retain(|server| match &server.cloud_source {
+            Some(source) if source.provider == provider && listed(source) => discovered
+                .iter()
+                .any(|item| item.source.resource_id == source.resource_id),
+            _ => true,
+        });
+        summary.removed = before - servers.len();
+    }
+
+    summary
+}
+
+pub(crate) fn apply_discovery(
+    app: &AppHandle,
+    provider: &str,
+    discovered: &[DiscoveredServer],
+    remove_missing: bool,
+    regions: Option<&[String]>,
+) -> Result<CloudSyncResult, String> {
+    let app_dir = get_app_dir(app)?;
+    let mut servers = load_servers(&app_dir, app)?;
+    let summary = merge_discovered(&mut servers, provider, discovered, remove_missing, regions);
+    save_servers(&app_dir, &servers)?;
+    debug!(
+        provider,
+        added = summary.added,
+        updated = summary.updated,
+        removed = summary.removed,
+        "Cloud servers synced"
+    );
+    Ok(CloudSyncResult { summary, servers })
+}
+
+#[tauri::command]
+pub async fn discover_ec2_instances(
+    options: Option<Ec2DiscoveryOptions>,
+) -> Result<Vec<DiscoveredServer>, String> {
+    Ok(discover_ec2(&options.unwrap_or_default()).await?.0)
+}
+
+#[tauri::command]
+pub async fn sync_ec2_servers(
+    app: AppHandle,
+    options: Option<Ec2DiscoveryOptions>,
+    remove_missing: Option<bool>,
+) -> Result<CloudSyncResult, String> {
+    let (discovered, regions) = discover_ec2(&options.unwrap_or_default()).await?;
+    apply_discovery(
+        &app,
+        EC2_PROVIDER,
+        &discovered,
+        remove_missing.unwrap_or(false),
+        Some(&regions),
+    )
+}
+
+/// Stores the API token for `digitalocean` or `hetzner` in the keyring.
+#[tauri::command]
+pub async fn set_cloud_api_token(
+    app: AppHandle,
+    provider: String,
+    token: String,
+) -> Result<(), String> {
+    check_api_provider(&provider)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err("API token cannot be empty".to_string());
+    }
+    put_secret(&app, &api_token_secret_id(&provider), token)
+}
+
+#[tauri::command]
+pub async fn delete_cloud_api_token(app: AppHandle, provider: String) -> Result<(), String> {
+    check_api_provider(&provider)?;
+    delete_secret(&app, &api_token_secret_id(&provider))
+}
+
+#[tauri::command]
+pub async fn discover_cloud_servers(
+    app: AppHandle,
+    provider: String,
+    options: Option<CloudApiOptions>,
+) -> Result<Vec<DiscoveredServer>, String> {
+    discover_with_api(&app, &provider, &options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn sync_cloud_servers(
+    app: AppHandle,
+    provider: String,
+    options: Option<CloudApiOptions>,
+    remove_missing: Option<bool>,
+) -> Result<CloudSyncResult, String> {
+    let discovered = discover_with_api(&app, &provider, &options.unwrap_or_default()).await?;
+    apply_discovery(
+        &app,
+        &provider,
+        &discovered,
+        remove_missing.unwrap_or(false),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_ec2::types::Tag;
+
+    fn instance(id: &str, name: Option<&str>, public_ip: Option<&str>) -> Instance {
+        let mut builder = Instance::builder()
+            .instance_id(id)
+            .private_ip_address("10.0.0.5")
+            .key_name("deploy");
+        if let Some(name) = name {
+            builder = builder.tags(Tag::builder().key("Name").value(name).build());
+        }
+        if let Some(ip) = public_ip {
+            builder = builder.public_ip_address(ip);
+        }
+        builder.build()
+    }
+
+    fn discovered(id: &str, host: &str) -> DiscoveredServer {
+        DiscoveredServer {
+            source: CloudSource {
+                provider: EC2_PROVIDER.to_string(),
+                resource_id: id.to_string(),
+                region: Some("eu-west-1".to_string()),
+            },
+            name: id.to_string(),
+            host: host.to_string(),
+            port: DEFAULT_SSH_PORT,
+            user: DEFAULT_EC2_USER.to_string(),
+            key_secret_id: None,
+        }
+    }
+
+    #[test]
+    fn test_map_ec2_instance_prefers_public_address() {
+        let options = Ec2DiscoveryOptions::default();
+        let mapped = map_ec2_instance(
+            &instance("i-1", Some("web"), Some("3.3.3.3")),
+            None,
+            &options,
+        )
+        .expect("mapped");
+
+        assert_eq!(mapped.name, "web");
+        assert_eq!(mapped.host, "3.3.3.3");
+        assert_eq!(mapped.user, DEFAULT_EC2_USER);
+        assert_eq!(mapped.key_secret_id.as_deref(), Some("ec2-key:deploy"));
+
+        let private = Ec2DiscoveryOptions {
+            use_private_ip: true,
+            ..Default::default()
+        };
+        let mapped = map_ec2_instance(&instance("i-2", None, Some("3.3.3.3")), None, &private)
+            .expect("mapped");
+        assert_eq!(mapped.name, "i-2");
+        assert_eq!(mapped.host, "10.0.0.5");
+    }
+
+    #[test]
+    fn test_map_droplet_and_hetzner_server() {
+        let page: DigitalOceanPage = serde_json::from_str(
+            r#"{
+                "droplets": [{
+                    "id": 42,
+                    "name": "api-1",
+                    "networks": {"v4": [
+                        {"ip_address": "10.1.0.2", "type": "private"},
+                        {"ip_address": "5.5.5.5", "type": "public"}
+                    ]},
+                    "region": {"slug": "fra1"}
+                }],
+                "links": {}
+            }"#,
+        )
+        .expect("droplets");
+        assert!(page.links.pages.next.is_none());
+        let options = CloudApiOptions::default();
+        let mapped = map_droplet(&page.droplets[0], &options).expect("mapped");
+        assert_eq!(mapped.source.provider, DIGITALOCEAN_PROVIDER);
+        assert_eq!(mapped.source.resource_id, "42");
+        assert_eq!(mapped.source.region.as_deref(), Some("fra1"));
+        assert_eq!(mapped.host, "5.5.5.5");
+        assert_eq!(mapped.user, DEFAULT_ROOT_USER);
+
+        let page: HetznerPage = serde_json::from_str(
+            r#"{
+                "servers": [{
+                    "id": 7,
+                    "name": "db",
+                    "public_net": {"ipv4": null},
+                    "private_net": [{"ip": "10.0.0.3"}],
+                    "datacenter": {"location": {"name": "nbg1"}}
+                }],
+                "meta": {"pagination": {"next_page": null}}
+            }"#,
+        )
+        .expect("servers");
+        let mapped = map_hetzner_server(&page.servers[0], &options).expect("mapped");
+        assert_eq!(mapped.source.provider, HETZNER_PROVIDER);
+        assert_eq!(mapped.host, "10.0.0.3");
+        assert_eq!(mapped.source.region.as_deref(), Some("nbg1"));
+    }
+
+    #[test]
+    fn test_merge_discovered_updates_adds_and_removes() {
+        let mut servers = vec![
+            new_server_from(&discovered("i-1", "1.1.1.1")),
+            new_server_from(&discovered("i-gone", "2.2.2.2")),
+        ];
+        servers[0].user = "ubuntu".to_string();
+
+        let summary = merge_discovered(
+            &mut servers,
+            EC2_PROVIDER,
+            &[discovered("i-1", "9.9.9.9"), discovered("i-new", "4.4.4.4")],
+            true,
+            None,
+        );
+
+        assert_eq!(
+            summary,
+            CloudSyncSummary {
+                added: 1,
+                updated: 1,
+                removed: 1,
+            }
+        );
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].host, "9.9.9.9");
+        assert_eq!(servers[0].user, "ubuntu");
+        assert_eq!(servers[1].nickname.as_deref(), Some("i-new"));
+
+        // Listing only us-east-1 says nothing about eu-west-1.
+        let summary = merge_discovered(
+            &mut servers,
+            EC2_PROVIDER,
+            &[],
+            true,
+            Some(&["us-east-1".to_string()]),
+        );
+        assert_eq!(summary.removed, 0);
+        assert_eq!(servers.len(), 2);
+    }
+}
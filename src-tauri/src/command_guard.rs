@@ -0,0 +1,214 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::event_bus::Emitter;
+use crate::history::InputLineTracker;
+use crate::settings::load_settings;
+use crate::{get_app_dir, saved_server, AppState, Environment, ShellCommand};
+
+const DEFAULT_PATTERNS: [&str; 4] = [
+    r"\brm\s+-[a-zA-Z]*(rf|fr)[a-zA-Z]*\s+/(\*|\s|$)",
+    r"(?i)\bdrop\s+(database|schema)\b",
+    r"\bmkfs(\.\w+)?\b",
+    r"\bdd\b.*\bof=/dev/",
+];
+
+fn default_patterns() -> Vec<String> {
+    DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+fn default_environments() -> Vec<Environment> {
+    vec![Environment::Prod]
+}
+
+/// Commands that need an explicit confirmation before they reach servers
+/// in one of `environments`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandGuardSettings {
+    /// Regular expressions matched against each entered command line.
+    #[serde(default = "default_patterns")]
+    pub patterns: Vec<String>,
+    #[serde(default = "default_environments")]
+    pub environments: Vec<Environment>,
+}
+
+impl Default for CommandGuardSettings {
+    fn default() -> Self {
+        Self {
+            patterns: default_patterns(),
+            environments: default_environments(),
+        }
+    }
+}
+
+impl CommandGuardSettings {
+    pub fn compile(&self) -> Result<Vec<Regex>, String> {
+        self.patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid command guard pattern {}: {}", pattern, e))
+            })
+            .collect()
+    }
+}
+
+/// Payload of `command-confirmation-required`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandConfirmation {
+    pub id: String,
+    pub shell_id: String,
+    pub server_id: String,
+    pub command: String,
+    pub pattern: String,
+}
+
+struct HeldInput {
+    shell_id: String,
+    input: String,
+}
+
+/// Per-shell command lines typed so far, and input held for confirmation.
+#[derive(Default)]
+pub struct CommandGuard {
+    lines: Mutex<HashMap<String, InputLineTracker>>,
+    held: Mutex<HashMap<String, HeldInput>>,
+}
+
+fn first_match<'a>(patterns: &'a [Regex], lines: &[String]) -> Option<(&'a Regex, String)> {
+    lines.iter().find_map(|line| {
+        patterns
+            .iter()
+            .find(|pattern| pattern.is_match(line))
+            .map(|pattern| (pattern, line.clone()))
+    })
+}
+
+// Returns the pattern and line when `server_id` is guarded and one of
+// `lines` is dangerous.
+fn dangerous_line(app: &AppHandle, server_id: &str, lines: &[String]) -> Option<(String, String)> {
+    let settings = load_settings(&get_app_dir(app).ok()?).ok()?.command_guard;
+    let environment = saved_server(app, server_id)?.environment?;
+    if !settings.environments.contains(&environment) {
+        return None;
+    }
+    let patterns = settings.compile().ok()?;
+    first_match(&patterns, lines).map(|(pattern, line)| (pattern.as_str().to_string(), line))
+}
+
+/// Decides whether `input` may go to the shell now. Input that completes a
+/// dangerous command line is held, reported through
+/// `command-confirmation-required` and `false` is returned.
+pub async fn screen_input(app: &AppHandle, shell_id: &str, input: &str) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    let server_id = {
+        let shells = state.shells.lock().await;
+        shells
+            .get(shell_id)
+            .map(|shell| shell.server_id.clone())
+            .ok_or_else(|| format!("Shell with id {} not found", shell_id))?
+    };
+
+    let mut lines = state.command_guard.lines.lock().await;
+    let mut tracker = lines.get(shell_id).cloned().unwrap_or_default();
+    let completed = tracker.push(input);
+    if !completed.is_empty() {
+        if let Some((pattern, command)) = dangerous_line(app, &server_id, &completed) {
+            let id = uuid::Uuid::new_v4().to_string();
+            state.command_guard.held.lock().await.insert(
+                id.clone(),
+                HeldInput {
+                    shell_id: shell_id.to_string(),
+                    input: input.to_string(),
+                },
+            );
+            let _ = app.emit(
+                "command-confirmation-required",
+                CommandConfirmation {
+                    id,
+                    shell_id: shell_id.to_string(),
+                    server_id,
+                    command,
+                    pattern,
+                },
+            );
+            return Ok(false);
+        }
+    }
+    lines.insert(shell_id.to_string(), tracker);
+    Ok(true)
+}
+
+pub async fn forget_shell(app: &AppHandle, shell_id: &str) {
+    let state = app.state::<AppState>();
+    state.command_guard.lines.lock().await.remove(shell_id);
+    state
+        .command_guard
+        .held
+        .lock()
+        .await
+        .retain(|_, held| held.shell_id != shell_id);
+}
+
+/// Sends held input on to its shell.
+#[tauri::command]
+pub async fn confirm_command(app: AppHandle, id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let held = state
+        .command_guard
+        .held
+        .lock()
+        .await
+        .remove(&id)
+        .ok_or_else(|| "No command is waiting for confirmation".to_string())?;
+
+    let cmd_tx = {
+        let shells = state.shells.lock().await;
+        shells
+            .get(&held.shell_id)
+            .map(|shell| shell.cmd_tx.clone())
+            .ok_or_else(|| format!("Shell with id {} not found", held.shell_id))?
+    };
+    state
+        .command_guard
+        .lines
+        .lock()
+        .await
+        .entry(held.shell_id.clone())
+        .or_default()
+        .push(&held.input);
+    cmd_tx
+        .send(ShellCommand::SendInput(held.input))
+        .await
+        .map_err(|e| format!("Failed to send input: {}", e))
+}
+
+/// Drops held input. What was typed before it stays at the prompt.
+#[tauri::command]
+pub async fn cancel_command(app: AppHandle, id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.command_guard.held.lock().await.remove(&id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_patterns_match_destructive_commands_only() {
+        let patterns = CommandGuardSettings::default().compile().unwrap();
+        let matches = |line: &str| first_match(&patterns, &[line.to_string()]).is_some();
+
+        assert!(matches("sudo rm -rf /"));
+        assert!(matches("rm -fr /*"));
+        assert!(matches("drop database orders;"));
+        assert!(matches("mkfs.ext4 /dev/sdb1"));
+        assert!(matches("dd if=/dev/zero of=/dev/sda bs=1M"));
+        assert!(!matches("rm -rf /tmp/build"));
+        assert!(!matches("ls -la /"));
+    }
+}
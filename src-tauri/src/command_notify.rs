@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::shell_integration::CommandFinished;
+use crate::AppState;
+
+/// Shells whose running command should raise a notification when it ends.
+#[derive(Default)]
+pub struct CommandNotifier {
+    watched: Mutex<HashSet<String>>,
+}
+
+impl CommandNotifier {
+    fn set(&self, shell_id: &str, enabled: bool) {
+        let mut watched = self.watched.lock().unwrap_or_else(|e| e.into_inner());
+        if enabled {
+            watched.insert(shell_id.to_string());
+        } else {
+            watched.remove(shell_id);
+        }
+    }
+
+    fn take(&self, shell_id: &str) -> bool {
+        self.watched
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(shell_id)
+    }
+}
+
+fn format_duration(duration_ms: u64) -> String {
+    let seconds = duration_ms / 1000;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+fn notification_text(finished: &CommandFinished) -> (String, String) {
+    let title = match finished.exit_code {
+        Some(0) | None => "Command finished".to_string(),
+        Some(code) => format!("Command failed (exit {})", code),
+    };
+    let command = finished.command.as_deref().unwrap_or("Command");
+    let body = format!(
+        "{} ran for {}",
+        command,
+        format_duration(finished.duration_ms)
+    );
+    (title, body)
+}
+
+fn window_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+#[cfg(target_os = "linux")]
+async fn show_desktop_notification(title: &str, body: &str) -> std::io::Result<()> {
+    tokio::process::Command::new("notify-send")
+        .args(["--app-name=ssh-thing", title, body])
+        .status()
+        .await
+        .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+async fn show_desktop_notification(title: &str, body: &str) -> std::io::Result<()> {
+    // Passing the text as arguments avoids quoting it into the script.
+    tokio::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            title,
+            body,
+        ])
+        .status()
+        .await
+        .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn show_desktop_notification(_title: &str, _body: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Called for every `command-finished`. A watched shell is notified once,
+/// and only while no app window has focus; the watch ends either way.
+pub(crate) fn on_command_finished(app: &AppHandle, finished: &CommandFinished) {
+    let state = app.state::<AppState>();
+    if !state.command_notifier.take(&finished.shell_id) || window_focused(app) {
+        return;
+    }
+
+    let _ = app.emit("command-notification", finished.clone());
+    let (title, body) = notification_text(finished);
+    tokio::spawn(async move {
+        if let Err(e) = show_desktop_notification(&title, &body).await {
+            debug!(error = %e, "Failed to show desktop notification");
+        }
+    });
+}
+
+pub fn forget_shell(app: &AppHandle, shell_id: &str) {
+    app.state::<AppState>()
+        .command_notifier
+        .set(shell_id, false);
+}
+
+/// Arms or disarms a notification for when the shell's current foreground
+/// command finishes. Needs shell integration to be enabled in that shell.
+#[tauri::command]
+pub async fn notify_when_done(
+    app: AppHandle,
+    shell_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if !state.shells.lock().await.contains_key(&shell_id) {
+        return Err(format!("Shell with id {} not found", shell_id));
+    }
+    state.command_notifier.set(&shell_id, enabled);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_text_reports_failure_and_duration() {
+        let finished = CommandFinished {
+            connection_id: "conn-1".to_string(),
+            server_id: "server-1".to_string(),
+            shell_id: "shell-1".to_string(),
+            command: Some("make release".to_string()),
+            exit_code: Some(2),
+            started_at: 1_700_000_000,
+            duration_ms: 192_500,
+        };
+
+        let (title, body) = notification_text(&finished);
+
+        assert_eq!(title, "Command failed (exit 2)");
+        assert_eq!(body, "make release ran for 3m 12s");
+    }
+}
@@ -0,0 +1,149 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use ssh_thing_core::storage::SNIPPETS_FILE;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{
+    get_app_dir, load_servers, load_snippets, AppState, ServerConnection, Snippet, SERVERS_FILE,
+};
+
+const WATCHED_FILES: [&str; 2] = [SERVERS_FILE, SNIPPETS_FILE];
+// Editors and sync clients often write a file in several steps.
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Keeps the watcher for the active profile's directory alive. Replacing it
+/// stops the previous one.
+#[derive(Default)]
+pub struct ConfigWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+/// Payload of `config-changed`. Only the files that changed are filled in.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigChangedEvent {
+    pub servers: Option<Vec<ServerConnection>>,
+    pub snippets: Option<Vec<Snippet>>,
+}
+
+fn watched_files(event: &Event) -> HashSet<&'static str> {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return HashSet::new();
+    }
+
+    event
+        .paths
+        .iter()
+        .filter_map(|path| path.file_name()?.to_str())
+        .filter_map(|name| WATCHED_FILES.into_iter().find(|file| *file == name))
+        .collect()
+}
+
+fn read_contents(app_dir: &Path, file: &str) -> String {
+    fs::read_to_string(app_dir.join(file)).unwrap_or_default()
+}
+
+/// Starts watching the active profile's directory for edits made outside the
+/// app, such as a sync client or a text editor. Called again after a profile
+/// switch.
+pub fn watch_app_dir(app: &AppHandle) -> Result<(), String> {
+    let app_dir = get_app_dir(app)?;
+    fs::create_dir_all(&app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            let _ = tx.send(event);
+        }
+    })
+    .map_err(|e| format!("Failed to start config watcher: {}", e))?;
+    // The directory rather than the files, since atomic saves replace them.
+    watcher
+        .watch(&app_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", app_dir.display(), e))?;
+
+    let state = app.state::<AppState>();
+    *state
+        .config_watcher
+        .watcher
+        .lock()
+        .map_err(|_| "Config watcher is unavailable".to_string())? = Some(watcher);
+    tauri::async_runtime::spawn(forward_changes(app.clone(), app_dir, rx));
+    Ok(())
+}
+
+// Ends once the watcher feeding `events` is replaced or dropped.
+async fn forward_changes(
+    app: AppHandle,
+    app_dir: PathBuf,
+    mut events: mpsc::UnboundedReceiver<Event>,
+) {
+    let mut last_seen: HashMap<&str, String> = WATCHED_FILES
+        .into_iter()
+        .map(|file| (file, read_contents(&app_dir, file)))
+        .collect();
+
+    while let Some(event) = events.recv().await {
+        let mut changed = watched_files(&event);
+        loop {
+            match timeout(SETTLE_DELAY, events.recv()).await {
+                Ok(Some(event)) => changed.extend(watched_files(&event)),
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+
+        let mut payload = ConfigChangedEvent::default();
+        for file in changed {
+            let contents = read_contents(&app_dir, file);
+            if last_seen.get(file) == Some(&contents) {
+                continue;
+            }
+            last_seen.insert(file, contents);
+
+            let result = match file {
+                SERVERS_FILE => load_servers(&app_dir, &app).map(|servers| {
+                    payload.servers = Some(servers);
+                }),
+                _ => load_snippets(&app_dir).map(|snippets| {
+                    payload.snippets = Some(snippets);
+                }),
+            };
+            // Half-written or hand-broken files are picked up on the next save.
+            if let Err(e) = result {
+                debug!(file, error = %e, "Failed to reload config file");
+            }
+        }
+
+        if payload.servers.is_some() || payload.snippets.is_some() {
+            let _ = app.emit("config-changed", payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, ModifyKind};
+
+    #[test]
+    fn test_watched_files_ignores_other_files_and_reads() {
+        let app_dir = PathBuf::from("/data/ssh-thing");
+        let modified = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(app_dir.join(SERVERS_FILE))
+            .add_path(app_dir.join("history.json"));
+        let read =
+            Event::new(EventKind::Access(AccessKind::Any)).add_path(app_dir.join(SNIPPETS_FILE));
+
+        assert_eq!(watched_files(&modified), HashSet::from([SERVERS_FILE]));
+        assert!(watched_files(&read).is_empty());
+    }
+}
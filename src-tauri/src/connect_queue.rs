@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+use crate::event_bus::Emitter;
+use crate::{settings, AppState};
+
+/// Payload of `connection-queued`, sent while a connection attempt waits for
+/// a free slot and once more when it gets one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionQueued {
+    pub connection_id: Option<String>,
+    pub server_id: Option<String>,
+    /// Place in line, 1 for the next attempt to start; 0 once it has
+    /// started.
+    pub position: usize,
+}
+
+#[derive(Debug, Default)]
+struct Slots {
+    active: usize,
+    next_ticket: u64,
+    waiting: VecDeque<u64>,
+}
+
+impl Slots {
+    fn enqueue(&mut self) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.waiting.push_back(ticket);
+        ticket
+    }
+
+    /// Starts `ticket` when it is first in line and a slot is free, giving
+    /// `None`; otherwise its place in line. A `limit` of 0 never queues.
+    fn take(&mut self, ticket: u64, limit: usize) -> Option<usize> {
+        let index = self.waiting.iter().position(|waiting| *waiting == ticket)?;
+        if index == 0 && (limit == 0 || self.active < limit) {
+            self.waiting.pop_front();
+            self.active += 1;
+            return None;
+        }
+        Some(index + 1)
+    }
+
+    fn leave(&mut self, ticket: u64) {
+        self.waiting.retain(|waiting| *waiting != ticket);
+    }
+}
+
+/// Connection attempts running and waiting, so connecting a whole group
+/// does not start dozens of handshakes against one bastion at once.
+#[derive(Default)]
+pub struct ConnectQueue {
+    slots: Mutex<Slots>,
+    changed: Notify,
+}
+
+impl ConnectQueue {
+    fn release(&self) {
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.active = slots.active.saturating_sub(1);
+        }
+        self.changed.notify_waiters();
+    }
+}
+
+/// Held for the length of a connection attempt.
+pub(crate) struct ConnectSlot {
+    app: AppHandle,
+}
+
+impl Drop for ConnectSlot {
+    fn drop(&mut self) {
+        self.app.state::<AppState>().connect_queue.release();
+    }
+}
+
+// Takes the ticket out of line when the caller gives up waiting.
+struct Ticket<'a> {
+    queue: &'a ConnectQueue,
+    id: u64,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut slots) = self.queue.slots.lock() {
+            slots.leave(self.id);
+        }
+        self.queue.changed.notify_waiters();
+    }
+}
+
+/// Waits until fewer than `max_concurrent_connections` attempts are
+/// running, first come first served.
+pub(crate) async fn acquire(
+    app: &AppHandle,
+    connection_id: Option<&str>,
+    server_id: Option<&str>,
+) -> Result<ConnectSlot, String> {
+    let limit = settings::max_concurrent_connections(app);
+    let state = app.state::<AppState>();
+    let queue = &state.connect_queue;
+    let ticket = Ticket {
+        queue,
+        id: queue
+            .slots
+            .lock()
+            .map_err(|_| "Connection queue is unavailable".to_string())?
+            .enqueue(),
+    };
+
+    let emit = |position| {
+        let payload = ConnectionQueued {
+            connection_id: connection_id.map(str::to_string),
+            server_id: server_id.map(str::to_string),
+            position,
+        };
+        let _ = app.emit("connection-queued", payload);
+    };
+    let mut reported = None;
+    loop {
+        // Registered before checking, so a release in between is not missed.
+        let changed = queue.changed.notified();
+        let position = queue
+            .slots
+            .lock()
+            .map_err(|_| "Connection queue is unavailable".to_string())?
+            .take(ticket.id, limit);
+        let Some(position) = position else {
+            break;
+        };
+        if reported != Some(position) {
+            emit(position);
+            reported = Some(position);
+        }
+        changed.await;
+    }
+    if reported.is_some() {
+        emit(0);
+    }
+    Ok(ConnectSlot { app: app.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_start_attempts_in_order_up_to_the_limit() {
+        let mut slots = Slots::default();
+        let first = slots.enqueue();
+        let second = slots.enqueue();
+        let third = slots.enqueue();
+
+        assert_eq!(slots.take(second, 1), Some(2));
+        assert_eq!(slots.take(first, 1), None);
+        assert_eq!(slots.take(second, 1), Some(1));
+
+        slots.leave(second);
+        assert_eq!(slots.take(third, 1), Some(1));
+        slots.active -= 1;
+        assert_eq!(slots.take(third, 1), None);
+
+        let unlimited = slots.enqueue();
+        assert_eq!(slots.take(unlimited, 0), None);
+        assert_eq!(slots.active, 2);
+    }
+}
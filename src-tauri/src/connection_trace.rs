@@ -0,0 +1,81 @@
+use ssh_thing_core::trace::{HandshakeTrace, TraceEvent};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+/// Servers in handshake debug mode and the trace of each one's latest
+/// connection attempt.
+#[derive(Default)]
+pub struct ConnectionTraces {
+    enabled: Mutex<HashSet<String>>,
+    traces: Mutex<HashMap<String, Arc<HandshakeTrace>>>,
+}
+
+/// A fresh trace for a connection to `server_id` when debug mode is on for
+/// it, replacing the one from the previous attempt.
+pub(crate) fn start(app: &AppHandle, server_id: Option<&str>) -> Option<Arc<HandshakeTrace>> {
+    let server_id = server_id?;
+    let state = app.state::<AppState>();
+    if !state
+        .connection_traces
+        .enabled
+        .lock()
+        .ok()?
+        .contains(server_id)
+    {
+        return None;
+    }
+    let trace = Arc::new(HandshakeTrace::new());
+    state
+        .connection_traces
+        .traces
+        .lock()
+        .ok()?
+        .insert(server_id.to_string(), trace.clone());
+    Some(trace)
+}
+
+/// Turns handshake tracing on or off for connections to a server. Turning
+/// it off drops the last trace.
+#[tauri::command]
+pub async fn set_connection_trace(
+    app: AppHandle,
+    server_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let traces = &state.connection_traces;
+    let mut enabled_servers = traces
+        .enabled
+        .lock()
+        .map_err(|_| "Connection traces are unavailable".to_string())?;
+    if enabled {
+        enabled_servers.insert(server_id);
+    } else {
+        enabled_servers.remove(&server_id);
+        if let Ok(mut recorded) = traces.traces.lock() {
+            recorded.remove(&server_id);
+        }
+    }
+    Ok(())
+}
+
+/// The stages of the latest connection attempt to a server in debug mode.
+#[tauri::command]
+pub async fn get_connection_trace(
+    app: AppHandle,
+    server_id: String,
+) -> Result<Vec<TraceEvent>, String> {
+    let state = app.state::<AppState>();
+    let traces = state
+        .connection_traces
+        .traces
+        .lock()
+        .map_err(|_| "Connection traces are unavailable".to_string())?;
+    traces
+        .get(&server_id)
+        .map(|trace| trace.events())
+        .ok_or_else(|| format!("No connection trace recorded for server {}", server_id))
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::remote::{run_remote_command, shell_quote};
+use crate::{get_app_dir, limits, load_servers, open_pty_shell, settings, AppState, PtyConfig};
+
+const RUNTIMES: [&str; 2] = ["docker", "podman"];
+const DEFAULT_CONTAINER_SHELL: &str = "sh";
+// Uses the first runtime installed; podman takes the same format string.
+const LIST_SCRIPT: &str = "for runtime in docker podman; do \
+     if command -v $runtime >/dev/null 2>&1; then \
+     $runtime ps --format \"$runtime\\t{{.ID}}\\t{{.Names}}\\t{{.Image}}\\t{{.Status}}\"; exit $?; fi; \
+     done; echo 'Neither docker nor podman is installed' >&2; exit 127";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContainerInfo {
+    /// `docker` or `podman`.
+    pub runtime: String,
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub status: String,
+}
+
+/// The `exec` command of each shell attached to a container, by shell id,
+/// so reconnecting the shell attaches to the same container again.
+#[derive(Default)]
+pub struct ContainerShells {
+    commands: Mutex<HashMap<String, String>>,
+}
+
+fn parse_container_list(output: &str) -> Vec<ContainerInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+            let [runtime, id, name, image, status] = fields[..] else {
+                return None;
+            };
+            Some(ContainerInfo {
+                runtime: runtime.to_string(),
+                id: id.to_string(),
+                name: name.to_string(),
+                image: image.to_string(),
+                status: status.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn exec_command(runtime: &str, container: &str, shell: Option<&str>) -> Result<String, String> {
+    if !RUNTIMES.contains(&runtime) {
+        return Err(format!("Unsupported container runtime: {}", runtime));
+    }
+    let container = container.trim();
+    if container.is_empty() {
+        return Err("Container is required".to_string());
+    }
+    let shell = shell
+        .map(str::trim)
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or(DEFAULT_CONTAINER_SHELL);
+    Ok(format!(
+        "{} exec -it {} {}",
+        runtime,
+        shell_quote(container),
+        shell
+    ))
+}
+
+/// The command to reattach a container shell with, if `shell_id` is one.
+pub(crate) async fn command_for(app: &AppHandle, shell_id: &str) -> Option<String> {
+    let state = app.state::<AppState>();
+    let commands = state.container_shells.commands.lock().await;
+    commands.get(shell_id).cloned()
+}
+
+pub(crate) async fn forget_shells(app: &AppHandle, shell_ids: &[String]) {
+    let state = app.state::<AppState>();
+    let mut commands = state.container_shells.commands.lock().await;
+    for shell_id in shell_ids {
+        commands.remove(shell_id);
+    }
+}
+
+/// Running containers on the server, from docker or else podman.
+#[tauri::command]
+pub async fn list_containers(
+    app: AppHandle,
+    server_id: String,
+) -> Result<Vec<ContainerInfo>, String> {
+    let output = run_remote_command(&app, &server_id, LIST_SCRIPT).await?;
+    if !output.success() {
+        let detail = output.stderr.trim();
+        return Err(if detail.is_empty() {
+            format!(
+                "Listing containers failed with exit code {:?}",
+                output.exit_code
+            )
+        } else {
+            format!("Listing containers failed: {}", detail)
+        });
+    }
+    Ok(parse_container_list(&output.stdout))
+}
+
+/// Opens a shell on an existing connection that runs inside `container`
+/// (an id or name) instead of on the host. The shell is labelled with the
+/// container and reattaches to it when reconnected.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn attach_container(
+    app: AppHandle,
+    connection_id: String,
+    runtime: String,
+    container: String,
+    shell: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pixel_width: Option<u32>,
+    pixel_height: Option<u32>,
+) -> Result<String, String> {
+    let command = exec_command(&runtime, &container, shell.as_deref())?;
+    let state = app.state::<AppState>();
+    let (handle, server_id) = {
+        let sessions = state.sessions.lock().await;
+        let session = sessions
+            .get(&connection_id)
+            .ok_or_else(|| format!("No active session for connection {}", connection_id))?;
+        (session.handle.clone(), session.server_id.clone())
+    };
+
+    let server = load_servers(&get_app_dir(&app)?, &app)?
+        .into_iter()
+        .find(|server| server.id == server_id);
+    if let Some(server) = &server {
+        limits::check_shells(&app, server)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let defaults = settings::connection_defaults(&app);
+    let term = server
+        .as_ref()
+        .map_or_else(|| defaults.term.clone(), |server| defaults.term_for(server));
+    let config = PtyConfig {
+        term,
+        width: width.unwrap_or(80),
+        height: height.unwrap_or(24),
+        pixel_width: pixel_width.unwrap_or(0),
+        pixel_height: pixel_height.unwrap_or(0),
+        command: Some(command.clone()),
+    };
+    let mut shell =
+        open_pty_shell(&app, &handle, &config, &connection_id, &server_id, None).await?;
+    shell.label = Some(container.trim().to_string());
+
+    let shell_id = shell.id.clone();
+    state
+        .container_shells
+        .commands
+        .lock()
+        .await
+        .insert(shell_id.clone(), command);
+    state.shells.lock().await.insert(shell_id.clone(), shell);
+    Ok(shell_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_container_list_and_exec_command() {
+        let containers = parse_container_list(
+            "docker\t3f2a9c\tapp\tghcr.io/acme/app:1.4\tUp 2 hours\n\
+             docker\t9b1e07\tdb\tpostgres:16\tUp 3 days (healthy)\n\
+             garbage\n",
+        );
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[1].name, "db");
+        assert_eq!(containers[1].status, "Up 3 days (healthy)");
+
+        assert_eq!(
+            exec_command("docker", "app", None),
+            Ok("docker exec -it 'app' sh".to_string())
+        );
+        assert_eq!(
+            exec_command("podman", "db", Some("bash -l")),
+            Ok("podman exec -it 'db' bash -l".to_string())
+        );
+        assert!(exec_command("docker; rm -rf /", "app", None).is_err());
+        assert!(exec_command("docker", " ", None).is_err());
+    }
+}
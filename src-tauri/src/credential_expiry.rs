@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::settings::load_settings;
+use crate::{get_app_dir, load_servers, ServerConnection};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Payload of `credential-expiring`. `days_left` is negative once the
+/// credential has expired.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CredentialExpiring {
+    pub server_id: String,
+    pub nickname: Option<String>,
+    pub host: String,
+    pub expires_at: u64,
+    pub days_left: i64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Servers whose credentials expire within `reminder_days` of `now`,
+/// soonest first.
+pub fn expiring(
+    servers: &[ServerConnection],
+    now: u64,
+    reminder_days: u64,
+) -> Vec<CredentialExpiring> {
+    let horizon = now.saturating_add(reminder_days.saturating_mul(SECONDS_PER_DAY));
+    let mut expiring: Vec<CredentialExpiring> = servers
+        .iter()
+        .filter_map(|server| {
+            let expires_at = server.credential_expires_at?;
+            (expires_at <= horizon).then(|| CredentialExpiring {
+                server_id: server.id.clone(),
+                nickname: server.nickname.clone(),
+                host: server.host.clone(),
+                expires_at,
+                days_left: (expires_at as i64 - now as i64).div_euclid(SECONDS_PER_DAY as i64),
+            })
+        })
+        .collect();
+    expiring.sort_by_key(|entry| entry.expires_at);
+    expiring
+}
+
+fn check(app: &AppHandle) -> Result<Vec<CredentialExpiring>, String> {
+    let app_dir = get_app_dir(app)?;
+    let reminder_days = load_settings(&app_dir)?.credential_reminder_days;
+    let servers = load_servers(&app_dir, app)?;
+    Ok(expiring(&servers, now_secs(), reminder_days))
+}
+
+/// Checks every few hours for the life of the app. Each credential is
+/// announced once per launch, or again after its expiry date changes.
+pub fn start_expiry_check(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut announced: HashSet<(String, u64)> = HashSet::new();
+        let mut ticker = interval(CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let expiring = match check(&app) {
+                Ok(expiring) => expiring,
+                Err(e) => {
+                    debug!(error = %e, "Credential expiry check failed");
+                    continue;
+                }
+            };
+            for entry in expiring {
+                if announced.insert((entry.server_id.clone(), entry.expires_at)) {
+                    let _ = app.emit("credential-expiring", entry);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_expiring_credentials(app: AppHandle) -> Result<Vec<CredentialExpiring>, String> {
+    check(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, expires_at: Option<u64>) -> ServerConnection {
+        let mut server: ServerConnection = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "host": "example.com",
+            "port": 22,
+            "user": "root",
+            "auth": { "type": "SecretRef", "secret_id": format!("server:{}:password", id) },
+        }))
+        .unwrap();
+        server.credential_expires_at = expires_at;
+        server
+    }
+
+    #[test]
+    fn test_expiring_lists_credentials_inside_the_reminder_window() {
+        let now = 100 * SECONDS_PER_DAY;
+        let servers = vec![
+            server("later", Some(now + 30 * SECONDS_PER_DAY)),
+            server("soon", Some(now + 3 * SECONDS_PER_DAY)),
+            server("expired", Some(now - SECONDS_PER_DAY)),
+            server("none", None),
+        ];
+
+        let expiring = expiring(&servers, now, 14);
+
+        let ids: Vec<&str> = expiring.iter().map(|e| e.server_id.as_str()).collect();
+        assert_eq!(ids, vec!["expired", "soon"]);
+        assert_eq!(expiring[0].days_left, -1);
+        assert_eq!(expiring[1].days_left, 3);
+    }
+}
@@ -0,0 +1,218 @@
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tracing::debug;
+
+use crate::forwarding::{self, ActiveForward};
+use crate::local::spawn_pty_command;
+use crate::settings::{self, load_settings};
+use crate::{get_app_dir, saved_server, AppState, PortForward, PtyConfig};
+
+const DEFAULT_DATABASE_TAG: &str = "db";
+const FORWARD_HOST: &str = "127.0.0.1";
+
+fn default_database_tag() -> String {
+    DEFAULT_DATABASE_TAG.to_string()
+}
+
+/// How a client is started once its forward is up. `{host}` and `{port}`
+/// are replaced with the local end of the forward.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind")]
+pub enum DatabaseLaunch {
+    /// A command line run in a local terminal tab, e.g.
+    /// `psql -h {host} -p {port} -U app`. The forward closes with the tab.
+    Terminal { command: String },
+    /// A URI handed to the system, e.g. for DataGrip. The forward stays up
+    /// until stopped or the session disconnects.
+    Uri { uri: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatabaseClient {
+    pub name: String,
+    /// Where the database listens, as seen from the server.
+    #[serde(default)]
+    pub remote_host: Option<String>,
+    pub remote_port: u16,
+    pub launch: DatabaseLaunch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatabaseSettings {
+    /// Servers carrying this tag offer the database clients.
+    #[serde(default = "default_database_tag")]
+    pub tag: String,
+    #[serde(default)]
+    pub clients: Vec<DatabaseClient>,
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            tag: default_database_tag(),
+            clients: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSession {
+    pub forward: ActiveForward,
+    /// The terminal tab running the client, for terminal clients.
+    #[serde(default)]
+    pub shell_id: Option<String>,
+}
+
+fn fill_template(template: &str, port: u16) -> String {
+    template
+        .replace("{host}", FORWARD_HOST)
+        .replace("{port}", &port.to_string())
+}
+
+fn shell_command(command_line: &str) -> CommandBuilder {
+    #[cfg(windows)]
+    {
+        let mut command = CommandBuilder::new("cmd.exe");
+        command.args(["/C", command_line]);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let mut command = CommandBuilder::new("/bin/sh");
+        command.args(["-c", command_line]);
+        command
+    }
+}
+
+/// Forwards the database port of a server tagged as a database host and
+/// starts the configured client against the local end.
+#[tauri::command]
+pub async fn open_database_client(
+    app: AppHandle,
+    connection_id: String,
+    client: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<DatabaseSession, String> {
+    let server_id = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        sessions
+            .get(&connection_id)
+            .map(|session| session.server_id.clone())
+            .ok_or_else(|| format!("No active session for connection {}", connection_id))?
+    };
+    let server = saved_server(&app, &server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    let database = load_settings(&get_app_dir(&app)?)?.database;
+    if !server
+        .tags
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(&database.tag))
+    {
+        return Err(format!(
+            "Server is not tagged as a database host ({})",
+            database.tag
+        ));
+    }
+    let client = database
+        .clients
+        .into_iter()
+        .find(|candidate| candidate.name == client)
+        .ok_or_else(|| format!("Database client {} not found", client))?;
+
+    // Prefer the database's own port locally so default client settings
+    // work, falling back to a free one.
+    let forward = PortForward {
+        local_port: client.remote_port,
+        remote_host: client
+            .remote_host
+            .clone()
+            .unwrap_or_else(|| FORWARD_HOST.to_string()),
+        remote_port: client.remote_port,
+        bind_address: None,
+        reassign_if_busy: true,
+        dynamic: false,
+        allowed_destinations: Vec::new(),
+    };
+    let forward =
+        forwarding::start_forward(&app, &connection_id, &server_id, None, forward).await?;
+
+    let shell_id = match &client.launch {
+        DatabaseLaunch::Uri { uri } => {
+            let uri = fill_template(uri, forward.bound_port);
+            if let Err(e) = app.opener().open_url(&uri, None::<&str>) {
+                forwarding::stop_forward(&app, &forward.id).await;
+                return Err(format!("Failed to open {}: {}", uri, e));
+            }
+            None
+        }
+        DatabaseLaunch::Terminal { command } => {
+            let config = PtyConfig {
+                term: settings::connection_defaults(&app).term_for(&server),
+                width: width.unwrap_or(80),
+                height: height.unwrap_or(24),
+                pixel_width: 0,
+                pixel_height: 0,
+                command: None,
+            };
+            let command = shell_command(&fill_template(command, forward.bound_port));
+            let shell =
+                match spawn_pty_command(&app, &config, command, &connection_id, &server_id, None)
+                    .await
+                {
+                    Ok(shell) => shell,
+                    Err(e) => {
+                        forwarding::stop_forward(&app, &forward.id).await;
+                        return Err(e);
+                    }
+                };
+            let shell_id = shell.id.clone();
+            let cmd_tx = shell.cmd_tx.clone();
+            app.state::<AppState>()
+                .shells
+                .lock()
+                .await
+                .insert(shell_id.clone(), shell);
+
+            // The tab's command channel closes once the client exits.
+            let app = app.clone();
+            let forward_id = forward.id.clone();
+            tokio::spawn(async move {
+                cmd_tx.closed().await;
+                debug!(forward_id = %forward_id, "Database client exited");
+                forwarding::stop_forward(&app, &forward_id).await;
+            });
+            Some(shell_id)
+        }
+    };
+
+    Ok(DatabaseSession { forward, shell_id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_database_settings_parse_clients() {
+        let database: DatabaseSettings = serde_json::from_str(
+            r#"{
+                "clients": [{
+                    "name": "psql",
+                    "remote_port": 5432,
+                    "launch": { "kind": "Terminal", "command": "psql -h {host} -p {port}" }
+                }]
+            }"#,
+        )
+        .expect("Failed to parse database settings");
+
+        assert_eq!(database.tag, "db");
+        let DatabaseLaunch::Terminal { command } = &database.clients[0].launch else {
+            panic!("Expected a terminal client");
+        };
+        assert_eq!(fill_template(command, 15432), "psql -h 127.0.0.1 -p 15432");
+    }
+}
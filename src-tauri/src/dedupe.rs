@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    delete_secret, get_app_dir, identities, load_servers, save_servers, CompressionMode,
+    ServerConnection,
+};
+
+/// What `add_server` does when the new entry has the same host, port and
+/// user as a saved one.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Fail with an error naming the existing entry, so the UI can ask.
+    #[default]
+    Reject,
+    Merge,
+    Append,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeduplicateResult {
+    pub removed: usize,
+    pub servers: Vec<ServerConnection>,
+}
+
+pub fn is_same_target(a: &ServerConnection, b: &ServerConnection) -> bool {
+    a.host.eq_ignore_ascii_case(&b.host) && a.port == b.port && a.user == b.user
+}
+
+/// Index of a saved server other than `candidate` itself that points at the
+/// same host, port and user.
+pub fn find_duplicate(servers: &[ServerConnection], candidate: &ServerConnection) -> Option<usize> {
+    servers
+        .iter()
+        .position(|server| server.id != candidate.id && is_same_target(server, candidate))
+}
+
+pub fn describe(server: &ServerConnection) -> String {
+    let target = format!("{}@{}:{}", server.user, server.host, server.port);
+    match &server.nickname {
+        Some(nickname) => format!("{} ({})", nickname, target),
+        None => target,
+    }
+}
+
+/// Fills in what `existing` does not have from `incoming`. The existing
+/// entry's id and credentials are kept.
+pub fn merge_server(existing: &mut ServerConnection, incoming: &ServerConnection) {
+    fn fill<T: Clone>(target: &mut Option<T>, source: &Option<T>) {
+        if target.is_none() {
+            target.clone_from(source);
+        }
+    }
+
+    fill(&mut existing.nickname, &incoming.nickname);
+    fill(&mut existing.timeout_seconds, &incoming.timeout_seconds);
+    fill(&mut existing.wake_on_lan, &incoming.wake_on_lan);
+    fill(&mut existing.port_knock, &incoming.port_knock);
+    fill(&mut existing.cloud_source, &incoming.cloud_source);
+    fill(&mut existing.ssm, &incoming.ssm);
+    fill(&mut existing.group, &incoming.group);
+    fill(&mut existing.term, &incoming.term);
+    fill(&mut existing.shell, &incoming.shell);
+    fill(&mut existing.elevate, &incoming.elevate);
+    fill(&mut existing.max_sessions, &incoming.max_sessions);
+    fill(&mut existing.max_shells, &incoming.max_shells);
+    fill(&mut existing.keepalive_seconds, &incoming.keepalive_seconds);
+    fill(&mut existing.reconnect, &incoming.reconnect);
+    if existing.compression == CompressionMode::Auto {
+        existing.compression = incoming.compression;
+    }
+    fill(&mut existing.notes, &incoming.notes);
+    fill(&mut existing.color, &incoming.color);
+    fill(&mut existing.icon, &incoming.icon);
+    fill(&mut existing.environment, &incoming.environment);
+    fill(
+        &mut existing.credential_expires_at,
+        &incoming.credential_expires_at,
+    );
+    if existing.pinned_fingerprints.is_empty() {
+        existing
+            .pinned_fingerprints
+            .clone_from(&incoming.pinned_fingerprints);
+    }
+    for profile in &incoming.forward_profiles {
+        if !existing
+            .forward_profiles
+            .iter()
+            .any(|kept| kept.name == profile.name)
+        {
+            existing.forward_profiles.push(profile.clone());
+        }
+    }
+    existing.last_connected_at = existing.last_connected_at.max(incoming.last_connected_at);
+    existing.bind_tailnet |= incoming.bind_tailnet;
+    for tag in &incoming.tags {
+        if !existing.tags.contains(tag) {
+            existing.tags.push(tag.clone());
+        }
+    }
+}
+
+/// Folds later duplicates into the first entry for each target and returns
+/// the entries that were removed.
+pub fn deduplicate(servers: &mut Vec<ServerConnection>) -> Vec<ServerConnection> {
+    let mut kept: Vec<ServerConnection> = Vec::with_capacity(servers.len());
+    let mut removed = Vec::new();
+    for server in servers.drain(..) {
+        match kept
+            .iter_mut()
+            .find(|existing| is_same_target(existing, &server))
+        {
+            Some(existing) => {
+                merge_server(existing, &server);
+                removed.push(server);
+            }
+            None => kept.push(server),
+        }
+    }
+    *servers = kept;
+    removed
+}
+
+/// Returns the saved server `server` would duplicate, if any.
+#[tauri::command]
+pub async fn find_duplicate_server(
+    app: AppHandle,
+    server: ServerConnection,
+) -> Result<Option<ServerConnection>, String> {
+    let servers = load_servers(&get_app_dir(&app)?, &app)?;
+    Ok(find_duplicate(&servers, &server).map(|index| servers[index].clone()))
+}
+
+#[tauri::command]
+pub async fn deduplicate_servers(app: AppHandle) -> Result<DeduplicateResult, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut servers = load_servers(&app_dir, &app)?;
+    let removed = deduplicate(&mut servers);
+    save_servers(&app_dir, &servers)?;
+
+    for server in &removed {
+        let Some(secret_id) = identities::own_secret_id(server) else {
+            continue;
+        };
+        let still_used = servers
+            .iter()
+            .any(|kept| identities::own_secret_id(kept) == Some(secret_id));
+        if !still_used {
+            let _ = delete_secret(&app, secret_id);
+        }
+    }
+
+    Ok(DeduplicateResult {
+        removed: removed.len(),
+        servers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuthMethod;
+
+    fn server(id: &str, host: &str, user: &str, tags: &[&str]) -> ServerConnection {
+        ServerConnection {
+            id: id.to_string(),
+            nickname: None,
+            host: host.to_string(),
+            port: 22,
+            user: user.to_string(),
+            timeout_seconds: None,
+            last_connected_at: None,
+            wake_on_lan: None,
+            port_knock: None,
+            cloud_source: None,
+            ssm: None,
+            bind_tailnet: false,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            group: None,
+            term: None,
+            shell: None,
+            elevate: None,
+            keepalive_seconds: None,
+            reconnect: None,
+            compression: CompressionMode::Auto,
+            notes: None,
+            color: None,
+            icon: None,
+            environment: None,
+            credential_expires_at: None,
+            pinned_fingerprints: Vec::new(),
+            forward_profiles: Vec::new(),
+            identity_id: None,
+            max_sessions: None,
+            max_shells: None,
+            auth: AuthMethod::SecretRef {
+                secret_id: format!("server:{}:password", id),
+                kind: crate::SecretKind::Password,
+            },
+        }
+    }
+
+    #[test]
+    fn test_deduplicate_keeps_first_entry_and_merges_details() {
+        let mut second = server("b", "Example.com", "root", &["prod"]);
+        second.nickname = Some("API".to_string());
+        let mut servers = vec![
+            server("a", "example.com", "root", &["web"]),
+            server("c", "example.com", "deploy", &[]),
+            second,
+        ];
+
+        let removed = deduplicate(&mut servers);
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, "b");
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].id, "a");
+        assert_eq!(servers[0].nickname.as_deref(), Some("API"));
+        assert_eq!(servers[0].tags, vec!["web".to_string(), "prod".to_string()]);
+    }
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::debug;
+
+use crate::cloud::DEFAULT_SSH_PORT;
+use crate::event_bus::Emitter;
+use crate::importers::percent_decode;
+use crate::{
+    get_app_dir, load_servers, save_servers, AuthMethod, CompressionMode, SecretKind,
+    ServerConnection,
+};
+
+const SSH_SCHEME: &str = "ssh://";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SshUrl {
+    #[serde(default)]
+    pub user: Option<String>,
+    pub host: String,
+    pub port: u16,
+}
+
+/// Payload of `ssh-url-requested`, sent when the OS hands the app a URL.
+/// Nothing is saved or connected until the user approves and the frontend
+/// calls `open_ssh_url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshUrlRequested {
+    pub url: String,
+    #[serde(flatten)]
+    pub target: SshUrl,
+    /// The saved server the URL points at; `None` when approving it would
+    /// add a new one.
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshUrlOpened {
+    pub url: String,
+    pub server_id: String,
+    pub connection_id: String,
+    pub shell_id: String,
+    pub created_server: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshUrlFailed {
+    pub url: String,
+    pub error: String,
+}
+
+/// Parses `ssh://[user[;params]@]host[:port][/]` (RFC draft-ietf-secsh-scp-sftp-ssh-uri).
+/// Connection parameters after `;` are accepted but ignored.
+pub fn parse_ssh_url(url: &str) -> Result<SshUrl, String> {
+    let rest = url
+        .get(..SSH_SCHEME.len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case(SSH_SCHEME))
+        .map(|_| &url[SSH_SCHEME.len()..])
+        .ok_or_else(|| format!("Not an ssh:// URL: {}", url))?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+
+    let (user, host_port) = match authority.rsplit_once('@') {
+        Some((userinfo, host_port)) => {
+            let user = userinfo.split(';').next().unwrap_or_default();
+            let user = percent_decode(user.split(':').next().unwrap_or_default());
+            (Some(user).filter(|user| !user.is_empty()), host_port)
+        }
+        None => (None, authority),
+    };
+
+    let (host, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid IPv6 host in URL: {}", url))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(format!("Missing host in URL: {}", url));
+    }
+    let port = match port.filter(|port| !port.is_empty()) {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port in URL: {}", url))?,
+        None => DEFAULT_SSH_PORT,
+    };
+
+    Ok(SshUrl {
+        user,
+        host: host.to_string(),
+        port,
+    })
+}
+
+fn local_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+/// Finds the saved server the URL points at. Without a user in the URL any
+/// server on that host and port matches.
+fn find_server<'a>(
+    servers: &'a [ServerConnection],
+    target: &SshUrl,
+) -> Option<&'a ServerConnection> {
+    servers.iter().find(|server| {
+        server.host.eq_ignore_ascii_case(&target.host)
+            && server.port == target.port
+            && target.user.as_ref().is_none_or(|user| &server.user == user)
+    })
+}
+
+fn server_from_url(target: &SshUrl, user: String) -> ServerConnection {
+    let id = uuid::Uuid::new_v4().to_string();
+    ServerConnection {
+        auth: AuthMethod::SecretRef {
+            secret_id: format!("server:{}:password", id),
+            kind: SecretKind::Password,
+        },
+        id,
+        nickname: None,
+        host: target.host.clone(),
+        port: target.port,
+        user,
+        timeout_seconds: None,
+        last_connected_at: None,
+        wake_on_lan: None,
+        port_knock: None,
+        cloud_source: None,
+        ssm: None,
+        bind_tailnet: false,
+        tags: Vec::new(),
+        group: None,
+        term: None,
+        shell: None,
+        elevate: None,
+        keepalive_seconds: None,
+        reconnect: None,
+        compression: CompressionMode::Auto,
+        notes: None,
+        color: None,
+        icon: None,
+        environment: None,
+        credential_expires_at: None,
+        pinned_fingerprints: Vec::new(),
+        forward_profiles: Vec::new(),
+        identity_id: None,
+        max_sessions: None,
+        max_shells: None,
+    }
+}
+
+async fn open_url(
+    app: &AppHandle,
+    url: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<SshUrlOpened, String> {
+    let target = parse_ssh_url(url)?;
+    let app_dir = get_app_dir(app)?;
+    let mut servers = load_servers(&app_dir, app)?;
+
+    let (server, created_server) = match find_server(&servers, &target) {
+        Some(server) => (server.clone(), false),
+        None => {
+            let user = target
+                .user
+                .clone()
+                .or_else(local_user)
+                .ok_or_else(|| format!("No user in URL and no local user: {}", url))?;
+            let server = server_from_url(&target, user);
+            servers.push(server.clone());
+            save_servers(&app_dir, &servers)?;
+            (server, true)
+        }
+    };
+
+    debug!(url, server_id = %server.id, created_server, "Opening ssh:// URL");
+
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let server_id = server.id.clone();
+    let shell_id =
+        crate::connect(app.clone(), server, connection_id.clone(), width, height).await?;
+    Ok(SshUrlOpened {
+        url: url.to_string(),
+        server_id,
+        connection_id,
+        shell_id,
+        created_server,
+    })
+}
+
+fn request_url(app: &AppHandle, url: &str) -> Result<SshUrlRequested, String> {
+    let target = parse_ssh_url(url)?;
+    let servers = load_servers(&get_app_dir(app)?, app)?;
+    let server_id = find_server(&servers, &target).map(|server| server.id.clone());
+    Ok(SshUrlRequested {
+        url: url.to_string(),
+        target,
+        server_id,
+    })
+}
+
+/// Handles URLs delivered by the OS. Any web page can open one, so each is
+/// only announced for the user to confirm; results are emitted as events
+/// since nothing on the frontend is waiting on a command response.
+pub fn handle_opened_urls(app: &AppHandle, urls: Vec<String>) {
+    for url in urls {
+        match request_url(app, &url) {
+            Ok(requested) => {
+                debug!(url, server_id = ?requested.server_id, "Asking to open ssh:// URL");
+                let _ = app.emit("ssh-url-requested", requested);
+            }
+            Err(error) => {
+                let _ = app.emit("ssh-url-failed", SshUrlFailed { url, error });
+            }
+        }
+    }
+}
+
+/// Opens a URL the user approved, saving a server for it first when none
+/// matches.
+#[tauri::command]
+pub async fn open_ssh_url(
+    app: AppHandle,
+    url: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<SshUrlOpened, String> {
+    open_url(&app, &url, width, height).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_url() {
+        assert_eq!(
+            parse_ssh_url("ssh://deploy@web.example.com:2222").expect("parsed"),
+            SshUrl {
+                user: Some("deploy".to_string()),
+                host: "web.example.com".to_string(),
+                port: 2222,
+            }
+        );
+        assert_eq!(
+            parse_ssh_url("SSH://host/").expect("parsed"),
+            SshUrl {
+                user: None,
+                host: "host".to_string(),
+                port: 22,
+            }
+        );
+
+        let ipv6 = parse_ssh_url("ssh://a%40b;fingerprint=ssh-rsa-xx@[::1]:22").expect("parsed");
+        assert_eq!(ipv6.user.as_deref(), Some("a@b"));
+        assert_eq!(ipv6.host, "::1");
+
+        assert!(parse_ssh_url("http://host").is_err());
+        assert!(parse_ssh_url("ssh://user@").is_err());
+        assert!(parse_ssh_url("ssh://host:99999").is_err());
+    }
+
+    #[test]
+    fn test_find_server_matches_user_when_given() {
+        let target = parse_ssh_url("ssh://Web.Example.com").expect("parsed");
+        let servers = vec![server_from_url(
+            &SshUrl {
+                user: None,
+                host: "web.example.com".to_string(),
+                port: 22,
+            },
+            "ops".to_string(),
+        )];
+        assert!(find_server(&servers, &target).is_some());
+
+        let other_user = parse_ssh_url("ssh://root@web.example.com").expect("parsed");
+        assert!(find_server(&servers, &other_user).is_none());
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tracing::debug;
+
+use crate::remote::shell_quote;
+use crate::{get_secret, saved_server, Elevation, ElevationMethod, ServerConnection};
+
+// Long enough for a slow PAM stack, short enough that a later "Password:"
+// from something the user runs is not answered.
+const PROMPT_WINDOW: Duration = Duration::from_secs(15);
+const MAX_LINE_BYTES: usize = 256;
+// Passed to `sudo -p`, which expands %p to the user whose password it wants.
+// Only a line ending in the marker is answered, not any "password:".
+const SUDO_PROMPT: &str = "[sudo] password for %p (ssh-thing): ";
+const SUDO_PROMPT_MARKER: &str = " (ssh-thing): ";
+// su has no option to set its prompt, so only its own line is answered.
+const SU_PROMPT: &str = "Password:";
+
+fn elevation_command(elevation: &Elevation) -> String {
+    let user = elevation
+        .user
+        .as_deref()
+        .map(str::trim)
+        .filter(|user| !user.is_empty());
+    match (elevation.method, user) {
+        (ElevationMethod::Sudo, Some(user)) => format!(
+            "sudo -p {} -i -u {}",
+            shell_quote(SUDO_PROMPT),
+            shell_quote(user)
+        ),
+        (ElevationMethod::Sudo, None) => format!("sudo -p {} -i", shell_quote(SUDO_PROMPT)),
+        (ElevationMethod::Su, Some(user)) => format!("su - {}", shell_quote(user)),
+        (ElevationMethod::Su, None) => "su -".to_string(),
+    }
+}
+
+/// What the PTY runs instead of the login shell: the shell override, or
+/// else the elevation command.
+pub(crate) fn pty_command(server: &ServerConnection) -> Option<String> {
+    server
+        .shell
+        .clone()
+        .or_else(|| server.elevate.as_ref().map(elevation_command))
+}
+
+/// Answers the first password prompt after an elevated shell opens with the
+/// saved elevation password, once. A second prompt means the password was
+/// wrong and is left to the user.
+pub(crate) struct ElevationWatcher {
+    method: ElevationMethod,
+    password: String,
+    /// The line being written, or its end once it grew past the cap.
+    line: String,
+    line_cut: bool,
+    deadline: Instant,
+    done: bool,
+}
+
+impl ElevationWatcher {
+    fn new(method: ElevationMethod, password: String) -> Self {
+        Self {
+            method,
+            password,
+            line: String::new(),
+            line_cut: false,
+            deadline: Instant::now() + PROMPT_WINDOW,
+            done: false,
+        }
+    }
+
+    fn at_prompt(&self) -> bool {
+        match self.method {
+            ElevationMethod::Sudo => self.line.ends_with(SUDO_PROMPT_MARKER),
+            ElevationMethod::Su => !self.line_cut && self.line.trim_end() == SU_PROMPT,
+        }
+    }
+
+    /// Input to send in reply to `output`, if it ends in the prompt.
+    pub(crate) fn scan(&mut self, output: &str) -> Option<String> {
+        if self.done {
+            return None;
+        }
+        if Instant::now() > self.deadline {
+            self.done = true;
+            return None;
+        }
+        match output.rfind(['\r', '\n']) {
+            Some(end) => {
+                self.line = output[end + 1..].to_string();
+                self.line_cut = false;
+            }
+            None => self.line.push_str(output),
+        }
+        if self.line.len() > MAX_LINE_BYTES {
+            let mut start = self.line.len() - MAX_LINE_BYTES;
+            while !self.line.is_char_boundary(start) {
+                start += 1;
+            }
+            self.line.drain(..start);
+            self.line_cut = true;
+        }
+        if !self.at_prompt() {
+            return None;
+        }
+        self.done = true;
+        Some(format!("{}\r", self.password))
+    }
+}
+
+/// A watcher for a shell on `server_id` that is running the server's
+/// elevation command with a saved password.
+pub(crate) fn watcher_for(
+    app: &AppHandle,
+    server_id: &str,
+    command: Option<&str>,
+) -> Option<ElevationWatcher> {
+    let server = saved_server(app, server_id)?;
+    let elevation = server.elevate.as_ref()?;
+    if server.shell.is_some() || command != Some(elevation_command(elevation).as_str()) {
+        return None;
+    }
+    let secret_id = elevation.secret_id.as_deref()?;
+    match get_secret(app, secret_id) {
+        Ok(password) => Some(ElevationWatcher::new(elevation.method, password)),
+        Err(e) => {
+            debug!(server_id, error = %e, "Elevation password unavailable");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elevation_command_and_watcher_answer_once() {
+        let su = Elevation {
+            method: ElevationMethod::Su,
+            user: Some("postgres".to_string()),
+            secret_id: None,
+        };
+        assert_eq!(elevation_command(&su), "su - 'postgres'");
+        let sudo = Elevation {
+            method: ElevationMethod::Sudo,
+            user: None,
+            secret_id: None,
+        };
+        assert_eq!(
+            elevation_command(&sudo),
+            "sudo -p '[sudo] password for %p (ssh-thing): ' -i"
+        );
+
+        let mut watcher = ElevationWatcher::new(ElevationMethod::Sudo, "hunter2".to_string());
+        assert_eq!(watcher.scan("Last login: Mon\r\n"), None);
+        assert_eq!(watcher.scan("Current password: "), None);
+        assert_eq!(watcher.scan("\r\n[sudo] password for "), None);
+        assert_eq!(
+            watcher.scan("deploy (ssh-thing): "),
+            Some("hunter2\r".to_string())
+        );
+        assert_eq!(watcher.scan("\r\nSorry, try again.\r\nPassword: "), None);
+
+        let mut watcher = ElevationWatcher::new(ElevationMethod::Su, "hunter2".to_string());
+        assert_eq!(watcher.scan("deploy@host's password: "), None);
+        assert_eq!(watcher.scan("\r\nNew password: "), None);
+        assert_eq!(
+            watcher.scan("\r\nPassword: "),
+            Some("hunter2\r".to_string())
+        );
+    }
+}
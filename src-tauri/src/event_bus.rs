@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+// Per stream, so a chatty shell cannot push other streams' events out.
+const MAX_BUFFERED_EVENTS: usize = 2000;
+// Streams of shells and connections closed long ago are dropped past this.
+const MAX_STREAMS: usize = 256;
+const GLOBAL_STREAM: &str = "global";
+
+/// An event as it was sent to the webview.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BusEvent {
+    /// Position in `stream`; each stream counts from 1.
+    pub seq: u64,
+    /// The shell or connection the event belongs to, or `global`.
+    pub stream: String,
+    pub event: String,
+    pub payload: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayedEvents {
+    pub events: Vec<BusEvent>,
+    /// False when events of the stream after `since_seq` were already
+    /// dropped from its buffer, so the webview should reload its state
+    /// instead.
+    pub complete: bool,
+    pub latest_seq: u64,
+}
+
+#[derive(Default)]
+struct StreamState {
+    last_seq: u64,
+    buffer: VecDeque<BusEvent>,
+    // When the stream last had an event, for dropping idle streams.
+    touched: u64,
+}
+
+#[derive(Default)]
+struct BusState {
+    ticks: u64,
+    streams: HashMap<String, StreamState>,
+}
+
+/// Numbers every event sent to the webview within its stream and keeps the
+/// most recent ones of each, so a reloaded webview can catch up with
+/// `replay_events`.
+#[derive(Default)]
+pub struct EventBus {
+    state: Mutex<BusState>,
+}
+
+fn stream_of(payload: &Value) -> String {
+    ["shell_id", "connection_id"]
+        .iter()
+        .find_map(|key| payload.get(key).and_then(Value::as_str))
+        .unwrap_or(GLOBAL_STREAM)
+        .to_string()
+}
+
+impl EventBus {
+    /// Stamps object payloads with `event_seq` and `event_stream` and
+    /// buffers the event in its stream.
+    fn record(&self, event: &str, mut payload: Value) -> Value {
+        let stream = stream_of(&payload);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.ticks += 1;
+        let touched = state.ticks;
+        if !state.streams.contains_key(&stream) && state.streams.len() >= MAX_STREAMS {
+            let idle = state
+                .streams
+                .iter()
+                .filter(|(name, _)| name.as_str() != GLOBAL_STREAM)
+                .min_by_key(|(_, stream)| stream.touched)
+                .map(|(name, _)| name.clone());
+            if let Some(idle) = idle {
+                state.streams.remove(&idle);
+            }
+        }
+        let entry = state.streams.entry(stream.clone()).or_default();
+        entry.last_seq += 1;
+        entry.touched = touched;
+        let seq = entry.last_seq;
+        if let Value::Object(fields) = &mut payload {
+            fields.insert("event_seq".to_string(), Value::from(seq));
+            fields.insert("event_stream".to_string(), Value::from(stream.clone()));
+        }
+
+        entry.buffer.push_back(BusEvent {
+            seq,
+            stream,
+            event: event.to_string(),
+            payload: payload.clone(),
+        });
+        if entry.buffer.len() > MAX_BUFFERED_EVENTS {
+            entry.buffer.pop_front();
+        }
+        payload
+    }
+
+    fn replay(&self, since_seq: u64, stream: &str) -> ReplayedEvents {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(stream) = state.streams.get(stream) else {
+            // Nothing recorded, or dropped as idle: only complete if the
+            // webview had seen nothing of it either.
+            return ReplayedEvents {
+                events: Vec::new(),
+                complete: since_seq == 0,
+                latest_seq: 0,
+            };
+        };
+        let oldest = stream.buffer.front().map_or(stream.last_seq + 1, |e| e.seq);
+        ReplayedEvents {
+            events: stream
+                .buffer
+                .iter()
+                .filter(|event| event.seq > since_seq)
+                .cloned()
+                .collect(),
+            complete: since_seq + 1 >= oldest,
+            latest_seq: stream.last_seq,
+        }
+    }
+}
+
+/// Stands in for `tauri::Emitter` on the app handle so every event goes
+/// through the bus on its way to the webview.
+pub trait Emitter {
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()>;
+}
+
+impl Emitter for AppHandle {
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()> {
+        let payload = serde_json::to_value(payload)?;
+        let Some(state) = self.try_state::<AppState>() else {
+            return tauri::Emitter::emit(self, event, payload);
+        };
+        // Viewers' copies go through the bus too, so they replay like the
+        // shell's own events.
+        let mirrored = state.shell_mirrors.mirror(event, &payload);
+        tauri::Emitter::emit(self, event, state.event_bus.record(event, payload))?;
+        for payload in mirrored {
+            tauri::Emitter::emit(self, event, state.event_bus.record(event, payload))?;
+        }
+        Ok(())
+    }
+}
+
+/// Events of one shell or connection sent after `since_seq`, or of the
+/// `global` stream when `stream` is left out.
+#[tauri::command]
+pub async fn replay_events(
+    app: AppHandle,
+    since_seq: u64,
+    stream: Option<String>,
+) -> Result<ReplayedEvents, String> {
+    let state = app.state::<AppState>();
+    Ok(state
+        .event_bus
+        .replay(since_seq, stream.as_deref().unwrap_or(GLOBAL_STREAM)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus_numbers_and_replays_each_stream() {
+        let bus = EventBus::default();
+
+        let output = bus.record(
+            "terminal-output",
+            serde_json::json!({ "shell_id": "shell-1", "output": "ls\r\n" }),
+        );
+        let queued = bus.record("transfer-queue", serde_json::json!({ "id": "t-1" }));
+        bus.record(
+            "terminal-output",
+            serde_json::json!({ "shell_id": "shell-1" }),
+        );
+
+        assert_eq!(output["event_seq"], 1);
+        assert_eq!(output["event_stream"], "shell-1");
+        assert_eq!(queued["event_seq"], 1);
+
+        let replayed = bus.replay(1, "shell-1");
+        assert!(replayed.complete);
+        assert_eq!(replayed.latest_seq, 2);
+        assert_eq!(replayed.events.len(), 1);
+        assert_eq!(replayed.events[0].seq, 2);
+
+        for _ in 0..=MAX_BUFFERED_EVENTS {
+            bus.record(
+                "terminal-output",
+                serde_json::json!({ "shell_id": "shell-2" }),
+            );
+        }
+        assert!(!bus.replay(0, "shell-2").complete);
+        assert!(bus.replay(1, "shell-2").complete);
+        let global = bus.replay(0, GLOBAL_STREAM);
+        assert!(global.complete);
+        assert_eq!(global.events.len(), 1);
+        assert!(bus.replay(0, "shell-9").complete);
+    }
+}
@@ -0,0 +1,757 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::net::{CountingStream, TrafficCounters};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::io::{copy_bidirectional, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{saved_server, AppState, ForwardProfile, PortForward};
+
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+// How many ports above a busy one are tried before giving up.
+const PORT_SEARCH_RANGE: u16 = 100;
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_NO_AUTH: u8 = 0;
+const SOCKS_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS_CONNECT: u8 = 1;
+const SOCKS_SUCCEEDED: u8 = 0;
+const SOCKS_GENERAL_FAILURE: u8 = 1;
+const SOCKS_NOT_ALLOWED: u8 = 2;
+const SOCKS_COMMAND_NOT_SUPPORTED: u8 = 7;
+const SOCKS_ADDRESS_NOT_SUPPORTED: u8 = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveForward {
+    pub id: String,
+    pub connection_id: String,
+    pub server_id: String,
+    /// The saved profile the forward was started from, if any.
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub forward: PortForward,
+    /// The port actually listened on; differs from `forward.local_port`
+    /// when a busy port was reassigned.
+    pub bound_port: u16,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ForwardState {
+    Started,
+    Stopped,
+    Failed,
+}
+
+/// Payload of `port-forward`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardEvent {
+    pub forward: ActiveForward,
+    pub state: ForwardState,
+    pub error: Option<String>,
+}
+
+/// Payload of `port-forward-conflict`, sent when the requested local port
+/// was already taken. `bound_port` is unset when no port was reassigned.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardConflictEvent {
+    pub connection_id: String,
+    pub server_id: String,
+    pub requested_port: u16,
+    pub bound_port: Option<u16>,
+}
+
+/// Payload of `port-forward-denied`, sent when a client asks for a
+/// destination outside the forward's allowlist.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardDeniedEvent {
+    pub forward_id: String,
+    pub connection_id: String,
+    pub server_id: String,
+    pub host: String,
+    pub port: u16,
+}
+
+/// Usage of one forward. Also the payload of `forward-stats`, sent
+/// periodically while the numbers change.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForwardStats {
+    pub forward_id: String,
+    pub connection_id: String,
+    pub server_id: String,
+    pub bound_port: u16,
+    pub active_connections: u64,
+    pub total_connections: u64,
+    /// Bytes the local clients sent through the tunnel.
+    pub bytes_sent: u64,
+    /// Bytes that came back through the tunnel to the local clients.
+    pub bytes_received: u64,
+    #[serde(default)]
+    pub last_connection_at: Option<u64>,
+}
+
+#[derive(Default)]
+struct ForwardUsage {
+    // Counted on the local socket: in is what clients send, out what they get.
+    traffic: Arc<TrafficCounters>,
+    active: AtomicU64,
+    total: AtomicU64,
+    last_connection_at: AtomicU64,
+}
+
+impl ForwardUsage {
+    fn connection_opened(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.active.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(1, Ordering::Relaxed);
+        self.last_connection_at.store(now, Ordering::Relaxed);
+    }
+
+    fn connection_closed(&self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, forward: &ActiveForward) -> ForwardStats {
+        let last_connection_at = self.last_connection_at.load(Ordering::Relaxed);
+        ForwardStats {
+            forward_id: forward.id.clone(),
+            connection_id: forward.connection_id.clone(),
+            server_id: forward.server_id.clone(),
+            bound_port: forward.bound_port,
+            active_connections: self.active.load(Ordering::Relaxed),
+            total_connections: self.total.load(Ordering::Relaxed),
+            bytes_sent: self.traffic.bytes_in(),
+            bytes_received: self.traffic.bytes_out(),
+            last_connection_at: (last_connection_at > 0).then_some(last_connection_at),
+        }
+    }
+}
+
+struct RunningForward {
+    info: ActiveForward,
+    usage: Arc<ForwardUsage>,
+    task: JoinHandle<()>,
+}
+
+/// Local listeners tunnelled over SSH sessions. A forward looks its session
+/// up by connection id for every new client, so it keeps working across a
+/// reconnect that reuses the id.
+#[derive(Default)]
+pub struct ForwardManager {
+    forwards: Mutex<HashMap<String, RunningForward>>,
+}
+
+fn emit_forward(
+    app: &AppHandle,
+    forward: &ActiveForward,
+    state: ForwardState,
+    error: Option<String>,
+) {
+    let payload = ForwardEvent {
+        forward: forward.clone(),
+        state,
+        error,
+    };
+    let _ = app.emit("port-forward", payload);
+}
+
+async fn session_server_id(app: &AppHandle, connection_id: &str) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let sessions = state.sessions.lock().await;
+    sessions
+        .get(connection_id)
+        .map(|session| session.server_id.clone())
+        .ok_or_else(|| format!("No active session for connection {}", connection_id))
+}
+
+async fn socks_reply<S: AsyncWrite + Unpin>(socket: &mut S, code: u8) -> Result<(), String> {
+    // The bound address is not meaningful for a tunnel, so it is left zero.
+    socket
+        .write_all(&[SOCKS_VERSION, code, 0, 1, 0, 0, 0, 0, 0, 0])
+        .await
+        .map_err(|e| format!("Failed to answer SOCKS client: {}", e))
+}
+
+/// Reads a SOCKS5 greeting and CONNECT request, returning the destination.
+/// Only unauthenticated CONNECT is supported; the listener is local.
+async fn socks_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    socket: &mut S,
+) -> Result<(String, u16), String> {
+    let read_err = |e: std::io::Error| format!("Failed to read SOCKS request: {}", e);
+    let mut header = [0u8; 2];
+    socket.read_exact(&mut header).await.map_err(read_err)?;
+    if header[0] != SOCKS_VERSION {
+        return Err(format!("Unsupported SOCKS version {}", header[0]));
+    }
+    let mut methods = vec![0u8; usize::from(header[1])];
+    socket.read_exact(&mut methods).await.map_err(read_err)?;
+    let method = if methods.contains(&SOCKS_NO_AUTH) {
+        SOCKS_NO_AUTH
+    } else {
+        SOCKS_NO_ACCEPTABLE_METHOD
+    };
+    socket
+        .write_all(&[SOCKS_VERSION, method])
+        .await
+        .map_err(|e| format!("Failed to answer SOCKS client: {}", e))?;
+    if method == SOCKS_NO_ACCEPTABLE_METHOD {
+        return Err("SOCKS client requires authentication".to_string());
+    }
+
+    let mut request = [0u8; 4];
+    socket.read_exact(&mut request).await.map_err(read_err)?;
+    if request[1] != SOCKS_CONNECT {
+        socks_reply(socket, SOCKS_COMMAND_NOT_SUPPORTED).await?;
+        return Err(format!("Unsupported SOCKS command {}", request[1]));
+    }
+    let host = match request[3] {
+        1 => {
+            let mut octets = [0u8; 4];
+            socket.read_exact(&mut octets).await.map_err(read_err)?;
+            std::net::Ipv4Addr::from(octets).to_string()
+        }
+        3 => {
+            let mut len = [0u8; 1];
+            socket.read_exact(&mut len).await.map_err(read_err)?;
+            let mut name = vec![0u8; usize::from(len[0])];
+            socket.read_exact(&mut name).await.map_err(read_err)?;
+            String::from_utf8(name).map_err(|_| "Invalid SOCKS host name".to_string())?
+        }
+        4 => {
+            let mut octets = [0u8; 16];
+            socket.read_exact(&mut octets).await.map_err(read_err)?;
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        other => {
+            socks_reply(socket, SOCKS_ADDRESS_NOT_SUPPORTED).await?;
+            return Err(format!("Unsupported SOCKS address type {}", other));
+        }
+    };
+    let mut port = [0u8; 2];
+    socket.read_exact(&mut port).await.map_err(read_err)?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+async fn tunnel(
+    app: &AppHandle,
+    forward: &ActiveForward,
+    mut socket: TcpStream,
+    peer: SocketAddr,
+    traffic: Arc<TrafficCounters>,
+) -> Result<(), String> {
+    let dynamic = forward.forward.dynamic;
+    let (host, port) = if dynamic {
+        socks_handshake(&mut socket).await?
+    } else {
+        (
+            forward.forward.remote_host.clone(),
+            forward.forward.remote_port,
+        )
+    };
+    if !forward.forward.allows(&host, port) {
+        if dynamic {
+            socks_reply(&mut socket, SOCKS_NOT_ALLOWED).await?;
+        }
+        let message = format!("Destination {}:{} is not allowed", host, port);
+        let payload = ForwardDeniedEvent {
+            forward_id: forward.id.clone(),
+            connection_id: forward.connection_id.clone(),
+            server_id: forward.server_id.clone(),
+            host,
+            port,
+        };
+        let _ = app.emit("port-forward-denied", payload);
+        return Err(message);
+    }
+
+    let handle = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        sessions
+            .get(&forward.connection_id)
+            .map(|session| session.handle.clone())
+    };
+    let opened = match handle {
+        Some(handle) => handle
+            .channel_open_direct_tcpip(
+                host,
+                u32::from(port),
+                peer.ip().to_string(),
+                u32::from(peer.port()),
+            )
+            .await
+            .map_err(|e| format!("Failed to open forwarding channel: {}", e)),
+        None => Err(format!(
+            "No active session for connection {}",
+            forward.connection_id
+        )),
+    };
+    if dynamic {
+        let code = if opened.is_ok() {
+            SOCKS_SUCCEEDED
+        } else {
+            SOCKS_GENERAL_FAILURE
+        };
+        socks_reply(&mut socket, code).await?;
+    }
+    let mut stream = opened?.into_stream();
+    let mut socket = CountingStream::new(socket, traffic);
+    copy_bidirectional(&mut socket, &mut stream)
+        .await
+        .map_err(|e| format!("Forwarded connection failed: {}", e))?;
+    Ok(())
+}
+
+async fn accept_loop(
+    app: AppHandle,
+    forward: ActiveForward,
+    listener: TcpListener,
+    usage: Arc<ForwardUsage>,
+) {
+    let mut ticker = interval(STATS_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_sent = None;
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = ticker.tick() => {
+                let stats = usage.snapshot(&forward);
+                if last_sent.as_ref() != Some(&stats) {
+                    let _ = app.emit("forward-stats", stats.clone());
+                    last_sent = Some(stats);
+                }
+                continue;
+            }
+        };
+        let (socket, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!(forward_id = %forward.id, error = %e, "Failed to accept forwarded connection");
+                continue;
+            }
+        };
+        let app = app.clone();
+        let forward = forward.clone();
+        let usage = usage.clone();
+        tokio::spawn(async move {
+            usage.connection_opened();
+            let result = tunnel(&app, &forward, socket, peer, usage.traffic.clone()).await;
+            usage.connection_closed();
+            if let Err(e) = result {
+                debug!(forward_id = %forward.id, error = %e, "Forwarded connection ended");
+            }
+        });
+    }
+}
+
+#[derive(Debug)]
+struct BindError {
+    /// The requested port was taken, as opposed to e.g. a bad address.
+    busy: bool,
+    message: String,
+}
+
+/// Listens on `port`, or when it is taken and `reassign` is set, on the
+/// next free port above it.
+async fn bind_listener(
+    address: &str,
+    port: u16,
+    reassign: bool,
+) -> Result<(TcpListener, u16), BindError> {
+    let busy = format!("Port {} on {} is already in use", port, address);
+    match TcpListener::bind((address, port)).await {
+        Ok(listener) => return Ok((listener, port)),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {
+            if !reassign {
+                return Err(BindError {
+                    busy: true,
+                    message: busy,
+                });
+            }
+        }
+        Err(e) => {
+            return Err(BindError {
+                busy: false,
+                message: format!("Failed to listen on {}:{}: {}", address, port, e),
+            })
+        }
+    }
+
+    let last = port.saturating_add(PORT_SEARCH_RANGE);
+    for candidate in port.saturating_add(1)..=last {
+        if let Ok(listener) = TcpListener::bind((address, candidate)).await {
+            return Ok((listener, candidate));
+        }
+    }
+    Err(BindError {
+        busy: true,
+        message: format!("{}, and no free port was found up to {}", busy, last),
+    })
+}
+
+pub(crate) async fn start_forward(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: &str,
+    profile: Option<&str>,
+    forward: PortForward,
+) -> Result<ActiveForward, String> {
+    let mut info = ActiveForward {
+        id: uuid::Uuid::new_v4().to_string(),
+        connection_id: connection_id.to_string(),
+        server_id: server_id.to_string(),
+        profile: profile.map(str::to_string),
+        bound_port: forward.local_port,
+        forward,
+    };
+    let address = info
+        .forward
+        .bind_address
+        .as_deref()
+        .unwrap_or(DEFAULT_BIND_ADDRESS);
+    let requested_port = info.forward.local_port;
+    let bound = bind_listener(address, requested_port, info.forward.reassign_if_busy).await;
+    let conflict = match &bound {
+        Ok((_, port)) => (*port != requested_port).then_some(Some(*port)),
+        Err(e) => e.busy.then_some(None),
+    };
+    if let Some(bound_port) = conflict {
+        let payload = ForwardConflictEvent {
+            connection_id: connection_id.to_string(),
+            server_id: server_id.to_string(),
+            requested_port,
+            bound_port,
+        };
+        let _ = app.emit("port-forward-conflict", payload);
+    }
+    let listener = match bound {
+        Ok((listener, port)) => {
+            info.bound_port = port;
+            listener
+        }
+        Err(e) => {
+            emit_forward(app, &info, ForwardState::Failed, Some(e.message.clone()));
+            return Err(e.message);
+        }
+    };
+
+    let usage = Arc::new(ForwardUsage::default());
+    let task = tokio::spawn(accept_loop(
+        app.clone(),
+        info.clone(),
+        listener,
+        usage.clone(),
+    ));
+    let state = app.state::<AppState>();
+    state.forwards.forwards.lock().await.insert(
+        info.id.clone(),
+        RunningForward {
+            info: info.clone(),
+            usage,
+            task,
+        },
+    );
+    emit_forward(app, &info, ForwardState::Started, None);
+    Ok(info)
+}
+
+async fn stop_where(
+    app: &AppHandle,
+    matches: impl Fn(&ActiveForward) -> bool,
+) -> Vec<ActiveForward> {
+    let stopped: Vec<RunningForward> = {
+        let state = app.state::<AppState>();
+        let mut forwards = state.forwards.forwards.lock().await;
+        let ids: Vec<String> = forwards
+            .values()
+            .filter(|running| matches(&running.info))
+            .map(|running| running.info.id.clone())
+            .collect();
+        ids.iter().filter_map(|id| forwards.remove(id)).collect()
+    };
+
+    stopped
+        .into_iter()
+        .map(|running| {
+            running.task.abort();
+            emit_forward(app, &running.info, ForwardState::Stopped, None);
+            running.info
+        })
+        .collect()
+}
+
+pub(crate) async fn stop_forward(app: &AppHandle, id: &str) -> bool {
+    !stop_where(app, |info| info.id == id).await.is_empty()
+}
+
+pub(crate) async fn profile_active(app: &AppHandle, connection_id: &str, name: &str) -> bool {
+    let state = app.state::<AppState>();
+    let forwards = state.forwards.forwards.lock().await;
+    forwards.values().any(|running| {
+        running.info.connection_id == connection_id && running.info.profile.as_deref() == Some(name)
+    })
+}
+
+/// Starts every forward in the profile, or none: if one cannot listen, the
+/// ones already started are stopped again.
+pub(crate) async fn start_profile(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: &str,
+    profile: &ForwardProfile,
+) -> Result<Vec<ActiveForward>, String> {
+    let mut started = Vec::with_capacity(profile.forwards.len());
+    for forward in &profile.forwards {
+        match start_forward(
+            app,
+            connection_id,
+            server_id,
+            Some(&profile.name),
+            forward.clone(),
+        )
+        .await
+        {
+            Ok(info) => started.push(info),
+            Err(e) => {
+                stop_where(app, |info| started.iter().any(|kept| kept.id == info.id)).await;
+                return Err(format!("Profile {}: {}", profile.name, e));
+            }
+        }
+    }
+    Ok(started)
+}
+
+/// Starts the server's auto-start profiles for a new session. Profiles
+/// still running from an earlier session on the same connection are kept.
+pub(crate) async fn start_auto_profiles(app: &AppHandle, connection_id: &str, server_id: &str) {
+    let Some(server) = saved_server(app, server_id) else {
+        return;
+    };
+    for profile in server
+        .forward_profiles
+        .iter()
+        .filter(|profile| profile.auto_start)
+    {
+        if profile_active(app, connection_id, &profile.name).await {
+            continue;
+        }
+        if let Err(e) = start_profile(app, connection_id, server_id, profile).await {
+            debug!(connection_id, error = %e, "Failed to start forward profile");
+        }
+    }
+}
+
+pub(crate) async fn stop_connection(app: &AppHandle, connection_id: &str) {
+    stop_where(app, |info| info.connection_id == connection_id).await;
+}
+
+#[tauri::command]
+pub async fn list_port_forwards(app: AppHandle) -> Result<Vec<ActiveForward>, String> {
+    let state = app.state::<AppState>();
+    let forwards = state.forwards.forwards.lock().await;
+    let mut active: Vec<ActiveForward> = forwards
+        .values()
+        .map(|running| running.info.clone())
+        .collect();
+    active.sort_by_key(|info| (info.connection_id.clone(), info.forward.local_port));
+    Ok(active)
+}
+
+#[tauri::command]
+pub async fn get_forward_stats(app: AppHandle) -> Result<Vec<ForwardStats>, String> {
+    let state = app.state::<AppState>();
+    let forwards = state.forwards.forwards.lock().await;
+    let mut stats: Vec<ForwardStats> = forwards
+        .values()
+        .map(|running| running.usage.snapshot(&running.info))
+        .collect();
+    stats.sort_by_key(|stats| (stats.connection_id.clone(), stats.bound_port));
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn start_port_forward(
+    app: AppHandle,
+    connection_id: String,
+    forward: PortForward,
+) -> Result<ActiveForward, String> {
+    let server_id = session_server_id(&app, &connection_id).await?;
+    start_forward(&app, &connection_id, &server_id, None, forward).await
+}
+
+#[tauri::command]
+pub async fn stop_port_forward(app: AppHandle, id: String) -> Result<(), String> {
+    if !stop_forward(&app, &id).await {
+        return Err(format!("Port forward with id {} not found", id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_forward_profile(
+    app: AppHandle,
+    connection_id: String,
+    name: String,
+) -> Result<Vec<ActiveForward>, String> {
+    let server_id = session_server_id(&app, &connection_id).await?;
+    let server = saved_server(&app, &server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    let profile = server
+        .forward_profiles
+        .iter()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| format!("Forward profile {} not found", name))?;
+    if profile_active(&app, &connection_id, &name).await {
+        return Err(format!("Forward profile {} is already running", name));
+    }
+    start_profile(&app, &connection_id, &server_id, profile).await
+}
+
+#[tauri::command]
+pub async fn stop_forward_profile(
+    app: AppHandle,
+    connection_id: String,
+    name: String,
+) -> Result<Vec<ActiveForward>, String> {
+    let stopped = stop_where(&app, |info| {
+        info.connection_id == connection_id && info.profile.as_deref() == Some(name.as_str())
+    })
+    .await;
+    if stopped.is_empty() {
+        return Err(format!("Forward profile {} is not running", name));
+    }
+    Ok(stopped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_profile_defaults() {
+        let profile: ForwardProfile = serde_json::from_str(
+            r#"{
+                "name": "dev DB set",
+                "forwards": [
+                    { "local_port": 5432, "remote_host": "db", "remote_port": 5432 },
+                    { "local_port": 6379, "remote_host": "redis", "remote_port": 6379 }
+                ]
+            }"#,
+        )
+        .expect("Failed to parse forward profile");
+
+        assert!(!profile.auto_start);
+        assert_eq!(profile.forwards.len(), 2);
+        assert_eq!(profile.forwards[1].remote_host, "redis");
+        assert!(profile.forwards[0].bind_address.is_none());
+    }
+
+    #[test]
+    fn test_forward_usage_snapshot_counts_connections() {
+        let usage = ForwardUsage::default();
+        let forward = ActiveForward {
+            id: "forward-1".to_string(),
+            connection_id: "conn-1".to_string(),
+            server_id: "server-1".to_string(),
+            profile: None,
+            forward: PortForward {
+                local_port: 5432,
+                remote_host: "db".to_string(),
+                remote_port: 5432,
+                bind_address: None,
+                reassign_if_busy: false,
+                dynamic: false,
+                allowed_destinations: Vec::new(),
+            },
+            bound_port: 5432,
+        };
+
+        assert_eq!(usage.snapshot(&forward).last_connection_at, None);
+        usage.connection_opened();
+        usage.connection_opened();
+        usage.connection_closed();
+        let stats = usage.snapshot(&forward);
+
+        assert_eq!(stats.active_connections, 1);
+        assert_eq!(stats.total_connections, 2);
+        assert!(stats.last_connection_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_socks_handshake_reads_domain_destination() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(&[5, 1, 0, 5, 1, 0, 3, 9])
+            .await
+            .expect("Failed to write greeting");
+        client
+            .write_all(b"wiki.corp")
+            .await
+            .expect("Failed to write host");
+        client
+            .write_all(&[0x01, 0xbb])
+            .await
+            .expect("Failed to write port");
+
+        let destination = socks_handshake(&mut server)
+            .await
+            .expect("Failed to read SOCKS request");
+        assert_eq!(destination, ("wiki.corp".to_string(), 443));
+
+        let mut method = [0u8; 2];
+        client
+            .read_exact(&mut method)
+            .await
+            .expect("Failed to read method");
+        assert_eq!(method, [5, SOCKS_NO_AUTH]);
+
+        let forward = PortForward {
+            local_port: 1080,
+            remote_host: String::new(),
+            remote_port: 0,
+            bind_address: None,
+            reassign_if_busy: false,
+            dynamic: true,
+            allowed_destinations: vec![crate::ForwardDestination {
+                host: "*.corp.internal".to_string(),
+                ports: vec![443],
+            }],
+        };
+        assert!(forward.allows("grafana.corp.internal", 443));
+        assert!(!forward.allows("grafana.corp.internal", 22));
+        assert!(!forward.allows("corp.internal", 443));
+        assert!(!forward.allows("example.com", 443));
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_reassigns_busy_port() {
+        let taken = TcpListener::bind((DEFAULT_BIND_ADDRESS, 0))
+            .await
+            .expect("Failed to bind test listener");
+        let port = taken.local_addr().expect("No local address").port();
+
+        let error = bind_listener(DEFAULT_BIND_ADDRESS, port, false)
+            .await
+            .expect_err("Busy port should not be bound");
+        assert!(error.busy);
+
+        let (_listener, bound_port) = bind_listener(DEFAULT_BIND_ADDRESS, port, true)
+            .await
+            .expect("Failed to reassign busy port");
+        assert_ne!(bound_port, port);
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::storage::{self, KNOWN_HOSTS_FILE, SNIPPETS_FILE};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::settings::load_settings;
+use crate::{get_app_dir, load_known_hosts, load_servers, load_snippets, AuthMethod, SERVERS_FILE};
+
+const SYNC_REPO_DIR: &str = "sync-repo";
+// Secrets stay in the keychain; server records only carry references to them.
+const SYNCED_FILES: [&str; 3] = [SERVERS_FILE, SNIPPETS_FILE, KNOWN_HOSTS_FILE];
+const COMMIT_MESSAGE: &str = "Update ssh-thing configuration";
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GitSyncSettings {
+    pub remote_url: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitSyncResult {
+    /// Files updated locally from the repository.
+    pub pulled: Vec<String>,
+    pub pushed: bool,
+    /// Files edited on both sides since the last sync. Nothing is pulled or
+    /// pushed until they are resolved in the repository.
+    pub conflicts: Vec<String>,
+    /// Local files replaced by the repository's copy when this machine was
+    /// first linked. The old contents are kept next to them as `.before-sync`.
+    pub replaced: Vec<String>,
+}
+
+async fn git(repo: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        // Fail instead of waiting on a credential prompt nobody can see.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn files_differ(a: &Path, b: &Path) -> bool {
+    fs::read(a).ok() != fs::read(b).ok()
+}
+
+// Writes this machine's files into the repository, leaving out any that do
+// not exist yet so they cannot replace the other machine's copy.
+fn export_files(app: &AppHandle, app_dir: &Path, repo: &Path) -> Result<(), String> {
+    if app_dir.join(SERVERS_FILE).exists() {
+        let servers = load_servers(app_dir, app)?;
+        if servers
+            .iter()
+            .any(|server| !matches!(server.auth, AuthMethod::SecretRef { .. }))
+        {
+            return Err("Refusing to sync servers that still hold inline credentials".to_string());
+        }
+        storage::save_servers(repo, &servers)?;
+    }
+    if app_dir.join(SNIPPETS_FILE).exists() {
+        storage::save_snippets(repo, &load_snippets(app_dir)?)?;
+    }
+    if app_dir.join(KNOWN_HOSTS_FILE).exists() {
+        storage::save_known_hosts(repo, &load_known_hosts(app_dir)?)?;
+    }
+    Ok(())
+}
+
+// Copies the repository's files over the local ones that differ. Files that
+// no longer parse are left alone.
+fn import_files(app_dir: &Path, repo: &Path, backup: bool) -> Result<Vec<String>, String> {
+    let mut updated = Vec::new();
+    for file in SYNCED_FILES {
+        let source = repo.join(file);
+        let target = app_dir.join(file);
+        if !source.exists() || !files_differ(&source, &target) {
+            continue;
+        }
+        let valid = match file {
+            SERVERS_FILE => storage::load_servers(repo).map(|_| ()),
+            SNIPPETS_FILE => storage::load_snippets(repo).map(|_| ()),
+            _ => storage::load_known_hosts(repo).map(|_| ()),
+        };
+        if let Err(e) = valid {
+            debug!(file, error = %e, "Skipping synced file that does not parse");
+            continue;
+        }
+        if backup && target.exists() {
+            fs::copy(&target, app_dir.join(format!("{}.before-sync", file)))
+                .map_err(|e| format!("Failed to back up {}: {}", file, e))?;
+        }
+        fs::copy(&source, &target).map_err(|e| format!("Failed to update {}: {}", file, e))?;
+        updated.push(file.to_string());
+    }
+    Ok(updated)
+}
+
+// Returns whether the repository has no commits yet, i.e. this machine has
+// never synced successfully.
+async fn prepare_repo(repo: &Path, settings: &GitSyncSettings) -> Result<bool, String> {
+    if !repo.join(".git").exists() {
+        fs::create_dir_all(repo).map_err(|e| format!("Failed to create sync repository: {}", e))?;
+        git(repo, &["init", "--quiet"]).await?;
+        git(repo, &["remote", "add", "origin", &settings.remote_url]).await?;
+        git(repo, &["checkout", "--quiet", "-B", &settings.branch]).await?;
+        // Commits are made by the app, not by whoever owns the global config.
+        git(repo, &["config", "user.name", "ssh-thing"]).await?;
+        git(repo, &["config", "user.email", "ssh-thing@localhost"]).await?;
+    } else {
+        git(repo, &["remote", "set-url", "origin", &settings.remote_url]).await?;
+    }
+    let has_commits = git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await
+        .is_ok();
+    Ok(!has_commits)
+}
+
+async fn commit_local_changes(repo: &Path) -> Result<(), String> {
+    let mut add = vec!["add", "--"];
+    add.extend(SYNCED_FILES);
+    git(repo, &add).await?;
+    if git(repo, &["status", "--porcelain"])
+        .await?
+        .trim()
+        .is_empty()
+    {
+        return Ok(());
+    }
+    git(repo, &["commit", "--quiet", "-m", COMMIT_MESSAGE]).await?;
+    Ok(())
+}
+
+/// Commits local changes, merges the remote branch, copies the result back
+/// into the app and pushes. A merge conflict stops the sync and is reported
+/// instead, leaving local files untouched.
+pub async fn sync(app: &AppHandle) -> Result<GitSyncResult, String> {
+    let app_dir = get_app_dir(app)?;
+    let settings = load_settings(&app_dir)?
+        .git_sync
+        .ok_or_else(|| "Git sync is not configured".to_string())?;
+    let repo = app_dir.join(SYNC_REPO_DIR);
+    let mut result = GitSyncResult::default();
+
+    let first_sync = prepare_repo(&repo, &settings).await?;
+    git(&repo, &["fetch", "--quiet", "origin"]).await?;
+    let remote_ref = format!("origin/{}", settings.branch);
+    let remote_exists = git(&repo, &["rev-parse", "--verify", "--quiet", &remote_ref])
+        .await
+        .is_ok();
+
+    if first_sync && remote_exists {
+        // The first link adopts what the other machines already share.
+        git(&repo, &["reset", "--quiet", "--hard", &remote_ref]).await?;
+        result.replaced = import_files(&app_dir, &repo, true)?;
+        return Ok(result);
+    }
+
+    export_files(app, &app_dir, &repo)?;
+    commit_local_changes(&repo).await?;
+
+    if remote_exists {
+        if let Err(e) = git(&repo, &["merge", "--quiet", "--no-edit", &remote_ref]).await {
+            let conflicts = lines(&git(&repo, &["diff", "--name-only", "--diff-filter=U"]).await?);
+            if conflicts.is_empty() {
+                return Err(e);
+            }
+            git(&repo, &["merge", "--abort"]).await?;
+            result.conflicts = conflicts;
+            return Ok(result);
+        }
+        result.pulled = import_files(&app_dir, &repo, false)?;
+    }
+
+    let ahead = if remote_exists {
+        let range = format!("{}..HEAD", remote_ref);
+        git(&repo, &["rev-list", "--count", &range]).await?.trim() != "0"
+    } else {
+        git(&repo, &["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .is_ok()
+    };
+    if ahead {
+        let refspec = format!("HEAD:refs/heads/{}", settings.branch);
+        git(&repo, &["push", "--quiet", "origin", &refspec]).await?;
+        result.pushed = true;
+    }
+    Ok(result)
+}
+
+/// Pulls on launch when sync is set up. The outcome, including conflicts, is
+/// reported as a `config-sync` event.
+pub fn sync_on_launch(app: &AppHandle) {
+    let configured = get_app_dir(app)
+        .and_then(|app_dir| load_settings(&app_dir))
+        .is_ok_and(|settings| settings.git_sync.is_some());
+    if !configured {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match sync(&app).await {
+            Ok(result) => {
+                let _ = app.emit("config-sync", result);
+            }
+            Err(e) => debug!(error = %e, "Git sync on launch failed"),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn sync_config(app: AppHandle) -> Result<GitSyncResult, String> {
+    sync(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_sync_settings_default_to_main_branch() {
+        let settings: GitSyncSettings =
+            serde_json::from_str(r#"{"remote_url":"git@example.com:me/config.git"}"#).unwrap();
+
+        assert_eq!(settings.branch, "main");
+    }
+}
@@ -0,0 +1,212 @@
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, timeout, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{get_app_dir, load_servers, AppState, ServerConnection};
+
+const DEFAULT_HEALTH_INTERVAL_SECONDS: u64 = 60;
+const MIN_HEALTH_INTERVAL_SECONDS: u64 = 5;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerHealth {
+    pub server_id: String,
+    pub status: HealthStatus,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub checked_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthMonitorStatus {
+    pub running: bool,
+    #[serde(default)]
+    pub interval_seconds: Option<u64>,
+}
+
+/// Background TCP reachability checks for saved servers. Nothing is sent
+/// beyond the TCP handshake, so no credentials are involved.
+#[derive(Default)]
+pub struct HealthMonitor {
+    task: Mutex<Option<(JoinHandle<()>, u64)>>,
+    results: Mutex<HashMap<String, ServerHealth>>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+async fn probe_server(server: &ServerConnection) -> ServerHealth {
+    let started = Instant::now();
+    let result = timeout(
+        PROBE_TIMEOUT,
+        TcpStream::connect((server.host.as_str(), server.port)),
+    )
+    .await;
+
+    let (status, latency_ms, error) = match result {
+        Ok(Ok(_stream)) => (
+            HealthStatus::Up,
+            Some(started.elapsed().as_millis() as u64),
+            None,
+        ),
+        Ok(Err(e)) => (HealthStatus::Down, None, Some(e.to_string())),
+        Err(_) => (
+            HealthStatus::Down,
+            None,
+            Some(format!(
+                "Timed out after {} seconds",
+                PROBE_TIMEOUT.as_secs()
+            )),
+        ),
+    };
+
+    ServerHealth {
+        server_id: server.id.clone(),
+        status,
+        latency_ms,
+        error,
+        checked_at: now_secs(),
+    }
+}
+
+async fn check_all(app: &AppHandle) -> Result<Vec<ServerHealth>, String> {
+    let app_dir = get_app_dir(app)?;
+    let servers = load_servers(&app_dir, app)?;
+    let results = join_all(servers.iter().map(probe_server)).await;
+
+    let state = app.state::<AppState>();
+    let mut stored = state.health.results.lock().await;
+    stored.retain(|server_id, _| servers.iter().any(|server| &server.id == server_id));
+    for health in &results {
+        stored.insert(health.server_id.clone(), health.clone());
+        let _ = app.emit("server-health", health.clone());
+    }
+    Ok(results)
+}
+
+async fn run_monitor(app: AppHandle, interval_seconds: u64) {
+    let mut ticker = interval(Duration::from_secs(interval_seconds));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = check_all(&app).await {
+            debug!(error = %e, "Server health check failed");
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn start_health_monitor(
+    app: AppHandle,
+    interval_seconds: Option<u64>,
+) -> Result<HealthMonitorStatus, String> {
+    let interval_seconds = interval_seconds
+        .unwrap_or(DEFAULT_HEALTH_INTERVAL_SECONDS)
+        .max(MIN_HEALTH_INTERVAL_SECONDS);
+
+    let state = app.state::<AppState>();
+    let mut task = state.health.task.lock().await;
+    if let Some((handle, _)) = task.take() {
+        handle.abort();
+    }
+    *task = Some((
+        tokio::spawn(run_monitor(app.clone(), interval_seconds)),
+        interval_seconds,
+    ));
+
+    Ok(HealthMonitorStatus {
+        running: true,
+        interval_seconds: Some(interval_seconds),
+    })
+}
+
+#[tauri::command]
+pub async fn stop_health_monitor(app: AppHandle) -> Result<HealthMonitorStatus, String> {
+    let state = app.state::<AppState>();
+    let mut task = state.health.task.lock().await;
+    if let Some((handle, _)) = task.take() {
+        handle.abort();
+    }
+    Ok(HealthMonitorStatus {
+        running: false,
+        interval_seconds: None,
+    })
+}
+
+#[tauri::command]
+pub async fn get_health_monitor_status(app: AppHandle) -> Result<HealthMonitorStatus, String> {
+    let state = app.state::<AppState>();
+    let task = state.health.task.lock().await;
+    let interval_seconds = task
+        .as_ref()
+        .filter(|(handle, _)| !handle.is_finished())
+        .map(|(_, interval_seconds)| *interval_seconds);
+    Ok(HealthMonitorStatus {
+        running: interval_seconds.is_some(),
+        interval_seconds,
+    })
+}
+
+#[tauri::command]
+pub async fn get_server_health(app: AppHandle) -> Result<Vec<ServerHealth>, String> {
+    let state = app.state::<AppState>();
+    let results = state.health.results.lock().await;
+    Ok(results.values().cloned().collect())
+}
+
+#[tauri::command]
+pub async fn check_server_health(app: AppHandle) -> Result<Vec<ServerHealth>, String> {
+    check_all(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn server(port: u16) -> ServerConnection {
+        serde_json::from_value(serde_json::json!({
+            "id": "probe",
+            "host": "127.0.0.1",
+            "port": port,
+            "user": "dev",
+            "auth": { "type": "SecretRef", "secret_id": "unused" },
+        }))
+        .expect("server json")
+    }
+
+    #[tokio::test]
+    async fn test_probe_server_reports_up_and_down() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("local addr").port();
+
+        let health = probe_server(&server(port)).await;
+        assert_eq!(health.status, HealthStatus::Up);
+        assert!(health.latency_ms.is_some());
+
+        drop(listener);
+        let health = probe_server(&server(port)).await;
+        assert_eq!(health.status, HealthStatus::Down);
+        assert!(health.error.is_some());
+    }
+}
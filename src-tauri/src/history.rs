@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::redaction::redactor;
+use crate::{get_app_dir, parse_json_array_lenient};
+
+const COMMAND_HISTORY_FILE: &str = "command-history.json";
+const MAX_ENTRIES_PER_SERVER: usize = 2000;
+const DEFAULT_HISTORY_LIMIT: usize = 200;
+const MAX_PROMPT_TAIL_CHARS: usize = 256;
+
+/// Changes to the history file, applied in order by one writer thread so
+/// typing never waits on the disk and concurrent shells cannot lose each
+/// other's commands.
+enum HistoryWrite {
+    Record {
+        app_dir: PathBuf,
+        server_id: String,
+        commands: Vec<String>,
+        executed_at: u64,
+    },
+    Clear {
+        app_dir: PathBuf,
+        server_id: Option<String>,
+        done: oneshot::Sender<Result<(), String>>,
+    },
+}
+
+static HISTORY_WRITER: OnceLock<mpsc::Sender<HistoryWrite>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandHistoryEntry {
+    pub server_id: String,
+    pub command: String,
+    pub executed_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum EscapeState {
+    #[default]
+    None,
+    Escape,
+    Csi,
+}
+
+/// Rebuilds command lines from raw keystrokes sent to a shell. Line editing
+/// keys are applied; cursor movement and history recall are ignored, so the
+/// result is best effort for lines edited that way.
+#[derive(Debug, Clone, Default)]
+pub struct InputLineTracker {
+    line: String,
+    escape: EscapeState,
+}
+
+impl InputLineTracker {
+    pub fn push(&mut self, input: &str) -> Vec<String> {
+        let mut completed = Vec::new();
+        for ch in input.chars() {
+            match self.escape {
+                EscapeState::Escape => {
+                    self.escape = if ch == '[' {
+                        EscapeState::Csi
+                    } else {
+                        EscapeState::None
+                    };
+                    continue;
+                }
+                EscapeState::Csi => {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        self.escape = EscapeState::None;
+                    }
+                    continue;
+                }
+                EscapeState::None => {}
+            }
+
+            match ch {
+                '\r' | '\n' => {
+                    let line = self.line.trim().to_string();
+                    self.line.clear();
+                    if !line.is_empty() {
+                        completed.push(line);
+                    }
+                }
+                '\x7f' | '\x08' => {
+                    self.line.pop();
+                }
+                // Ctrl-C and Ctrl-U abandon the line.
+                '\x03' | '\x15' => self.line.clear(),
+                // Ctrl-W deletes the previous word.
+                '\x17' => {
+                    let trimmed = self.line.trim_end().len();
+                    self.line.truncate(trimmed);
+                    let start = self.line.rfind(' ').map_or(0, |index| index + 1);
+                    self.line.truncate(start);
+                }
+                '\x1b' => self.escape = EscapeState::Escape,
+                '\t' => self.line.push(' '),
+                ch if ch.is_control() => {}
+                ch => self.line.push(ch),
+            }
+        }
+        completed
+    }
+}
+
+/// Keeps the last partial line of terminal output, which is where a shell or
+/// program prints the prompt the next input answers.
+pub fn update_prompt_tail(tail: &mut String, output: &str) {
+    match output.rfind(['\n', '\r']) {
+        Some(index) => {
+            tail.clear();
+            tail.push_str(&output[index + 1..]);
+        }
+        None => tail.push_str(output),
+    }
+    let excess = tail.chars().count().saturating_sub(MAX_PROMPT_TAIL_CHARS);
+    if excess > 0 {
+        let cut = tail
+            .char_indices()
+            .nth(excess)
+            .map_or(tail.len(), |(index, _)| index);
+        tail.drain(..cut);
+    }
+}
+
+/// Input typed at a password prompt must never end up in history.
+pub fn is_secret_prompt(tail: &str) -> bool {
+    let lower = tail.to_lowercase();
+    let trimmed = lower.trim_end();
+    (trimmed.contains("password") || trimmed.contains("passphrase")) && trimmed.ends_with(':')
+}
+
+fn get_command_history_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(COMMAND_HISTORY_FILE)
+}
+
+pub(crate) fn load_command_history(app_dir: &Path) -> Result<Vec<CommandHistoryEntry>, String> {
+    let path = get_command_history_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read command history file: {}", e))?;
+    parse_json_array_lenient(&data, "command history")
+}
+
+fn save_command_history(app_dir: &Path, entries: &[CommandHistoryEntry]) -> Result<(), String> {
+    let path = get_command_history_path(app_dir);
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Invalid path for command history file".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize command history: {}", e))?;
+    // Replaced in one step so a reader never sees a half-written file.
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)
+        .map_err(|e| format!("Failed to write command history file: {}", e))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to write command history file: {}", e))?;
+    Ok(())
+}
+
+fn append_entry(entries: &mut Vec<CommandHistoryEntry>, entry: CommandHistoryEntry) {
+    let last_for_server = entries
+        .iter()
+        .rev()
+        .find(|existing| existing.server_id == entry.server_id);
+    if last_for_server.is_some_and(|existing| existing.command == entry.command) {
+        return;
+    }
+
+    let server_id = entry.server_id.clone();
+    entries.push(entry);
+
+    let count = entries
+        .iter()
+        .filter(|existing| existing.server_id == server_id)
+        .count();
+    let mut excess = count.saturating_sub(MAX_ENTRIES_PER_SERVER);
+    entries.retain(|existing| {
+        if excess > 0 && existing.server_id == server_id {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+fn record_in(
+    app_dir: &Path,
+    server_id: &str,
+    commands: Vec<String>,
+    executed_at: u64,
+) -> Result<(), String> {
+    let mut entries = load_command_history(app_dir)?;
+    let redactor = redactor(app_dir);
+    for command in commands {
+        append_entry(
+            &mut entries,
+            CommandHistoryEntry {
+                server_id: server_id.to_string(),
+                command: redactor.redact(&command),
+                executed_at,
+            },
+        );
+    }
+    save_command_history(app_dir, &entries)
+}
+
+fn clear_in(app_dir: &Path, server_id: Option<&str>) -> Result<(), String> {
+    let mut entries = load_command_history(app_dir)?;
+    match server_id {
+        Some(server_id) => entries.retain(|entry| entry.server_id != server_id),
+        None => entries.clear(),
+    }
+    save_command_history(app_dir, &entries)
+}
+
+fn history_writer() -> &'static mpsc::Sender<HistoryWrite> {
+    HISTORY_WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for write in rx {
+                match write {
+                    HistoryWrite::Record {
+                        app_dir,
+                        server_id,
+                        commands,
+                        executed_at,
+                    } => {
+                        if let Err(e) = record_in(&app_dir, &server_id, commands, executed_at) {
+                            debug!(server_id = %server_id, error = %e, "Failed to record command history");
+                        }
+                    }
+                    HistoryWrite::Clear {
+                        app_dir,
+                        server_id,
+                        done,
+                    } => {
+                        let _ = done.send(clear_in(&app_dir, server_id.as_deref()));
+                    }
+                }
+            }
+        });
+        tx
+    })
+}
+
+/// Queues `commands` for the history file; the write happens off the
+/// shell's task.
+pub fn record_commands(app: &AppHandle, server_id: &str, commands: Vec<String>) {
+    if commands.is_empty() {
+        return;
+    }
+
+    let result = get_app_dir(app).and_then(|app_dir| {
+        let executed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("Time error: {}", e))?
+            .as_secs();
+        history_writer()
+            .send(HistoryWrite::Record {
+                app_dir,
+                server_id: server_id.to_string(),
+                commands,
+                executed_at,
+            })
+            .map_err(|_| "Command history writer stopped".to_string())
+    });
+
+    if let Err(e) = result {
+        debug!(server_id, error = %e, "Failed to record command history");
+    }
+}
+
+fn search_history(
+    entries: Vec<CommandHistoryEntry>,
+    server_id: &str,
+    query: Option<&str>,
+    limit: usize,
+) -> Vec<CommandHistoryEntry> {
+    let query = query
+        .map(|q| q.trim().to_lowercase())
+        .filter(|q| !q.is_empty());
+    entries
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.server_id == server_id)
+        .filter(|entry| {
+            query
+                .as_deref()
+                .is_none_or(|q| entry.command.to_lowercase().contains(q))
+        })
+        .take(limit)
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_command_history(
+    app: AppHandle,
+    server_id: String,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let entries = load_command_history(&app_dir)?;
+    Ok(search_history(
+        entries,
+        &server_id,
+        query.as_deref(),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    ))
+}
+
+#[tauri::command]
+pub async fn clear_command_history(
+    app: AppHandle,
+    server_id: Option<String>,
+) -> Result<(), String> {
+    let app_dir = get_app_dir(&app)?;
+    let (done, result) = oneshot::channel();
+    history_writer()
+        .send(HistoryWrite::Clear {
+            app_dir,
+            server_id,
+            done,
+        })
+        .map_err(|_| "Command history writer stopped".to_string())?;
+    result
+        .await
+        .map_err(|_| "Command history writer stopped".to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(server_id: &str, command: &str, executed_at: u64) -> CommandHistoryEntry {
+        CommandHistoryEntry {
+            server_id: server_id.to_string(),
+            command: command.to_string(),
+            executed_at,
+        }
+    }
+
+    #[test]
+    fn test_tracker_applies_line_editing() {
+        let mut tracker = InputLineTracker::default();
+
+        assert!(tracker.push("ls -la").is_empty());
+        assert_eq!(tracker.push("\x7f\x7fh\r"), vec!["ls -h"]);
+        assert_eq!(tracker.push("rm -rf foo\x17bar\n"), vec!["rm -rf bar"]);
+        assert!(tracker.push("oops\x03\r").is_empty());
+    }
+
+    #[test]
+    fn test_tracker_skips_escape_sequences() {
+        let mut tracker = InputLineTracker::default();
+
+        assert_eq!(tracker.push("git st\x1b[Datus\r"), vec!["git status"]);
+        assert_eq!(
+            tracker.push("\x1b[200~echo pasted\x1b[201~\r"),
+            vec!["echo pasted"]
+        );
+    }
+
+    #[test]
+    fn test_secret_prompt_detection() {
+        let mut tail = String::new();
+        update_prompt_tail(&mut tail, "Last login: today\r\n[sudo] password for dev: ");
+
+        assert_eq!(tail, "[sudo] password for dev: ");
+        assert!(is_secret_prompt(&tail));
+        assert!(is_secret_prompt(
+            "Enter passphrase for key '/home/dev/.ssh/id':"
+        ));
+        assert!(!is_secret_prompt("dev@host:~$ "));
+    }
+
+    #[test]
+    fn test_append_entry_skips_consecutive_duplicates() {
+        let mut entries = vec![entry("a", "ls", 1), entry("b", "pwd", 2)];
+        append_entry(&mut entries, entry("a", "ls", 3));
+        append_entry(&mut entries, entry("b", "ls", 4));
+
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn test_search_history_filters_and_orders_newest_first() {
+        let entries = vec![
+            entry("a", "systemctl status nginx", 1),
+            entry("b", "systemctl restart api", 2),
+            entry("a", "tail -f /var/log/nginx/error.log", 3),
+            entry("a", "df -h", 4),
+        ];
+
+        let found = search_history(entries, "a", Some("NGINX"), 10);
+
+        assert_eq!(
+            found,
+            vec![
+                entry("a", "tail -f /var/log/nginx/error.log", 3),
+                entry("a", "systemctl status nginx", 1),
+            ]
+        );
+    }
+}
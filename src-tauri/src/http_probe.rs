@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{timeout, Duration};
+
+use crate::AppState;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_STATUS_LINE_BYTES: usize = 8 * 1024;
+const REQUEST_HEADERS: &str = "User-Agent: ssh-thing\r\nAccept: */*\r\nConnection: close\r\n\r\n";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpProbeResult {
+    pub url: String,
+    pub status: u16,
+    pub reason: String,
+    /// From opening the channel to the status line arriving.
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct ProbeTarget {
+    host: String,
+    port: u16,
+    host_header: String,
+    path: String,
+}
+
+fn probe_target(url: &str) -> Result<ProbeTarget, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if parsed.scheme() != "http" {
+        return Err(format!(
+            "Only http:// URLs can be probed through a session, not {}://",
+            parsed.scheme()
+        ));
+    }
+    let host_str = parsed
+        .host_str()
+        .ok_or_else(|| format!("URL {} has no host", url))?;
+    // IPv6 literals keep their brackets in the Host header only.
+    let host = host_str
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = parsed.port().unwrap_or(80);
+    let host_header = match parsed.port() {
+        Some(port) => format!("{}:{}", host_str, port),
+        None => host_str.to_string(),
+    };
+    let path = match parsed.query() {
+        Some(query) => format!("{}?{}", parsed.path(), query),
+        None => parsed.path().to_string(),
+    };
+    Ok(ProbeTarget {
+        host,
+        port,
+        host_header,
+        path,
+    })
+}
+
+fn parse_status_line(line: &str) -> Result<(u16, String), String> {
+    let mut parts = line.trim_end().splitn(3, ' ');
+    let version = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/") {
+        return Err(format!("Unexpected response: {}", line.trim_end()));
+    }
+    let status = parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("Invalid status line: {}", line.trim_end()))?;
+    Ok((status, parts.next().unwrap_or_default().to_string()))
+}
+
+async fn probe(
+    app: &AppHandle,
+    server_id: &str,
+    target: &ProbeTarget,
+) -> Result<(u16, String, u64), String> {
+    let started = Instant::now();
+    let handle = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        sessions
+            .values()
+            .find(|session| session.server_id == server_id)
+            .map(|session| session.handle.clone())
+            .ok_or_else(|| format!("No active session for server {}", server_id))?
+    };
+    let channel = handle
+        .channel_open_direct_tcpip(target.host.clone(), u32::from(target.port), "127.0.0.1", 0)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to open channel to {}:{}: {}",
+                target.host, target.port, e
+            )
+        })?;
+    let mut stream = channel.into_stream();
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\n{}",
+        target.path, target.host_header, REQUEST_HEADERS
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let line_end = loop {
+        if let Some(end) = response.windows(2).position(|pair| pair == b"\r\n") {
+            break end;
+        }
+        if response.len() > MAX_STATUS_LINE_BYTES {
+            return Err("Response status line is too long".to_string());
+        }
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        if read == 0 {
+            return Err("Connection closed before a response was received".to_string());
+        }
+        response.extend_from_slice(&chunk[..read]);
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (status, reason) = parse_status_line(&String::from_utf8_lossy(&response[..line_end]))?;
+    Ok((status, reason, latency_ms))
+}
+
+/// Sends a GET to `url` from the server's side of an open session, for
+/// checking internal services without setting up a forward. Only the
+/// status line is read.
+#[tauri::command]
+pub async fn probe_http_via_session(
+    app: AppHandle,
+    server_id: String,
+    url: String,
+) -> Result<HttpProbeResult, String> {
+    let target = probe_target(&url)?;
+    let (status, reason, latency_ms) = timeout(PROBE_TIMEOUT, probe(&app, &server_id, &target))
+        .await
+        .map_err(|_| format!("Timed out after {} seconds", PROBE_TIMEOUT.as_secs()))??;
+    Ok(HttpProbeResult {
+        url,
+        status,
+        reason,
+        latency_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_target_and_status_line() {
+        let target = probe_target("http://grafana.internal:3000/api/health?full=1")
+            .expect("Failed to parse URL");
+        assert_eq!(
+            target,
+            ProbeTarget {
+                host: "grafana.internal".to_string(),
+                port: 3000,
+                host_header: "grafana.internal:3000".to_string(),
+                path: "/api/health?full=1".to_string(),
+            }
+        );
+        assert!(probe_target("https://grafana.internal/").is_err());
+
+        assert_eq!(
+            parse_status_line("HTTP/1.1 503 Service Unavailable\r\n"),
+            Ok((503, "Service Unavailable".to_string()))
+        );
+        assert!(parse_status_line("SSH-2.0-OpenSSH_9.6").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use ssh_thing_core::storage;
+use tauri::AppHandle;
+
+use crate::{
+    delete_secret, get_app_dir, get_secret, load_servers, put_secret, save_servers, AuthMethod,
+    Identity, SecretKind, ServerConnection,
+};
+
+const IDENTITY_SECRET_PREFIX: &str = "identity:";
+
+fn identity_secret_id(identity_id: &str) -> String {
+    format!("{}{}", IDENTITY_SECRET_PREFIX, identity_id)
+}
+
+/// The keyring entry that belongs to `server` alone, if any. Servers using
+/// an identity carry its secret, which must outlive them.
+pub(crate) fn own_secret_id(server: &ServerConnection) -> Option<&str> {
+    match &server.auth {
+        AuthMethod::SecretRef { secret_id, .. }
+            if server.identity_id.is_none() && !secret_id.starts_with(IDENTITY_SECRET_PREFIX) =>
+        {
+            Some(secret_id)
+        }
+        _ => None,
+    }
+}
+
+fn identity_secret(identity: &Identity) -> Option<&str> {
+    match &identity.auth {
+        AuthMethod::SecretRef { secret_id, .. } => Some(secret_id),
+        _ => None,
+    }
+}
+
+/// Keyring entries held by identities, which count as in use even when no
+/// server uses the identity yet.
+pub(crate) fn secret_ids(app: &AppHandle) -> Vec<String> {
+    let Ok(app_dir) = get_app_dir(app) else {
+        return Vec::new();
+    };
+    storage::load_identities(&app_dir)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(identity_secret)
+        .map(str::to_string)
+        .collect()
+}
+
+fn normalize_username(username: String) -> Option<String> {
+    let username = username.trim();
+    (!username.is_empty()).then(|| username.to_string())
+}
+
+/// The identity marked as the default, if any.
+pub(crate) fn default_identity(app: &AppHandle) -> Option<Identity> {
+    let app_dir = get_app_dir(app).ok()?;
+    storage::load_identities(&app_dir)
+        .ok()?
+        .into_iter()
+        .find(|identity| identity.is_default)
+}
+
+/// `server` as it connects: with its identity's credential, and the
+/// identity's user when it has one. Identities are applied here rather than
+/// when servers load, so saving a server never writes them into it.
+pub(crate) fn with_identity(
+    app: &AppHandle,
+    server: &ServerConnection,
+) -> Result<ServerConnection, String> {
+    let mut server = server.clone();
+    if server.identity_id.is_some() {
+        let identities = storage::load_identities(&get_app_dir(app)?)?;
+        storage::apply_identities(std::slice::from_mut(&mut server), &identities);
+    }
+    Ok(server)
+}
+
+/// Whether `server` arrived without a credential of its own, such as one
+/// added with the password left blank.
+pub(crate) fn lacks_credential(server: &ServerConnection) -> bool {
+    server.identity_id.is_none()
+        && matches!(&server.auth, AuthMethod::Password { password } if password.is_empty())
+}
+
+fn make_default(identities: &mut [Identity], id: &str) {
+    for identity in identities.iter_mut() {
+        identity.is_default = identity.id == id;
+    }
+}
+
+#[tauri::command]
+pub async fn list_identities(app: AppHandle) -> Result<Vec<Identity>, String> {
+    storage::load_identities(&get_app_dir(&app)?)
+}
+
+/// Saves `secret` in the keyring and records an identity for it.
+#[tauri::command]
+pub async fn create_identity(
+    app: AppHandle,
+    name: String,
+    username: Option<String>,
+    secret: String,
+    kind: SecretKind,
+    is_default: Option<bool>,
+) -> Result<Identity, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Identity name is required".to_string());
+    }
+    let app_dir = get_app_dir(&app)?;
+    let mut identities = storage::load_identities(&app_dir)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let secret_id = identity_secret_id(&id);
+    put_secret(&app, &secret_id, &secret)?;
+
+    let is_default = is_default.unwrap_or(false);
+    if is_default {
+        for other in identities.iter_mut() {
+            other.is_default = false;
+        }
+    }
+    let identity = Identity {
+        id,
+        name,
+        username: username.and_then(normalize_username),
+        auth: AuthMethod::SecretRef { secret_id, kind },
+        is_default,
+    };
+    identities.push(identity.clone());
+    storage::save_identities(&app_dir, &identities)?;
+    Ok(identity)
+}
+
+/// Changes an identity. A new secret or username takes effect on every
+/// server using the identity from its next connection; an empty username
+/// leaves the servers' own users in place.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_identity(
+    app: AppHandle,
+    id: String,
+    name: Option<String>,
+    username: Option<String>,
+    secret: Option<String>,
+    kind: Option<SecretKind>,
+    is_default: Option<bool>,
+) -> Result<Identity, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut identities = storage::load_identities(&app_dir)?;
+    let identity = identities
+        .iter_mut()
+        .find(|identity| identity.id == id)
+        .ok_or_else(|| format!("Identity with id {} not found", id))?;
+
+    if let Some(name) = name.map(|name| name.trim().to_string()) {
+        if name.is_empty() {
+            return Err("Identity name is required".to_string());
+        }
+        identity.name = name;
+    }
+    if let Some(username) = username {
+        identity.username = normalize_username(username);
+    }
+    if let Some(secret) = secret {
+        let secret_id = identity_secret_id(&identity.id);
+        put_secret(&app, &secret_id, &secret)?;
+        let kind = match (kind, &identity.auth) {
+            (Some(kind), _) => kind,
+            (None, AuthMethod::SecretRef { kind, .. }) => kind.clone(),
+            (None, _) => SecretKind::Password,
+        };
+        identity.auth = AuthMethod::SecretRef { secret_id, kind };
+    }
+    match is_default {
+        Some(true) => make_default(&mut identities, &id),
+        Some(false) => identity.is_default = false,
+        None => {}
+    }
+
+    let identity = identities
+        .iter()
+        .find(|identity| identity.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Identity with id {} not found", id))?;
+    storage::save_identities(&app_dir, &identities)?;
+    Ok(identity)
+}
+
+/// Deletes an identity and its secret. Refused while servers still use it.
+#[tauri::command]
+pub async fn delete_identity(app: AppHandle, id: String) -> Result<Vec<Identity>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let in_use = load_servers(&app_dir, &app)?
+        .iter()
+        .filter(|server| server.identity_id.as_deref() == Some(id.as_str()))
+        .count();
+    if in_use > 0 {
+        return Err(format!("Identity is still used by {} server(s)", in_use));
+    }
+
+    let mut identities = storage::load_identities(&app_dir)?;
+    let index = identities
+        .iter()
+        .position(|identity| identity.id == id)
+        .ok_or_else(|| format!("Identity with id {} not found", id))?;
+    let identity = identities.remove(index);
+    if let Some(secret_id) = identity_secret(&identity) {
+        let _ = delete_secret(&app, secret_id);
+    }
+    storage::save_identities(&app_dir, &identities)?;
+    Ok(identities)
+}
+
+/// Makes `server_ids` log in as `identity_id`, or as themselves again when
+/// it is `None`. Assigned servers drop their own secret; detached servers
+/// get a copy of the identity's, so they keep connecting.
+#[tauri::command]
+pub async fn assign_identity(
+    app: AppHandle,
+    server_ids: Vec<String>,
+    identity_id: Option<String>,
+) -> Result<Vec<ServerConnection>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut servers = load_servers(&app_dir, &app)?;
+    if let Some(missing) = server_ids
+        .iter()
+        .find(|id| !servers.iter().any(|server| &server.id == *id))
+    {
+        return Err(format!("Server with id {} not found", missing));
+    }
+    let identities = storage::load_identities(&app_dir)?;
+    let identity = match &identity_id {
+        Some(id) => Some(
+            identities
+                .iter()
+                .find(|identity| &identity.id == id)
+                .ok_or_else(|| format!("Identity with id {} not found", id))?,
+        ),
+        None => None,
+    };
+
+    for server in servers
+        .iter_mut()
+        .filter(|server| server_ids.contains(&server.id))
+    {
+        match identity {
+            Some(identity) => {
+                if let Some(secret_id) = own_secret_id(server) {
+                    let _ = delete_secret(&app, secret_id);
+                }
+                server.identity_id = Some(identity.id.clone());
+                // The server's own user stays; the identity's, if any, is
+                // used when connecting.
+                server.auth = identity.auth.clone();
+            }
+            None => {
+                let Some(previous) = server.identity_id.take() else {
+                    continue;
+                };
+                if let Some(identity) = identities.iter().find(|identity| identity.id == previous) {
+                    server.auth = identity.auth.clone();
+                }
+                if let AuthMethod::SecretRef { secret_id, kind } = &server.auth {
+                    let secret = get_secret(&app, secret_id)?;
+                    let own_secret_id = format!("server:{}:{}", server.id, uuid::Uuid::new_v4());
+                    put_secret(&app, &own_secret_id, &secret)?;
+                    server.auth = AuthMethod::SecretRef {
+                        secret_id: own_secret_id,
+                        kind: kind.clone(),
+                    };
+                }
+            }
+        }
+    }
+    save_servers(&app_dir, &servers)?;
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_secret_id_skips_shared_credentials() {
+        let mut server: ServerConnection = serde_json::from_value(serde_json::json!({
+            "id": "s1",
+            "host": "example.com",
+            "port": 22,
+            "user": "ops",
+            "auth": {"type": "SecretRef", "secret_id": "server:s1:password"}
+        }))
+        .expect("server");
+        assert_eq!(own_secret_id(&server), Some("server:s1:password"));
+
+        server.identity_id = Some("ops".to_string());
+        assert_eq!(own_secret_id(&server), None);
+
+        server.identity_id = None;
+        server.auth = AuthMethod::SecretRef {
+            secret_id: identity_secret_id("ops"),
+            kind: SecretKind::Password,
+        };
+        assert_eq!(own_secret_id(&server), None);
+        assert!(!lacks_credential(&server));
+
+        server.auth = AuthMethod::Password {
+            password: String::new(),
+        };
+        assert!(lacks_credential(&server));
+        assert_eq!(normalize_username("  ".to_string()), None);
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+// Enough of the previous output to catch an introducer split across reads.
+const MAX_TAIL_BYTES: usize = 32;
+
+/// Escape sequences programs use to draw images in the terminal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineImageProtocol {
+    /// DCS `ESC P ... q`, e.g. from `img2sixel` or `lsix`.
+    Sixel,
+    /// OSC 1337 `File=`, e.g. from `imgcat`.
+    Iterm2,
+    /// APC `ESC _ G`, e.g. from `kitty +kitten icat`.
+    Kitty,
+}
+
+fn starts_sixel(output: &str) -> bool {
+    output.match_indices("\x1bP").any(|(start, _)| {
+        output[start + 2..]
+            .chars()
+            .find(|c| !c.is_ascii_digit() && *c != ';')
+            == Some('q')
+    })
+}
+
+fn protocol_in(protocol: InlineImageProtocol, output: &str) -> bool {
+    match protocol {
+        InlineImageProtocol::Sixel => starts_sixel(output),
+        InlineImageProtocol::Iterm2 => output.contains("\x1b]1337;File="),
+        InlineImageProtocol::Kitty => output.contains("\x1b_G"),
+    }
+}
+
+/// Notices which image protocols a shell's programs use, so the frontend
+/// can turn on rendering for them. The sequences themselves pass through
+/// the read loop untouched.
+#[derive(Default)]
+pub(crate) struct InlineImageDetector {
+    tail: String,
+    seen: Vec<InlineImageProtocol>,
+}
+
+impl InlineImageDetector {
+    /// Protocols used in `output` for the first time.
+    pub(crate) fn scan(&mut self, output: &str) -> Vec<InlineImageProtocol> {
+        let text = format!("{}{}", self.tail, output);
+        let found: Vec<InlineImageProtocol> = [
+            InlineImageProtocol::Sixel,
+            InlineImageProtocol::Iterm2,
+            InlineImageProtocol::Kitty,
+        ]
+        .into_iter()
+        .filter(|protocol| !self.seen.contains(protocol) && protocol_in(*protocol, &text))
+        .collect();
+        self.seen.extend(&found);
+
+        let mut start = text.len().saturating_sub(MAX_TAIL_BYTES);
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        self.tail = text[start..].to_string();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector_reports_each_protocol_once() {
+        let mut detector = InlineImageDetector::default();
+
+        assert!(detector.scan("ls -la\r\n\x1bP is not sixel").is_empty());
+        assert_eq!(
+            detector.scan("\x1bP0;1;"),
+            Vec::<InlineImageProtocol>::new()
+        );
+        assert_eq!(
+            detector.scan("0q\"1;1;10;10#0"),
+            vec![InlineImageProtocol::Sixel]
+        );
+        assert!(detector.scan("\x1bPq#1~~~").is_empty());
+        assert_eq!(
+            detector.scan("\x1b]1337;File=inline=1:iVBOR\x07"),
+            vec![InlineImageProtocol::Iterm2]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::{settings, AppState, PtyShell, ShellCommand};
+
+// Enough for what someone types while a reconnect is underway; a paste
+// larger than this is better retried than replayed into an unknown state.
+const MAX_QUEUED_INPUT_BYTES: usize = 16 * 1024;
+
+/// Input typed into shells whose connection dropped, by shell id, sent once
+/// the shell is reconnected. Only used when
+/// `queue_input_while_reconnecting` is on.
+#[derive(Default)]
+pub struct InputQueue {
+    queued: Mutex<HashMap<String, String>>,
+}
+
+/// Appends `input` to `queued` unless that would go over the cap.
+fn push_bounded(queued: &mut String, input: &str) -> bool {
+    if queued.len() + input.len() > MAX_QUEUED_INPUT_BYTES {
+        return false;
+    }
+    queued.push_str(input);
+    true
+}
+
+/// Holds `input` for a shell that is waiting to be reconnected, or sends it
+/// right away if the shell came back in the meantime.
+pub(crate) async fn enqueue(app: &AppHandle, shell_id: &str, input: String) -> Result<(), String> {
+    let not_found = || format!("Shell with id {} not found", shell_id);
+    let state = app.state::<AppState>();
+    // Same lock order as `insert_reconnected_shell`, so input cannot be
+    // queued after the queue was flushed.
+    let shells = state.shells.lock().await;
+    if let Some(shell) = shells.get(shell_id) {
+        let cmd_tx = shell.cmd_tx.clone();
+        drop(shells);
+        return cmd_tx
+            .send(ShellCommand::SendInput(input))
+            .await
+            .map_err(|e| format!("Failed to send input: {}", e));
+    }
+
+    if !settings::connection_defaults(app).queue_input_while_reconnecting {
+        return Err(not_found());
+    }
+    // Only shells whose connection dropped get reconnected; one that exited
+    // on its own would hold the input forever.
+    let dropped = state
+        .exited_shells
+        .lock()
+        .await
+        .get(shell_id)
+        .is_some_and(|shell| shell.exit_status.is_none());
+    if !dropped {
+        return Err(not_found());
+    }
+
+    let mut queued = state.input_queue.queued.lock().await;
+    if !push_bounded(queued.entry(shell_id.to_string()).or_default(), &input) {
+        return Err("Too much input is waiting for the shell to reconnect".to_string());
+    }
+    Ok(())
+}
+
+/// Puts a reconnected shell back in `shells`, sending the input queued for
+/// it first.
+pub(crate) async fn insert_reconnected_shell(app: &AppHandle, shell: PtyShell) {
+    let state = app.state::<AppState>();
+    let mut shells = state.shells.lock().await;
+    let queued = state.input_queue.queued.lock().await.remove(&shell.id);
+    if let Some(input) = queued {
+        // A new shell's command channel is empty, so this does not wait.
+        let _ = shell.cmd_tx.send(ShellCommand::SendInput(input)).await;
+    }
+    shells.insert(shell.id.clone(), shell);
+}
+
+pub(crate) async fn forget_shells(app: &AppHandle, shell_ids: &[String]) {
+    let state = app.state::<AppState>();
+    let mut queued = state.input_queue.queued.lock().await;
+    for shell_id in shell_ids {
+        queued.remove(shell_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_bounded_rejects_input_over_the_cap() {
+        let mut queued = String::new();
+
+        assert!(push_bounded(&mut queued, "ls -la\r"));
+        assert!(!push_bounded(
+            &mut queued,
+            &"x".repeat(MAX_QUEUED_INPUT_BYTES)
+        ));
+        assert!(push_bounded(&mut queued, "pwd\r"));
+        assert_eq!(queued, "ls -la\rpwd\r");
+    }
+}
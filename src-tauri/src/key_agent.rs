@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{timeout, Duration};
+
+use crate::event_bus::Emitter;
+use crate::settings::load_settings;
+use crate::{get_app_dir, get_secret, private_keys, AppState, AuthMethod, SecretKind};
+
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The built-in agent keeps keys decrypted in memory once they are first
+/// used or unlocked, and asks before each use.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeyAgentSettings {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Payload of `agent-sign-request`, answered with `answer_agent_request`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentSignRequest {
+    pub id: String,
+    pub secret_id: String,
+    pub server_id: Option<String>,
+    pub host: String,
+    pub user: String,
+    pub timeout_seconds: u64,
+}
+
+/// Payload of `agent-key-locked`, sent when an encrypted key is needed
+/// before `unlock_agent_key` was called for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentKeyLocked {
+    pub secret_id: String,
+    pub server_id: Option<String>,
+}
+
+/// Decrypted keys by secret id, and signature requests waiting for an
+/// answer.
+#[derive(Default)]
+pub struct KeyAgent {
+    keys: Mutex<HashMap<String, String>>,
+    pending: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    get_app_dir(app)
+        .and_then(|app_dir| load_settings(&app_dir))
+        .is_ok_and(|settings| settings.key_agent.enabled)
+}
+
+async fn cached_key(
+    app: &AppHandle,
+    secret_id: &str,
+    server_id: Option<&str>,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    if let Some(key) = state.key_agent.keys.lock().await.get(secret_id) {
+        return Ok(key.clone());
+    }
+    let secret = get_secret(app, secret_id)?;
+    if private_keys::is_encrypted(&secret) {
+        let locked = AgentKeyLocked {
+            secret_id: secret_id.to_string(),
+            server_id: server_id.map(str::to_string),
+        };
+        let _ = app.emit("agent-key-locked", locked);
+        return Err("The private key is locked; unlock it to connect".to_string());
+    }
+    let (key, _) = private_keys::decode_key(&secret, None)?;
+    state
+        .key_agent
+        .keys
+        .lock()
+        .await
+        .insert(secret_id.to_string(), key.clone());
+    Ok(key)
+}
+
+/// The credential to log in with when the agent is on and `auth` is a
+/// saved key: the agent's decrypted copy, once the user approves its use.
+/// `None` leaves `auth` as it is.
+pub(crate) async fn agent_auth(
+    app: &AppHandle,
+    auth: &AuthMethod,
+    server_id: Option<&str>,
+    host: &str,
+    user: &str,
+) -> Result<Option<AuthMethod>, String> {
+    let AuthMethod::SecretRef {
+        secret_id,
+        kind: SecretKind::PrivateKey,
+    } = auth
+    else {
+        return Ok(None);
+    };
+    if !enabled(app) {
+        return Ok(None);
+    }
+    let key = cached_key(app, secret_id, server_id).await?;
+
+    let state = app.state::<AppState>();
+    let id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    state.key_agent.pending.lock().await.insert(id.clone(), tx);
+    let request = AgentSignRequest {
+        id: id.clone(),
+        secret_id: secret_id.clone(),
+        server_id: server_id.map(str::to_string),
+        host: host.to_string(),
+        user: user.to_string(),
+        timeout_seconds: APPROVAL_TIMEOUT.as_secs(),
+    };
+    let _ = app.emit("agent-sign-request", request);
+
+    let approved = timeout(APPROVAL_TIMEOUT, rx)
+        .await
+        .is_ok_and(|answer| answer.unwrap_or(false));
+    state.key_agent.pending.lock().await.remove(&id);
+    if !approved {
+        return Err("Use of the private key was not approved".to_string());
+    }
+    Ok(Some(AuthMethod::Key { private_key: key }))
+}
+
+#[tauri::command]
+pub async fn answer_agent_request(app: AppHandle, id: String, approve: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let sender = state
+        .key_agent
+        .pending
+        .lock()
+        .await
+        .remove(&id)
+        .ok_or_else(|| "No key use is waiting for approval".to_string())?;
+    let _ = sender.send(approve);
+    Ok(())
+}
+
+/// Decrypts the key saved under `secret_id` and holds it until
+/// `lock_agent_keys` or the app closes.
+#[tauri::command]
+pub async fn unlock_agent_key(
+    app: AppHandle,
+    secret_id: String,
+    passphrase: String,
+) -> Result<(), String> {
+    let secret = get_secret(&app, &secret_id)?;
+    let (key, _) = private_keys::decode_key(&secret, Some(&passphrase))?;
+    let state = app.state::<AppState>();
+    state.key_agent.keys.lock().await.insert(secret_id, key);
+    Ok(())
+}
+
+/// Secret ids of the keys the agent holds.
+#[tauri::command]
+pub async fn list_agent_keys(app: AppHandle) -> Result<Vec<String>, String> {
+    let state = app.state::<AppState>();
+    let mut ids: Vec<String> = state.key_agent.keys.lock().await.keys().cloned().collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Drops every decrypted key, so encrypted ones need their passphrase
+/// again.
+#[tauri::command]
+pub async fn lock_agent_keys(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.key_agent.keys.lock().await.clear();
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::{get_app_dir, load_known_hosts, save_known_hosts, KnownHost};
+
+/// One line in OpenSSH `known_hosts` format. Non-default ports use the
+/// bracketed `[host]:port` form.
+fn openssh_line(known: &KnownHost) -> String {
+    let host = if known.port == 22 {
+        known.host.clone()
+    } else {
+        format!("[{}]:{}", known.host, known.port)
+    };
+    format!("{} {} {}", host, known.key_type, known.public_key_base64)
+}
+
+#[tauri::command]
+pub async fn get_known_hosts(app: AppHandle) -> Result<Vec<KnownHost>, String> {
+    load_known_hosts(&get_app_dir(&app)?)
+}
+
+/// Forgets every trusted key for `host:port`, so the next connection
+/// prompts again.
+#[tauri::command]
+pub async fn delete_known_host(
+    app: AppHandle,
+    host: String,
+    port: u16,
+) -> Result<Vec<KnownHost>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut hosts = load_known_hosts(&app_dir)?;
+    let before = hosts.len();
+    hosts.retain(|known| !(known.host == host && known.port == port));
+    if hosts.len() == before {
+        return Err(format!("No trusted key for {}:{}", host, port));
+    }
+    save_known_hosts(&app_dir, &hosts)?;
+    Ok(hosts)
+}
+
+/// Writes the trusted keys to `path` in OpenSSH `known_hosts` format and
+/// returns how many were written.
+#[tauri::command]
+pub async fn export_known_hosts(app: AppHandle, path: String) -> Result<usize, String> {
+    let hosts = load_known_hosts(&get_app_dir(&app)?)?;
+    let content: String = hosts
+        .iter()
+        .map(|known| format!("{}\n", openssh_line(known)))
+        .collect();
+    fs::write(Path::new(&path), content)
+        .map_err(|e| format!("Failed to write known hosts export: {}", e))?;
+    Ok(hosts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(host: &str, port: u16) -> KnownHost {
+        KnownHost {
+            host: host.to_string(),
+            port,
+            key_type: "ssh-ed25519".to_string(),
+            fingerprint: "SHA256:abc".to_string(),
+            public_key_base64: "AAAAC3NzaC1lZDI1NTE5AAAAIA".to_string(),
+            added_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_openssh_line_brackets_non_default_ports() {
+        assert_eq!(
+            openssh_line(&known("example.com", 22)),
+            "example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIA"
+        );
+        assert_eq!(
+            openssh_line(&known("10.0.0.5", 2222)),
+            "[10.0.0.5]:2222 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIA"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use russh::ChannelMsg;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::event_bus::Emitter;
+use crate::remote::{run_remote_command, shell_quote};
+use crate::{open_server_channel, AppState};
+
+// Log lines are sent in batches so a chatty pod does not flood the webview
+// with one event per line.
+const MAX_LOG_BATCH_LINES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KubePod {
+    pub name: String,
+    pub namespace: String,
+    pub phase: String,
+    pub ready_containers: u32,
+    pub total_containers: u32,
+    pub restarts: u32,
+    #[serde(default)]
+    pub node: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KubeLogLine {
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    pub message: String,
+}
+
+/// Payload of `kube-log`.
+#[derive(Debug, Clone, Serialize)]
+pub struct KubeLogBatch {
+    pub stream_id: String,
+    pub server_id: String,
+    pub pod: String,
+    pub lines: Vec<KubeLogLine>,
+}
+
+/// Payload of `kube-log-ended`.
+#[derive(Debug, Clone, Serialize)]
+pub struct KubeLogEnded {
+    pub stream_id: String,
+    pub server_id: String,
+    pub pod: String,
+    pub exit_code: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// `kubectl logs` commands streaming from a bastion, by stream id.
+#[derive(Default)]
+pub struct KubeLogStreams {
+    streams: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+fn kubectl(context: Option<&str>) -> String {
+    match context {
+        Some(context) => format!("kubectl --context {}", shell_quote(context)),
+        None => "kubectl".to_string(),
+    }
+}
+
+pub(crate) fn parse_pods(json: &str) -> Result<Vec<KubePod>, String> {
+    let list: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse kubectl output: {}", e))?;
+    let items = list["items"]
+        .as_array()
+        .ok_or_else(|| "kubectl output has no pod list".to_string())?;
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let statuses = item["status"]["containerStatuses"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let declared = item["spec"]["containers"]
+                .as_array()
+                .map_or(0, |containers| containers.len() as u32);
+            let text = |value: &serde_json::Value| value.as_str().map(str::to_string);
+            KubePod {
+                name: text(&item["metadata"]["name"]).unwrap_or_default(),
+                namespace: text(&item["metadata"]["namespace"]).unwrap_or_default(),
+                phase: text(&item["status"]["phase"]).unwrap_or_else(|| "Unknown".to_string()),
+                ready_containers: statuses
+                    .iter()
+                    .filter(|status| status["ready"].as_bool() == Some(true))
+                    .count() as u32,
+                total_containers: declared.max(statuses.len() as u32),
+                restarts: statuses
+                    .iter()
+                    .filter_map(|status| status["restartCount"].as_u64())
+                    .sum::<u64>() as u32,
+                node: text(&item["spec"]["nodeName"]),
+                created_at: text(&item["metadata"]["creationTimestamp"]),
+            }
+        })
+        .collect())
+}
+
+/// Splits a `kubectl logs --timestamps` line into its RFC 3339 timestamp
+/// and message.
+pub(crate) fn parse_log_line(line: &str) -> KubeLogLine {
+    let line = line.trim_end_matches('\r');
+    match line.split_once(' ') {
+        Some((timestamp, message))
+            if timestamp.len() >= 20
+                && timestamp.as_bytes()[4] == b'-'
+                && timestamp.contains('T') =>
+        {
+            KubeLogLine {
+                timestamp: Some(timestamp.to_string()),
+                message: message.to_string(),
+            }
+        }
+        _ => KubeLogLine {
+            timestamp: None,
+            message: line.to_string(),
+        },
+    }
+}
+
+/// Takes the complete lines out of `pending`, leaving a trailing partial
+/// line for the next chunk.
+fn take_lines(pending: &mut String) -> Vec<KubeLogLine> {
+    let Some(end) = pending.rfind('\n') else {
+        return Vec::new();
+    };
+    let lines = pending[..end].split('\n').map(parse_log_line).collect();
+    pending.drain(..=end);
+    lines
+}
+
+/// Lists pods as seen from the bastion's kubectl, in one namespace or all
+/// of them.
+#[tauri::command]
+pub async fn get_kube_pods(
+    app: AppHandle,
+    server_id: String,
+    namespace: Option<String>,
+    context: Option<String>,
+) -> Result<Vec<KubePod>, String> {
+    let scope = match namespace.as_deref() {
+        Some(namespace) => format!("-n {}", shell_quote(namespace)),
+        None => "--all-namespaces".to_string(),
+    };
+    let command = format!("{} get pods {} -o json", kubectl(context.as_deref()), scope);
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    if !output.success() {
+        return Err(format!("kubectl get pods failed: {}", output.stderr.trim()));
+    }
+    parse_pods(&output.stdout)
+}
+
+/// Streams `kubectl logs` for a pod as `kube-log` batches until the command
+/// ends or `stop_kube_logs` is called, then sends `kube-log-ended`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_kube_logs(
+    app: AppHandle,
+    server_id: String,
+    pod: String,
+    namespace: Option<String>,
+    container: Option<String>,
+    context: Option<String>,
+    follow: bool,
+    tail_lines: Option<u32>,
+) -> Result<String, String> {
+    let mut command = format!(
+        "{} logs --timestamps {}",
+        kubectl(context.as_deref()),
+        shell_quote(&pod)
+    );
+    if let Some(namespace) = &namespace {
+        command.push_str(&format!(" -n {}", shell_quote(namespace)));
+    }
+    if let Some(container) = &container {
+        command.push_str(&format!(" -c {}", shell_quote(container)));
+    }
+    if let Some(tail_lines) = tail_lines {
+        command.push_str(&format!(" --tail={}", tail_lines));
+    }
+    if follow {
+        command.push_str(" -f");
+    }
+
+    let mut channel = open_server_channel(&app, &server_id).await?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    let state = app.state::<AppState>();
+    // Held until the task is stored so it cannot remove itself first.
+    let mut streams = state.kube_logs.streams.lock().await;
+    let app_for_task = app.clone();
+    let stream_id_for_task = stream_id.clone();
+    let task = tokio::spawn(async move {
+        let emit_lines = |lines: Vec<KubeLogLine>| {
+            for lines in lines.chunks(MAX_LOG_BATCH_LINES) {
+                let payload = KubeLogBatch {
+                    stream_id: stream_id_for_task.clone(),
+                    server_id: server_id.clone(),
+                    pod: pod.clone(),
+                    lines: lines.to_vec(),
+                };
+                let _ = app_for_task.emit("kube-log", payload);
+            }
+        };
+
+        let mut pending = String::new();
+        let mut stderr = String::new();
+        let mut exit_code = None;
+        while let Some(message) = channel.wait().await {
+            match message {
+                ChannelMsg::Data { data } => {
+                    pending.push_str(&String::from_utf8_lossy(&data));
+                    emit_lines(take_lines(&mut pending));
+                }
+                ChannelMsg::ExtendedData { data, .. } => {
+                    stderr.push_str(&String::from_utf8_lossy(&data));
+                }
+                ChannelMsg::ExitStatus { exit_status } => exit_code = Some(exit_status),
+                _ => {}
+            }
+        }
+        if !pending.is_empty() {
+            emit_lines(vec![parse_log_line(&pending)]);
+        }
+
+        let failed = exit_code.is_some_and(|code| code != 0);
+        let payload = KubeLogEnded {
+            stream_id: stream_id_for_task.clone(),
+            server_id,
+            pod,
+            exit_code,
+            error: (failed && !stderr.trim().is_empty()).then(|| stderr.trim().to_string()),
+        };
+        let _ = app_for_task.emit("kube-log-ended", payload);
+        app_for_task
+            .state::<AppState>()
+            .kube_logs
+            .streams
+            .lock()
+            .await
+            .remove(&stream_id_for_task);
+    });
+
+    streams.insert(stream_id.clone(), task);
+    Ok(stream_id)
+}
+
+#[tauri::command]
+pub async fn stop_kube_logs(app: AppHandle, stream_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let task = state
+        .kube_logs
+        .streams
+        .lock()
+        .await
+        .remove(&stream_id)
+        .ok_or_else(|| format!("Log stream with id {} not found", stream_id))?;
+    // Dropping the channel with the task closes it on the server, which
+    // stops kubectl.
+    task.abort();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pods_and_log_lines() {
+        let json = r#"{
+            "items": [{
+                "metadata": {
+                    "name": "api-7d9f",
+                    "namespace": "prod",
+                    "creationTimestamp": "2026-10-01T08:00:00Z"
+                },
+                "spec": { "nodeName": "node-2", "containers": [{}, {}] },
+                "status": {
+                    "phase": "Running",
+                    "containerStatuses": [
+                        { "ready": true, "restartCount": 3 },
+                        { "ready": false, "restartCount": 1 }
+                    ]
+                }
+            }]
+        }"#;
+
+        let pods = parse_pods(json).expect("Failed to parse pods");
+        assert_eq!(pods.len(), 1);
+        assert_eq!(pods[0].name, "api-7d9f");
+        assert_eq!(pods[0].ready_containers, 1);
+        assert_eq!(pods[0].total_containers, 2);
+        assert_eq!(pods[0].restarts, 4);
+        assert_eq!(pods[0].node.as_deref(), Some("node-2"));
+
+        let mut pending =
+            "2026-10-01T08:00:01.123456789Z started\nno timestamp\r\n2026-10".to_string();
+        let lines = take_lines(&mut pending);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0].timestamp.as_deref(),
+            Some("2026-10-01T08:00:01.123456789Z")
+        );
+        assert_eq!(lines[0].message, "started");
+        assert_eq!(lines[1].timestamp, None);
+        assert_eq!(lines[1].message, "no timestamp");
+        assert_eq!(pending, "2026-10");
+    }
+}
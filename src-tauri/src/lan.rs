@@ -0,0 +1,218 @@
+use futures::stream::{self, StreamExt};
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use tauri::AppHandle;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration, Instant};
+use tracing::debug;
+
+use crate::cloud::{apply_discovery, CloudSource, CloudSyncResult, DiscoveredServer};
+
+const LAN_PROVIDER: &str = "lan";
+const SSH_SERVICE_TYPE: &str = "_ssh._tcp.local.";
+const DEFAULT_BROWSE_SECONDS: u64 = 3;
+const MAX_BROWSE_SECONDS: u64 = 30;
+const SCAN_CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const SCAN_CONCURRENCY: usize = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LanHostSource {
+    Mdns,
+    Scan,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LanHost {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub source: LanHostSource,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanDiscoveryOptions {
+    #[serde(default)]
+    pub browse_seconds: Option<u64>,
+    /// Also try port 22 on every address of the local /24.
+    #[serde(default)]
+    pub scan_subnet: bool,
+}
+
+async fn browse_mdns(duration: Duration) -> Result<Vec<LanHost>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    let receiver = daemon
+        .browse(SSH_SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse mDNS: {}", e))?;
+
+    let deadline = Instant::now() + duration;
+    let mut hosts = Vec::new();
+    while let Ok(Ok(event)) = timeout(
+        deadline.saturating_duration_since(Instant::now()),
+        receiver.recv_async(),
+    )
+    .await
+    {
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let name = info
+            .get_hostname()
+            .trim_end_matches('.')
+            .trim_end_matches(".local")
+            .to_string();
+        // Prefer IPv4; link-local IPv6 needs a scope id we cannot carry here.
+        let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+        addresses.sort_by_key(|addr| !addr.is_ipv4());
+        if let Some(addr) = addresses.first() {
+            hosts.push(LanHost {
+                name: Some(name),
+                host: addr.to_string(),
+                port: info.get_port(),
+                source: LanHostSource::Mdns,
+            });
+        }
+    }
+
+    let _ = daemon.stop_browse(SSH_SERVICE_TYPE);
+    let _ = daemon.shutdown();
+    Ok(hosts)
+}
+
+fn subnet_hosts(local: Ipv4Addr) -> Vec<Ipv4Addr> {
+    let [a, b, c, own] = local.octets();
+    (1..=254)
+        .filter(|last| *last != own)
+        .map(|last| Ipv4Addr::new(a, b, c, last))
+        .collect()
+}
+
+async fn scan_subnet() -> Result<Vec<LanHost>, String> {
+    let local = match local_ip_address::local_ip()
+        .map_err(|e| format!("Failed to find local address: {}", e))?
+    {
+        IpAddr::V4(ip) => ip,
+        IpAddr::V6(_) => return Err("Subnet scan needs an IPv4 address".to_string()),
+    };
+
+    debug!(%local, "Scanning local subnet for SSH");
+    let hosts = stream::iter(subnet_hosts(local))
+        .map(|ip| async move {
+            match timeout(SCAN_CONNECT_TIMEOUT, TcpStream::connect((ip, 22))).await {
+                Ok(Ok(_stream)) => Some(LanHost {
+                    name: None,
+                    host: ip.to_string(),
+                    port: 22,
+                    source: LanHostSource::Scan,
+                }),
+                _ => None,
+            }
+        })
+        .buffer_unordered(SCAN_CONCURRENCY)
+        .filter_map(|host| async move { host })
+        .collect()
+        .await;
+    Ok(hosts)
+}
+
+/// mDNS results win over scan hits for the same address since they carry a name.
+fn merge_hosts(mdns: Vec<LanHost>, scanned: Vec<LanHost>) -> Vec<LanHost> {
+    let mut hosts = Vec::new();
+    for host in mdns.into_iter().chain(scanned) {
+        if !hosts
+            .iter()
+            .any(|known: &LanHost| known.host == host.host && known.port == host.port)
+        {
+            hosts.push(host);
+        }
+    }
+    hosts.sort_by(|a, b| a.host.cmp(&b.host));
+    hosts
+}
+
+#[tauri::command]
+pub async fn discover_lan_hosts(
+    options: Option<LanDiscoveryOptions>,
+) -> Result<Vec<LanHost>, String> {
+    let options = options.unwrap_or_default();
+    let browse = Duration::from_secs(
+        options
+            .browse_seconds
+            .unwrap_or(DEFAULT_BROWSE_SECONDS)
+            .clamp(1, MAX_BROWSE_SECONDS),
+    );
+
+    if options.scan_subnet {
+        let (mdns, scanned) = tokio::join!(browse_mdns(browse), scan_subnet());
+        Ok(merge_hosts(mdns?, scanned?))
+    } else {
+        Ok(merge_hosts(browse_mdns(browse).await?, Vec::new()))
+    }
+}
+
+/// Saves the chosen hosts as servers. Re-importing a host updates its entry.
+#[tauri::command]
+pub async fn import_lan_hosts(
+    app: AppHandle,
+    hosts: Vec<LanHost>,
+    user: String,
+) -> Result<CloudSyncResult, String> {
+    let discovered: Vec<DiscoveredServer> = hosts
+        .iter()
+        .map(|host| DiscoveredServer {
+            source: CloudSource {
+                provider: LAN_PROVIDER.to_string(),
+                resource_id: format!("{}:{}", host.host, host.port),
+                region: None,
+            },
+            name: host.name.clone().unwrap_or_else(|| host.host.clone()),
+            host: host.host.clone(),
+            port: host.port,
+            user: user.clone(),
+            key_secret_id: None,
+        })
+        .collect();
+    apply_discovery(&app, LAN_PROVIDER, &discovered, false, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(addr: &str, name: Option<&str>, source: LanHostSource) -> LanHost {
+        LanHost {
+            name: name.map(|n| n.to_string()),
+            host: addr.to_string(),
+            port: 22,
+            source,
+        }
+    }
+
+    #[test]
+    fn test_subnet_hosts_skips_own_address() {
+        let hosts = subnet_hosts(Ipv4Addr::new(192, 168, 1, 10));
+        assert_eq!(hosts.len(), 253);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert!(!hosts.contains(&Ipv4Addr::new(192, 168, 1, 10)));
+        assert!(!hosts.contains(&Ipv4Addr::new(192, 168, 1, 255)));
+    }
+
+    #[test]
+    fn test_merge_hosts_prefers_mdns() {
+        let merged = merge_hosts(
+            vec![host("192.168.1.20", Some("pi"), LanHostSource::Mdns)],
+            vec![
+                host("192.168.1.20", None, LanHostSource::Scan),
+                host("192.168.1.3", None, LanHostSource::Scan),
+            ],
+        );
+        assert_eq!(merged.len(), 2);
+        let pi = merged
+            .iter()
+            .find(|h| h.host == "192.168.1.20")
+            .expect("pi");
+        assert_eq!(pi.source, LanHostSource::Mdns);
+        assert_eq!(pi.name.as_deref(), Some("pi"));
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{AppState, ServerConnection};
+
+/// Why a session or shell was refused.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum LimitError {
+    TooManySessions { server_id: String, limit: u32 },
+    TooManyShells { server_id: String, limit: u32 },
+    Failed { message: String },
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::TooManySessions { server_id, limit } => write!(
+                f,
+                "Server {} already has {} open session(s), the most allowed",
+                server_id, limit
+            ),
+            LimitError::TooManyShells { server_id, limit } => write!(
+                f,
+                "Server {} already has {} open shell(s), the most allowed",
+                server_id, limit
+            ),
+            LimitError::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for LimitError {
+    fn from(message: String) -> Self {
+        LimitError::Failed { message }
+    }
+}
+
+fn check(
+    open: usize,
+    limit: Option<u32>,
+    error: impl FnOnce(u32) -> LimitError,
+) -> Result<(), LimitError> {
+    match limit {
+        Some(limit) if open >= limit as usize => Err(error(limit)),
+        _ => Ok(()),
+    }
+}
+
+/// Refuses another session to `server` once it has `max_sessions` live
+/// ones. The session under `connection_id` is left out, since connecting
+/// again under the same id replaces it.
+pub(crate) async fn check_sessions(
+    app: &AppHandle,
+    server: &ServerConnection,
+    connection_id: &str,
+) -> Result<(), LimitError> {
+    if server.max_sessions.is_none() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let open = state
+        .sessions
+        .lock()
+        .await
+        .values()
+        .filter(|session| {
+            session.server_id == server.id
+                && session.connection_id != connection_id
+                && !session.handle.is_closed()
+        })
+        .count();
+    check(open, server.max_sessions, |limit| {
+        LimitError::TooManySessions {
+            server_id: server.id.clone(),
+            limit,
+        }
+    })
+}
+
+/// Refuses another shell on `server` once it has `max_shells` open.
+pub(crate) async fn check_shells(
+    app: &AppHandle,
+    server: &ServerConnection,
+) -> Result<(), LimitError> {
+    if server.max_shells.is_none() {
+        return Ok(());
+    }
+    let state = app.state::<AppState>();
+    let open = state
+        .shells
+        .lock()
+        .await
+        .values()
+        .filter(|shell| shell.server_id == server.id)
+        .count();
+    check(open, server.max_shells, |limit| LimitError::TooManyShells {
+        server_id: server.id.clone(),
+        limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_refuses_at_the_limit() {
+        let too_many = |limit| LimitError::TooManyShells {
+            server_id: "prod".to_string(),
+            limit,
+        };
+        assert!(check(3, Some(4), too_many).is_ok());
+        assert!(check(9, None, too_many).is_ok());
+        let error = check(4, Some(4), too_many).unwrap_err();
+        assert_eq!(error, too_many(4));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "type": "TooManyShells", "server_id": "prod", "limit": 4 })
+        );
+    }
+}
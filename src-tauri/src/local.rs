@@ -0,0 +1,184 @@
+use portable_pty::{native_pty_system, Child, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::io::{self, Write};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+#[cfg(debug_assertions)]
+use tracing::debug;
+
+use crate::resize::TerminalSize;
+use crate::stream_shell::{spawn_reader, spawn_stream_shell, ShellBackend};
+use crate::{settings, AppState, PtyConfig, PtyShell};
+
+/// Stands in for a server id on local shells so they share the `shells` map
+/// and events with SSH shells. Server ids are UUIDs, so this cannot collide.
+pub const LOCAL_SERVER_ID: &str = "local";
+
+fn default_shell_program() -> String {
+    #[cfg(windows)]
+    {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+}
+
+fn pty_size(size: TerminalSize) -> PtySize {
+    let clamp = |value: u32| value.min(u16::MAX as u32) as u16;
+    PtySize {
+        rows: clamp(size.height),
+        cols: clamp(size.width),
+        pixel_width: clamp(size.pixel_width),
+        pixel_height: clamp(size.pixel_height),
+    }
+}
+
+fn spawn_waiter(mut child: Box<dyn Child + Send + Sync>) -> oneshot::Receiver<u32> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        let exit_status = child.wait().map(|status| status.exit_code()).unwrap_or(1);
+        let _ = tx.send(exit_status);
+    });
+    rx
+}
+
+struct LocalPty {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+impl ShellBackend for LocalPty {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(data)?;
+        self.writer.flush()
+    }
+
+    fn resize(&mut self, size: TerminalSize) -> io::Result<()> {
+        self.master
+            .resize(pty_size(size))
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
+
+    fn close(&mut self) {
+        let _ = self.killer.kill();
+    }
+
+    fn ended_message(&self, exit_status: Option<u32>) -> String {
+        match exit_status {
+            Some(code) => format!("\r\n\r\nProcess exited (exit code: {})\r\n", code),
+            None => "\r\n\r\nProcess exited\r\n".to_string(),
+        }
+    }
+}
+
+/// Runs `command` in a local PTY and pumps it through the same
+/// `terminal-output`, `shell-exited` and `shell-closed` events as SSH shells.
+pub(crate) async fn spawn_pty_command(
+    app: &AppHandle,
+    config: &PtyConfig,
+    mut command: CommandBuilder,
+    connection_id: &str,
+    server_id: &str,
+    shell_id: Option<&str>,
+) -> Result<PtyShell, String> {
+    let pair = native_pty_system()
+        .openpty(pty_size(config.size()))
+        .map_err(|e| format!("Failed to open local PTY: {}", e))?;
+
+    command.env("TERM", &config.term);
+    let program = command
+        .get_argv()
+        .first()
+        .map(|program| program.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    #[cfg(debug_assertions)]
+    debug!(program = %program, connection_id, server_id, "Starting local process");
+
+    let child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    // Holding the slave open would keep the reader from seeing EOF after the
+    // process exits.
+    drop(pair.slave);
+
+    let master = pair.master;
+    let reader = master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from local PTY: {}", e))?;
+    let writer = master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to local PTY: {}", e))?;
+    let killer = child.clone_killer();
+
+    spawn_stream_shell(
+        app,
+        connection_id,
+        server_id,
+        shell_id,
+        spawn_reader(reader),
+        Some(spawn_waiter(child)),
+        LocalPty {
+            master,
+            writer,
+            killer,
+        },
+    )
+    .await
+    .map(|shell| PtyShell {
+        size: Some(config.size()),
+        ..shell
+    })
+}
+
+/// Starts the user's shell in a local PTY.
+pub(crate) async fn spawn_local_shell(
+    app: &AppHandle,
+    config: &PtyConfig,
+    connection_id: &str,
+    shell_id: Option<&str>,
+) -> Result<PtyShell, String> {
+    let mut command = CommandBuilder::new(default_shell_program());
+    if let Ok(home) = app.path().home_dir() {
+        command.cwd(home);
+    }
+    spawn_pty_command(
+        app,
+        config,
+        command,
+        connection_id,
+        LOCAL_SERVER_ID,
+        shell_id,
+    )
+    .await
+}
+
+/// Opens a local terminal under `connection_id`. It is closed with
+/// `disconnect` like any other connection.
+#[tauri::command]
+pub async fn open_local_shell(
+    app: AppHandle,
+    connection_id: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    let config = PtyConfig {
+        term: settings::connection_defaults(&app).term,
+        width: width.unwrap_or(80),
+        height: height.unwrap_or(24),
+        pixel_width: 0,
+        pixel_height: 0,
+        command: None,
+    };
+    let shell = spawn_local_shell(&app, &config, &connection_id, None).await?;
+    let shell_id = shell.id.clone();
+
+    let state = app.state::<AppState>();
+    let mut shells = state.shells.lock().await;
+    shells.insert(shell_id.clone(), shell);
+    Ok(shell_id)
+}
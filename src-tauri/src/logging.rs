@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE_PREFIX: &str = "ssh-thing";
+const LOG_FILE_SUFFIX: &str = "log";
+// One file a day, a week of them.
+const MAX_LOG_FILES: usize = 7;
+const DEFAULT_RECENT_LOGS: usize = 200;
+// How much of a log file is read at a time, from the end.
+const READ_BLOCK_BYTES: u64 = 64 * 1024;
+
+struct Logging {
+    level: reload::Handle<LevelFilter, Registry>,
+    // Flushes buffered lines when the app exits.
+    _guard: WorkerGuard,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+/// One line of the log file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// Structured fields besides the message, such as `server_id`.
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(LOG_DIR))
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+fn parse_level(level: &str) -> Result<Level, String> {
+    Level::from_str(level.trim()).map_err(|_| format!("Unknown log level {}", level))
+}
+
+/// Sends `tracing` output to a daily log file in the app data directory,
+/// as JSON lines so `get_recent_logs` can filter them. Much of the
+/// per-connection `debug!` output is only compiled into debug builds, so
+/// release logs stay sparse even at the DEBUG level.
+pub(crate) fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = log_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (level, handle) = reload::Layer::new(LevelFilter::INFO);
+    tracing_subscriber::registry()
+        .with(level)
+        .with(fmt::layer().json().with_ansi(false).with_writer(writer))
+        .try_init()
+        .map_err(|e| format!("Failed to start logging: {}", e))?;
+    let _ = LOGGING.set(Logging {
+        level: handle,
+        _guard: guard,
+    });
+    Ok(())
+}
+
+/// Changes which events reach the log file until the app restarts.
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<(), String> {
+    let level = parse_level(&level)?;
+    let logging = LOGGING
+        .get()
+        .ok_or_else(|| "Logging is not running".to_string())?;
+    logging
+        .level
+        .reload(LevelFilter::from_level(level))
+        .map_err(|e| format!("Failed to change log level: {}", e))
+}
+
+fn parse_entry(line: &str) -> Option<LogEntry> {
+    let mut value: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line).ok()?;
+    let text = |value: Option<serde_json::Value>| match value {
+        Some(serde_json::Value::String(text)) => text,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let mut fields = match value.remove("fields") {
+        Some(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    Some(LogEntry {
+        timestamp: text(value.remove("timestamp")),
+        level: text(value.remove("level")),
+        target: text(value.remove("target")),
+        message: text(fields.remove("message")),
+        fields,
+    })
+}
+
+/// Lines of a file from last to first, read a block at a time from the
+/// end so only as much as is needed is read.
+struct LinesBackwards {
+    file: fs::File,
+    // Bytes of the file not read yet.
+    remaining: u64,
+    // The start of the earliest line read so far, which may continue in the
+    // block before.
+    partial: Vec<u8>,
+    lines: Vec<String>,
+}
+
+impl LinesBackwards {
+    fn open(path: &Path) -> Result<Self, String> {
+        let file = fs::File::open(path).map_err(|e| format!("Failed to read log file: {}", e))?;
+        let remaining = file
+            .metadata()
+            .map_err(|e| format!("Failed to read log file: {}", e))?
+            .len();
+        Ok(Self {
+            file,
+            remaining,
+            partial: Vec::new(),
+            lines: Vec::new(),
+        })
+    }
+
+    fn read_block(&mut self) -> std::io::Result<()> {
+        let size = self.remaining.min(READ_BLOCK_BYTES);
+        self.remaining -= size;
+        self.file.seek(SeekFrom::Start(self.remaining))?;
+        let mut block = vec![0u8; size as usize];
+        self.file.read_exact(&mut block)?;
+        block.append(&mut self.partial);
+
+        let mut pieces = block.split(|byte| *byte == b'\n');
+        // The first piece may be the end of a line from an earlier block.
+        let first = pieces.next().unwrap_or_default().to_vec();
+        self.lines.extend(
+            pieces
+                .filter(|line| !line.is_empty())
+                .map(|line| String::from_utf8_lossy(line).into_owned()),
+        );
+        if self.remaining == 0 {
+            if !first.is_empty() {
+                self.lines
+                    .insert(0, String::from_utf8_lossy(&first).into_owned());
+            }
+        } else {
+            self.partial = first;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for LinesBackwards {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while self.lines.is_empty() && self.remaining > 0 {
+            self.read_block().ok()?;
+        }
+        self.lines.pop()
+    }
+}
+
+/// The first `limit` entries at `level` or more severe from `lines`, given
+/// newest first, returned oldest first.
+fn recent_entries(
+    lines: impl Iterator<Item = String>,
+    level: Level,
+    limit: usize,
+) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = lines
+        .filter_map(|line| parse_entry(&line))
+        .filter(|entry| parse_level(&entry.level).is_ok_and(|entry_level| entry_level <= level))
+        .take(limit)
+        .collect();
+    entries.reverse();
+    entries
+}
+
+fn log_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read log directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+    // The date in the name sorts the files oldest first.
+    files.sort();
+    files.reverse();
+    Ok(files)
+}
+
+fn read_recent_entries(dir: &Path, level: Level, limit: usize) -> Result<Vec<LogEntry>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let files = log_files(dir)?
+        .iter()
+        .map(|path| LinesBackwards::open(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(recent_entries(files.into_iter().flatten(), level, limit))
+}
+
+/// The most recent log entries, for attaching to bug reports.
+#[tauri::command]
+pub async fn get_recent_logs(
+    app: AppHandle,
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = level
+        .as_deref()
+        .map(parse_level)
+        .transpose()?
+        .unwrap_or(Level::TRACE);
+    let dir = log_dir(&app)?;
+    let limit = limit.unwrap_or(DEFAULT_RECENT_LOGS);
+    tauri::async_runtime::spawn_blocking(move || read_recent_entries(&dir, level, limit))
+        .await
+        .map_err(|e| format!("Failed to read logs: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_entries_filter_by_level_and_keep_the_newest() {
+        let lines = [
+            r#"{"timestamp":"t1","level":"DEBUG","fields":{"message":"Opening PTY shell channel"},"target":"tauri_app_lib"}"#,
+            r#"{"timestamp":"t2","level":"WARN","fields":{"message":"Failed to watch config files","error":"denied"},"target":"tauri_app_lib"}"#,
+            "not json",
+            r#"{"timestamp":"t3","level":"ERROR","fields":{"message":"Connection failed"},"target":"tauri_app_lib::ssh"}"#,
+        ]
+        .map(str::to_string);
+        let newest_first = || lines.clone().into_iter().rev();
+
+        let warnings = recent_entries(newest_first(), Level::WARN, 10);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].message, "Failed to watch config files");
+        assert_eq!(warnings[0].fields["error"], "denied");
+
+        let newest = recent_entries(newest_first(), Level::TRACE, 2);
+        assert_eq!(
+            newest
+                .iter()
+                .map(|e| e.timestamp.as_str())
+                .collect::<Vec<_>>(),
+            vec!["t2", "t3"]
+        );
+        assert!(parse_level("verbose").is_err());
+
+        let path = std::env::temp_dir().join(format!("ssh-thing-log-{}", std::process::id()));
+        let mut content = lines.join("\n");
+        content.push_str(&format!("\n{}\n", "x".repeat(READ_BLOCK_BYTES as usize)));
+        fs::write(&path, content).expect("write log");
+        let backwards: Vec<String> = LinesBackwards::open(&path).expect("open").collect();
+        let _ = fs::remove_file(&path);
+        assert_eq!(backwards.len(), 5);
+        assert_eq!(backwards[1], lines[3]);
+        assert_eq!(backwards[4], lines[0]);
+    }
+}
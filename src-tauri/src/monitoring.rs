@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::remote::{parse_df_output, run_remote_command, DiskUsageEntry, WINDOWS_DF_SCRIPT};
+use crate::{os_fingerprint, powershell, AppState};
+
+const DEFAULT_MONITOR_INTERVAL_SECONDS: u64 = 5;
+const MIN_MONITOR_INTERVAL_SECONDS: u64 = 1;
+const SECTION_MARKER: &str = "--ssh-thing-section--";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CpuSample {
+    total: u64,
+    idle: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResourceMetrics {
+    pub server_id: String,
+    /// Needs two samples, so the first report after starting has none.
+    #[serde(default)]
+    pub cpu_percent: Option<f64>,
+    #[serde(default)]
+    pub memory: Option<MemoryUsage>,
+    pub disks: Vec<DiskUsageEntry>,
+    #[serde(default)]
+    pub load_average: Option<[f64; 3]>,
+    pub collected_at: u64,
+}
+
+#[derive(Default)]
+pub struct ResourceMonitor {
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+    latest: Mutex<HashMap<String, ResourceMetrics>>,
+}
+
+fn metrics_command() -> String {
+    format!(
+        "head -n 1 /proc/stat; echo {marker}; free -b; echo {marker}; df -P -k; echo {marker}; uptime",
+        marker = SECTION_MARKER
+    )
+}
+
+// The same sections from PowerShell, with CPU time and memory written the
+// way `/proc/stat` and `free -b` show them. Windows has no load average.
+fn windows_metrics_command() -> String {
+    let cpu =
+        "$cpu = Get-CimInstance Win32_PerfRawData_PerfOS_Processor -Filter \"Name='_Total'\"; \
+         'cpu  {0} 0 0 {1}' -f [uint64]($cpu.Timestamp_Sys100NS - $cpu.PercentProcessorTime), \
+         [uint64]$cpu.PercentProcessorTime";
+    let memory = "$os = Get-CimInstance Win32_OperatingSystem; \
+         'Mem: {0} {1} {2} 0 0 {2}' -f [uint64]($os.TotalVisibleMemorySize * 1024), \
+         [uint64](($os.TotalVisibleMemorySize - $os.FreePhysicalMemory) * 1024), \
+         [uint64]($os.FreePhysicalMemory * 1024)";
+    powershell::command(&format!(
+        "{cpu}; '{marker}'; {memory}; '{marker}'; {disks}; '{marker}'",
+        cpu = cpu,
+        memory = memory,
+        disks = WINDOWS_DF_SCRIPT,
+        marker = SECTION_MARKER
+    ))
+}
+
+// Idle time includes iowait, matching what top reports as idle.
+fn parse_cpu_sample(output: &str) -> Option<CpuSample> {
+    let line = output.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    if values.len() < 4 {
+        return None;
+    }
+    Some(CpuSample {
+        total: values.iter().sum(),
+        idle: values[3] + values.get(4).copied().unwrap_or(0),
+    })
+}
+
+fn cpu_percent(previous: CpuSample, current: CpuSample) -> Option<f64> {
+    let total = current.total.checked_sub(previous.total)?;
+    let idle = current.idle.checked_sub(previous.idle)?;
+    if total == 0 {
+        return None;
+    }
+    let busy = total.saturating_sub(idle) as f64 / total as f64 * 100.0;
+    Some((busy * 10.0).round() / 10.0)
+}
+
+fn parse_free_output(output: &str) -> Option<MemoryUsage> {
+    let line = output.lines().find(|line| line.starts_with("Mem:"))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    let total = *values.first()?;
+    // Older procps has no "available" column; fall back to free memory.
+    let available = values.get(5).or(values.get(2)).copied()?;
+    Some(MemoryUsage {
+        total_bytes: total,
+        used_bytes: total.saturating_sub(available),
+        available_bytes: available,
+    })
+}
+
+fn parse_load_average(output: &str) -> Option<[f64; 3]> {
+    let (_, loads) = output.rsplit_once("load average")?;
+    let loads = loads.trim_start_matches('s').trim_start_matches(':');
+    let values: Vec<f64> = loads
+        .split([',', ' '])
+        .filter_map(|value| value.trim().parse().ok())
+        .collect();
+    match values.as_slice() {
+        [one, five, fifteen, ..] => Some([*one, *five, *fifteen]),
+        _ => None,
+    }
+}
+
+fn parse_metrics(
+    server_id: &str,
+    output: &str,
+    previous_cpu: Option<CpuSample>,
+) -> (ResourceMetrics, Option<CpuSample>) {
+    let sections: Vec<&str> = output.split(SECTION_MARKER).collect();
+    let section = |index: usize| sections.get(index).copied().unwrap_or_default();
+
+    let cpu_sample = parse_cpu_sample(section(0));
+    let cpu_percent = match (previous_cpu, cpu_sample) {
+        (Some(previous), Some(current)) => cpu_percent(previous, current),
+        _ => None,
+    };
+    let collected_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let metrics = ResourceMetrics {
+        server_id: server_id.to_string(),
+        cpu_percent,
+        memory: parse_free_output(section(1)),
+        disks: parse_df_output(section(2).trim_start()),
+        load_average: parse_load_average(section(3)),
+        collected_at,
+    };
+    (metrics, cpu_sample)
+}
+
+async fn run_monitor(app: AppHandle, server_id: String, interval_seconds: u64) {
+    let mut ticker = interval(Duration::from_secs(interval_seconds));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut previous_cpu = None;
+    let command = if os_fingerprint::is_windows(&app, &server_id).await {
+        windows_metrics_command()
+    } else {
+        metrics_command()
+    };
+
+    loop {
+        ticker.tick().await;
+        let output = match run_remote_command(&app, &server_id, &command).await {
+            Ok(output) => output,
+            Err(e) => {
+                debug!(server_id, error = %e, "Resource monitor stopped");
+                break;
+            }
+        };
+
+        let (metrics, cpu_sample) = parse_metrics(&server_id, &output.stdout, previous_cpu);
+        previous_cpu = cpu_sample;
+
+        let state = app.state::<AppState>();
+        state
+            .resource_monitor
+            .latest
+            .lock()
+            .await
+            .insert(server_id.clone(), metrics.clone());
+        let _ = app.emit("resource-metrics", metrics);
+    }
+
+    let state = app.state::<AppState>();
+    state.resource_monitor.tasks.lock().await.remove(&server_id);
+}
+
+#[tauri::command]
+pub async fn start_resource_monitor(
+    app: AppHandle,
+    server_id: String,
+    interval_seconds: Option<u64>,
+) -> Result<(), String> {
+    let interval_seconds = interval_seconds
+        .unwrap_or(DEFAULT_MONITOR_INTERVAL_SECONDS)
+        .max(MIN_MONITOR_INTERVAL_SECONDS);
+
+    let state = app.state::<AppState>();
+    let mut tasks = state.resource_monitor.tasks.lock().await;
+    if let Some(handle) = tasks.remove(&server_id) {
+        handle.abort();
+    }
+    tasks.insert(
+        server_id.clone(),
+        tokio::spawn(run_monitor(app.clone(), server_id, interval_seconds)),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_resource_monitor(app: AppHandle, server_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if let Some(handle) = state.resource_monitor.tasks.lock().await.remove(&server_id) {
+        handle.abort();
+    }
+    state
+        .resource_monitor
+        .latest
+        .lock()
+        .await
+        .remove(&server_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_resource_metrics(
+    app: AppHandle,
+    server_id: String,
+) -> Result<Option<ResourceMetrics>, String> {
+    let state = app.state::<AppState>();
+    let latest = state.resource_monitor.latest.lock().await;
+    Ok(latest.get(&server_id).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_percent_between_samples() {
+        let first = parse_cpu_sample("cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 1 2 3 4\n")
+            .expect("first sample");
+        let second = parse_cpu_sample("cpu  150 0 150 850 150 0 0 0 0 0\n").expect("second");
+
+        assert_eq!(first.total, 1000);
+        assert_eq!(first.idle, 800);
+        assert_eq!(cpu_percent(first, second), Some(33.3));
+        assert_eq!(cpu_percent(second, first), None);
+    }
+
+    #[test]
+    fn test_parse_free_output() {
+        let output = "              total        used        free      shared  buff/cache   available\n\
+                      Mem:     8000000000  2000000000  1000000000    10000000  5000000000  5500000000\n\
+                      Swap:             0           0           0\n";
+
+        assert_eq!(
+            parse_free_output(output),
+            Some(MemoryUsage {
+                total_bytes: 8_000_000_000,
+                used_bytes: 2_500_000_000,
+                available_bytes: 5_500_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_load_average_linux_and_bsd() {
+        assert_eq!(
+            parse_load_average(" 10:01:02 up 3 days,  2 users,  load average: 0.52, 0.58, 0.59"),
+            Some([0.52, 0.58, 0.59])
+        );
+        assert_eq!(
+            parse_load_average("10:01  up 3 days, 2 users, load averages: 1.20 1.10 1.00"),
+            Some([1.2, 1.1, 1.0])
+        );
+        assert_eq!(parse_load_average("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_metrics_sections() {
+        let output = format!(
+            "cpu  1 0 1 8 0\n{m}\nMem: 100 40 10 0 50 60\n{m}\nFilesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 100 50 50 50% /\n{m}\n up 1 day, load average: 0.10, 0.20, 0.30\n",
+            m = SECTION_MARKER
+        );
+
+        let (metrics, sample) = parse_metrics("srv", &output, None);
+
+        assert_eq!(metrics.cpu_percent, None);
+        assert!(sample.is_some());
+        assert_eq!(metrics.memory.map(|m| m.used_bytes), Some(40));
+        assert_eq!(metrics.disks.len(), 1);
+        assert_eq!(metrics.load_average, Some([0.1, 0.2, 0.3]));
+    }
+}
@@ -0,0 +1,188 @@
+use portable_pty::CommandBuilder;
+use russh::ChannelMsg;
+use std::net::IpAddr;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tokio::time::{timeout, Duration};
+
+#[cfg(debug_assertions)]
+use tracing::debug;
+
+use crate::local::spawn_pty_command;
+use crate::{
+    connect_server, limits, settings, stats, AppState, PtyConfig, ServerConnection, SshSession,
+};
+
+// `-s` binds the server to the address the SSH connection came in on, which
+// is also the one the client will use.
+const MOSH_SERVER_COMMAND: &str = "mosh-server new -s -l LANG=en_US.UTF-8";
+const MOSH_SERVER_TIMEOUT: Duration = Duration::from_secs(30);
+const MOSH_CLIENT: &str = "mosh-client";
+// GUI apps on macOS do not inherit the shell's PATH, so Homebrew installs
+// would not be found by name.
+const MOSH_CLIENT_FALLBACKS: [&str; 2] = [
+    "/opt/homebrew/bin/mosh-client",
+    "/usr/local/bin/mosh-client",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+struct MoshConnect {
+    port: u16,
+    key: String,
+}
+
+/// Finds the `MOSH CONNECT <port> <key>` line `mosh-server new` prints.
+fn parse_mosh_connect(output: &str) -> Option<MoshConnect> {
+    output.lines().find_map(|line| {
+        let mut parts = line
+            .trim()
+            .strip_prefix("MOSH CONNECT ")?
+            .split_whitespace();
+        let port = parts.next()?.parse().ok()?;
+        let key = parts.next()?.to_string();
+        Some(MoshConnect { port, key })
+    })
+}
+
+fn mosh_client_program() -> String {
+    MOSH_CLIENT_FALLBACKS
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .map(|path| path.to_string())
+        .unwrap_or_else(|| MOSH_CLIENT.to_string())
+}
+
+async fn start_mosh_server(session: &SshSession) -> Result<MoshConnect, String> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+    channel
+        .exec(true, MOSH_SERVER_COMMAND)
+        .await
+        .map_err(|e| format!("Failed to start mosh-server: {}", e))?;
+
+    let mut output = Vec::new();
+    let mut exit_status = None;
+    let finished = timeout(MOSH_SERVER_TIMEOUT, async {
+        while let Some(message) = channel.wait().await {
+            match message {
+                ChannelMsg::Data { data } | ChannelMsg::ExtendedData { data, .. } => {
+                    output.extend_from_slice(&data)
+                }
+                ChannelMsg::ExitStatus {
+                    exit_status: status,
+                } => exit_status = Some(status),
+                _ => {}
+            }
+        }
+    })
+    .await;
+    if finished.is_err() {
+        let _ = channel.close().await;
+        return Err(format!(
+            "mosh-server did not finish starting within {} seconds",
+            MOSH_SERVER_TIMEOUT.as_secs()
+        ));
+    }
+
+    let output = String::from_utf8_lossy(&output);
+    parse_mosh_connect(&output).ok_or_else(|| match exit_status {
+        Some(127) => "mosh-server is not installed on this server".to_string(),
+        _ => format!("mosh-server did not start: {}", output.trim()),
+    })
+}
+
+async fn resolve_host(host: &str) -> Result<IpAddr, String> {
+    tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| format!("Failed to resolve {}", host))
+}
+
+/// Starts `mosh-server` over SSH, then hands the session to a local
+/// `mosh-client`, which keeps the UDP connection alive across roaming and
+/// sleep. The SSH connection is only used for the bootstrap.
+#[tauri::command]
+pub async fn connect_mosh(
+    app: AppHandle,
+    server: ServerConnection,
+    connection_id: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    if server.ssm.is_some() {
+        return Err("Mosh needs UDP and cannot run through SSM".to_string());
+    }
+    limits::check_shells(&app, &server)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let session = connect_server(&app, &server, &connection_id).await?;
+    let started = start_mosh_server(&session).await;
+    // mosh-server listens on the address SSH came in on, which may not be
+    // the first one the host name resolves to.
+    let peer_ip = stats::peer_addr(&app, &connection_id).map(|addr| addr.ip());
+    stats::untrack_session(&app, &connection_id);
+    ssh_thing_core::ssh::disconnect(&session).await;
+    let mosh = started?;
+
+    #[cfg(debug_assertions)]
+    debug!(server_id = %server.id, port = mosh.port, "mosh-server started");
+
+    let ip = match peer_ip {
+        Some(ip) => ip,
+        None => resolve_host(&server.host).await?,
+    };
+    let mut command = CommandBuilder::new(mosh_client_program());
+    command.arg(ip.to_string());
+    command.arg(mosh.port.to_string());
+    command.env("MOSH_KEY", &mosh.key);
+    // mosh-client refuses to start without a UTF-8 locale, which GUI apps
+    // are often launched without.
+    let utf8_locale =
+        std::env::var("LANG").is_ok_and(|lang| lang.to_ascii_uppercase().contains("UTF-8"));
+    if !utf8_locale {
+        command.env("LANG", "en_US.UTF-8");
+    }
+
+    let config = PtyConfig {
+        term: settings::connection_defaults(&app).term_for(&server),
+        width: width.unwrap_or(80),
+        height: height.unwrap_or(24),
+        pixel_width: 0,
+        pixel_height: 0,
+        command: None,
+    };
+    let shell = spawn_pty_command(&app, &config, command, &connection_id, &server.id, None).await?;
+    let shell_id = shell.id.clone();
+
+    let state = app.state::<AppState>();
+    let mut shells = state.shells.lock().await;
+    shells.insert(shell_id.clone(), shell);
+    Ok(shell_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mosh_connect() {
+        let output = "\r\nMOSH CONNECT 60001 4NeCCgvZFe2RnPgrcU1PQw\r\n\r\nmosh-server (mosh 1.4.0) [build mosh 1.4.0]\r\n";
+
+        assert_eq!(
+            parse_mosh_connect(output),
+            Some(MoshConnect {
+                port: 60001,
+                key: "4NeCCgvZFe2RnPgrcU1PQw".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_mosh_connect("bash: mosh-server: command not found"),
+            None
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tokio::time::{timeout, Duration};
+use tracing::debug;
+
+use crate::remote::{run_on_channel, shell_quote};
+use crate::{AppState, SshSession};
+
+const DETECT_TIMEOUT: Duration = Duration::from_secs(5);
+// Run under `sh` so csh login shells on older BSDs parse it too.
+const DETECT_SCRIPT: &str = "echo \"kernel $(uname -s 2>/dev/null)\"; \
+     echo \"release $(uname -r 2>/dev/null)\"; \
+     echo \"arch $(uname -m 2>/dev/null)\"; \
+     case \"$(readlink -f /bin/ls 2>/dev/null)\" in *busybox*) echo busybox;; esac; \
+     cat /etc/os-release 2>/dev/null";
+// Works from both shells Win32-OpenSSH can be set to use.
+const WINDOWS_DETECT_COMMAND: &str = "cmd /c ver";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OsFamily {
+    Linux,
+    /// FreeBSD, OpenBSD, NetBSD and DragonFly.
+    Bsd,
+    Macos,
+    Solaris,
+    /// Win32-OpenSSH, where helpers run PowerShell instead of POSIX tools.
+    Windows,
+    #[default]
+    Other,
+}
+
+/// What a server runs, detected once per connection so commands sent to it
+/// can allow for BSD or BusyBox tools.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteOs {
+    pub family: OsFamily,
+    /// `uname -s`, e.g. `Linux` or `FreeBSD`.
+    pub kernel: Option<String>,
+    pub kernel_release: Option<String>,
+    pub arch: Option<String>,
+    /// `ID` from `/etc/os-release`, e.g. `ubuntu` or `alpine`.
+    pub distro: Option<String>,
+    /// `VERSION_ID` from `/etc/os-release`.
+    pub version: Option<String>,
+    /// `PRETTY_NAME` from `/etc/os-release`, or the kernel and release.
+    pub name: Option<String>,
+    /// Core tools are BusyBox applets with fewer options than GNU's.
+    pub busybox: bool,
+}
+
+/// Detected systems by connection id, `None` where detection failed so it
+/// is not tried again on every helper call.
+#[derive(Default)]
+pub struct OsFingerprints {
+    by_connection: Mutex<HashMap<String, Option<RemoteOs>>>,
+}
+
+fn family_of(kernel: &str) -> OsFamily {
+    match kernel {
+        "Linux" => OsFamily::Linux,
+        "FreeBSD" | "OpenBSD" | "NetBSD" | "DragonFly" => OsFamily::Bsd,
+        "Darwin" => OsFamily::Macos,
+        "SunOS" => OsFamily::Solaris,
+        _ => OsFamily::Other,
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+pub(crate) fn parse_fingerprint(output: &str) -> RemoteOs {
+    let mut os = RemoteOs::default();
+    let mut pretty_name = None;
+    for line in output.lines() {
+        if line.trim() == "busybox" {
+            os.busybox = true;
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let value = non_empty(value.trim().trim_matches(['"', '\'']));
+            match key.trim() {
+                "ID" => os.distro = value,
+                "VERSION_ID" => os.version = value,
+                "PRETTY_NAME" => pretty_name = value,
+                _ => {}
+            }
+            continue;
+        }
+        match line.split_once(' ') {
+            Some(("kernel", value)) => os.kernel = non_empty(value),
+            Some(("release", value)) => os.kernel_release = non_empty(value),
+            Some(("arch", value)) => os.arch = non_empty(value),
+            _ => {}
+        }
+    }
+
+    os.family = os.kernel.as_deref().map_or(OsFamily::Other, family_of);
+    os.name = pretty_name.or_else(|| {
+        let kernel = os.kernel.as_deref()?;
+        Some(match os.kernel_release.as_deref() {
+            Some(release) => format!("{} {}", kernel, release),
+            None => kernel.to_string(),
+        })
+    });
+    // BSDs and macOS have no os-release; their version is the release.
+    if os.version.is_none() && matches!(os.family, OsFamily::Bsd | OsFamily::Macos) {
+        os.version = os.kernel_release.clone();
+    }
+    os
+}
+
+// `ver` prints e.g. `Microsoft Windows [Version 10.0.20348.2227]`.
+fn parse_windows_ver(output: &str) -> Option<RemoteOs> {
+    let line = output
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("Microsoft Windows"))?;
+    let version = line
+        .split_once("[Version ")
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(version, _)| version.trim().to_string());
+    Some(RemoteOs {
+        family: OsFamily::Windows,
+        kernel: Some("Windows_NT".to_string()),
+        kernel_release: version.clone(),
+        version,
+        name: Some("Microsoft Windows".to_string()),
+        ..RemoteOs::default()
+    })
+}
+
+async fn run_detection(session: &SshSession) -> Result<RemoteOs, String> {
+    let run = |command: String| async move {
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Failed to open channel: {}", e))?;
+        run_on_channel(channel, &command).await
+    };
+    let output = run(format!("sh -c {}", shell_quote(DETECT_SCRIPT))).await?;
+    let os = parse_fingerprint(&output.stdout);
+    if os.kernel.is_some() {
+        return Ok(os);
+    }
+    // No `sh`, so probably Windows.
+    let output = run(WINDOWS_DETECT_COMMAND.to_string()).await?;
+    Ok(parse_windows_ver(&output.stdout).unwrap_or(os))
+}
+
+/// Detects the system behind `session` unless detection already ran for
+/// the connection. Failures are logged, remembered and give `None`.
+pub(crate) async fn detect(
+    app: &AppHandle,
+    session: &SshSession,
+    connection_id: &str,
+) -> Option<RemoteOs> {
+    let state = app.state::<AppState>();
+    if let Some(os) = state
+        .os_fingerprints
+        .by_connection
+        .lock()
+        .await
+        .get(connection_id)
+    {
+        return os.clone();
+    }
+
+    let os = timeout(DETECT_TIMEOUT, run_detection(session))
+        .await
+        .map_err(|_| "OS detection timed out".to_string())
+        .and_then(|os| os);
+    let os = match os {
+        Ok(os) => Some(os),
+        Err(e) => {
+            debug!(connection_id, error = %e, "OS detection failed");
+            None
+        }
+    };
+    state
+        .os_fingerprints
+        .by_connection
+        .lock()
+        .await
+        .insert(connection_id.to_string(), os.clone());
+    os
+}
+
+/// The system of the server's active session, detecting it first if no
+/// shell has been opened on the session yet.
+pub(crate) async fn for_server(app: &AppHandle, server_id: &str) -> Option<RemoteOs> {
+    let (handle, connection_id) = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        let session = sessions
+            .values()
+            .find(|session| session.server_id == server_id)?;
+        (session.handle.clone(), session.connection_id.clone())
+    };
+    detect(app, &handle, &connection_id).await
+}
+
+/// Whether helpers for the server should use PowerShell.
+pub(crate) async fn is_windows(app: &AppHandle, server_id: &str) -> bool {
+    for_server(app, server_id)
+        .await
+        .is_some_and(|os| os.family == OsFamily::Windows)
+}
+
+pub(crate) async fn forget_connection(app: &AppHandle, connection_id: &str) {
+    let state = app.state::<AppState>();
+    state
+        .os_fingerprints
+        .by_connection
+        .lock()
+        .await
+        .remove(connection_id);
+}
+
+/// The system detected for a connection, or `None` before its first shell
+/// opens or when detection failed.
+#[tauri::command]
+pub async fn get_remote_os(
+    app: AppHandle,
+    connection_id: String,
+) -> Result<Option<RemoteOs>, String> {
+    let state = app.state::<AppState>();
+    let by_connection = state.os_fingerprints.by_connection.lock().await;
+    Ok(by_connection.get(&connection_id).cloned().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint_for_unix_and_windows_targets() {
+        let alpine = parse_fingerprint(
+            "kernel Linux\nrelease 6.6.7-0-lts\narch x86_64\nbusybox\n\
+             NAME=\"Alpine Linux\"\nID=alpine\nVERSION_ID=3.19.0\n\
+             PRETTY_NAME=\"Alpine Linux v3.19\"\n",
+        );
+        assert_eq!(alpine.family, OsFamily::Linux);
+        assert_eq!(alpine.distro.as_deref(), Some("alpine"));
+        assert_eq!(alpine.version.as_deref(), Some("3.19.0"));
+        assert_eq!(alpine.name.as_deref(), Some("Alpine Linux v3.19"));
+        assert!(alpine.busybox);
+
+        let freebsd = parse_fingerprint("kernel FreeBSD\nrelease 14.0-RELEASE\narch amd64\n");
+        assert_eq!(freebsd.family, OsFamily::Bsd);
+        assert_eq!(freebsd.version.as_deref(), Some("14.0-RELEASE"));
+        assert_eq!(freebsd.name.as_deref(), Some("FreeBSD 14.0-RELEASE"));
+        assert!(!freebsd.busybox);
+
+        assert_eq!(parse_fingerprint("kernel \n").family, OsFamily::Other);
+
+        let windows =
+            parse_windows_ver("\r\nMicrosoft Windows [Version 10.0.20348.2227]\r\n").unwrap();
+        assert_eq!(windows.family, OsFamily::Windows);
+        assert_eq!(windows.version.as_deref(), Some("10.0.20348.2227"));
+        assert!(parse_windows_ver("").is_none());
+    }
+}
@@ -6,6 +6,7 @@ use tracing::debug;
 const ESC: u8 = 0x1b;
 const BEL: u8 = 0x07;
 const MAX_OSC_SEQUENCE_BYTES: usize = 1024 * 1024;
+const OSC52_PREFIX: &[u8] = b"52;";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ParserState {
@@ -13,6 +14,10 @@ enum ParserState {
     Escape,
     Osc,
     OscEscape,
+    /// Inside an OSC other than 52, e.g. an iTerm2 inline image, which is
+    /// streamed through as it arrives instead of being held until it ends.
+    OscPassthrough,
+    OscPassthroughEscape,
 }
 
 pub trait ClipboardSink {
@@ -97,7 +102,13 @@ where
                         self.state = ParserState::OscEscape;
                     } else {
                         self.sequence.push(byte);
-                        self.enforce_sequence_cap(&mut output);
+                        if self.is_osc52_candidate() {
+                            self.enforce_sequence_cap(&mut output);
+                        } else {
+                            output.extend_from_slice(&self.sequence);
+                            self.sequence.clear();
+                            self.state = ParserState::OscPassthrough;
+                        }
                     }
                 }
                 ParserState::OscEscape => {
@@ -112,6 +123,22 @@ where
                         self.enforce_sequence_cap(&mut output);
                     }
                 }
+                ParserState::OscPassthrough => {
+                    output.push(byte);
+                    if byte == BEL {
+                        self.state = ParserState::Ground;
+                    } else if byte == ESC {
+                        self.state = ParserState::OscPassthroughEscape;
+                    }
+                }
+                ParserState::OscPassthroughEscape => {
+                    output.push(byte);
+                    self.state = if byte == b'\\' {
+                        ParserState::Ground
+                    } else {
+                        ParserState::OscPassthrough
+                    };
+                }
             }
         }
 
@@ -130,6 +157,12 @@ where
         pending
     }
 
+    /// Whether the OSC collected so far can still turn out to be OSC 52.
+    fn is_osc52_candidate(&self) -> bool {
+        let body = &self.sequence[2..];
+        body.starts_with(OSC52_PREFIX) || OSC52_PREFIX.starts_with(body)
+    }
+
     fn finish_sequence(&mut self, output: &mut Vec<u8>, bell_terminated: bool) {
         let handled = self.try_handle_osc52(bell_terminated);
         if !handled {
@@ -265,6 +298,17 @@ mod tests {
         assert!(processor.clipboard.values.is_empty());
     }
 
+    #[test]
+    fn process_streams_other_osc_sequences_before_they_end() {
+        let mut processor = Osc52Processor::new(TestClipboard::default());
+        let first = processor.process(b"\x1b]1337;File=inline=1:iVBORw0K");
+        let second = processor.process(b"GgoAAAAN\x07\x1b]52;c;SGk=\x07done");
+
+        assert_eq!(first, b"\x1b]1337;File=inline=1:iVBORw0K");
+        assert_eq!(second, b"GgoAAAAN\x07done");
+        assert_eq!(processor.clipboard.values, vec!["Hi"]);
+    }
+
     #[test]
     fn process_swallows_invalid_osc52_payloads() {
         let mut processor = Osc52Processor::new(TestClipboard::default());
@@ -0,0 +1,134 @@
+use encoding_rs::{Decoder, Encoding, UTF_8};
+
+/// Turns terminal output into text one read at a time. A character split
+/// between two reads is held back until the rest arrives, instead of
+/// becoming two replacement characters. Output from a server whose locale
+/// is not UTF-8 is transcoded from its character set.
+pub(crate) struct OutputDecoder {
+    pending: Vec<u8>,
+    /// Set when output is in a legacy character set such as Latin-1 or GBK.
+    legacy: Option<Decoder>,
+}
+
+impl Default for OutputDecoder {
+    fn default() -> Self {
+        Self::for_charmap(None)
+    }
+}
+
+/// How many bytes at the end of `bytes` start a UTF-8 character that is not
+/// complete yet.
+fn incomplete_tail_len(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xc0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return 0,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+/// The encoding for a `locale charmap` name, or `None` for UTF-8 and names
+/// that are not recognised.
+fn legacy_encoding(charmap: &str) -> Option<&'static Encoding> {
+    // A plain ASCII locale is usually the C locale on a box whose programs
+    // still write UTF-8 file names and messages.
+    if matches!(
+        charmap.to_ascii_uppercase().as_str(),
+        "ANSI_X3.4-1968" | "ASCII" | "US-ASCII"
+    ) {
+        return None;
+    }
+    Encoding::for_label(charmap.as_bytes()).filter(|encoding| *encoding != UTF_8)
+}
+
+fn decode_legacy(decoder: &mut Decoder, data: &[u8], last: bool) -> String {
+    let capacity = decoder
+        .max_utf8_buffer_length(data.len())
+        .unwrap_or(data.len() * 3);
+    let mut text = String::with_capacity(capacity);
+    // With room for the worst case the whole input is consumed in one call.
+    let _ = decoder.decode_to_string(data, &mut text, last);
+    text
+}
+
+impl OutputDecoder {
+    /// A decoder for the character set `locale charmap` reported, falling
+    /// back to UTF-8.
+    pub(crate) fn for_charmap(charmap: Option<&str>) -> Self {
+        Self {
+            pending: Vec::new(),
+            legacy: charmap
+                .and_then(legacy_encoding)
+                .map(Encoding::new_decoder_without_bom_handling),
+        }
+    }
+
+    pub(crate) fn decode(&mut self, data: &[u8]) -> String {
+        if let Some(decoder) = &mut self.legacy {
+            return decode_legacy(decoder, data, false);
+        }
+        self.pending.extend_from_slice(data);
+        let end = self.pending.len() - incomplete_tail_len(&self.pending);
+        let text = String::from_utf8_lossy(&self.pending[..end]).into_owned();
+        self.pending.drain(..end);
+        text
+    }
+
+    /// Decodes the last of the output, including a trailing partial
+    /// character.
+    pub(crate) fn finish(&mut self, data: &[u8]) -> String {
+        if let Some(decoder) = &mut self.legacy {
+            return decode_legacy(decoder, data, true);
+        }
+        self.pending.extend_from_slice(data);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_joins_characters_split_across_reads() {
+        let mut decoder = OutputDecoder::default();
+        let text = "größe ✓ 🚀".as_bytes();
+
+        let mut decoded = String::new();
+        for chunk in text.chunks(3) {
+            decoded.push_str(&decoder.decode(chunk));
+        }
+        decoded.push_str(&decoder.finish(&[]));
+        assert_eq!(decoded, "größe ✓ 🚀");
+
+        assert_eq!(decoder.decode(&[b'a', 0xe2, 0x9c]), "a");
+        assert_eq!(decoder.finish(&[]), "\u{fffd}");
+    }
+
+    #[test]
+    fn test_decoder_transcodes_legacy_charsets() {
+        let mut latin1 = OutputDecoder::for_charmap(Some("ISO-8859-1"));
+        assert_eq!(latin1.decode(b"gr\xf6\xdfe\r\n"), "größe\r\n");
+
+        // GBK for "中文", split inside the first character.
+        let mut gbk = OutputDecoder::for_charmap(Some("GBK"));
+        assert_eq!(gbk.decode(b"\x1b[0m\xd6"), "\x1b[0m");
+        assert_eq!(gbk.decode(b"\xd0\xce\xc4"), "中文");
+        assert_eq!(gbk.finish(&[]), "");
+
+        assert!(OutputDecoder::for_charmap(Some("UTF-8")).legacy.is_none());
+        assert!(OutputDecoder::for_charmap(Some("ANSI_X3.4-1968"))
+            .legacy
+            .is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::event_bus::Emitter;
+use crate::shell_metadata::partial_prefix_start;
+use crate::{command_guard, session_lock, shell_mirror, AppState, ShellCommand};
+
+const ENABLE_BRACKETED_PASTE: &str = "\x1b[?2004h";
+const DISABLE_BRACKETED_PASTE: &str = "\x1b[?2004l";
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Payload of `paste-warning`, sent instead of pasting text that the shell
+/// would run line by line as it arrives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteWarning {
+    pub connection_id: String,
+    pub server_id: String,
+    pub shell_id: String,
+    pub lines: usize,
+    /// Whether the text has control characters besides newlines and tabs,
+    /// which the shell would act on like keystrokes.
+    pub control_characters: bool,
+}
+
+/// Follows the bracketed paste mode the remote application turns on and off
+/// with `ESC [ ? 2004 h` / `l`, usually around each prompt.
+#[derive(Default)]
+pub(crate) struct BracketedPasteTracker {
+    pending: String,
+}
+
+impl BracketedPasteTracker {
+    /// The mode set by the last toggle in `output`, if it has one.
+    pub(crate) fn scan(&mut self, output: &str) -> Option<bool> {
+        self.pending.push_str(output);
+        let enabled = self.pending.rfind(ENABLE_BRACKETED_PASTE);
+        let disabled = self.pending.rfind(DISABLE_BRACKETED_PASTE);
+        let mode = match (enabled, disabled) {
+            (Some(enabled), Some(disabled)) => Some(enabled > disabled),
+            (Some(_), None) => Some(true),
+            (None, Some(_)) => Some(false),
+            (None, None) => None,
+        };
+        // Both toggles share everything but the last byte.
+        let keep_from = partial_prefix_start(&self.pending, ENABLE_BRACKETED_PASTE);
+        self.pending.drain(..keep_from);
+        mode
+    }
+}
+
+fn has_control_characters(text: &str) -> bool {
+    text.chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+}
+
+/// Wraps `text` in paste markers. An end marker inside the text is dropped
+/// so the paste cannot end early and run what follows.
+pub(crate) fn bracket(text: &str) -> String {
+    format!(
+        "{}{}{}",
+        PASTE_START,
+        text.replace(PASTE_END, ""),
+        PASTE_END
+    )
+}
+
+/// Pastes `text` into a shell, bracketed when the remote asked for it.
+/// Multi-line or control-character text for a shell without bracketed
+/// paste is not sent unless `force` is set; a `paste-warning` goes out
+/// instead. Returns whether the text was sent.
+#[tauri::command]
+pub async fn paste_input(
+    app: AppHandle,
+    shell_id: String,
+    text: String,
+    force: Option<bool>,
+) -> Result<bool, String> {
+    session_lock::ensure_input_allowed(&app)?;
+    shell_mirror::ensure_writable(&app, &shell_id)?;
+    let (connection_id, server_id, bracketed, cmd_tx) = {
+        let state = app.state::<AppState>();
+        let shells = state.shells.lock().await;
+        let shell = shells
+            .get(&shell_id)
+            .ok_or_else(|| format!("Shell with id {} not found", shell_id))?;
+        (
+            shell.connection_id.clone(),
+            shell.server_id.clone(),
+            shell.bracketed_paste,
+            shell.cmd_tx.clone(),
+        )
+    };
+
+    let lines = text.trim_end_matches(['\r', '\n']).lines().count();
+    let control_characters = has_control_characters(&text);
+    if !bracketed && (lines > 1 || control_characters) && !force.unwrap_or(false) {
+        let warning = PasteWarning {
+            connection_id,
+            server_id,
+            shell_id,
+            lines,
+            control_characters,
+        };
+        let _ = app.emit("paste-warning", warning);
+        return Ok(false);
+    }
+
+    if !command_guard::screen_input(&app, &shell_id, &text).await? {
+        return Ok(false);
+    }
+    let input = if bracketed { bracket(&text) } else { text };
+    cmd_tx
+        .send(ShellCommand::SendInput(input))
+        .await
+        .map_err(|e| format!("Failed to send input: {}", e))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_follows_split_toggles_and_bracket_strips_end_marker() {
+        let mut tracker = BracketedPasteTracker::default();
+
+        assert_eq!(tracker.scan("user@host:~$ "), None);
+        assert_eq!(tracker.scan("\x1b[?20"), None);
+        assert_eq!(tracker.scan("04h"), Some(true));
+        assert_eq!(tracker.scan("ls\r\n\x1b[?2004l\r\nfile\r\n"), Some(false));
+        assert_eq!(tracker.scan("\x1b[?2004l\x1b[?2004h$ "), Some(true));
+
+        assert_eq!(
+            bracket("echo hi\x1b[201~\nrm -rf ~\n"),
+            "\x1b[200~echo hi\nrm -rf ~\n\x1b[201~"
+        );
+        assert!(has_control_characters("ls\x03"));
+        assert!(!has_control_characters("ls -la\n\tpwd\r\n"));
+    }
+}
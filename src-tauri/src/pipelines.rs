@@ -0,0 +1,530 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::event_bus::Emitter;
+use crate::remote::{run_remote_command, RemoteCommandOutput};
+use crate::sftp::open_sftp;
+use crate::{get_app_dir, load_servers, parse_json_array_lenient, snippet_exec};
+
+const PIPELINES_FILE: &str = "pipelines.json";
+const DEFAULT_HEALTH_TIMEOUT_SECONDS: u64 = 60;
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const HEALTH_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How a `WaitForHealth` step decides the server is ready. `{host}` in a
+/// URL is replaced with the server's host.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind")]
+pub enum HealthCheck {
+    Http { url: String },
+    Command { command: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind")]
+pub enum PipelineStepAction {
+    RunSnippet {
+        snippet_id: String,
+    },
+    Upload {
+        local_path: String,
+        remote_path: String,
+    },
+    WaitForHealth {
+        check: HealthCheck,
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PipelineStep {
+    pub name: String,
+    pub action: PipelineStepAction,
+    /// Run on the server, newest step first, when a later step fails.
+    #[serde(default)]
+    pub rollback_command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Pipeline {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<PipelineStep>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStatus {
+    Running,
+    Success,
+    Failed,
+    RolledBack,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStepLog {
+    pub step: String,
+    /// Set for the entries written while undoing a step.
+    #[serde(default)]
+    pub rollback: bool,
+    pub status: PipelineStatus,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineServerRun {
+    pub server_id: String,
+    pub status: PipelineStatus,
+    pub steps: Vec<PipelineStepLog>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineRun {
+    pub pipeline_id: String,
+    pub status: PipelineStatus,
+    pub servers: Vec<PipelineServerRun>,
+}
+
+/// Payload of `pipeline-progress`, sent as each step starts and ends.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineProgress {
+    pub pipeline_id: String,
+    pub server_id: String,
+    pub step_index: usize,
+    pub step: String,
+    pub rollback: bool,
+    pub status: PipelineStatus,
+    pub log: Option<PipelineStepLog>,
+}
+
+fn get_pipelines_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(PIPELINES_FILE)
+}
+
+pub fn load_pipelines(app_dir: &Path) -> Result<Vec<Pipeline>, String> {
+    let path = get_pipelines_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read pipelines file: {}", e))?;
+    parse_json_array_lenient(&data, "pipelines")
+}
+
+pub fn save_pipelines(app_dir: &Path, pipelines: &[Pipeline]) -> Result<(), String> {
+    let path = get_pipelines_path(app_dir);
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Invalid path for pipelines file".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(pipelines)
+        .map_err(|e| format!("Failed to serialize pipelines: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write pipelines file: {}", e))?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn combined_output(output: &RemoteCommandOutput) -> Option<String> {
+    let text = format!("{}{}", output.stdout, output.stderr);
+    (!text.is_empty()).then_some(text)
+}
+
+fn command_result(output: RemoteCommandOutput) -> (Option<String>, Result<(), String>) {
+    let result = match output.exit_code {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!("Command exited with status {}", code)),
+        None => Err("Command finished without an exit status".to_string()),
+    };
+    (combined_output(&output), result)
+}
+
+async fn upload_file(
+    app: &AppHandle,
+    server_id: &str,
+    local_path: &str,
+    remote_path: &str,
+) -> Result<String, String> {
+    let sftp = open_sftp(app, server_id).await?;
+    let mut reader = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", local_path, e))?;
+    let mut writer = sftp
+        .create(remote_path.to_string())
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", remote_path, e))?;
+    let copied = tokio::io::copy(&mut reader, &mut writer)
+        .await
+        .map_err(|e| format!("Failed to upload {}: {}", local_path, e))?;
+    writer
+        .shutdown()
+        .await
+        .map_err(|e| format!("Failed to finish upload: {}", e))?;
+    Ok(format!("Uploaded {} bytes to {}", copied, remote_path))
+}
+
+async fn check_once(app: &AppHandle, server_id: &str, host: &str, check: &HealthCheck) -> bool {
+    match check {
+        HealthCheck::Http { url } => {
+            let url = url.replace("{host}", host);
+            let Ok(client) = reqwest::Client::builder()
+                .timeout(HEALTH_REQUEST_TIMEOUT)
+                .build()
+            else {
+                return false;
+            };
+            client
+                .get(url)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success())
+        }
+        HealthCheck::Command { command } => run_remote_command(app, server_id, command)
+            .await
+            .is_ok_and(|output| output.success()),
+    }
+}
+
+async fn wait_for_health(
+    app: &AppHandle,
+    server_id: &str,
+    host: &str,
+    check: &HealthCheck,
+    timeout_seconds: u64,
+) -> Result<String, String> {
+    let started = Instant::now();
+    let poll = async {
+        loop {
+            if check_once(app, server_id, host, check).await {
+                return;
+            }
+            sleep(HEALTH_POLL_INTERVAL).await;
+        }
+    };
+    timeout(Duration::from_secs(timeout_seconds), poll)
+        .await
+        .map_err(|_| format!("Not healthy after {} seconds", timeout_seconds))?;
+    Ok(format!(
+        "Healthy after {} seconds",
+        started.elapsed().as_secs()
+    ))
+}
+
+async fn run_step(
+    app: &AppHandle,
+    server_id: &str,
+    host: &str,
+    action: &PipelineStepAction,
+) -> (Option<String>, Result<(), String>) {
+    match action {
+        PipelineStepAction::RunSnippet { snippet_id } => {
+            match snippet_exec::run_snippet(app.clone(), server_id.to_string(), snippet_id.clone())
+                .await
+            {
+                Ok(output) => command_result(output),
+                Err(e) => (None, Err(e)),
+            }
+        }
+        PipelineStepAction::Upload {
+            local_path,
+            remote_path,
+        } => match upload_file(app, server_id, local_path, remote_path).await {
+            Ok(message) => (Some(message), Ok(())),
+            Err(e) => (None, Err(e)),
+        },
+        PipelineStepAction::WaitForHealth {
+            check,
+            timeout_seconds,
+        } => {
+            let timeout_seconds = timeout_seconds.unwrap_or(DEFAULT_HEALTH_TIMEOUT_SECONDS);
+            match wait_for_health(app, server_id, host, check, timeout_seconds).await {
+                Ok(message) => (Some(message), Ok(())),
+                Err(e) => (None, Err(e)),
+            }
+        }
+    }
+}
+
+struct ServerRunContext<'a> {
+    app: &'a AppHandle,
+    pipeline_id: &'a str,
+    server_id: &'a str,
+    logs: Vec<PipelineStepLog>,
+}
+
+impl ServerRunContext<'_> {
+    fn emit(&self, step_index: usize, step: &str, rollback: bool, status: PipelineStatus) {
+        let payload = PipelineProgress {
+            pipeline_id: self.pipeline_id.to_string(),
+            server_id: self.server_id.to_string(),
+            step_index,
+            step: step.to_string(),
+            rollback,
+            status,
+            log: (status != PipelineStatus::Running)
+                .then(|| self.logs.last().cloned())
+                .flatten(),
+        };
+        let _ = self.app.emit("pipeline-progress", payload);
+    }
+
+    fn record(
+        &mut self,
+        step: &str,
+        rollback: bool,
+        started_at: u64,
+        started: Instant,
+        (output, result): (Option<String>, Result<(), String>),
+    ) -> bool {
+        let ok = result.is_ok();
+        self.logs.push(PipelineStepLog {
+            step: step.to_string(),
+            rollback,
+            status: if ok {
+                PipelineStatus::Success
+            } else {
+                PipelineStatus::Failed
+            },
+            output,
+            error: result.err(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        ok
+    }
+}
+
+async fn run_on_server(
+    app: &AppHandle,
+    pipeline: &Pipeline,
+    server_id: &str,
+    host: &str,
+) -> PipelineServerRun {
+    let mut ctx = ServerRunContext {
+        app,
+        pipeline_id: &pipeline.id,
+        server_id,
+        logs: Vec::new(),
+    };
+
+    let mut failed_at = None;
+    for (index, step) in pipeline.steps.iter().enumerate() {
+        ctx.emit(index, &step.name, false, PipelineStatus::Running);
+        let (started_at, started) = (now_secs(), Instant::now());
+        let outcome = run_step(app, server_id, host, &step.action).await;
+        let ok = ctx.record(&step.name, false, started_at, started, outcome);
+        let status = ctx
+            .logs
+            .last()
+            .map_or(PipelineStatus::Failed, |log| log.status);
+        ctx.emit(index, &step.name, false, status);
+        if !ok {
+            failed_at = Some(index);
+            break;
+        }
+    }
+
+    let Some(failed_at) = failed_at else {
+        return PipelineServerRun {
+            server_id: server_id.to_string(),
+            status: PipelineStatus::Success,
+            steps: ctx.logs,
+        };
+    };
+
+    // Undo the failed step too, since it may have got partway.
+    let mut rolled_back = true;
+    for index in (0..=failed_at).rev() {
+        let step = &pipeline.steps[index];
+        let Some(command) = &step.rollback_command else {
+            continue;
+        };
+        ctx.emit(index, &step.name, true, PipelineStatus::Running);
+        let (started_at, started) = (now_secs(), Instant::now());
+        let outcome = match run_remote_command(app, server_id, command).await {
+            Ok(output) => command_result(output),
+            Err(e) => (None, Err(e)),
+        };
+        rolled_back &= ctx.record(&step.name, true, started_at, started, outcome);
+        let status = ctx
+            .logs
+            .last()
+            .map_or(PipelineStatus::Failed, |log| log.status);
+        ctx.emit(index, &step.name, true, status);
+    }
+
+    let any_rollback = ctx.logs.iter().any(|log| log.rollback);
+    PipelineServerRun {
+        server_id: server_id.to_string(),
+        status: if any_rollback && rolled_back {
+            PipelineStatus::RolledBack
+        } else {
+            PipelineStatus::Failed
+        },
+        steps: ctx.logs,
+    }
+}
+
+#[tauri::command]
+pub async fn get_pipelines(app: AppHandle) -> Result<Vec<Pipeline>, String> {
+    load_pipelines(&get_app_dir(&app)?)
+}
+
+#[tauri::command]
+pub async fn add_pipeline(app: AppHandle, pipeline: Pipeline) -> Result<Vec<Pipeline>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut pipelines = load_pipelines(&app_dir)?;
+    pipelines.push(pipeline);
+    save_pipelines(&app_dir, &pipelines)?;
+    Ok(pipelines)
+}
+
+#[tauri::command]
+pub async fn update_pipeline(
+    app: AppHandle,
+    id: String,
+    pipeline: Pipeline,
+) -> Result<Vec<Pipeline>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut pipelines = load_pipelines(&app_dir)?;
+    let index = pipelines
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Pipeline with id {} not found", id))?;
+    pipelines[index] = pipeline;
+    save_pipelines(&app_dir, &pipelines)?;
+    Ok(pipelines)
+}
+
+#[tauri::command]
+pub async fn delete_pipeline(app: AppHandle, id: String) -> Result<Vec<Pipeline>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut pipelines = load_pipelines(&app_dir)?;
+    let index = pipelines
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Pipeline with id {} not found", id))?;
+    pipelines.remove(index);
+    save_pipelines(&app_dir, &pipelines)?;
+    Ok(pipelines)
+}
+
+/// Runs the pipeline on each server in turn, over their active sessions.
+/// A server that fails is rolled back and the remaining servers are
+/// skipped, so a bad release stops at the first host.
+#[tauri::command]
+pub async fn run_pipeline(
+    app: AppHandle,
+    pipeline_id: String,
+    server_ids: Vec<String>,
+) -> Result<PipelineRun, String> {
+    let app_dir = get_app_dir(&app)?;
+    let pipeline = load_pipelines(&app_dir)?
+        .into_iter()
+        .find(|pipeline| pipeline.id == pipeline_id)
+        .ok_or_else(|| format!("Pipeline with id {} not found", pipeline_id))?;
+    let servers = load_servers(&app_dir, &app)?;
+    let mut targets = Vec::with_capacity(server_ids.len());
+    for server_id in &server_ids {
+        let server = servers
+            .iter()
+            .find(|server| &server.id == server_id)
+            .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+        targets.push((server.id.clone(), server.host.clone()));
+    }
+
+    let mut runs = Vec::with_capacity(targets.len());
+    let mut stopped = false;
+    for (server_id, host) in targets {
+        if stopped {
+            runs.push(PipelineServerRun {
+                server_id,
+                status: PipelineStatus::Skipped,
+                steps: Vec::new(),
+            });
+            continue;
+        }
+        let run = run_on_server(&app, &pipeline, &server_id, &host).await;
+        stopped = run.status != PipelineStatus::Success;
+        runs.push(run);
+    }
+
+    Ok(PipelineRun {
+        pipeline_id,
+        status: overall_status(&runs),
+        servers: runs,
+    })
+}
+
+fn overall_status(runs: &[PipelineServerRun]) -> PipelineStatus {
+    runs.iter()
+        .map(|run| run.status)
+        .find(|status| *status != PipelineStatus::Success && *status != PipelineStatus::Skipped)
+        .unwrap_or(PipelineStatus::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_serialization() {
+        let json = r#"{
+            "id": "deploy-api",
+            "name": "Deploy API",
+            "steps": [
+                {
+                    "name": "Upload build",
+                    "action": {
+                        "kind": "Upload",
+                        "local_path": "/tmp/api.tar.gz",
+                        "remote_path": "/srv/api.tar.gz"
+                    },
+                    "rollback_command": "rm -f /srv/api.tar.gz"
+                },
+                {
+                    "name": "Wait for API",
+                    "action": {
+                        "kind": "WaitForHealth",
+                        "check": { "kind": "Http", "url": "http://{host}:8080/health" }
+                    }
+                }
+            ]
+        }"#;
+
+        let pipeline: Pipeline = serde_json::from_str(json).expect("Failed to parse pipeline");
+
+        assert_eq!(pipeline.steps.len(), 2);
+        assert_eq!(
+            pipeline.steps[1].action,
+            PipelineStepAction::WaitForHealth {
+                check: HealthCheck::Http {
+                    url: "http://{host}:8080/health".to_string()
+                },
+                timeout_seconds: None,
+            }
+        );
+        assert!(pipeline.steps[1].rollback_command.is_none());
+    }
+}
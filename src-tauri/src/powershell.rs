@@ -0,0 +1,46 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+
+// Keeps progress records out of stderr and makes output UTF-8 with
+// invariant-culture numbers, whatever the server's locale.
+const PRELUDE: &str = "$ProgressPreference = 'SilentlyContinue'; \
+     [Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+     [Threading.Thread]::CurrentThread.CurrentCulture = [Globalization.CultureInfo]::InvariantCulture; ";
+
+/// A command line that runs `script` in Windows PowerShell. The script is
+/// passed encoded so it arrives intact whether the server's default shell
+/// is cmd.exe or PowerShell.
+pub(crate) fn command(script: &str) -> String {
+    let utf16: Vec<u8> = format!("{}{}", PRELUDE, script)
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    format!(
+        "powershell -NoProfile -NonInteractive -EncodedCommand {}",
+        BASE64.encode(utf16)
+    )
+}
+
+/// Quotes a value as a PowerShell string literal.
+pub(crate) fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_encodes_script_as_utf16() {
+        let command = command("Get-Date");
+        let encoded = command.rsplit(' ').next().unwrap();
+        let bytes = BASE64.decode(encoded).unwrap();
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        assert!(String::from_utf16(&units).unwrap().ends_with("; Get-Date"));
+        assert_eq!(quote("C:\\Users\\O'Brien"), "'C:\\Users\\O''Brien'");
+    }
+}
@@ -0,0 +1,317 @@
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{
+    audit, config_watch, parse_json_array_lenient, AppState, AuthMethod, ServerConnection,
+    SERVERS_FILE,
+};
+
+use ssh_thing_core::profiles::PROFILES_FILE;
+pub use ssh_thing_core::profiles::{keyring_service_name, profile_dir, DEFAULT_PROFILE_ID};
+use ssh_thing_core::storage;
+use ssh_thing_core::KeyringSecretStore;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfilesConfig {
+    #[serde(default)]
+    active_profile: Option<String>,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileList {
+    pub active_profile: String,
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileChangedEvent {
+    pub active_profile: String,
+}
+
+/// The profile every lookup of the app directory and keyring goes through.
+/// Kept in memory so path resolution does not touch the disk.
+#[derive(Debug)]
+pub struct ProfileState {
+    active: RwLock<String>,
+}
+
+impl Default for ProfileState {
+    fn default() -> Self {
+        Self {
+            active: RwLock::new(DEFAULT_PROFILE_ID.to_string()),
+        }
+    }
+}
+
+fn default_profile() -> Profile {
+    Profile {
+        id: DEFAULT_PROFILE_ID.to_string(),
+        name: "Default".to_string(),
+    }
+}
+
+pub fn active_profile_id(app: &AppHandle) -> String {
+    let state = app.state::<AppState>();
+    let active = state
+        .profiles
+        .active
+        .read()
+        .map(|active| active.clone())
+        .unwrap_or_else(|_| DEFAULT_PROFILE_ID.to_string());
+    active
+}
+
+fn set_active_profile_id(app: &AppHandle, profile_id: &str) {
+    let state = app.state::<AppState>();
+    let result = state.profiles.active.write();
+    if let Ok(mut active) = result {
+        *active = profile_id.to_string();
+    }
+}
+
+fn root_app_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+fn get_profiles_path(root: &Path) -> PathBuf {
+    root.join(PROFILES_FILE)
+}
+
+fn load_profiles_config(root: &Path) -> Result<ProfilesConfig, String> {
+    let path = get_profiles_path(root);
+    if !path.exists() {
+        return Ok(ProfilesConfig::default());
+    }
+
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read profiles file: {}", e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse profiles file: {}", e))
+}
+
+fn save_profiles_config(root: &Path, config: &ProfilesConfig) -> Result<(), String> {
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    fs::write(get_profiles_path(root), content)
+        .map_err(|e| format!("Failed to write profiles file: {}", e))?;
+    Ok(())
+}
+
+fn profile_list(config: &ProfilesConfig) -> ProfileList {
+    let mut profiles = vec![default_profile()];
+    profiles.extend(
+        config
+            .profiles
+            .iter()
+            .filter(|profile| profile.id != DEFAULT_PROFILE_ID)
+            .cloned(),
+    );
+
+    let active_profile = config
+        .active_profile
+        .clone()
+        .filter(|active| profiles.iter().any(|profile| &profile.id == active))
+        .unwrap_or_else(|| DEFAULT_PROFILE_ID.to_string());
+
+    ProfileList {
+        active_profile,
+        profiles,
+    }
+}
+
+fn validate_profile_name(config: &ProfilesConfig, name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    let taken = profile_list(config)
+        .profiles
+        .iter()
+        .any(|profile| profile.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(format!("A profile named {} already exists", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Restores the profile that was active when the app last closed.
+pub fn load_active_profile(app: &AppHandle) {
+    let result = root_app_dir(app).and_then(|root| load_profiles_config(&root));
+    match result {
+        Ok(config) => set_active_profile_id(app, &profile_list(&config).active_profile),
+        Err(e) => debug!(error = %e, "Failed to load profiles, using default profile"),
+    }
+}
+
+// Secrets are not stored in the profile directory, so they have to be removed
+// from the keyring explicitly before the directory goes away.
+fn delete_profile_secrets(profile_dir: &Path, profile_id: &str) -> Result<(), String> {
+    let path = profile_dir.join(SERVERS_FILE);
+    let servers: Vec<ServerConnection> = if path.exists() {
+        let data =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read servers file: {}", e))?;
+        parse_json_array_lenient(&data, "servers")?
+    } else {
+        Vec::new()
+    };
+    let identities = storage::load_identities(profile_dir)?;
+
+    let service = keyring_service_name(profile_id);
+    let auths = servers
+        .into_iter()
+        .map(|server| server.auth)
+        .chain(identities.into_iter().map(|identity| identity.auth));
+    for auth in auths {
+        if let AuthMethod::SecretRef { secret_id, .. } = auth {
+            if let Ok(entry) = Entry::new(&service, &secret_id) {
+                let _ = entry.delete_password();
+            }
+        }
+    }
+    audit::forget_keyring_state(&KeyringSecretStore::new(service));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle) -> Result<ProfileList, String> {
+    let root = root_app_dir(&app)?;
+    let config = load_profiles_config(&root)?;
+    Ok(profile_list(&config))
+}
+
+#[tauri::command]
+pub async fn create_profile(app: AppHandle, name: String) -> Result<ProfileList, String> {
+    let root = root_app_dir(&app)?;
+    let mut config = load_profiles_config(&root)?;
+    let name = validate_profile_name(&config, &name)?;
+    config.profiles.push(Profile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+    });
+    save_profiles_config(&root, &config)?;
+    Ok(profile_list(&config))
+}
+
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, id: String) -> Result<ProfileList, String> {
+    if id == DEFAULT_PROFILE_ID {
+        return Err("The default profile cannot be deleted".to_string());
+    }
+    if id == active_profile_id(&app) {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+
+    let root = root_app_dir(&app)?;
+    let mut config = load_profiles_config(&root)?;
+    let original_len = config.profiles.len();
+    config.profiles.retain(|profile| profile.id != id);
+    if config.profiles.len() == original_len {
+        return Err(format!("Profile with id {} not found", id));
+    }
+
+    let dir = profile_dir(&root, &id);
+    delete_profile_secrets(&dir, &id)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to remove profile directory: {}", e))?;
+    }
+    save_profiles_config(&root, &config)?;
+    Ok(profile_list(&config))
+}
+
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, id: String) -> Result<ProfileList, String> {
+    let root = root_app_dir(&app)?;
+    let mut config = load_profiles_config(&root)?;
+    if !profile_list(&config)
+        .profiles
+        .iter()
+        .any(|profile| profile.id == id)
+    {
+        return Err(format!("Profile with id {} not found", id));
+    }
+
+    // Open sessions resolve secrets and server records through the active
+    // profile, so switching underneath them would break reconnects.
+    {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        if !sessions.is_empty() {
+            return Err("Disconnect all sessions before switching profiles".to_string());
+        }
+    }
+
+    config.active_profile = Some(id.clone());
+    save_profiles_config(&root, &config)?;
+    set_active_profile_id(&app, &id);
+    if let Err(e) = config_watch::watch_app_dir(&app) {
+        debug!(error = %e, "Failed to watch config files for the new profile");
+    }
+    let _ = app.emit(
+        "profile-changed",
+        ProfileChangedEvent {
+            active_profile: id.clone(),
+        },
+    );
+    Ok(profile_list(&config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: &str, name: &str) -> Profile {
+        Profile {
+            id: id.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_profile_list_falls_back_to_default() {
+        let config = ProfilesConfig {
+            active_profile: Some("missing".to_string()),
+            profiles: vec![profile("work", "Work")],
+        };
+
+        let list = profile_list(&config);
+
+        assert_eq!(list.active_profile, DEFAULT_PROFILE_ID);
+        assert_eq!(
+            list.profiles,
+            vec![default_profile(), profile("work", "Work")]
+        );
+    }
+
+    #[test]
+    fn test_validate_profile_name_rejects_duplicates() {
+        let config = ProfilesConfig {
+            active_profile: None,
+            profiles: vec![profile("work", "Work")],
+        };
+
+        assert_eq!(
+            validate_profile_name(&config, "  Personal "),
+            Ok("Personal".to_string())
+        );
+        assert!(validate_profile_name(&config, "work").is_err());
+        assert!(validate_profile_name(&config, "default").is_err());
+        assert!(validate_profile_name(&config, "   ").is_err());
+    }
+}
@@ -0,0 +1,628 @@
+use russh::client::Msg;
+use russh::{Channel, ChannelMsg};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::{open_server_channel, os_fingerprint, powershell};
+
+const MAX_REMOTE_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+const DEFAULT_SEARCH_RESULTS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiskUsageEntry {
+    pub filesystem: String,
+    pub size_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    pub use_percent: u8,
+    pub mount_point: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirectorySize {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RemoteFileType {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSearchOptions {
+    #[serde(default)]
+    pub file_type: Option<RemoteFileType>,
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileSearchResult {
+    pub paths: Vec<String>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+    pub rss_bytes: u64,
+    pub state: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+    Pid,
+    Command,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteCommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(default)]
+    pub exit_code: Option<u32>,
+    /// Wall time from starting the command until the channel closed.
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+impl RemoteCommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Quotes a value for a POSIX shell so remote paths with spaces or quotes
+/// reach the command as a single argument.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn push_capped(target: &mut Vec<u8>, chunk: &[u8]) {
+    let remaining = MAX_REMOTE_OUTPUT_BYTES.saturating_sub(target.len());
+    target.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+}
+
+/// Runs a non-interactive command over the server's active session and
+/// collects stdout and stderr separately.
+pub(crate) async fn run_remote_command(
+    app: &AppHandle,
+    server_id: &str,
+    command: &str,
+) -> Result<RemoteCommandOutput, String> {
+    let channel = open_server_channel(app, server_id).await?;
+    run_on_channel(channel, command).await
+}
+
+/// Like `run_remote_command` on a channel the caller opened, for when it
+/// already holds the session.
+pub(crate) async fn run_on_channel(
+    mut channel: Channel<Msg>,
+    command: &str,
+) -> Result<RemoteCommandOutput, String> {
+    let started = Instant::now();
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_code = None;
+
+    while let Some(message) = channel.wait().await {
+        match message {
+            ChannelMsg::Data { data } => push_capped(&mut stdout, data.as_ref()),
+            ChannelMsg::ExtendedData { data, .. } => push_capped(&mut stderr, data.as_ref()),
+            ChannelMsg::ExitStatus { exit_status } => exit_code = Some(exit_status),
+            ChannelMsg::ExitSignal {
+                signal_name,
+                error_message,
+                ..
+            } => {
+                return Err(format!(
+                    "Command terminated by signal {:?}: {}",
+                    signal_name, error_message
+                ));
+            }
+            ChannelMsg::Failure => {
+                return Err("Remote command request failed".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(RemoteCommandOutput {
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        exit_code,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+fn command_error(action: &str, output: &RemoteCommandOutput) -> String {
+    let detail = output.stderr.trim();
+    if detail.is_empty() {
+        format!("{} failed with exit code {:?}", action, output.exit_code)
+    } else {
+        format!("{} failed: {}", action, detail)
+    }
+}
+
+// `df -P` keeps each filesystem on one line even when the device name is
+// long, which the default output wraps.
+pub(crate) fn parse_df_output(output: &str) -> Vec<DiskUsageEntry> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let kib = |value: &str| value.parse::<u64>().ok().map(|v| v * 1024);
+            Some(DiskUsageEntry {
+                filesystem: fields[0].to_string(),
+                size_bytes: kib(fields[1])?,
+                used_bytes: kib(fields[2])?,
+                available_bytes: kib(fields[3])?,
+                use_percent: fields[4].trim_end_matches('%').parse().unwrap_or(0),
+                mount_point: fields[5..].join(" "),
+            })
+        })
+        .collect()
+}
+
+// Local fixed disks in `df -P -k` form, so one parser reads both.
+pub(crate) const WINDOWS_DF_SCRIPT: &str = "'Filesystem 1024-blocks Used Available Capacity Mounted on'; \
+     Get-CimInstance Win32_LogicalDisk -Filter 'DriveType=3' | ForEach-Object { \
+     $used = $_.Size - $_.FreeSpace; \
+     '{0} {1} {2} {3} {4}% {0}\\' -f $_.DeviceID, [uint64]($_.Size / 1024), [uint64]($used / 1024), \
+     [uint64]($_.FreeSpace / 1024), [math]::Round($used * 100 / [math]::Max($_.Size, 1)) }";
+
+fn windows_du_script(path: &str) -> String {
+    format!(
+        "if (Test-Path -LiteralPath {path}) {{ \
+         [uint64][math]::Ceiling([double](Get-ChildItem -LiteralPath {path} -Recurse -Force -File \
+         -ErrorAction SilentlyContinue | Measure-Object -Property Length -Sum).Sum / 1024) }}",
+        path = powershell::quote(path)
+    )
+}
+
+fn parse_du_output(output: &str, path: &str) -> Result<DirectorySize, String> {
+    let kib = output
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.parse::<u64>().ok())
+        .ok_or_else(|| format!("Unexpected du output for {}", path))?;
+    Ok(DirectorySize {
+        path: path.to_string(),
+        size_bytes: kib * 1024,
+    })
+}
+
+fn build_search_command(
+    path: &str,
+    pattern: &str,
+    options: &FileSearchOptions,
+    limit: usize,
+) -> String {
+    // find has no `--`, so a leading dash would be read as an expression.
+    let path = if path.starts_with('-') {
+        shell_quote(&format!("./{}", path))
+    } else {
+        shell_quote(path)
+    };
+    let pattern = shell_quote(pattern);
+    let mut fd_args = String::new();
+    let mut find_args = String::new();
+    if let Some(depth) = options.max_depth {
+        fd_args.push_str(&format!(" --max-depth {}", depth));
+        find_args.push_str(&format!(" -maxdepth {}", depth));
+    }
+    match options.file_type {
+        Some(RemoteFileType::File) => {
+            fd_args.push_str(" --type f");
+            find_args.push_str(" -type f");
+        }
+        Some(RemoteFileType::Directory) => {
+            fd_args.push_str(" --type d");
+            find_args.push_str(" -type d");
+        }
+        None => {}
+    }
+    // One extra line tells us whether the result was truncated.
+    format!(
+        "if command -v fd >/dev/null 2>&1; then fd --hidden --no-ignore --absolute-path --glob{fd} -- {pattern} {path}; \
+         else find {path}{find} -name {pattern}; fi 2>/dev/null | head -n {limit}",
+        fd = fd_args,
+        find = find_args,
+        pattern = pattern,
+        path = path,
+        limit = limit + 1,
+    )
+}
+
+fn windows_search_script(
+    path: &str,
+    pattern: &str,
+    options: &FileSearchOptions,
+    limit: usize,
+) -> String {
+    let mut args = String::new();
+    // `find -maxdepth 1` lists direct children, which is `-Depth 0`.
+    if let Some(depth) = options.max_depth {
+        args.push_str(&format!(" -Depth {}", depth.saturating_sub(1)));
+    }
+    match options.file_type {
+        Some(RemoteFileType::File) => args.push_str(" -File"),
+        Some(RemoteFileType::Directory) => args.push_str(" -Directory"),
+        None => {}
+    }
+    format!(
+        "Get-ChildItem -LiteralPath {path} -Recurse -Force -Filter {pattern}{args} \
+         -ErrorAction SilentlyContinue | Select-Object -First {limit} | ForEach-Object {{ $_.FullName }}",
+        path = powershell::quote(path),
+        pattern = powershell::quote(pattern),
+        args = args,
+        limit = limit + 1,
+    )
+}
+
+fn parse_search_output(output: &str, limit: usize) -> FileSearchResult {
+    let mut paths: Vec<String> = output
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    let truncated = paths.len() > limit;
+    paths.truncate(limit);
+    FileSearchResult { paths, truncated }
+}
+
+// The trailing `=` on each column suppresses the header on both procps and
+// BSD ps; `args` goes last because it may contain spaces.
+const PS_COMMAND: &str = "ps -eo pid=,ppid=,user=,pcpu=,pmem=,rss=,stat=,args=";
+
+fn parse_ps_output(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let user = fields.next()?.to_string();
+            let cpu_percent = fields.next()?.parse().ok()?;
+            let memory_percent = fields.next()?.parse().ok()?;
+            let rss_kib: u64 = fields.next()?.parse().ok()?;
+            let state = fields.next()?.to_string();
+            let command = fields.collect::<Vec<_>>().join(" ");
+            Some(ProcessInfo {
+                pid,
+                ppid,
+                user,
+                cpu_percent,
+                memory_percent,
+                rss_bytes: rss_kib * 1024,
+                state,
+                command,
+            })
+        })
+        .collect()
+}
+
+// Tab-separated in `ProcessInfo` order. The working set stands in for RSS;
+// the user would need a slow per-process owner lookup and Windows has no
+// process state, so both are left empty.
+const WINDOWS_PS_SCRIPT: &str = "$memory = (Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory; \
+     $cores = [Environment]::ProcessorCount; $cpu = @{}; \
+     Get-CimInstance Win32_PerfFormattedData_PerfProc_Process | Where-Object { $_.Name -ne '_Total' } | \
+     ForEach-Object { $cpu[[int]$_.IDProcess] = $_.PercentProcessorTime / $cores }; \
+     Get-CimInstance Win32_Process | ForEach-Object { \
+     $command = if ($_.CommandLine) { $_.CommandLine } else { $_.Name }; \
+     \"{0}`t{1}`t`t{2:0.0}`t{3:0.0}`t{4}`t`t{5}\" -f $_.ProcessId, $_.ParentProcessId, \
+     [double]$cpu[[int]$_.ProcessId], ($_.WorkingSetSize * 100.0 / $memory), \
+     [uint64]($_.WorkingSetSize / 1024), ($command -replace '\\s+', ' ') }";
+
+fn parse_windows_ps_output(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim_end_matches('\r').splitn(8, '\t').collect();
+            let [pid, ppid, user, cpu, memory, rss_kib, state, command] = fields[..] else {
+                return None;
+            };
+            Some(ProcessInfo {
+                pid: pid.parse().ok()?,
+                ppid: ppid.parse().ok()?,
+                user: user.to_string(),
+                cpu_percent: cpu.parse().ok()?,
+                memory_percent: memory.parse().ok()?,
+                rss_bytes: rss_kib.parse::<u64>().ok()? * 1024,
+                state: state.to_string(),
+                command: command.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn filter_and_sort_processes(
+    mut processes: Vec<ProcessInfo>,
+    sort: ProcessSort,
+    filter: Option<&str>,
+) -> Vec<ProcessInfo> {
+    if let Some(filter) = filter.map(str::trim).filter(|f| !f.is_empty()) {
+        let filter = filter.to_lowercase();
+        processes.retain(|process| {
+            process.command.to_lowercase().contains(&filter)
+                || process.user.to_lowercase().contains(&filter)
+                || process.pid.to_string() == filter
+        });
+    }
+
+    match sort {
+        ProcessSort::Cpu => {
+            processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+        }
+        ProcessSort::Memory => processes.sort_by_key(|process| Reverse(process.rss_bytes)),
+        ProcessSort::Pid => processes.sort_by_key(|process| process.pid),
+        ProcessSort::Command => {
+            processes.sort_by_key(|process| process.command.to_lowercase());
+        }
+    }
+    processes
+}
+
+fn validate_signal(signal: &str) -> Result<String, String> {
+    let signal = signal.trim().trim_start_matches("SIG").to_uppercase();
+    let valid = !signal.is_empty()
+        && (signal.chars().all(|c| c.is_ascii_digit())
+            || signal.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(format!("Invalid signal: {}", signal));
+    }
+    Ok(signal)
+}
+
+#[tauri::command]
+pub async fn get_disk_usage(
+    app: AppHandle,
+    server_id: String,
+) -> Result<Vec<DiskUsageEntry>, String> {
+    let command = if os_fingerprint::is_windows(&app, &server_id).await {
+        powershell::command(WINDOWS_DF_SCRIPT)
+    } else {
+        "df -P -k".to_string()
+    };
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    if !output.success() && output.stdout.trim().is_empty() {
+        return Err(command_error("df", &output));
+    }
+    Ok(parse_df_output(&output.stdout))
+}
+
+#[tauri::command]
+pub async fn get_directory_size(
+    app: AppHandle,
+    server_id: String,
+    path: String,
+) -> Result<DirectorySize, String> {
+    let command = if os_fingerprint::is_windows(&app, &server_id).await {
+        powershell::command(&windows_du_script(&path))
+    } else {
+        format!("du -s -k -- {}", shell_quote(&path))
+    };
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    // du exits non-zero when it hits unreadable subdirectories but still
+    // prints a usable total.
+    if output.stdout.trim().is_empty() {
+        return Err(command_error("du", &output));
+    }
+    parse_du_output(&output.stdout, &path)
+}
+
+#[tauri::command]
+pub async fn find_remote_files(
+    app: AppHandle,
+    server_id: String,
+    path: String,
+    pattern: String,
+    options: Option<FileSearchOptions>,
+) -> Result<FileSearchResult, String> {
+    let options = options.unwrap_or_default();
+    let limit = options.max_results.unwrap_or(DEFAULT_SEARCH_RESULTS).max(1);
+    let command = if os_fingerprint::is_windows(&app, &server_id).await {
+        powershell::command(&windows_search_script(&path, &pattern, &options, limit))
+    } else {
+        build_search_command(&path, &pattern, &options, limit)
+    };
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    Ok(parse_search_output(&output.stdout, limit))
+}
+
+#[tauri::command]
+pub async fn list_processes(
+    app: AppHandle,
+    server_id: String,
+    sort: Option<ProcessSort>,
+    filter: Option<String>,
+) -> Result<Vec<ProcessInfo>, String> {
+    let windows = os_fingerprint::is_windows(&app, &server_id).await;
+    let command = if windows {
+        powershell::command(WINDOWS_PS_SCRIPT)
+    } else {
+        PS_COMMAND.to_string()
+    };
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    if !output.success() && output.stdout.trim().is_empty() {
+        return Err(command_error("ps", &output));
+    }
+    let processes = if windows {
+        parse_windows_ps_output(&output.stdout)
+    } else {
+        parse_ps_output(&output.stdout)
+    };
+    Ok(filter_and_sort_processes(
+        processes,
+        sort.unwrap_or_default(),
+        filter.as_deref(),
+    ))
+}
+
+#[tauri::command]
+pub async fn kill_process(
+    app: AppHandle,
+    server_id: String,
+    pid: u32,
+    signal: Option<String>,
+) -> Result<(), String> {
+    let signal = validate_signal(signal.as_deref().unwrap_or("TERM"))?;
+    // Windows has no signals; every signal terminates the process.
+    let command = if os_fingerprint::is_windows(&app, &server_id).await {
+        powershell::command(&format!(
+            "Stop-Process -Id {} -Force -ErrorAction Stop",
+            pid
+        ))
+    } else {
+        format!("kill -s {} {}", signal, pid)
+    };
+    let output = run_remote_command(&app, &server_id, &command).await?;
+    if !output.success() {
+        return Err(command_error("kill", &output));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("/var/www"), "'/var/www'");
+        assert_eq!(shell_quote("it's here"), "'it'\\''s here'");
+    }
+
+    #[test]
+    fn test_parse_df_output() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1         41152736  12345678  26703280      32% /\n\
+                      tmpfs               512000         0    512000       0% /run/user files\n\
+                      garbage line\n";
+
+        let entries = parse_df_output(output);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filesystem, "/dev/sda1");
+        assert_eq!(entries[0].size_bytes, 41152736 * 1024);
+        assert_eq!(entries[0].use_percent, 32);
+        assert_eq!(entries[0].mount_point, "/");
+        assert_eq!(entries[1].mount_point, "/run/user files");
+    }
+
+    #[test]
+    fn test_parse_du_output() {
+        let size = parse_du_output("2048\t/var/log\n", "/var/log").expect("parse du");
+        assert_eq!(size.size_bytes, 2048 * 1024);
+        assert!(parse_du_output("", "/var/log").is_err());
+    }
+
+    #[test]
+    fn test_parse_search_output_marks_truncation() {
+        let result = parse_search_output("/a\n/b\n/c\n", 2);
+        assert_eq!(result.paths, vec!["/a", "/b"]);
+        assert!(result.truncated);
+
+        let result = parse_search_output("/a\n", 2);
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_parse_ps_output_and_sort() {
+        let output = "    1     0 root      0.0  0.1  11000 Ss   /sbin/init splash\n\
+                      4242     1 www-data 12.5  2.0 204800 Sl   nginx: worker process\n\
+                      4300     1 dev       3.1  8.0 819200 S    node server.js --port 3000\n\
+                      not a process line\n";
+
+        let processes = parse_ps_output(output);
+        assert_eq!(processes.len(), 3);
+        assert_eq!(processes[1].command, "nginx: worker process");
+        assert_eq!(processes[2].rss_bytes, 819200 * 1024);
+
+        let by_cpu = filter_and_sort_processes(processes.clone(), ProcessSort::Cpu, None);
+        assert_eq!(by_cpu[0].pid, 4242);
+
+        let by_memory = filter_and_sort_processes(processes.clone(), ProcessSort::Memory, None);
+        assert_eq!(by_memory[0].pid, 4300);
+
+        let filtered = filter_and_sort_processes(processes, ProcessSort::Pid, Some("NODE"));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].user, "dev");
+    }
+
+    #[test]
+    fn test_windows_helpers() {
+        let output = "4\t0\t\t0.0\t0.0\t140\t\tSystem\r\n\
+                      5120\t812\t\t12.5\t1.5\t204800\t\t\"C:\\Program Files\\app.exe\" --serve\r\n\
+                      garbage\r\n";
+        let processes = parse_windows_ps_output(output);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[1].pid, 5120);
+        assert_eq!(processes[1].rss_bytes, 204800 * 1024);
+        assert_eq!(
+            processes[1].command,
+            "\"C:\\Program Files\\app.exe\" --serve"
+        );
+
+        let options = FileSearchOptions {
+            file_type: Some(RemoteFileType::Directory),
+            max_depth: Some(1),
+            max_results: None,
+        };
+        let script = windows_search_script("C:\\Users\\O'Brien", "*.log", &options, 10);
+        assert!(script.contains("-LiteralPath 'C:\\Users\\O''Brien' -Recurse -Force -Filter '*.log' -Depth 0 -Directory"));
+        assert!(script.contains("Select-Object -First 11"));
+    }
+
+    #[test]
+    fn test_validate_signal() {
+        assert_eq!(validate_signal("SIGKILL"), Ok("KILL".to_string()));
+        assert_eq!(validate_signal("hup"), Ok("HUP".to_string()));
+        assert_eq!(validate_signal("9"), Ok("9".to_string()));
+        assert!(validate_signal("TERM; rm -rf /").is_err());
+        assert!(validate_signal("").is_err());
+    }
+
+    #[test]
+    fn test_build_search_command_quotes_arguments() {
+        let options = FileSearchOptions {
+            file_type: Some(RemoteFileType::File),
+            max_depth: Some(3),
+            max_results: None,
+        };
+        let command = build_search_command("/srv/my app", "*.log", &options, 10);
+
+        assert!(command.contains("find '/srv/my app' -maxdepth 3 -type f -name '*.log'"));
+        assert!(command.contains("--max-depth 3 --type f -- '*.log' '/srv/my app'"));
+        assert!(command.ends_with("head -n 11"));
+
+        let command = build_search_command("-delete", "-x", &options, 10);
+        assert!(command.contains("find './-delete' -maxdepth 3"));
+        assert!(command.contains("-- '-x' './-delete'"));
+    }
+}
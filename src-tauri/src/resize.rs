@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep_until, Duration, Instant};
+
+// Window drags send a resize per frame; the remote only needs the size the
+// drag settles on, and each one costs a SIGWINCH and a full repaint.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// A terminal's size in cells, and in pixels for tools that draw images
+/// (sixel, kitty graphics). Pixel sizes are 0 when unknown.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub pixel_width: u32,
+    #[serde(default)]
+    pub pixel_height: u32,
+}
+
+/// Collects resize requests for a shell and releases the latest one at most
+/// every `RESIZE_DEBOUNCE`, dropping requests for the size already applied.
+pub(crate) struct ResizeDebouncer {
+    applied: Option<TerminalSize>,
+    pending: Option<TerminalSize>,
+    deadline: Instant,
+}
+
+impl ResizeDebouncer {
+    /// `applied` is the size the terminal was opened with, when known.
+    pub(crate) fn new(applied: Option<TerminalSize>) -> Self {
+        Self {
+            applied,
+            pending: None,
+            deadline: Instant::now(),
+        }
+    }
+
+    pub(crate) fn request(&mut self, size: TerminalSize) {
+        if self.applied == Some(size) {
+            self.pending = None;
+            return;
+        }
+        if self.pending.is_none() {
+            self.deadline = Instant::now() + RESIZE_DEBOUNCE;
+        }
+        self.pending = Some(size);
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Resolves once the pending size is due; only poll while `is_pending`.
+    pub(crate) async fn due(&self) {
+        sleep_until(self.deadline).await
+    }
+
+    /// The size to apply now, marking it as applied.
+    pub(crate) fn take(&mut self) -> Option<TerminalSize> {
+        let size = self.pending.take()?;
+        self.applied = Some(size);
+        Some(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_debouncer_keeps_latest_and_skips_no_ops() {
+        let cells = |width, height| TerminalSize {
+            width,
+            height,
+            ..TerminalSize::default()
+        };
+        let mut debouncer = ResizeDebouncer::new(Some(cells(80, 24)));
+
+        debouncer.request(cells(80, 24));
+        assert!(!debouncer.is_pending());
+
+        debouncer.request(cells(100, 30));
+        debouncer.request(cells(120, 40));
+        assert_eq!(debouncer.take(), Some(cells(120, 40)));
+        assert_eq!(debouncer.take(), None);
+
+        debouncer.request(cells(120, 40));
+        assert!(!debouncer.is_pending());
+
+        // Dragging back to the applied size cancels the pending resize.
+        debouncer.request(cells(130, 40));
+        debouncer.request(cells(120, 40));
+        assert_eq!(debouncer.take(), None);
+
+        // A font change keeps the cell count but not the pixel size.
+        debouncer.request(TerminalSize {
+            pixel_width: 1200,
+            pixel_height: 800,
+            ..cells(120, 40)
+        });
+        assert!(debouncer.is_pending());
+    }
+}
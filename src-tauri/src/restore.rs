@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+use crate::remote::shell_quote;
+use crate::{
+    connect_server, get_app_dir, load_servers, open_pty_shell, parse_json_array_lenient, AppState,
+    ManagedSession, PtyConfig, ShellCommand,
+};
+use crate::{elevation, limits, session_watch, settings};
+
+const OPEN_SHELLS_FILE: &str = "open-shells.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MultiplexerKind {
+    Tmux,
+    Screen,
+}
+
+/// A tmux or screen session to reattach to after reconnecting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteMultiplexer {
+    pub kind: MultiplexerKind,
+    pub session: String,
+}
+
+impl RemoteMultiplexer {
+    fn attach_command(&self) -> String {
+        let session = shell_quote(&self.session);
+        match self.kind {
+            MultiplexerKind::Tmux => format!("tmux new-session -A -s {}\r", session),
+            MultiplexerKind::Screen => format!("screen -D -R {}\r", session),
+        }
+    }
+}
+
+/// An SSH shell that was open when the app last ran.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedShell {
+    pub shell_id: String,
+    pub server_id: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub multiplexer: Option<RemoteMultiplexer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredShell {
+    pub shell_id: String,
+    /// Empty when the shell could not be restored.
+    pub connection_id: String,
+    pub server_id: String,
+    pub label: Option<String>,
+    pub error: Option<String>,
+}
+
+fn get_open_shells_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(OPEN_SHELLS_FILE)
+}
+
+fn load_saved_shells(app_dir: &Path) -> Result<Vec<SavedShell>, String> {
+    let path = get_open_shells_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read open shells file: {}", e))?;
+    parse_json_array_lenient(&data, "open shells")
+}
+
+fn save_saved_shells(app_dir: &Path, shells: &[SavedShell]) -> Result<(), String> {
+    let path = get_open_shells_path(app_dir);
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Invalid path for open shells file".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(shells)
+        .map_err(|e| format!("Failed to serialize open shells: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write open shells file: {}", e))?;
+    Ok(())
+}
+
+// Bookkeeping for restore must never get in the way of the shell itself, so
+// failures are only logged.
+fn update_saved_shells(app: &AppHandle, update: impl FnOnce(&mut Vec<SavedShell>) -> bool) {
+    let result = get_app_dir(app).and_then(|app_dir| {
+        let mut shells = load_saved_shells(&app_dir)?;
+        if update(&mut shells) {
+            save_saved_shells(&app_dir, &shells)?;
+        }
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        debug!(error = %e, "Failed to update open shells");
+    }
+}
+
+/// Records an SSH shell so it is reopened on the next launch. Label and
+/// multiplexer settings survive a reconnect under the same id.
+pub fn remember_shell(app: &AppHandle, shell_id: &str, server_id: &str, width: u32, height: u32) {
+    update_saved_shells(app, |shells| {
+        match shells.iter_mut().find(|shell| shell.shell_id == shell_id) {
+            Some(shell) => {
+                shell.server_id = server_id.to_string();
+                shell.width = width;
+                shell.height = height;
+            }
+            None => shells.push(SavedShell {
+                shell_id: shell_id.to_string(),
+                server_id: server_id.to_string(),
+                width,
+                height,
+                label: None,
+                multiplexer: None,
+            }),
+        }
+        true
+    });
+}
+
+pub fn update_shell_size(app: &AppHandle, shell_id: &str, width: u32, height: u32) {
+    update_saved_shells(app, |shells| {
+        match shells.iter_mut().find(|shell| shell.shell_id == shell_id) {
+            Some(shell) if (shell.width, shell.height) != (width, height) => {
+                shell.width = width;
+                shell.height = height;
+                true
+            }
+            _ => false,
+        }
+    });
+}
+
+/// Shells the user closed or exited are not brought back.
+pub fn forget_shells(app: &AppHandle, shell_ids: &[String]) {
+    update_saved_shells(app, |shells| {
+        let before = shells.len();
+        shells.retain(|shell| !shell_ids.contains(&shell.shell_id));
+        shells.len() != before
+    });
+}
+
+pub fn set_saved_label(app: &AppHandle, shell_id: &str, label: Option<String>) {
+    update_saved_shells(app, |shells| {
+        match shells.iter_mut().find(|shell| shell.shell_id == shell_id) {
+            Some(shell) if shell.label != label => {
+                shell.label = label;
+                true
+            }
+            _ => false,
+        }
+    });
+}
+
+pub fn saved_size(app: &AppHandle, shell_id: &str) -> Option<(u32, u32)> {
+    let app_dir = get_app_dir(app).ok()?;
+    load_saved_shells(&app_dir)
+        .ok()?
+        .into_iter()
+        .find(|shell| shell.shell_id == shell_id)
+        .map(|shell| (shell.width, shell.height))
+}
+
+#[tauri::command]
+pub async fn get_saved_shells(app: AppHandle) -> Result<Vec<SavedShell>, String> {
+    load_saved_shells(&get_app_dir(&app)?)
+}
+
+#[tauri::command]
+pub async fn set_shell_restore_options(
+    app: AppHandle,
+    shell_id: String,
+    label: Option<String>,
+    multiplexer: Option<RemoteMultiplexer>,
+) -> Result<SavedShell, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut shells = load_saved_shells(&app_dir)?;
+    let shell = shells
+        .iter_mut()
+        .find(|shell| shell.shell_id == shell_id)
+        .ok_or_else(|| format!("Shell with id {} not found", shell_id))?;
+    shell.label = label
+        .map(|label| label.trim().to_string())
+        .filter(|l| !l.is_empty());
+    shell.multiplexer = multiplexer.filter(|m| !m.session.trim().is_empty());
+    let updated = shell.clone();
+    save_saved_shells(&app_dir, &shells)?;
+    Ok(updated)
+}
+
+async fn restore_shell(app: &AppHandle, saved: &SavedShell) -> Result<String, String> {
+    let app_dir = get_app_dir(app)?;
+    let server = load_servers(&app_dir, app)?
+        .into_iter()
+        .find(|server| server.id == saved.server_id)
+        .ok_or_else(|| format!("Server with id {} not found", saved.server_id))?;
+    limits::check_shells(app, &server)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let session = connect_server(app, &server, &connection_id).await?;
+    let session = Arc::new(session);
+    let state = app.state::<AppState>();
+    state.sessions.lock().await.insert(
+        connection_id.clone(),
+        ManagedSession {
+            connection_id: connection_id.clone(),
+            server_id: server.id.clone(),
+            handle: session.clone(),
+        },
+    );
+
+    let config = PtyConfig {
+        term: settings::connection_defaults(app).term_for(&server),
+        width: saved.width,
+        height: saved.height,
+        pixel_width: 0,
+        pixel_height: 0,
+        command: elevation::pty_command(&server),
+    };
+    let mut shell = open_pty_shell(
+        app,
+        &session,
+        &config,
+        &connection_id,
+        &server.id,
+        Some(&saved.shell_id),
+    )
+    .await?;
+    session_watch::watch(app, &connection_id);
+    shell.label = saved.label.clone();
+
+    if let Some(multiplexer) = &saved.multiplexer {
+        let _ = shell
+            .cmd_tx
+            .send(ShellCommand::SendInput(multiplexer.attach_command()))
+            .await;
+    }
+    state
+        .shells
+        .lock()
+        .await
+        .insert(saved.shell_id.clone(), shell);
+    Ok(connection_id)
+}
+
+/// Reconnects every shell that was open when the app last closed, reattaching
+/// to its tmux or screen session when one was set. Failures are reported per
+/// shell and those shells are kept for the next attempt.
+#[tauri::command]
+pub async fn restore_sessions(app: AppHandle) -> Result<Vec<RestoredShell>, String> {
+    let saved = load_saved_shells(&get_app_dir(&app)?)?;
+    let mut restored = Vec::with_capacity(saved.len());
+
+    for shell in saved {
+        let already_open = app
+            .state::<AppState>()
+            .shells
+            .lock()
+            .await
+            .contains_key(&shell.shell_id);
+        if already_open {
+            continue;
+        }
+
+        let result = restore_shell(&app, &shell).await;
+        let (connection_id, error) = match result {
+            Ok(connection_id) => (connection_id, None),
+            Err(e) => (String::new(), Some(e)),
+        };
+        restored.push(RestoredShell {
+            shell_id: shell.shell_id,
+            connection_id,
+            server_id: shell.server_id,
+            label: shell.label,
+            error,
+        });
+    }
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach_command_quotes_session_name() {
+        let tmux = RemoteMultiplexer {
+            kind: MultiplexerKind::Tmux,
+            session: "work".to_string(),
+        };
+        let screen = RemoteMultiplexer {
+            kind: MultiplexerKind::Screen,
+            session: "it's mine".to_string(),
+        };
+
+        assert_eq!(tmux.attach_command(), "tmux new-session -A -s 'work'\r");
+        assert_eq!(screen.attach_command(), "screen -D -R 'it'\\''s mine'\r");
+    }
+}
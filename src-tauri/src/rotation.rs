@@ -0,0 +1,155 @@
+use ssh_thing_core::secrets::SecretStore;
+use tauri::AppHandle;
+
+use crate::{
+    connect_ssh, disconnect_ssh, get_app_dir, load_servers, migrate_server_auth, save_servers,
+    secret_store, AuthMethod, ServerConnection,
+};
+
+fn pending_id(secret_id: &str) -> String {
+    format!("{}:pending", secret_id)
+}
+
+fn previous_id(secret_id: &str) -> String {
+    format!("{}:previous", secret_id)
+}
+
+// Moves the current value aside as the previous one and the verified pending
+// value into its place. Only one previous value is kept.
+fn promote_pending(store: &dyn SecretStore, secret_id: &str) -> Result<(), String> {
+    let pending = store.get(&pending_id(secret_id))?;
+    if let Ok(current) = store.get(secret_id) {
+        store.put(&previous_id(secret_id), &current)?;
+    }
+    store.put(secret_id, &pending)?;
+    store.delete(&pending_id(secret_id))
+}
+
+fn restore_previous(store: &dyn SecretStore, secret_id: &str) -> Result<(), String> {
+    let previous = store
+        .get(&previous_id(secret_id))
+        .map_err(|_| "There is no previous secret to restore".to_string())?;
+    store.put(secret_id, &previous)?;
+    store.delete(&previous_id(secret_id))
+}
+
+async fn verify_secret(
+    app: &AppHandle,
+    server: &ServerConnection,
+    auth: &AuthMethod,
+) -> Result<(), String> {
+    let candidate = ServerConnection {
+        auth: auth.clone(),
+        ..server.clone()
+    };
+    let session = connect_ssh(app, &candidate, None).await?;
+    disconnect_ssh(app, Some(&session), None, None).await
+}
+
+/// Replaces a server's password or key once it has been shown to work. The
+/// new value is staged in the keyring, used for a test login and only then
+/// swapped in; the old one can be brought back with `restore_previous_secret`
+/// until the next rotation.
+#[tauri::command]
+pub async fn rotate_secret(
+    app: AppHandle,
+    server_id: String,
+    new_secret: String,
+) -> Result<Vec<ServerConnection>, String> {
+    if new_secret.is_empty() {
+        return Err("The new secret cannot be empty".to_string());
+    }
+
+    let app_dir = get_app_dir(&app)?;
+    let mut servers = load_servers(&app_dir, &app)?;
+    let index = servers
+        .iter()
+        .position(|server| server.id == server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    migrate_server_auth(&app, &mut servers[index])?;
+    let AuthMethod::SecretRef { secret_id, kind } = servers[index].auth.clone() else {
+        return Err("Server has no stored secret to rotate".to_string());
+    };
+
+    let store = secret_store(&app);
+    let staged = pending_id(&secret_id);
+    store.put(&staged, &new_secret)?;
+    let staged_auth = AuthMethod::SecretRef {
+        secret_id: staged.clone(),
+        kind,
+    };
+    if let Err(e) = verify_secret(&app, &servers[index], &staged_auth).await {
+        let _ = store.delete(&staged);
+        return Err(format!("The new secret was not accepted: {}", e));
+    }
+    promote_pending(&store, &secret_id)?;
+
+    // The expiry belonged to the old credential.
+    servers[index].credential_expires_at = None;
+    save_servers(&app_dir, &servers)?;
+    Ok(servers)
+}
+
+#[tauri::command]
+pub async fn restore_previous_secret(app: AppHandle, server_id: String) -> Result<(), String> {
+    let servers = load_servers(&get_app_dir(&app)?, &app)?;
+    let server = servers
+        .iter()
+        .find(|server| server.id == server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    let AuthMethod::SecretRef { secret_id, .. } = &server.auth else {
+        return Err("Server has no stored secret to restore".to_string());
+    };
+    restore_previous(&secret_store(&app), secret_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, secret_id: &str) -> Result<String, String> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(secret_id)
+                .cloned()
+                .ok_or_else(|| "missing".to_string())
+        }
+
+        fn put(&self, secret_id: &str, secret: &str) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(secret_id.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, secret_id: &str) -> Result<(), String> {
+            self.0.lock().unwrap().remove(secret_id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rotation_keeps_one_previous_value() {
+        let store = MemoryStore::default();
+        store.put("server:s1:password", "first").unwrap();
+
+        for next in ["second", "third"] {
+            store.put(&pending_id("server:s1:password"), next).unwrap();
+            promote_pending(&store, "server:s1:password").unwrap();
+        }
+
+        assert_eq!(store.get("server:s1:password").unwrap(), "third");
+        assert!(store.get(&pending_id("server:s1:password")).is_err());
+        restore_previous(&store, "server:s1:password").unwrap();
+        assert_eq!(store.get("server:s1:password").unwrap(), "second");
+        assert!(restore_previous(&store, "server:s1:password").is_err());
+    }
+}
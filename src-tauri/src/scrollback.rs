@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+pub(crate) const MAX_SCROLLBACK_BYTES: usize = 512 * 1024;
+pub(crate) const MAX_SPILL_BYTES: u64 = 32 * 1024 * 1024;
+const DEFAULT_PAGE_BYTES: usize = 256 * 1024;
+const SPILL_DIR: &str = "scrollback";
+const SPILL_FILE_PREFIX: &str = "ssh-thing-scrollback-";
+
+pub type SharedScrollback = Arc<Mutex<ScrollbackBuffer>>;
+
+// Set once the app starts; the temp directory until then, as in tests.
+static SPILL_DIR_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Spill files go in a directory of the app's cache that only the user can
+/// read. Files left by a previous run, including ones from the shared temp
+/// directory older versions used, are removed.
+pub(crate) fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to get app cache directory: {}", e))?
+        .join(SPILL_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create scrollback directory: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to protect scrollback directory: {}", e))?;
+    }
+    remove_stale_spill_files(&dir);
+    remove_stale_spill_files(&std::env::temp_dir());
+    let _ = SPILL_DIR_PATH.set(dir);
+    Ok(())
+}
+
+fn remove_stale_spill_files(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(SPILL_FILE_PREFIX));
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+// Readable and writable by the user only, since scrollback can hold
+// anything that was on screen.
+fn open_private(path: &Path, create_new: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).append(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Part of a shell's full scrollback, from `get_full_scrollback`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScrollbackPage {
+    pub text: String,
+    /// Where the next page starts; equal to `total_bytes` after the last.
+    pub next_offset: u64,
+    /// Spilled and in-memory output together. Offsets count from the
+    /// oldest output still kept, which moves as old output is dropped.
+    pub total_bytes: u64,
+}
+
+/// Output pushed out of memory, kept in a private file that is removed
+/// with the buffer. Past `max_bytes` the older half of the file is dropped.
+#[derive(Debug)]
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+    bytes: u64,
+    max_bytes: u64,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        let dir = SPILL_DIR_PATH
+            .get()
+            .cloned()
+            .unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(format!("{}{}.log", SPILL_FILE_PREFIX, uuid::Uuid::new_v4()));
+        let file = open_private(&path, true)?;
+        Ok(Self {
+            path,
+            file,
+            bytes: 0,
+            max_bytes: 0,
+        })
+    }
+
+    fn append(&mut self, text: &str) -> io::Result<()> {
+        self.file.write_all(text.as_bytes())?;
+        self.bytes += text.len() as u64;
+        if self.bytes > self.max_bytes {
+            self.keep_tail(self.max_bytes / 2)?;
+        }
+        Ok(())
+    }
+
+    // Copies the last `keep` bytes into a fresh file rather than reading
+    // them into memory, which is what the spill file is there to avoid.
+    fn keep_tail(&mut self, keep: u64) -> io::Result<()> {
+        let tail_path = self.path.with_extension("tail");
+        let _ = fs::remove_file(&tail_path);
+        let mut tail = open_private(&tail_path, true)?;
+        self.file.seek(SeekFrom::Start(self.bytes - keep))?;
+        let copied = io::copy(&mut (&self.file).take(keep), &mut tail)?;
+        drop(tail);
+        fs::rename(&tail_path, &self.path)?;
+        self.file = open_private(&self.path, false)?;
+        self.bytes = copied;
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, limit: usize, out: &mut Vec<u8>) -> io::Result<()> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.take(limit as u64).read_to_end(out)?;
+        Ok(())
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Terminal output kept on the backend per shell id, so a tab that is
+/// reconnected or reloaded can repaint what it showed before. Only
+/// `max_bytes` stay in memory; older output spills to a private file of up to
+/// `spill_bytes`, or is dropped when that is 0.
+#[derive(Debug)]
+pub struct ScrollbackBuffer {
+    data: String,
+    max_bytes: usize,
+    spill_bytes: u64,
+    spill: Option<SpillFile>,
+}
+
+impl Default for ScrollbackBuffer {
+    fn default() -> Self {
+        Self::with_capacity(MAX_SCROLLBACK_BYTES)
+    }
+}
+
+impl ScrollbackBuffer {
+    pub fn with_capacity(max_bytes: usize) -> Self {
+        Self::with_spill(max_bytes, 0)
+    }
+
+    pub fn with_spill(max_bytes: usize, spill_bytes: u64) -> Self {
+        Self {
+            data: String::new(),
+            max_bytes,
+            spill_bytes,
+            spill: None,
+        }
+    }
+
+    fn spill(&mut self, text: &str) {
+        if self.spill_bytes == 0 {
+            return;
+        }
+        if self.spill.is_none() {
+            match SpillFile::create() {
+                Ok(file) => self.spill = Some(file),
+                Err(e) => {
+                    debug!(error = %e, "Failed to create scrollback spill file");
+                    self.spill_bytes = 0;
+                    return;
+                }
+            }
+        }
+        if let Some(spill) = self.spill.as_mut() {
+            spill.max_bytes = self.spill_bytes;
+            if let Err(e) = spill.append(text) {
+                debug!(error = %e, "Failed to spill scrollback");
+                self.spill = None;
+                self.spill_bytes = 0;
+            }
+        }
+    }
+
+    pub fn push(&mut self, output: &str) {
+        self.data.push_str(output);
+        if self.data.len() <= self.max_bytes {
+            return;
+        }
+
+        let mut cut = self.data.len() - self.max_bytes;
+        while !self.data.is_char_boundary(cut) {
+            cut += 1;
+        }
+        let overflow: String = self.data.drain(..cut).collect();
+        self.spill(&overflow);
+    }
+
+    /// The output still in memory, which is what a tab repaints.
+    pub fn contents(&self) -> String {
+        self.data.clone()
+    }
+
+    /// Up to `limit` bytes of the spilled output followed by what is in
+    /// memory, starting `offset` bytes in. A page never ends inside a
+    /// character.
+    pub fn page(&self, offset: u64, limit: Option<usize>) -> Result<ScrollbackPage, String> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_BYTES).max(4);
+        let spilled = self.spill.as_ref().map_or(0, |spill| spill.bytes);
+        let total_bytes = spilled + self.data.len() as u64;
+        let offset = offset.min(total_bytes);
+
+        let mut bytes = Vec::new();
+        if let Some(spill) = self.spill.as_ref().filter(|_| offset < spilled) {
+            let want = limit.min((spilled - offset) as usize);
+            spill
+                .read_at(offset, want, &mut bytes)
+                .map_err(|e| format!("Failed to read spilled scrollback: {}", e))?;
+        }
+        if bytes.len() < limit {
+            let start = (offset + bytes.len() as u64).saturating_sub(spilled) as usize;
+            let end = (start + limit - bytes.len()).min(self.data.len());
+            bytes.extend_from_slice(&self.data.as_bytes()[start..end]);
+        }
+        if let Err(e) = std::str::from_utf8(&bytes) {
+            // Leave a character cut off at the end for the next page.
+            if e.error_len().is_none() && offset + (bytes.len() as u64) < total_bytes {
+                bytes.truncate(e.valid_up_to());
+            }
+        }
+
+        Ok(ScrollbackPage {
+            next_offset: offset + bytes.len() as u64,
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            total_bytes,
+        })
+    }
+}
+
+pub fn shared(max_bytes: usize, spill_bytes: u64) -> SharedScrollback {
+    Arc::new(Mutex::new(ScrollbackBuffer::with_spill(
+        max_bytes,
+        spill_bytes,
+    )))
+}
+
+pub fn push_output(scrollback: &SharedScrollback, output: &str) {
+    if let Ok(mut buffer) = scrollback.lock() {
+        buffer.push(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrollback_keeps_most_recent_output() {
+        let mut buffer = ScrollbackBuffer::with_capacity(8);
+        buffer.push("hello ");
+        buffer.push("world");
+
+        assert_eq!(buffer.contents(), "lo world");
+    }
+
+    #[test]
+    fn test_scrollback_trims_on_char_boundary() {
+        let mut buffer = ScrollbackBuffer::with_capacity(4);
+        buffer.push("aé€");
+
+        assert_eq!(buffer.contents(), "€");
+    }
+
+    #[test]
+    fn test_scrollback_spills_overflow_to_disk() {
+        let mut buffer = ScrollbackBuffer::with_spill(4, 8);
+        buffer.push("abcdef");
+        buffer.push("ghij");
+
+        assert_eq!(buffer.contents(), "ghij");
+        assert_eq!(buffer.page(0, None).unwrap().text, "abcdefghij");
+
+        // Past the spill limit the older half of the file goes.
+        buffer.push("klmnop");
+        assert_eq!(buffer.page(0, None).unwrap().text, "ijklmnop");
+
+        // Pages cross from the file into memory and stop before a split
+        // character.
+        let first = buffer.page(0, Some(5)).unwrap();
+        assert_eq!((first.text.as_str(), first.next_offset), ("ijklm", 5));
+        let rest = buffer.page(first.next_offset, Some(5)).unwrap();
+        assert_eq!(rest.text, "nop");
+        assert_eq!(rest.next_offset, rest.total_bytes);
+        buffer.push("é");
+        let split = buffer.page(5, Some(4)).unwrap();
+        assert_eq!((split.text.as_str(), split.next_offset), ("nop", 8));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let spill = buffer.spill.as_ref().unwrap();
+            let mode = fs::metadata(&spill.path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+
+        let path = buffer
+            .spill
+            .as_ref()
+            .map(|spill| spill.path.clone())
+            .unwrap();
+        drop(buffer);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::secrets::{KeyringSecretStore, SecretStore};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::debug;
+
+use crate::audit::{self, AuditEvent};
+use crate::{get_app_dir, identities, load_servers, secret_store, AuthMethod, ServerConnection};
+
+// The keyring cannot be listed, so the ids written for a profile are kept
+// next to its servers.
+const SECRET_INDEX_FILE: &str = "secret-ids.json";
+// Entries that belong to the app rather than to a server.
+const APP_SECRET_PREFIX: &str = "cloud:";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretCleanupResult {
+    pub removed: Vec<String>,
+}
+
+fn get_index_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SECRET_INDEX_FILE)
+}
+
+fn load_index(app_dir: &Path) -> BTreeSet<String> {
+    fs::read_to_string(get_index_path(app_dir))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(app_dir: &Path, ids: &BTreeSet<String>) -> Result<(), String> {
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(ids)
+        .map_err(|e| format!("Failed to serialize secret index: {}", e))?;
+    fs::write(get_index_path(app_dir), content)
+        .map_err(|e| format!("Failed to write secret index file: {}", e))
+}
+
+fn update_index(app_dir: &Path, update: impl FnOnce(&mut BTreeSet<String>) -> bool) {
+    let mut ids = load_index(app_dir);
+    if !update(&mut ids) {
+        return;
+    }
+    if let Err(e) = save_index(app_dir, &ids) {
+        debug!(error = %e, "Failed to update secret index");
+    }
+}
+
+/// Adds the secrets `servers` point at, so entries written before the index
+/// existed are still found once their server goes away.
+pub fn remember_referenced(app_dir: &Path, servers: &[ServerConnection]) {
+    update_index(app_dir, |ids| {
+        let mut changed = false;
+        for secret_id in servers.iter().flat_map(secret_ids_of) {
+            changed |= ids.insert(secret_id.to_string());
+        }
+        changed
+    });
+}
+
+fn secret_ids_of(server: &ServerConnection) -> impl Iterator<Item = &str> {
+    let auth = match &server.auth {
+        AuthMethod::SecretRef { secret_id, .. } => Some(secret_id.as_str()),
+        _ => None,
+    };
+    let elevation = server
+        .elevate
+        .as_ref()
+        .and_then(|elevation| elevation.secret_id.as_deref());
+    auth.into_iter().chain(elevation)
+}
+
+/// The active profile's keyring, recording which ids it writes and
+/// auditing reads.
+pub struct TrackedSecretStore {
+    keyring: KeyringSecretStore,
+    app_dir: Option<PathBuf>,
+}
+
+impl TrackedSecretStore {
+    pub fn new(keyring: KeyringSecretStore, app_dir: Option<PathBuf>) -> Self {
+        Self { keyring, app_dir }
+    }
+}
+
+impl SecretStore for TrackedSecretStore {
+    fn get(&self, secret_id: &str) -> Result<String, String> {
+        let secret = self.keyring.get(secret_id)?;
+        if let Some(app_dir) = &self.app_dir {
+            audit::record_in(
+                app_dir,
+                &self.keyring,
+                AuditEvent::SecretRead {
+                    secret_id: secret_id.to_string(),
+                },
+            );
+        }
+        Ok(secret)
+    }
+
+    fn put(&self, secret_id: &str, secret: &str) -> Result<(), String> {
+        self.keyring.put(secret_id, secret)?;
+        if let Some(app_dir) = &self.app_dir {
+            update_index(app_dir, |ids| ids.insert(secret_id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn delete(&self, secret_id: &str) -> Result<(), String> {
+        self.keyring.delete(secret_id)?;
+        if let Some(app_dir) = &self.app_dir {
+            update_index(app_dir, |ids| ids.remove(secret_id));
+        }
+        Ok(())
+    }
+}
+
+// Rotation keeps `<id>:previous` around for as long as `<id>` is in use.
+fn is_orphan(secret_id: &str, referenced: &HashSet<&str>) -> bool {
+    if secret_id.starts_with(APP_SECRET_PREFIX) {
+        return false;
+    }
+    let base = secret_id.strip_suffix(":previous").unwrap_or(secret_id);
+    !referenced.contains(base)
+}
+
+/// Removes keyring entries no server refers to any more, such as those left
+/// behind when servers.json is edited by hand.
+#[tauri::command]
+pub async fn cleanup_secrets(app: AppHandle) -> Result<SecretCleanupResult, String> {
+    let app_dir = get_app_dir(&app)?;
+    let servers = load_servers(&app_dir, &app)?;
+    let identity_secrets = identities::secret_ids(&app);
+    let referenced: HashSet<&str> = servers
+        .iter()
+        .flat_map(secret_ids_of)
+        .chain(identity_secrets.iter().map(String::as_str))
+        .collect();
+    let orphans: Vec<String> = load_index(&app_dir)
+        .into_iter()
+        .filter(|secret_id| is_orphan(secret_id, &referenced))
+        .collect();
+
+    let store = secret_store(&app);
+    let mut result = SecretCleanupResult::default();
+    for secret_id in orphans {
+        let deleted = store.delete(&secret_id);
+        // An entry that is already gone only needs dropping from the index.
+        if deleted.is_err() && store.get(&secret_id).is_ok() {
+            debug!(secret_id = %secret_id, "Failed to delete orphaned secret");
+            continue;
+        }
+        if deleted.is_err() {
+            update_index(&app_dir, |ids| ids.remove(&secret_id));
+        }
+        result.removed.push(secret_id);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_orphan_keeps_referenced_and_app_secrets() {
+        let referenced = HashSet::from(["server:s1:password"]);
+
+        assert!(!is_orphan("server:s1:password", &referenced));
+        assert!(!is_orphan("server:s1:password:previous", &referenced));
+        assert!(!is_orphan("cloud:hetzner:api_token", &referenced));
+        assert!(is_orphan("server:s2:password", &referenced));
+        assert!(is_orphan("server:s1:password:pending", &referenced));
+    }
+}
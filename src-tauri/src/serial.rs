@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::io::{self, Read, Write};
+use std::sync::mpsc;
+use tauri::{AppHandle, Manager};
+use tokio::time::Duration;
+
+#[cfg(debug_assertions)]
+use tracing::debug;
+
+use crate::stream_shell::{spawn_reader, spawn_stream_shell, ShellBackend};
+use crate::AppState;
+
+/// Serial shells use `serial:<port>` as their server id.
+pub const SERIAL_SERVER_PREFIX: &str = "serial:";
+const DEFAULT_BAUD_RATE: u32 = 9600;
+// Short enough that the reader thread notices a closed shell promptly.
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SerialParity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+fn default_baud_rate() -> u32 {
+    DEFAULT_BAUD_RATE
+}
+
+fn default_data_bits() -> u8 {
+    8
+}
+
+fn default_stop_bits() -> u8 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SerialConfig {
+    /// Device path such as `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: u32,
+    #[serde(default)]
+    pub parity: SerialParity,
+    #[serde(default = "default_data_bits")]
+    pub data_bits: u8,
+    #[serde(default = "default_stop_bits")]
+    pub stop_bits: u8,
+}
+
+fn data_bits(bits: u8) -> Result<DataBits, String> {
+    match bits {
+        5 => Ok(DataBits::Five),
+        6 => Ok(DataBits::Six),
+        7 => Ok(DataBits::Seven),
+        8 => Ok(DataBits::Eight),
+        other => Err(format!("Unsupported data bits: {}", other)),
+    }
+}
+
+fn stop_bits(bits: u8) -> Result<StopBits, String> {
+    match bits {
+        1 => Ok(StopBits::One),
+        2 => Ok(StopBits::Two),
+        other => Err(format!("Unsupported stop bits: {}", other)),
+    }
+}
+
+fn parity(parity: SerialParity) -> Parity {
+    match parity {
+        SerialParity::None => Parity::None,
+        SerialParity::Odd => Parity::Odd,
+        SerialParity::Even => Parity::Even,
+    }
+}
+
+fn open_port(config: &SerialConfig) -> Result<Box<dyn SerialPort>, String> {
+    if config.baud_rate == 0 {
+        return Err("Baud rate must be greater than zero".to_string());
+    }
+    serialport::new(&config.port, config.baud_rate)
+        .data_bits(data_bits(config.data_bits)?)
+        .stop_bits(stop_bits(config.stop_bits)?)
+        .parity(parity(config.parity))
+        .flow_control(FlowControl::None)
+        .timeout(READ_TIMEOUT)
+        .open()
+        .map_err(|e| format!("Failed to open {}: {}", config.port, e))
+}
+
+/// Writes to `port` on its own thread, since a slow device can block each
+/// write for up to the port timeout. The thread ends, closing the port, when
+/// the sender is dropped or a write fails.
+fn spawn_writer(mut port: Box<dyn SerialPort>) -> mpsc::Sender<Vec<u8>> {
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+    std::thread::spawn(move || {
+        for data in rx {
+            if let Err(_e) = port.write_all(&data).and_then(|()| port.flush()) {
+                #[cfg(debug_assertions)]
+                debug!(error = %_e, "Failed to write to serial port");
+                break;
+            }
+        }
+    });
+    tx
+}
+
+struct SerialConsole {
+    writer: mpsc::Sender<Vec<u8>>,
+    name: String,
+}
+
+impl ShellBackend for SerialConsole {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.writer.send(data.to_vec()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                format!("{} can no longer be written to", self.name),
+            )
+        })
+    }
+
+    // Dropping the shell task drops the writer, which ends its thread; the
+    // reader thread's clone goes away on its next timeout.
+    fn close(&mut self) {}
+
+    fn ended_message(&self, _exit_status: Option<u32>) -> String {
+        format!("\r\n\r\nSerial port {} closed\r\n", self.name)
+    }
+}
+
+#[tauri::command]
+pub async fn list_serial_ports() -> Result<Vec<String>, String> {
+    let ports = tauri::async_runtime::spawn_blocking(serialport::available_ports)
+        .await
+        .map_err(|e| format!("Failed to list serial ports: {}", e))?
+        .map_err(|e| format!("Failed to list serial ports: {}", e))?;
+    Ok(ports.into_iter().map(|port| port.port_name).collect())
+}
+
+/// Opens a console on a serial port under `connection_id`. It is closed with
+/// `disconnect` like any other connection.
+#[tauri::command]
+pub async fn open_serial_shell(
+    app: AppHandle,
+    connection_id: String,
+    config: SerialConfig,
+) -> Result<String, String> {
+    #[cfg(debug_assertions)]
+    debug!(port = %config.port, baud_rate = config.baud_rate, "Opening serial console");
+
+    let open_config = config.clone();
+    let port = tauri::async_runtime::spawn_blocking(move || open_port(&open_config))
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", config.port, e))??;
+    let reader = port
+        .try_clone()
+        .map_err(|e| format!("Failed to read from {}: {}", config.port, e))?;
+    let reader: Box<dyn Read + Send> = Box::new(reader);
+
+    let server_id = format!("{}{}", SERIAL_SERVER_PREFIX, config.port);
+    let shell = spawn_stream_shell(
+        &app,
+        &connection_id,
+        &server_id,
+        None,
+        spawn_reader(reader),
+        None,
+        SerialConsole {
+            writer: spawn_writer(port),
+            name: config.port.clone(),
+        },
+    )
+    .await?;
+    let shell_id = shell.id.clone();
+
+    let state = app.state::<AppState>();
+    let mut shells = state.shells.lock().await;
+    shells.insert(shell_id.clone(), shell);
+    Ok(shell_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_config_defaults() {
+        let config: SerialConfig =
+            serde_json::from_str(r#"{"port": "/dev/ttyUSB0"}"#).expect("config");
+
+        assert_eq!(config.baud_rate, DEFAULT_BAUD_RATE);
+        assert_eq!(config.parity, SerialParity::None);
+        assert!(matches!(data_bits(config.data_bits), Ok(DataBits::Eight)));
+        assert!(matches!(stop_bits(config.stop_bits), Ok(StopBits::One)));
+        assert!(data_bits(9).is_err());
+    }
+}
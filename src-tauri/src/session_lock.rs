@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::settings::load_settings;
+use crate::{disconnect, get_app_dir, AppState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// More wall-clock time than this between polls means the machine slept.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// What happens to open sessions when the machine sleeps or the screen locks.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LockPolicy {
+    #[default]
+    Off,
+    /// Keep sessions open but refuse input until `unlock_sessions`.
+    SuspendInput,
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LockReason {
+    Sleep,
+    ScreenLocked,
+}
+
+/// Payload of `sessions-locked`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionsLockedEvent {
+    pub reason: LockReason,
+    pub policy: LockPolicy,
+}
+
+#[derive(Default)]
+pub struct SessionLock {
+    input_locked: AtomicBool,
+}
+
+/// Fails while input is suspended after a sleep or screen lock.
+pub fn ensure_input_allowed(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if state.session_lock.input_locked.load(Ordering::SeqCst) {
+        return Err("Sessions are locked; unlock them to send input".to_string());
+    }
+    Ok(())
+}
+
+// The monotonic clock stops while the machine is suspended on Linux and
+// macOS, the wall clock does not.
+fn slept_between(wall_elapsed: Duration, monotonic_elapsed: Duration) -> bool {
+    wall_elapsed.saturating_sub(monotonic_elapsed) > SLEEP_THRESHOLD
+}
+
+#[cfg(target_os = "linux")]
+async fn screen_locked() -> bool {
+    tokio::process::Command::new("loginctl")
+        .args(["show-session", "auto", "--property=LockedHint", "--value"])
+        .output()
+        .await
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "yes")
+}
+
+#[cfg(target_os = "macos")]
+async fn screen_locked() -> bool {
+    // The key is only present in the session dictionary while locked.
+    tokio::process::Command::new("ioreg")
+        .args(["-n", "Root", "-d1"])
+        .output()
+        .await
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout).contains("CGSSessionScreenIsLocked")
+        })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn screen_locked() -> bool {
+    false
+}
+
+fn lock_policy(app: &AppHandle) -> LockPolicy {
+    get_app_dir(app)
+        .and_then(|app_dir| load_settings(&app_dir))
+        .map(|settings| settings.lock_policy)
+        .unwrap_or_default()
+}
+
+async fn apply_policy(app: &AppHandle, policy: LockPolicy, reason: LockReason) {
+    let state = app.state::<AppState>();
+    match policy {
+        LockPolicy::Off => return,
+        LockPolicy::SuspendInput => state
+            .session_lock
+            .input_locked
+            .store(true, Ordering::SeqCst),
+        LockPolicy::Disconnect => {
+            let mut connection_ids: HashSet<String> =
+                state.sessions.lock().await.keys().cloned().collect();
+            connection_ids.extend(
+                state
+                    .shells
+                    .lock()
+                    .await
+                    .values()
+                    .map(|shell| shell.connection_id.clone()),
+            );
+            for connection_id in connection_ids {
+                if let Err(e) = disconnect(app.clone(), connection_id).await {
+                    debug!(error = %e, "Failed to disconnect session on lock");
+                }
+            }
+        }
+    }
+    let _ = app.emit("sessions-locked", SessionsLockedEvent { reason, policy });
+}
+
+/// Polls for sleep and screen lock for the life of the app and applies the
+/// lock policy from settings when either happens.
+pub fn start_lock_watcher(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = interval(POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_wall = SystemTime::now();
+        let mut last_monotonic = Instant::now();
+        let mut was_locked = false;
+        loop {
+            ticker.tick().await;
+            let wall_elapsed = last_wall.elapsed().unwrap_or_default();
+            let monotonic_elapsed = last_monotonic.elapsed();
+            last_wall = SystemTime::now();
+            last_monotonic = Instant::now();
+
+            let policy = lock_policy(&app);
+            if policy == LockPolicy::Off {
+                was_locked = false;
+                continue;
+            }
+            let locked = screen_locked().await;
+            let reason = if slept_between(wall_elapsed, monotonic_elapsed) {
+                Some(LockReason::Sleep)
+            } else if locked && !was_locked {
+                Some(LockReason::ScreenLocked)
+            } else {
+                None
+            };
+            was_locked = locked;
+            if let Some(reason) = reason {
+                apply_policy(&app, policy, reason).await;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn unlock_sessions(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if state
+        .session_lock
+        .input_locked
+        .swap(false, Ordering::SeqCst)
+    {
+        let _ = app.emit("sessions-unlocked", ());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slept_between_ignores_small_clock_drift() {
+        let poll = Duration::from_secs(5);
+
+        assert!(!slept_between(poll + Duration::from_secs(2), poll));
+        assert!(slept_between(Duration::from_secs(600), poll));
+        assert!(!slept_between(Duration::ZERO, poll));
+    }
+}
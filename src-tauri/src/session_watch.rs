@@ -0,0 +1,145 @@
+use futures::future::join_all;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::{
+    disconnect_ssh, forwarding, os_fingerprint, stats, term_probe, AppState, ManagedSession,
+    SshSession,
+};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Connection ids with a watcher running, so a session replaced under the
+/// same id does not get a second one.
+#[derive(Default)]
+pub struct SessionWatch {
+    watched: std::sync::Mutex<HashSet<String>>,
+}
+
+/// Watches the session stored under `connection_id` until it is
+/// disconnected. If the server or the network drops it first, the stale
+/// entry is removed and `connection-state` reports the server
+/// `Disconnected`; shells notice on their own.
+pub(crate) fn watch(app: &AppHandle, connection_id: &str) {
+    let state = app.state::<AppState>();
+    let Ok(mut watched) = state.session_watch.watched.lock() else {
+        return;
+    };
+    if !watched.insert(connection_id.to_string()) {
+        return;
+    }
+    drop(watched);
+
+    let app = app.clone();
+    let connection_id = connection_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = interval(WATCH_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await;
+        let dropped = loop {
+            ticker.tick().await;
+            let state = app.state::<AppState>();
+            let mut sessions = state.sessions.lock().await;
+            match sessions.get(&connection_id) {
+                // Disconnected on purpose.
+                None => break None,
+                Some(session) if session.handle.is_closed() => {
+                    break sessions.remove(&connection_id)
+                }
+                Some(_) => {}
+            }
+        };
+
+        let state = app.state::<AppState>();
+        if let Ok(mut watched) = state.session_watch.watched.lock() {
+            watched.remove(&connection_id);
+        }
+        let Some(session) = dropped else {
+            return;
+        };
+        debug!(connection_id, server_id = %session.server_id, "Session closed by the server");
+        forget_session(&app, &connection_id, &session.server_id).await;
+    });
+}
+
+/// Cleans up after a session that is gone without `disconnect`, and
+/// reports the server `Disconnected`.
+async fn forget_session(app: &AppHandle, connection_id: &str, server_id: &str) {
+    forwarding::stop_connection(app, connection_id).await;
+    term_probe::forget_connection(app, connection_id).await;
+    os_fingerprint::forget_connection(app, connection_id).await;
+    let _ = disconnect_ssh(app, None, Some(connection_id), Some(server_id)).await;
+}
+
+/// One stored session as `verify_sessions` found it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionHealth {
+    pub connection_id: String,
+    pub server_id: String,
+    pub alive: bool,
+    pub rtt_ms: Option<u64>,
+}
+
+/// Sends a no-op request on every stored session and removes those that
+/// do not answer, such as the ones left behind by a laptop sleeping.
+#[tauri::command]
+pub async fn verify_sessions(app: AppHandle) -> Result<Vec<SessionHealth>, String> {
+    let stored: Vec<(String, String, Arc<SshSession>)> = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        sessions
+            .values()
+            .map(|session| {
+                (
+                    session.connection_id.clone(),
+                    session.server_id.clone(),
+                    session.handle.clone(),
+                )
+            })
+            .collect()
+    };
+    let probes = stored
+        .iter()
+        .map(|(connection_id, server_id, handle)| async move {
+            let rtt_ms = if handle.is_closed() {
+                None
+            } else {
+                stats::round_trip(handle).await
+            };
+            SessionHealth {
+                connection_id: connection_id.clone(),
+                server_id: server_id.clone(),
+                alive: rtt_ms.is_some(),
+                rtt_ms,
+            }
+        });
+    let results = join_all(probes).await;
+
+    // A session replaced while the probes ran is left alone.
+    let dead: Vec<ManagedSession> = {
+        let state = app.state::<AppState>();
+        let mut sessions = state.sessions.lock().await;
+        results
+            .iter()
+            .zip(&stored)
+            .filter(|(health, (connection_id, _, handle))| {
+                !health.alive
+                    && sessions
+                        .get(connection_id)
+                        .is_some_and(|session| Arc::ptr_eq(&session.handle, handle))
+            })
+            .filter_map(|(health, _)| sessions.remove(&health.connection_id))
+            .collect()
+    };
+
+    for session in dead {
+        debug!(connection_id = %session.connection_id, "Removing unresponsive session");
+        forget_session(&app, &session.connection_id, &session.server_id).await;
+        ssh_thing_core::ssh::disconnect(&session.handle).await;
+    }
+    Ok(results)
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::ssh::{
+    ChannelTuning, DEFAULT_KEEPALIVE_SECONDS, DEFAULT_MAX_PACKET_SIZE, DEFAULT_WINDOW_SIZE,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::debug;
+
+use crate::command_guard::CommandGuardSettings;
+use crate::db_connect::DatabaseSettings;
+use crate::git_sync::GitSyncSettings;
+use crate::key_agent::KeyAgentSettings;
+use crate::redaction::RedactionSettings;
+use crate::scrollback::{MAX_SCROLLBACK_BYTES, MAX_SPILL_BYTES};
+use crate::session_lock::LockPolicy;
+use crate::{get_app_dir, ReconnectPolicy, ServerConnection};
+
+const SETTINGS_FILE: &str = "settings.json";
+const DEFAULT_PORT: u16 = 22;
+const DEFAULT_TERM: &str = "xterm-256color";
+const MIN_SCROLLBACK_BYTES: usize = 16 * 1024;
+const DEFAULT_CREDENTIAL_REMINDER_DAYS: u64 = 14;
+const DEFAULT_HOST_KEY_PROMPT_TIMEOUT_SECONDS: u64 = 120;
+const DEFAULT_MAX_CONCURRENT_CONNECTIONS: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AppSettings {
+    /// Telnet sends everything in plain text, so it stays off until the user
+    /// opts in.
+    #[serde(default)]
+    pub allow_telnet: bool,
+    #[serde(default)]
+    pub defaults: ConnectionDefaults,
+    /// Shares servers, snippets and known hosts through a git repository.
+    #[serde(default)]
+    pub git_sync: Option<GitSyncSettings>,
+    /// Days before `credential_expires_at` that `credential-expiring` fires.
+    #[serde(default = "default_credential_reminder_days")]
+    pub credential_reminder_days: u64,
+    /// Seconds before an unanswered host key prompt rejects the key, 0 to
+    /// wait indefinitely.
+    #[serde(default = "default_host_key_prompt_timeout_seconds")]
+    pub host_key_prompt_timeout_seconds: u64,
+    /// Connection attempts allowed at once; the rest wait their turn. 0
+    /// for no limit.
+    #[serde(default = "default_max_concurrent_connections")]
+    pub max_concurrent_connections: usize,
+    #[serde(default)]
+    pub lock_policy: LockPolicy,
+    #[serde(default)]
+    pub command_guard: CommandGuardSettings,
+    /// Clients for the database quick-connect on tagged servers.
+    #[serde(default)]
+    pub database: DatabaseSettings,
+    #[serde(default)]
+    pub redaction: RedactionSettings,
+    #[serde(default)]
+    pub key_agent: KeyAgentSettings,
+}
+
+fn default_credential_reminder_days() -> u64 {
+    DEFAULT_CREDENTIAL_REMINDER_DAYS
+}
+
+fn default_host_key_prompt_timeout_seconds() -> u64 {
+    DEFAULT_HOST_KEY_PROMPT_TIMEOUT_SECONDS
+}
+
+fn default_max_concurrent_connections() -> usize {
+    DEFAULT_MAX_CONCURRENT_CONNECTIONS
+}
+
+/// Values servers fall back to when they do not set their own.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectionDefaults {
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_term")]
+    pub term: String,
+    /// 0 turns keepalives off.
+    #[serde(default = "default_keepalive_seconds")]
+    pub keepalive_seconds: u64,
+    /// Terminal output kept per shell for repainting reconnected tabs.
+    #[serde(default = "default_scrollback_bytes")]
+    pub scrollback_bytes: usize,
+    /// Older output kept per shell in a temp file once `scrollback_bytes`
+    /// is full, 0 to drop it instead.
+    #[serde(default = "default_scrollback_spill_bytes")]
+    pub scrollback_spill_bytes: u64,
+    /// Channel receive window; larger windows keep fast links busy.
+    #[serde(default = "default_channel_window_bytes")]
+    pub channel_window_bytes: u32,
+    #[serde(default = "default_channel_max_packet_bytes")]
+    pub channel_max_packet_bytes: u32,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    /// Hold input typed into a shell while its connection is being
+    /// re-established and send it once the shell is back.
+    #[serde(default)]
+    pub queue_input_while_reconnecting: bool,
+    /// Check the server's terminfo and locale when a shell opens, falling
+    /// back to a TERM the server knows.
+    #[serde(default = "default_probe_terminal")]
+    pub probe_terminal: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            allow_telnet: false,
+            defaults: ConnectionDefaults::default(),
+            git_sync: None,
+            credential_reminder_days: default_credential_reminder_days(),
+            host_key_prompt_timeout_seconds: default_host_key_prompt_timeout_seconds(),
+            max_concurrent_connections: default_max_concurrent_connections(),
+            lock_policy: LockPolicy::default(),
+            command_guard: CommandGuardSettings::default(),
+            database: DatabaseSettings::default(),
+            redaction: RedactionSettings::default(),
+            key_agent: KeyAgentSettings::default(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn default_term() -> String {
+    DEFAULT_TERM.to_string()
+}
+
+fn default_keepalive_seconds() -> u64 {
+    DEFAULT_KEEPALIVE_SECONDS
+}
+
+fn default_scrollback_bytes() -> usize {
+    MAX_SCROLLBACK_BYTES
+}
+
+fn default_scrollback_spill_bytes() -> u64 {
+    MAX_SPILL_BYTES
+}
+
+fn default_probe_terminal() -> bool {
+    true
+}
+
+fn default_channel_window_bytes() -> u32 {
+    DEFAULT_WINDOW_SIZE
+}
+
+fn default_channel_max_packet_bytes() -> u32 {
+    DEFAULT_MAX_PACKET_SIZE
+}
+
+impl Default for ConnectionDefaults {
+    fn default() -> Self {
+        Self {
+            username: None,
+            port: default_port(),
+            term: default_term(),
+            keepalive_seconds: default_keepalive_seconds(),
+            scrollback_bytes: default_scrollback_bytes(),
+            scrollback_spill_bytes: default_scrollback_spill_bytes(),
+            channel_window_bytes: default_channel_window_bytes(),
+            channel_max_packet_bytes: default_channel_max_packet_bytes(),
+            reconnect: ReconnectPolicy::default(),
+            queue_input_while_reconnecting: false,
+            probe_terminal: default_probe_terminal(),
+        }
+    }
+}
+
+impl ConnectionDefaults {
+    /// Fills in the username and port a new server was saved without.
+    pub fn apply_to(&self, server: &mut ServerConnection) {
+        if server.user.trim().is_empty() {
+            if let Some(username) = &self.username {
+                server.user = username.clone();
+            }
+        }
+        if server.port == 0 {
+            server.port = self.port;
+        }
+    }
+
+    pub fn term_for(&self, server: &ServerConnection) -> String {
+        server
+            .term
+            .clone()
+            .filter(|term| !term.trim().is_empty())
+            .unwrap_or_else(|| self.term.clone())
+    }
+
+    pub fn keepalive_for(&self, server: &ServerConnection) -> u64 {
+        server.keepalive_seconds.unwrap_or(self.keepalive_seconds)
+    }
+
+    pub fn channel_tuning(&self) -> ChannelTuning {
+        ChannelTuning {
+            window_size: self.channel_window_bytes,
+            maximum_packet_size: self.channel_max_packet_bytes,
+        }
+    }
+
+    pub fn reconnect_for(&self, server: &ServerConnection) -> ReconnectPolicy {
+        server.reconnect.unwrap_or(self.reconnect)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("Default port must be between 1 and 65535".to_string());
+        }
+        if self.term.trim().is_empty() {
+            return Err("Default TERM cannot be empty".to_string());
+        }
+        if self.scrollback_bytes < MIN_SCROLLBACK_BYTES {
+            return Err(format!(
+                "Scrollback size must be at least {} bytes",
+                MIN_SCROLLBACK_BYTES
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn get_settings_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SETTINGS_FILE)
+}
+
+pub fn load_settings(app_dir: &Path) -> Result<AppSettings, String> {
+    let path = get_settings_path(app_dir);
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read settings file: {}", e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse settings file: {}", e))
+}
+
+/// How long to wait on a host key prompt, or `None` to wait until answered.
+pub fn host_key_prompt_timeout(app: &AppHandle) -> Option<u64> {
+    let timeout_seconds = get_app_dir(app)
+        .and_then(|app_dir| load_settings(&app_dir))
+        .map(|settings| settings.host_key_prompt_timeout_seconds)
+        .unwrap_or(DEFAULT_HOST_KEY_PROMPT_TIMEOUT_SECONDS);
+    (timeout_seconds > 0).then_some(timeout_seconds)
+}
+
+pub fn max_concurrent_connections(app: &AppHandle) -> usize {
+    get_app_dir(app)
+        .and_then(|app_dir| load_settings(&app_dir))
+        .map(|settings| settings.max_concurrent_connections)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_CONNECTIONS)
+}
+
+/// The current defaults, or the built-in ones when settings cannot be read;
+/// a broken settings file should not stop connections.
+pub fn connection_defaults(app: &AppHandle) -> ConnectionDefaults {
+    match get_app_dir(app).and_then(|app_dir| load_settings(&app_dir)) {
+        Ok(settings) => settings.defaults,
+        Err(e) => {
+            debug!(error = %e, "Failed to load connection defaults");
+            ConnectionDefaults::default()
+        }
+    }
+}
+
+fn save_settings(app_dir: &Path, settings: &AppSettings) -> Result<(), String> {
+    fs::create_dir_all(app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(get_settings_path(app_dir), content)
+        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    load_settings(&get_app_dir(&app)?)
+}
+
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, settings: AppSettings) -> Result<AppSettings, String> {
+    settings.defaults.validate()?;
+    settings.command_guard.compile()?;
+    settings.redaction.compile()?;
+    if let Some(git_sync) = &settings.git_sync {
+        if git_sync.remote_url.trim().is_empty() || git_sync.branch.trim().is_empty() {
+            return Err("Git sync needs a repository URL and branch".to_string());
+        }
+    }
+    save_settings(&get_app_dir(&app)?, &settings)?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_without_defaults_use_built_in_values() {
+        let settings: AppSettings = serde_json::from_str(r#"{"allow_telnet":true}"#).unwrap();
+
+        assert!(settings.allow_telnet);
+        assert_eq!(settings.defaults, ConnectionDefaults::default());
+        assert_eq!(settings.defaults.port, 22);
+        assert_eq!(settings.defaults.reconnect, ReconnectPolicy::Manual);
+    }
+}
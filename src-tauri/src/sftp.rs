@@ -0,0 +1,1777 @@
+use glob::{MatchOptions, Pattern};
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::FileAttributes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::open_server_channel;
+use crate::remote::{run_remote_command, shell_quote};
+use crate::transfers::TransferControl;
+
+const TRANSFER_CHUNK_BYTES: usize = 64 * 1024;
+const PROGRESS_INTERVAL_BYTES: u64 = 1024 * 1024;
+const MAX_TREE_DEPTH: usize = 64;
+const GLOB_MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    #[default]
+    Skip,
+    Follow,
+    Preserve,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryTransferOptions {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub symlinks: SymlinkPolicy,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub verify_checksum: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferEntryKind {
+    Directory,
+    File,
+    Symlink,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPlanEntry {
+    pub relative_path: String,
+    pub kind: TransferEntryKind,
+    pub size: u64,
+    #[serde(default)]
+    pub link_target: Option<String>,
+    #[serde(default)]
+    pub modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryTransferResult {
+    pub transfer_id: String,
+    pub direction: TransferDirection,
+    pub dry_run: bool,
+    pub entries: Vec<TransferPlanEntry>,
+    pub skipped: Vec<String>,
+    pub total_bytes: u64,
+    pub files_transferred: usize,
+    pub bytes_transferred: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferProgressEvent {
+    pub transfer_id: String,
+    pub server_id: String,
+    pub relative_path: String,
+    pub bytes_transferred: u64,
+    pub total_bytes: u64,
+    pub files_completed: usize,
+    pub files_total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum TransferError {
+    ChecksumMismatch {
+        path: String,
+        local_sha256: String,
+        remote_sha256: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferError::ChecksumMismatch {
+                path,
+                local_sha256,
+                remote_sha256,
+            } => write!(
+                f,
+                "Checksum mismatch for {} (local {}, remote {})",
+                path, local_sha256, remote_sha256
+            ),
+            TransferError::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for TransferError {
+    fn from(message: String) -> Self {
+        TransferError::Failed { message }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncOptions {
+    #[serde(flatten)]
+    pub transfer: DirectoryTransferOptions,
+    #[serde(default)]
+    pub checksum: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncDiff {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDiffEvent {
+    pub transfer_id: String,
+    pub server_id: String,
+    pub direction: TransferDirection,
+    pub dry_run: bool,
+    pub diff: SyncDiff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResult {
+    pub transfer_id: String,
+    pub direction: TransferDirection,
+    pub dry_run: bool,
+    pub diff: SyncDiff,
+    pub files_transferred: usize,
+    pub bytes_transferred: u64,
+    pub files_deleted: usize,
+}
+
+#[derive(Debug, Default)]
+struct TransferPlan {
+    entries: Vec<TransferPlanEntry>,
+    skipped: Vec<String>,
+}
+
+impl TransferPlan {
+    fn total_bytes(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == TransferEntryKind::File)
+            .map(|entry| entry.size)
+            .sum()
+    }
+
+    fn file_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind != TransferEntryKind::Directory)
+            .count()
+    }
+
+    // With include patterns active, directories that ended up with nothing
+    // to transfer would otherwise be created empty on the destination.
+    fn prune_empty_directories(&mut self, filter: &TransferFilter) {
+        if filter.include.is_empty() {
+            return;
+        }
+
+        let non_dirs: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.kind != TransferEntryKind::Directory)
+            .map(|entry| entry.relative_path.clone())
+            .collect();
+
+        self.entries.retain(|entry| {
+            if entry.kind != TransferEntryKind::Directory {
+                return true;
+            }
+            let prefix = format!("{}/", entry.relative_path);
+            non_dirs.iter().any(|path| path.starts_with(&prefix))
+        });
+    }
+}
+
+struct TransferFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl TransferFilter {
+    fn new(options: &DirectoryTransferOptions) -> Result<Self, String> {
+        Ok(Self {
+            include: compile_patterns(&options.include)?,
+            exclude: compile_patterns(&options.exclude)?,
+        })
+    }
+
+    fn is_excluded(&self, relative_path: &str) -> bool {
+        matches_any(&self.exclude, relative_path)
+    }
+
+    // Directories are always walked so include patterns can match nested files;
+    // this only gates files and links.
+    fn is_included(&self, relative_path: &str) -> bool {
+        self.include.is_empty() || matches_any(&self.include, relative_path)
+    }
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>, String> {
+    patterns
+        .iter()
+        .filter(|pattern| !pattern.trim().is_empty())
+        .map(|pattern| {
+            Pattern::new(pattern.trim())
+                .map_err(|e| format!("Invalid glob pattern {}: {}", pattern, e))
+        })
+        .collect()
+}
+
+// A pattern matches either the full relative path or just the entry name, so
+// `*.log` excludes logs at any depth while `build/*` still targets one folder.
+fn matches_any(patterns: &[Pattern], relative_path: &str) -> bool {
+    let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    patterns.iter().any(|pattern| {
+        pattern.matches_with(relative_path, GLOB_MATCH_OPTIONS)
+            || pattern.matches_with(name, GLOB_MATCH_OPTIONS)
+    })
+}
+
+fn join_relative(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+pub(crate) fn join_remote(base: &str, relative: &str) -> String {
+    if relative.is_empty() {
+        base.to_string()
+    } else if base.ends_with('/') {
+        format!("{}{}", base, relative)
+    } else {
+        format!("{}/{}", base, relative)
+    }
+}
+
+pub(crate) fn join_local(base: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .fold(base.to_path_buf(), |path, part| path.join(part))
+}
+
+// Remote names come from the server; anything but a single plain component
+// would be joined onto the local root as a path of its own.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+/// Whether a link at `relative` pointing at `target` resolves inside the
+/// transfer root, judged from the path alone.
+fn link_stays_inside(relative: &str, target: &str) -> bool {
+    if target.starts_with(['/', '\\']) || Path::new(target).is_absolute() {
+        return false;
+    }
+    let mut depth = relative
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .count()
+        .saturating_sub(1);
+    for part in target.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            _ => depth += 1,
+        }
+    }
+    true
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+fn plan_local_tree(
+    root: &Path,
+    filter: &TransferFilter,
+    symlinks: SymlinkPolicy,
+) -> Result<TransferPlan, String> {
+    let mut plan = TransferPlan::default();
+    let mut pending = vec![(root.to_path_buf(), String::new(), 0usize)];
+
+    while let Some((dir, prefix, depth)) = pending.pop() {
+        let mut children = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+        children.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirs = Vec::new();
+        for child in children {
+            let name = child.file_name().to_string_lossy().into_owned();
+            let relative = join_relative(&prefix, &name);
+            if filter.is_excluded(&relative) {
+                plan.skipped.push(relative);
+                continue;
+            }
+
+            let path = child.path();
+            let mut metadata = fs::symlink_metadata(&path)
+                .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+
+            if metadata.file_type().is_symlink() {
+                match symlinks {
+                    SymlinkPolicy::Skip => {
+                        plan.skipped.push(relative);
+                        continue;
+                    }
+                    SymlinkPolicy::Preserve => {
+                        if filter.is_included(&relative) {
+                            let target = fs::read_link(&path).map_err(|e| {
+                                format!("Failed to read link {}: {}", path.display(), e)
+                            })?;
+                            plan.entries.push(TransferPlanEntry {
+                                relative_path: relative,
+                                kind: TransferEntryKind::Symlink,
+                                size: 0,
+                                link_target: Some(target.to_string_lossy().into_owned()),
+                                modified: None,
+                            });
+                        }
+                        continue;
+                    }
+                    SymlinkPolicy::Follow => match fs::metadata(&path) {
+                        Ok(target) => metadata = target,
+                        Err(_) => {
+                            // Dangling link
+                            plan.skipped.push(relative);
+                            continue;
+                        }
+                    },
+                }
+            }
+
+            if metadata.is_dir() {
+                if depth + 1 >= MAX_TREE_DEPTH {
+                    return Err(format!(
+                        "Directory tree exceeds {} levels at {}",
+                        MAX_TREE_DEPTH, relative
+                    ));
+                }
+                plan.entries.push(TransferPlanEntry {
+                    relative_path: relative.clone(),
+                    kind: TransferEntryKind::Directory,
+                    size: 0,
+                    link_target: None,
+                    modified: None,
+                });
+                subdirs.push((path, relative, depth + 1));
+            } else if filter.is_included(&relative) {
+                plan.entries.push(TransferPlanEntry {
+                    relative_path: relative,
+                    kind: TransferEntryKind::File,
+                    size: metadata.len(),
+                    link_target: None,
+                    modified: modified_secs(&metadata),
+                });
+            }
+        }
+
+        // Reverse so the stack pops directories in name order.
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    plan.prune_empty_directories(filter);
+    Ok(plan)
+}
+
+async fn plan_remote_tree(
+    sftp: &SftpSession,
+    root: &str,
+    filter: &TransferFilter,
+    symlinks: SymlinkPolicy,
+) -> Result<TransferPlan, String> {
+    let mut plan = TransferPlan::default();
+    let mut pending = vec![(root.to_string(), String::new(), 0usize)];
+
+    while let Some((dir, prefix, depth)) = pending.pop() {
+        let mut children: Vec<_> = sftp
+            .read_dir(dir.clone())
+            .await
+            .map_err(|e| format!("Failed to read remote directory {}: {}", dir, e))?
+            .collect();
+        children.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirs = Vec::new();
+        for child in children {
+            let name = child.file_name();
+            if !is_plain_name(&name) {
+                return Err(format!(
+                    "Remote directory {} lists an unsafe name {:?}",
+                    dir, name
+                ));
+            }
+            let relative = join_relative(&prefix, &name);
+            if filter.is_excluded(&relative) {
+                plan.skipped.push(relative);
+                continue;
+            }
+
+            let path = join_remote(&dir, &name);
+            let mut metadata = child.metadata();
+
+            if metadata.file_type().is_symlink() {
+                match symlinks {
+                    SymlinkPolicy::Skip => {
+                        plan.skipped.push(relative);
+                        continue;
+                    }
+                    SymlinkPolicy::Preserve => {
+                        if filter.is_included(&relative) {
+                            let target = sftp.read_link(path.clone()).await.map_err(|e| {
+                                format!("Failed to read remote link {}: {}", path, e)
+                            })?;
+                            // Recreated locally, a link out of the root would
+                            // let later entries be written through it.
+                            if !link_stays_inside(&relative, &target) {
+                                plan.skipped.push(relative);
+                                continue;
+                            }
+                            plan.entries.push(TransferPlanEntry {
+                                relative_path: relative,
+                                kind: TransferEntryKind::Symlink,
+                                size: 0,
+                                link_target: Some(target),
+                                modified: None,
+                            });
+                        }
+                        continue;
+                    }
+                    SymlinkPolicy::Follow => match sftp.metadata(path.clone()).await {
+                        Ok(target) => metadata = target,
+                        Err(_) => {
+                            plan.skipped.push(relative);
+                            continue;
+                        }
+                    },
+                }
+            }
+
+            if metadata.is_dir() {
+                if depth + 1 >= MAX_TREE_DEPTH {
+                    return Err(format!(
+                        "Remote directory tree exceeds {} levels at {}",
+                        MAX_TREE_DEPTH, relative
+                    ));
+                }
+                plan.entries.push(TransferPlanEntry {
+                    relative_path: relative.clone(),
+                    kind: TransferEntryKind::Directory,
+                    size: 0,
+                    link_target: None,
+                    modified: None,
+                });
+                subdirs.push((path, relative, depth + 1));
+            } else if filter.is_included(&relative) {
+                plan.entries.push(TransferPlanEntry {
+                    relative_path: relative,
+                    kind: TransferEntryKind::File,
+                    size: metadata.len(),
+                    link_target: None,
+                    modified: metadata.mtime.map(u64::from),
+                });
+            }
+        }
+
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    plan.prune_empty_directories(filter);
+    Ok(plan)
+}
+
+pub(crate) async fn open_sftp(app: &AppHandle, server_id: &str) -> Result<SftpSession, String> {
+    let channel = open_server_channel(app, server_id).await?;
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| format!("Failed to request SFTP subsystem: {}", e))?;
+    SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| format!("Failed to start SFTP session: {}", e))
+}
+
+async fn copy_stream<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    control: Option<&TransferControl>,
+    mut on_progress: impl FnMut(u64),
+) -> Result<u64, String>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_BYTES];
+    let mut copied = 0u64;
+    let mut last_reported = 0u64;
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        if let Some(control) = control {
+            control.checkpoint(read).await?;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .await
+            .map_err(|e| format!("Failed to write: {}", e))?;
+        copied += read as u64;
+
+        if copied - last_reported >= PROGRESS_INTERVAL_BYTES {
+            last_reported = copied;
+            on_progress(copied);
+        }
+    }
+
+    writer
+        .shutdown()
+        .await
+        .map_err(|e| format!("Failed to finish write: {}", e))?;
+    on_progress(copied);
+    Ok(copied)
+}
+
+struct TransferContext<'a> {
+    app: &'a AppHandle,
+    transfer_id: &'a str,
+    server_id: &'a str,
+    total_bytes: u64,
+    files_total: usize,
+    files_completed: usize,
+    bytes_completed: u64,
+    verify_checksum: bool,
+    control: Option<&'a TransferControl>,
+}
+
+impl TransferContext<'_> {
+    fn emit_progress(&self, relative_path: &str, file_bytes: u64) {
+        let payload = TransferProgressEvent {
+            transfer_id: self.transfer_id.to_string(),
+            server_id: self.server_id.to_string(),
+            relative_path: relative_path.to_string(),
+            bytes_transferred: self.bytes_completed + file_bytes,
+            total_bytes: self.total_bytes,
+            files_completed: self.files_completed,
+            files_total: self.files_total,
+        };
+        let _ = self.app.emit("transfer-progress", payload);
+    }
+}
+
+async fn upload_plan(
+    sftp: &SftpSession,
+    plan: &TransferPlan,
+    local_root: &Path,
+    remote_root: &str,
+    ctx: &mut TransferContext<'_>,
+) -> Result<(), TransferError> {
+    let root_exists = sftp
+        .try_exists(remote_root.to_string())
+        .await
+        .map_err(|e| format!("Failed to stat {}: {}", remote_root, e))?;
+    if !root_exists {
+        sftp.create_dir(remote_root.to_string())
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", remote_root, e))?;
+    }
+
+    for entry in &plan.entries {
+        let remote_path = join_remote(remote_root, &entry.relative_path);
+        match entry.kind {
+            TransferEntryKind::Directory => {
+                let exists = sftp
+                    .try_exists(remote_path.clone())
+                    .await
+                    .map_err(|e| format!("Failed to stat {}: {}", remote_path, e))?;
+                if !exists {
+                    sftp.create_dir(remote_path.clone())
+                        .await
+                        .map_err(|e| format!("Failed to create {}: {}", remote_path, e))?;
+                }
+            }
+            TransferEntryKind::Symlink => {
+                let target = entry.link_target.clone().unwrap_or_default();
+                let _ = sftp.remove_file(remote_path.clone()).await;
+                sftp.symlink(remote_path.clone(), target)
+                    .await
+                    .map_err(|e| format!("Failed to create link {}: {}", remote_path, e))?;
+                ctx.files_completed += 1;
+            }
+            TransferEntryKind::File => {
+                let local_path = join_local(local_root, &entry.relative_path);
+                let mut reader = tokio::fs::File::open(&local_path)
+                    .await
+                    .map_err(|e| format!("Failed to open {}: {}", local_path.display(), e))?;
+                let mut writer = sftp
+                    .create(remote_path.clone())
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", remote_path, e))?;
+                let control = ctx.control;
+                let copied = copy_stream(&mut reader, &mut writer, control, |bytes| {
+                    ctx.emit_progress(&entry.relative_path, bytes)
+                })
+                .await
+                .map_err(|e| format!("{} ({})", e, entry.relative_path))?;
+                if ctx.verify_checksum {
+                    verify_transfer(ctx, sftp, &local_path, &remote_path, &entry.relative_path)
+                        .await?;
+                }
+                preserve_remote_mtime(sftp, &remote_path, entry.modified).await;
+                ctx.bytes_completed += copied;
+                ctx.files_completed += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn download_plan(
+    sftp: &SftpSession,
+    plan: &TransferPlan,
+    remote_root: &str,
+    local_root: &Path,
+    ctx: &mut TransferContext<'_>,
+) -> Result<(), TransferError> {
+    fs::create_dir_all(local_root)
+        .map_err(|e| format!("Failed to create {}: {}", local_root.display(), e))?;
+
+    for entry in &plan.entries {
+        let local_path = join_local(local_root, &entry.relative_path);
+        match entry.kind {
+            TransferEntryKind::Directory => {
+                remove_local_link(&local_path)?;
+                fs::create_dir_all(&local_path)
+                    .map_err(|e| format!("Failed to create {}: {}", local_path.display(), e))?;
+            }
+            TransferEntryKind::Symlink => {
+                let target = entry.link_target.clone().unwrap_or_default();
+                create_local_symlink(&target, &local_path)?;
+                ctx.files_completed += 1;
+            }
+            TransferEntryKind::File => {
+                let remote_path = join_remote(remote_root, &entry.relative_path);
+                let mut reader = sftp
+                    .open(remote_path.clone())
+                    .await
+                    .map_err(|e| format!("Failed to open {}: {}", remote_path, e))?;
+                remove_local_link(&local_path)?;
+                let mut writer = tokio::fs::File::create(&local_path)
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", local_path.display(), e))?;
+                let control = ctx.control;
+                let copied = copy_stream(&mut reader, &mut writer, control, |bytes| {
+                    ctx.emit_progress(&entry.relative_path, bytes)
+                })
+                .await
+                .map_err(|e| format!("{} ({})", e, entry.relative_path))?;
+                if ctx.verify_checksum {
+                    verify_transfer(ctx, sftp, &local_path, &remote_path, &entry.relative_path)
+                        .await?;
+                }
+                preserve_local_mtime(&local_path, entry.modified);
+                ctx.bytes_completed += copied;
+                ctx.files_completed += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Keeping the source mtime on the copy is what lets a later sync treat the
+// file as unchanged; failures are not fatal since the bytes already landed.
+async fn preserve_remote_mtime(sftp: &SftpSession, remote_path: &str, modified: Option<u64>) {
+    let Some(mtime) = modified.and_then(|secs| u32::try_from(secs).ok()) else {
+        return;
+    };
+    let mut attrs = FileAttributes::empty();
+    attrs.atime = Some(mtime);
+    attrs.mtime = Some(mtime);
+    if let Err(e) = sftp.set_metadata(remote_path.to_string(), attrs).await {
+        debug!(remote_path, error = %e, "Failed to set remote mtime");
+    }
+}
+
+fn preserve_local_mtime(local_path: &Path, modified: Option<u64>) {
+    let Some(mtime) = modified else {
+        return;
+    };
+    let result = fs::File::options()
+        .write(true)
+        .open(local_path)
+        .and_then(|file| file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime)));
+    if let Err(e) = result {
+        debug!(path = %local_path.display(), error = %e, "Failed to set local mtime");
+    }
+}
+
+// Creating a file or directory through an existing link would write wherever
+// the link points, possibly outside the destination, so the link goes first.
+fn remove_local_link(path: &Path) -> Result<(), String> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::remove_file(path)
+            .or_else(|_| fs::remove_dir(path))
+            .map_err(|e| format!("Failed to remove link {}: {}", path.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn create_local_symlink(target: &str, link: &Path) -> Result<(), String> {
+    let _ = fs::remove_file(link);
+    std::os::unix::fs::symlink(target, link)
+        .map_err(|e| format!("Failed to create link {}: {}", link.display(), e))
+}
+
+#[cfg(not(unix))]
+fn create_local_symlink(target: &str, link: &Path) -> Result<(), String> {
+    Err(format!(
+        "Preserving symlinks is not supported on this platform ({} -> {})",
+        link.display(),
+        target
+    ))
+}
+
+fn transfer_result(
+    transfer_id: String,
+    direction: TransferDirection,
+    dry_run: bool,
+    plan: TransferPlan,
+    files_transferred: usize,
+    bytes_transferred: u64,
+) -> DirectoryTransferResult {
+    let total_bytes = plan.total_bytes();
+    DirectoryTransferResult {
+        transfer_id,
+        direction,
+        dry_run,
+        entries: plan.entries,
+        skipped: plan.skipped,
+        total_bytes,
+        files_transferred,
+        bytes_transferred,
+    }
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hash_local_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_BYTES];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+// Hashing a large file takes long enough to stall the runtime, so it runs on
+// the blocking pool.
+async fn local_sha256(path: &Path) -> Result<String, String> {
+    let owned = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || hash_local_file(&owned))
+        .await
+        .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?
+}
+
+async fn hash_remote_file(sftp: &SftpSession, remote_path: &str) -> Result<String, String> {
+    let mut file = sftp
+        .open(remote_path.to_string())
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", remote_path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_BYTES];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", remote_path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+fn parse_checksum_output(output: &str) -> Option<String> {
+    let digest = output.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| digest.to_ascii_lowercase())
+}
+
+// Hashing on the server avoids pulling the file back over the wire; hosts
+// without sha256sum/shasum fall back to reading it through SFTP.
+async fn remote_sha256(
+    app: &AppHandle,
+    sftp: &SftpSession,
+    server_id: &str,
+    remote_path: &str,
+) -> Result<String, String> {
+    let quoted = shell_quote(remote_path);
+    let command = format!(
+        "sha256sum -- {0} 2>/dev/null || shasum -a 256 -- {0} 2>/dev/null",
+        quoted
+    );
+    match run_remote_command(app, server_id, &command).await {
+        Ok(output) if output.success() => {
+            if let Some(digest) = parse_checksum_output(&output.stdout) {
+                return Ok(digest);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => debug!(remote_path, error = %e, "Remote checksum command failed"),
+    }
+    hash_remote_file(sftp, remote_path).await
+}
+
+async fn verify_transfer(
+    ctx: &TransferContext<'_>,
+    sftp: &SftpSession,
+    local_path: &Path,
+    remote_path: &str,
+    relative_path: &str,
+) -> Result<(), TransferError> {
+    let local_sha256 = local_sha256(local_path).await?;
+    let remote_sha256 = remote_sha256(ctx.app, sftp, ctx.server_id, remote_path).await?;
+    if local_sha256 != remote_sha256 {
+        return Err(TransferError::ChecksumMismatch {
+            path: relative_path.to_string(),
+            local_sha256,
+            remote_sha256,
+        });
+    }
+    Ok(())
+}
+
+// Quick-check comparison in the spirit of rsync: size or mtime differences
+// mark a file as modified. In checksum mode only sizes are compared here and
+// same-size pairs are left in `unchanged_paths` for hashing by the caller.
+fn diff_plans(
+    source: &TransferPlan,
+    destination: &TransferPlan,
+    delete: bool,
+    compare_mtime: bool,
+) -> (SyncDiff, Vec<String>) {
+    let existing: HashMap<&str, &TransferPlanEntry> = destination
+        .entries
+        .iter()
+        .map(|entry| (entry.relative_path.as_str(), entry))
+        .collect();
+    let mut diff = SyncDiff::default();
+    let mut unchanged_paths = Vec::new();
+
+    for entry in &source.entries {
+        if entry.kind == TransferEntryKind::Directory {
+            continue;
+        }
+        match existing.get(entry.relative_path.as_str()) {
+            None => diff.added.push(entry.relative_path.clone()),
+            Some(current) => {
+                let changed = current.kind != entry.kind
+                    || current.size != entry.size
+                    || current.link_target != entry.link_target
+                    || (compare_mtime
+                        && entry.kind == TransferEntryKind::File
+                        && current.modified != entry.modified);
+                if changed {
+                    diff.modified.push(entry.relative_path.clone());
+                } else {
+                    unchanged_paths.push(entry.relative_path.clone());
+                }
+            }
+        }
+    }
+
+    if delete {
+        let wanted: HashSet<&str> = source
+            .entries
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+        diff.deleted = destination
+            .entries
+            .iter()
+            .filter(|entry| !wanted.contains(entry.relative_path.as_str()))
+            .map(|entry| entry.relative_path.clone())
+            .collect();
+        // Children before parents so directories are empty when removed.
+        diff.deleted
+            .sort_by_key(|path| std::cmp::Reverse(path.matches('/').count()));
+    }
+
+    diff.unchanged = unchanged_paths.len();
+    (diff, unchanged_paths)
+}
+
+fn sync_transfer_plan(
+    source: &TransferPlan,
+    destination: &TransferPlan,
+    diff: &SyncDiff,
+) -> TransferPlan {
+    let existing: HashSet<&str> = destination
+        .entries
+        .iter()
+        .map(|entry| entry.relative_path.as_str())
+        .collect();
+    let changed: HashSet<&str> = diff
+        .added
+        .iter()
+        .chain(diff.modified.iter())
+        .map(|path| path.as_str())
+        .collect();
+
+    TransferPlan {
+        entries: source
+            .entries
+            .iter()
+            .filter(|entry| match entry.kind {
+                TransferEntryKind::Directory => !existing.contains(entry.relative_path.as_str()),
+                _ => changed.contains(entry.relative_path.as_str()),
+            })
+            .cloned()
+            .collect(),
+        skipped: Vec::new(),
+    }
+}
+
+async fn delete_remote_entries(
+    sftp: &SftpSession,
+    remote_root: &str,
+    destination: &TransferPlan,
+    deleted: &[String],
+) -> Result<usize, String> {
+    let kinds: HashMap<&str, TransferEntryKind> = destination
+        .entries
+        .iter()
+        .map(|entry| (entry.relative_path.as_str(), entry.kind))
+        .collect();
+    let mut removed = 0;
+    for relative in deleted {
+        let remote_path = join_remote(remote_root, relative);
+        let result = match kinds.get(relative.as_str()) {
+            Some(TransferEntryKind::Directory) => sftp.remove_dir(remote_path.clone()).await,
+            _ => sftp.remove_file(remote_path.clone()).await,
+        };
+        result.map_err(|e| format!("Failed to delete {}: {}", remote_path, e))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+fn delete_local_entries(
+    local_root: &Path,
+    destination: &TransferPlan,
+    deleted: &[String],
+) -> Result<usize, String> {
+    let kinds: HashMap<&str, TransferEntryKind> = destination
+        .entries
+        .iter()
+        .map(|entry| (entry.relative_path.as_str(), entry.kind))
+        .collect();
+    let mut removed = 0;
+    for relative in deleted {
+        let local_path = join_local(local_root, relative);
+        let result = match kinds.get(relative.as_str()) {
+            Some(TransferEntryKind::Directory) => fs::remove_dir(&local_path),
+            _ => fs::remove_file(&local_path),
+        };
+        result.map_err(|e| format!("Failed to delete {}: {}", local_path.display(), e))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+async fn plan_remote_tree_if_exists(
+    sftp: &SftpSession,
+    root: &str,
+    filter: &TransferFilter,
+    symlinks: SymlinkPolicy,
+) -> Result<TransferPlan, String> {
+    let exists = sftp
+        .try_exists(root.to_string())
+        .await
+        .map_err(|e| format!("Failed to stat {}: {}", root, e))?;
+    if exists {
+        plan_remote_tree(sftp, root, filter, symlinks).await
+    } else {
+        Ok(TransferPlan::default())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_sync(
+    app: &AppHandle,
+    sftp: &SftpSession,
+    server_id: &str,
+    transfer_id: &str,
+    local_root: &Path,
+    remote_root: &str,
+    direction: TransferDirection,
+    delete: bool,
+    options: &SyncOptions,
+    control: Option<&TransferControl>,
+) -> Result<SyncResult, TransferError> {
+    let filter = TransferFilter::new(&options.transfer)?;
+    let symlinks = options.transfer.symlinks;
+    let local_plan = if local_root.is_dir() {
+        plan_local_tree(local_root, &filter, symlinks)?
+    } else if direction == TransferDirection::Upload {
+        return Err(format!("{} is not a directory", local_root.display()).into());
+    } else {
+        TransferPlan::default()
+    };
+    let remote_plan = match direction {
+        TransferDirection::Upload => {
+            plan_remote_tree_if_exists(sftp, remote_root, &filter, symlinks).await?
+        }
+        TransferDirection::Download => {
+            plan_remote_tree(sftp, remote_root, &filter, symlinks).await?
+        }
+    };
+    let (source, destination) = match direction {
+        TransferDirection::Upload => (&local_plan, &remote_plan),
+        TransferDirection::Download => (&remote_plan, &local_plan),
+    };
+
+    let (mut diff, unchanged_paths) = diff_plans(source, destination, delete, !options.checksum);
+    if options.checksum {
+        let kinds: HashMap<&str, TransferEntryKind> = source
+            .entries
+            .iter()
+            .map(|entry| (entry.relative_path.as_str(), entry.kind))
+            .collect();
+        for relative in unchanged_paths {
+            if kinds.get(relative.as_str()) != Some(&TransferEntryKind::File) {
+                continue;
+            }
+            let local_hash = local_sha256(&join_local(local_root, &relative)).await?;
+            let remote_hash =
+                remote_sha256(app, sftp, server_id, &join_remote(remote_root, &relative)).await?;
+            if local_hash != remote_hash {
+                diff.modified.push(relative);
+                diff.unchanged -= 1;
+            }
+        }
+    }
+
+    let _ = app.emit(
+        "sync-diff",
+        SyncDiffEvent {
+            transfer_id: transfer_id.to_string(),
+            server_id: server_id.to_string(),
+            direction,
+            dry_run: options.transfer.dry_run,
+            diff: diff.clone(),
+        },
+    );
+
+    if options.transfer.dry_run {
+        return Ok(SyncResult {
+            transfer_id: transfer_id.to_string(),
+            direction,
+            dry_run: true,
+            diff,
+            files_transferred: 0,
+            bytes_transferred: 0,
+            files_deleted: 0,
+        });
+    }
+
+    debug!(
+        server_id,
+        added = diff.added.len(),
+        modified = diff.modified.len(),
+        deleted = diff.deleted.len(),
+        "Synchronizing directory"
+    );
+
+    let plan = sync_transfer_plan(source, destination, &diff);
+    let mut ctx = TransferContext {
+        app,
+        transfer_id,
+        server_id,
+        total_bytes: plan.total_bytes(),
+        files_total: plan.file_count(),
+        files_completed: 0,
+        bytes_completed: 0,
+        verify_checksum: options.transfer.verify_checksum,
+        control,
+    };
+    let files_deleted = match direction {
+        TransferDirection::Upload => {
+            upload_plan(sftp, &plan, local_root, remote_root, &mut ctx).await?;
+            delete_remote_entries(sftp, remote_root, destination, &diff.deleted).await?
+        }
+        TransferDirection::Download => {
+            download_plan(sftp, &plan, remote_root, local_root, &mut ctx).await?;
+            delete_local_entries(local_root, destination, &diff.deleted)?
+        }
+    };
+
+    Ok(SyncResult {
+        transfer_id: transfer_id.to_string(),
+        direction,
+        dry_run: false,
+        diff,
+        files_transferred: ctx.files_completed,
+        bytes_transferred: ctx.bytes_completed,
+        files_deleted,
+    })
+}
+
+pub(crate) async fn run_upload_directory(
+    app: &AppHandle,
+    server_id: &str,
+    transfer_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    options: &DirectoryTransferOptions,
+    control: Option<&TransferControl>,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let filter = TransferFilter::new(options)?;
+    let local_root = PathBuf::from(local_path);
+    if !local_root.is_dir() {
+        return Err(format!("{} is not a directory", local_path).into());
+    }
+
+    let plan = plan_local_tree(&local_root, &filter, options.symlinks)?;
+    if options.dry_run {
+        return Ok(transfer_result(
+            transfer_id.to_string(),
+            TransferDirection::Upload,
+            true,
+            plan,
+            0,
+            0,
+        ));
+    }
+
+    debug!(server_id, local_path, remote_path, "Uploading directory");
+
+    let sftp = open_sftp(app, server_id).await?;
+    let mut ctx = TransferContext {
+        app,
+        transfer_id,
+        server_id,
+        total_bytes: plan.total_bytes(),
+        files_total: plan.file_count(),
+        files_completed: 0,
+        bytes_completed: 0,
+        verify_checksum: options.verify_checksum,
+        control,
+    };
+    let outcome = upload_plan(&sftp, &plan, &local_root, remote_path, &mut ctx).await;
+    let (files_transferred, bytes_transferred) = (ctx.files_completed, ctx.bytes_completed);
+    let _ = sftp.close().await;
+    outcome?;
+
+    Ok(transfer_result(
+        transfer_id.to_string(),
+        TransferDirection::Upload,
+        false,
+        plan,
+        files_transferred,
+        bytes_transferred,
+    ))
+}
+
+/// Plans a single local file or directory so it lands under its own name,
+/// relative to its parent directory.
+fn plan_local_path(path: &Path, filter: &TransferFilter) -> Result<TransferPlan, String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("{} has no file name", path.display()))?;
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+    let mut plan = TransferPlan::default();
+    if !metadata.is_dir() {
+        plan.entries.push(TransferPlanEntry {
+            relative_path: name,
+            kind: TransferEntryKind::File,
+            size: metadata.len(),
+            link_target: None,
+            modified: modified_secs(&metadata),
+        });
+        return Ok(plan);
+    }
+
+    plan.entries.push(TransferPlanEntry {
+        relative_path: name.clone(),
+        kind: TransferEntryKind::Directory,
+        size: 0,
+        link_target: None,
+        modified: None,
+    });
+    let tree = plan_local_tree(path, filter, SymlinkPolicy::Skip)?;
+    plan.entries
+        .extend(tree.entries.into_iter().map(|mut entry| {
+            entry.relative_path = join_relative(&name, &entry.relative_path);
+            entry
+        }));
+    plan.skipped.extend(
+        tree.skipped
+            .iter()
+            .map(|skipped| join_relative(&name, skipped)),
+    );
+    Ok(plan)
+}
+
+/// Uploads files and directories from anywhere on disk into `remote_dir`,
+/// each under its own name, as one transfer.
+pub(crate) async fn run_upload_paths(
+    app: &AppHandle,
+    server_id: &str,
+    transfer_id: &str,
+    local_paths: &[PathBuf],
+    remote_dir: &str,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let filter = TransferFilter::new(&DirectoryTransferOptions::default())?;
+    let plans = local_paths
+        .iter()
+        .map(|path| {
+            let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            plan_local_path(path, &filter).map(|plan| (parent, plan))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    debug!(
+        server_id,
+        remote_dir,
+        count = local_paths.len(),
+        "Uploading paths"
+    );
+
+    let sftp = open_sftp(app, server_id).await?;
+    let mut ctx = TransferContext {
+        app,
+        transfer_id,
+        server_id,
+        total_bytes: plans.iter().map(|(_, plan)| plan.total_bytes()).sum(),
+        files_total: plans.iter().map(|(_, plan)| plan.file_count()).sum(),
+        files_completed: 0,
+        bytes_completed: 0,
+        verify_checksum: false,
+        control: None,
+    };
+    let mut outcome = Ok(());
+    for (parent, plan) in &plans {
+        outcome = upload_plan(&sftp, plan, parent, remote_dir, &mut ctx).await;
+        if outcome.is_err() {
+            break;
+        }
+    }
+    let (files_transferred, bytes_transferred) = (ctx.files_completed, ctx.bytes_completed);
+    let _ = sftp.close().await;
+    outcome?;
+
+    let plan = plans
+        .into_iter()
+        .fold(TransferPlan::default(), |mut merged, (_, plan)| {
+            merged.entries.extend(plan.entries);
+            merged.skipped.extend(plan.skipped);
+            merged
+        });
+    Ok(transfer_result(
+        transfer_id.to_string(),
+        TransferDirection::Upload,
+        false,
+        plan,
+        files_transferred,
+        bytes_transferred,
+    ))
+}
+
+pub(crate) async fn run_download_directory(
+    app: &AppHandle,
+    server_id: &str,
+    transfer_id: &str,
+    remote_path: &str,
+    local_path: &str,
+    options: &DirectoryTransferOptions,
+    control: Option<&TransferControl>,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let filter = TransferFilter::new(options)?;
+    let local_root = PathBuf::from(local_path);
+
+    let sftp = open_sftp(app, server_id).await?;
+    let plan = match plan_remote_tree(&sftp, remote_path, &filter, options.symlinks).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            let _ = sftp.close().await;
+            return Err(e.into());
+        }
+    };
+    if options.dry_run {
+        let _ = sftp.close().await;
+        return Ok(transfer_result(
+            transfer_id.to_string(),
+            TransferDirection::Download,
+            true,
+            plan,
+            0,
+            0,
+        ));
+    }
+
+    debug!(server_id, remote_path, local_path, "Downloading directory");
+
+    let mut ctx = TransferContext {
+        app,
+        transfer_id,
+        server_id,
+        total_bytes: plan.total_bytes(),
+        files_total: plan.file_count(),
+        files_completed: 0,
+        bytes_completed: 0,
+        verify_checksum: options.verify_checksum,
+        control,
+    };
+    let outcome = download_plan(&sftp, &plan, remote_path, &local_root, &mut ctx).await;
+    let (files_transferred, bytes_transferred) = (ctx.files_completed, ctx.bytes_completed);
+    let _ = sftp.close().await;
+    outcome?;
+
+    Ok(transfer_result(
+        transfer_id.to_string(),
+        TransferDirection::Download,
+        false,
+        plan,
+        files_transferred,
+        bytes_transferred,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_sync_directory(
+    app: &AppHandle,
+    server_id: &str,
+    transfer_id: &str,
+    local_path: &str,
+    remote_path: &str,
+    direction: TransferDirection,
+    delete: bool,
+    options: &SyncOptions,
+    control: Option<&TransferControl>,
+) -> Result<SyncResult, TransferError> {
+    let local_root = PathBuf::from(local_path);
+    let sftp = open_sftp(app, server_id).await?;
+    let result = run_sync(
+        app,
+        &sftp,
+        server_id,
+        transfer_id,
+        &local_root,
+        remote_path,
+        direction,
+        delete,
+        options,
+        control,
+    )
+    .await;
+    let _ = sftp.close().await;
+    result
+}
+
+#[tauri::command]
+pub async fn upload_directory(
+    app: AppHandle,
+    server_id: String,
+    local_path: String,
+    remote_path: String,
+    options: Option<DirectoryTransferOptions>,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let transfer_id = uuid::Uuid::new_v4().to_string();
+    run_upload_directory(
+        &app,
+        &server_id,
+        &transfer_id,
+        &local_path,
+        &remote_path,
+        &options.unwrap_or_default(),
+        None,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn download_directory(
+    app: AppHandle,
+    server_id: String,
+    remote_path: String,
+    local_path: String,
+    options: Option<DirectoryTransferOptions>,
+) -> Result<DirectoryTransferResult, TransferError> {
+    let transfer_id = uuid::Uuid::new_v4().to_string();
+    run_download_directory(
+        &app,
+        &server_id,
+        &transfer_id,
+        &remote_path,
+        &local_path,
+        &options.unwrap_or_default(),
+        None,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn sync_directory(
+    app: AppHandle,
+    server_id: String,
+    local_path: String,
+    remote_path: String,
+    direction: TransferDirection,
+    delete: bool,
+    options: Option<SyncOptions>,
+) -> Result<SyncResult, TransferError> {
+    let transfer_id = uuid::Uuid::new_v4().to_string();
+    run_sync_directory(
+        &app,
+        &server_id,
+        &transfer_id,
+        &local_path,
+        &remote_path,
+        direction,
+        delete,
+        &options.unwrap_or_default(),
+        None,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> TransferFilter {
+        TransferFilter::new(&DirectoryTransferOptions {
+            include: include.iter().map(|p| p.to_string()).collect(),
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            ..DirectoryTransferOptions::default()
+        })
+        .expect("patterns should compile")
+    }
+
+    fn temp_tree() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("ssh-thing-sftp-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("assets/img")).expect("create tree");
+        fs::create_dir_all(root.join("node_modules/pkg")).expect("create tree");
+        fs::write(root.join("index.html"), "<html>").expect("write file");
+        fs::write(root.join("debug.log"), "log").expect("write file");
+        fs::write(root.join("assets/site.css"), "body{}").expect("write file");
+        fs::write(root.join("assets/img/logo.png"), "png").expect("write file");
+        fs::write(root.join("node_modules/pkg/index.js"), "js").expect("write file");
+        root
+    }
+
+    #[test]
+    fn test_filter_matches_names_and_paths() {
+        let filter = filter(&[], &["*.log", "node_modules"]);
+
+        assert!(filter.is_excluded("debug.log"));
+        assert!(filter.is_excluded("nested/dir/app.log"));
+        assert!(filter.is_excluded("node_modules"));
+        assert!(!filter.is_excluded("index.html"));
+        assert!(filter.is_included("anything"));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        let result = TransferFilter::new(&DirectoryTransferOptions {
+            include: vec!["[".to_string()],
+            ..DirectoryTransferOptions::default()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_local_plan_applies_excludes() {
+        let root = temp_tree();
+        let plan = plan_local_tree(
+            &root,
+            &filter(&[], &["*.log", "node_modules"]),
+            SymlinkPolicy::Skip,
+        )
+        .expect("plan should build");
+        let paths: Vec<&str> = plan
+            .entries
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "assets",
+                "index.html",
+                "assets/img",
+                "assets/site.css",
+                "assets/img/logo.png"
+            ]
+        );
+        assert!(plan.skipped.contains(&"debug.log".to_string()));
+        assert!(plan.skipped.contains(&"node_modules".to_string()));
+        assert_eq!(plan.total_bytes(), 6 + 6 + 3);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_local_path_plan_keeps_own_name() {
+        let root = temp_tree();
+        let filter = filter(&[], &[]);
+
+        let file = plan_local_path(&root.join("index.html"), &filter).expect("plan should build");
+        assert_eq!(file.entries.len(), 1);
+        assert_eq!(file.entries[0].relative_path, "index.html");
+        assert_eq!(file.entries[0].kind, TransferEntryKind::File);
+
+        let dir = plan_local_path(&root.join("assets"), &filter).expect("plan should build");
+        let paths: Vec<&str> = dir
+            .entries
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "assets",
+                "assets/img",
+                "assets/site.css",
+                "assets/img/logo.png"
+            ]
+        );
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_local_plan_prunes_directories_without_included_files() {
+        let root = temp_tree();
+        let plan = plan_local_tree(&root, &filter(&["*.css"], &[]), SymlinkPolicy::Skip)
+            .expect("plan should build");
+        let paths: Vec<&str> = plan
+            .entries
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+
+        assert_eq!(paths, vec!["assets", "assets/site.css"]);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_remote_names_and_links_stay_inside_root() {
+        assert!(is_plain_name("notes.txt"));
+        for name in ["", ".", "..", "a/b", "..\\x"] {
+            assert!(!is_plain_name(name), "{:?}", name);
+        }
+
+        assert!(link_stays_inside("a/link", "../b"));
+        assert!(link_stays_inside("link", "a/./b/.."));
+        assert!(!link_stays_inside("link", "../outside"));
+        assert!(!link_stays_inside("a/link", "b/../../../etc"));
+        assert!(!link_stays_inside("link", "/etc/passwd"));
+    }
+
+    fn plan_entry(
+        path: &str,
+        kind: TransferEntryKind,
+        size: u64,
+        modified: u64,
+    ) -> TransferPlanEntry {
+        TransferPlanEntry {
+            relative_path: path.to_string(),
+            kind,
+            size,
+            link_target: None,
+            modified: Some(modified),
+        }
+    }
+
+    #[test]
+    fn test_diff_plans_detects_changes() {
+        let source = TransferPlan {
+            entries: vec![
+                plan_entry("assets", TransferEntryKind::Directory, 0, 0),
+                plan_entry("assets/app.css", TransferEntryKind::File, 10, 100),
+                plan_entry("index.html", TransferEntryKind::File, 20, 200),
+                plan_entry("new.html", TransferEntryKind::File, 5, 300),
+            ],
+            skipped: Vec::new(),
+        };
+        let destination = TransferPlan {
+            entries: vec![
+                plan_entry("assets", TransferEntryKind::Directory, 0, 0),
+                plan_entry("assets/app.css", TransferEntryKind::File, 10, 100),
+                plan_entry("index.html", TransferEntryKind::File, 20, 150),
+                plan_entry("old", TransferEntryKind::Directory, 0, 0),
+                plan_entry("old/page.html", TransferEntryKind::File, 1, 1),
+            ],
+            skipped: Vec::new(),
+        };
+
+        let (diff, unchanged) = diff_plans(&source, &destination, true, true);
+
+        assert_eq!(diff.added, vec!["new.html"]);
+        assert_eq!(diff.modified, vec!["index.html"]);
+        assert_eq!(diff.deleted, vec!["old/page.html", "old"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(unchanged, vec!["assets/app.css"]);
+
+        let plan = sync_transfer_plan(&source, &destination, &diff);
+        let paths: Vec<&str> = plan
+            .entries
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["index.html", "new.html"]);
+    }
+
+    #[test]
+    fn test_diff_plans_without_delete_or_mtime() {
+        let source = TransferPlan {
+            entries: vec![plan_entry("a.txt", TransferEntryKind::File, 3, 1)],
+            skipped: Vec::new(),
+        };
+        let destination = TransferPlan {
+            entries: vec![
+                plan_entry("a.txt", TransferEntryKind::File, 3, 2),
+                plan_entry("b.txt", TransferEntryKind::File, 3, 2),
+            ],
+            skipped: Vec::new(),
+        };
+
+        let (diff, unchanged) = diff_plans(&source, &destination, false, false);
+
+        assert!(diff.modified.is_empty());
+        assert!(diff.deleted.is_empty());
+        assert_eq!(unchanged, vec!["a.txt"]);
+    }
+
+    #[test]
+    fn test_hash_local_file() {
+        let path = std::env::temp_dir().join(format!("ssh-thing-hash-{}", uuid::Uuid::new_v4()));
+        fs::write(&path, "abc").expect("write file");
+
+        assert_eq!(
+            hash_local_file(&path).expect("hash should succeed"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let _ = fs::remove_file(path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_local_link_leaves_target_alone() {
+        let root = temp_tree();
+        let link = root.join("assets/linked.html");
+        std::os::unix::fs::symlink(root.join("index.html"), &link).expect("create link");
+
+        remove_local_link(&link).expect("remove link");
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(root.join("index.html").exists());
+        remove_local_link(&root.join("index.html")).expect("plain file is kept");
+        assert!(root.join("index.html").exists());
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_parse_checksum_output() {
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        assert_eq!(
+            parse_checksum_output(&format!("{}  /srv/app.tar\n", digest)).as_deref(),
+            Some(digest)
+        );
+        assert_eq!(parse_checksum_output("sha256sum: not found"), None);
+        assert_eq!(parse_checksum_output(""), None);
+    }
+
+    #[test]
+    fn test_transfer_error_serializes_with_type_tag() {
+        let error = TransferError::ChecksumMismatch {
+            path: "app.tar".to_string(),
+            local_sha256: "aa".to_string(),
+            remote_sha256: "bb".to_string(),
+        };
+        let value = serde_json::to_value(&error).expect("serialize error");
+
+        assert_eq!(value["type"], "ChecksumMismatch");
+        assert_eq!(value["path"], "app.tar");
+        assert_eq!(TransferError::from("boom".to_string()).to_string(), "boom");
+    }
+
+    #[test]
+    fn test_join_remote_handles_trailing_slash() {
+        assert_eq!(join_remote("/var/www", "a/b.txt"), "/var/www/a/b.txt");
+        assert_eq!(join_remote("/var/www/", "a"), "/var/www/a");
+        assert_eq!(join_remote("/var/www", ""), "/var/www");
+    }
+
+    #[test]
+    fn test_transfer_options_defaults() {
+        let options: DirectoryTransferOptions =
+            serde_json::from_str("{}").expect("Failed to deserialize");
+
+        assert!(options.include.is_empty());
+        assert!(options.exclude.is_empty());
+        assert_eq!(options.symlinks, SymlinkPolicy::Skip);
+        assert!(!options.dry_run);
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::event_bus::Emitter;
+use crate::shell_metadata::partial_prefix_start;
+use crate::{command_notify, AppState, ShellCommand};
+
+const OSC133_PREFIX: &str = "\x1b]133;";
+const MAX_PENDING_BYTES: usize = 4 * 1024;
+
+/// Hooks bash and zsh to print OSC 133 marks: `C` before each command runs
+/// and `D;<status>` when it finishes. The leading space keeps the line out
+/// of history for shells that ignore space-prefixed commands.
+const INTEGRATION_SCRIPT: &str = concat!(
+    r#" if [ -n "$ZSH_VERSION" ]; then autoload -Uz add-zsh-hook; "#,
+    r#"__st_precmd() { printf '\033]133;D;%s\007\033]133;A\007' "$?"; }; "#,
+    r#"__st_preexec() { printf '\033]133;C\007'; }; "#,
+    r#"add-zsh-hook precmd __st_precmd; add-zsh-hook preexec __st_preexec; "#,
+    r#"elif [ -n "$BASH_VERSION" ]; then __st_ran=1; "#,
+    r#"__st_prompt() { local s=$?; "#,
+    r#"printf '\033]133;D;%s\007\033]133;A\007' "$s"; __st_ran=; }; "#,
+    r#"trap '[ -n "$__st_ran" ] || { __st_ran=1; printf "\033]133;C\007"; }' DEBUG; "#,
+    r#"PROMPT_COMMAND="__st_prompt${PROMPT_COMMAND:+; $PROMPT_COMMAND}"; fi"#,
+    "\r"
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandMark {
+    Executed,
+    Finished { exit_code: Option<i32> },
+}
+
+/// Payload of `command-started`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandStarted {
+    pub connection_id: String,
+    pub server_id: String,
+    pub shell_id: String,
+    pub command: Option<String>,
+    pub started_at: u64,
+}
+
+/// Payload of `command-finished`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandFinished {
+    pub connection_id: String,
+    pub server_id: String,
+    pub shell_id: String,
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandActivity {
+    Started {
+        command: Option<String>,
+        started_at: u64,
+    },
+    Finished {
+        command: Option<String>,
+        exit_code: Option<i32>,
+        started_at: u64,
+        duration_ms: u64,
+    },
+}
+
+struct RunningCommand {
+    command: Option<String>,
+    started_at: u64,
+    started: Instant,
+}
+
+/// Follows OSC 133 marks in terminal output to tell when the foreground
+/// command starts and finishes. Marks split across chunks are held back
+/// until they complete, like OSC 7.
+#[derive(Default)]
+pub struct CommandTracker {
+    pending: String,
+    last_command: Option<String>,
+    running: Option<RunningCommand>,
+}
+
+impl CommandTracker {
+    /// The line most recently typed into the shell, reported as the
+    /// command when the next one starts.
+    pub fn note_command(&mut self, command: String) {
+        self.last_command = Some(command);
+    }
+
+    pub fn scan(&mut self, output: &str) -> Vec<CommandActivity> {
+        let mut activity = Vec::new();
+        for mark in self.scan_marks(output) {
+            match mark {
+                CommandMark::Executed => {
+                    let running = RunningCommand {
+                        command: self.last_command.take(),
+                        started_at: unix_now(),
+                        started: Instant::now(),
+                    };
+                    activity.push(CommandActivity::Started {
+                        command: running.command.clone(),
+                        started_at: running.started_at,
+                    });
+                    self.running = Some(running);
+                }
+                // A finish mark without a start is the first prompt.
+                CommandMark::Finished { exit_code } => {
+                    if let Some(running) = self.running.take() {
+                        activity.push(CommandActivity::Finished {
+                            command: running.command,
+                            exit_code,
+                            started_at: running.started_at,
+                            duration_ms: running.started.elapsed().as_millis() as u64,
+                        });
+                    }
+                }
+            }
+        }
+        activity
+    }
+
+    fn scan_marks(&mut self, output: &str) -> Vec<CommandMark> {
+        self.pending.push_str(output);
+        let mut marks = Vec::new();
+        let mut offset = 0;
+        let keep_from = loop {
+            let Some(start) = self.pending[offset..].find(OSC133_PREFIX) else {
+                break offset + partial_prefix_start(&self.pending[offset..], OSC133_PREFIX);
+            };
+            let body_start = offset + start + OSC133_PREFIX.len();
+            let Some(end) = self.pending[body_start..].find(['\x07', '\x1b']) else {
+                break offset + start;
+            };
+            marks.extend(parse_mark(&self.pending[body_start..body_start + end]));
+            offset = body_start + end + 1;
+        };
+
+        self.pending.drain(..keep_from);
+        if self.pending.len() > MAX_PENDING_BYTES {
+            self.pending.clear();
+        }
+        marks
+    }
+}
+
+fn parse_mark(body: &str) -> Option<CommandMark> {
+    let mut fields = body.split(';');
+    match fields.next()? {
+        "C" => Some(CommandMark::Executed),
+        "D" => Some(CommandMark::Finished {
+            exit_code: fields.next().and_then(|code| code.trim().parse().ok()),
+        }),
+        _ => None,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub(crate) fn emit_command_activity(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: &str,
+    shell_id: &str,
+    activity: CommandActivity,
+) {
+    match activity {
+        CommandActivity::Started {
+            command,
+            started_at,
+        } => {
+            let _ = app.emit(
+                "command-started",
+                CommandStarted {
+                    connection_id: connection_id.to_string(),
+                    server_id: server_id.to_string(),
+                    shell_id: shell_id.to_string(),
+                    command,
+                    started_at,
+                },
+            );
+        }
+        CommandActivity::Finished {
+            command,
+            exit_code,
+            started_at,
+            duration_ms,
+        } => {
+            let finished = CommandFinished {
+                connection_id: connection_id.to_string(),
+                server_id: server_id.to_string(),
+                shell_id: shell_id.to_string(),
+                command,
+                exit_code,
+                started_at,
+                duration_ms,
+            };
+            command_notify::on_command_finished(app, &finished);
+            let _ = app.emit("command-finished", finished);
+        }
+    }
+}
+
+/// Installs the OSC 133 hooks in a bash or zsh shell so it reports
+/// `command-started` and `command-finished`.
+#[tauri::command]
+pub async fn enable_shell_integration(app: AppHandle, shell_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let cmd_tx = {
+        let shells = state.shells.lock().await;
+        shells
+            .get(&shell_id)
+            .map(|shell| shell.cmd_tx.clone())
+            .ok_or_else(|| format!("Shell with id {} not found", shell_id))?
+    };
+    cmd_tx
+        .send(ShellCommand::Inject(INTEGRATION_SCRIPT.to_string()))
+        .await
+        .map_err(|e| format!("Failed to send input: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_tracker_pairs_split_marks() {
+        let mut tracker = CommandTracker::default();
+
+        assert!(tracker.scan("\x1b]133;D;0\x07\x1b]133;A\x07$ ").is_empty());
+        tracker.note_command("make build".to_string());
+        let started = tracker.scan("\x1b]133;C\x07compiling\r\n\x1b]13");
+        assert!(matches!(
+            started.as_slice(),
+            [CommandActivity::Started { command: Some(command), .. }] if command == "make build"
+        ));
+
+        let finished = tracker.scan("3;D;2\x1b\\\x1b]133;A\x07$ ");
+        assert!(matches!(
+            finished.as_slice(),
+            [CommandActivity::Finished {
+                exit_code: Some(2),
+                ..
+            }]
+        ));
+        assert!(tracker.scan("\x1b]133;D;0\x07").is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::event_bus::Emitter;
+use crate::inline_images::InlineImageProtocol;
+use crate::os_fingerprint::RemoteOs;
+use crate::resize::TerminalSize;
+use crate::{restore, AppState, PtyShell};
+
+const OSC7_PREFIX: &str = "\x1b]7;";
+const MAX_PENDING_BYTES: usize = 4 * 1024;
+
+/// What the UI shows for an open shell. Also the payload of `shell-metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShellInfo {
+    pub shell_id: String,
+    pub connection_id: String,
+    pub server_id: String,
+    pub label: Option<String>,
+    pub cwd: Option<String>,
+    pub last_command: Option<String>,
+    pub exit_status: Option<u32>,
+    /// Columns and rows, when known.
+    pub size: Option<TerminalSize>,
+    /// Image protocols seen in the output; the frontend renders these.
+    pub image_protocols: Vec<InlineImageProtocol>,
+    /// OS family and version of the server, for adapting commands to it.
+    pub os: Option<RemoteOs>,
+}
+
+impl From<&PtyShell> for ShellInfo {
+    fn from(shell: &PtyShell) -> Self {
+        Self {
+            shell_id: shell.id.clone(),
+            connection_id: shell.connection_id.clone(),
+            server_id: shell.server_id.clone(),
+            label: shell.label.clone(),
+            cwd: shell.cwd.clone(),
+            last_command: shell.last_command.clone(),
+            exit_status: shell.exit_status,
+            size: shell.size,
+            image_protocols: shell.image_protocols.clone(),
+            os: shell.os.clone(),
+        }
+    }
+}
+
+/// Watches terminal output for OSC 7 (`ESC ] 7 ; file://host/path BEL`),
+/// which shells with integration enabled print whenever the directory changes.
+/// Sequences split across chunks are held back until they complete.
+#[derive(Default)]
+pub struct Osc7Tracker {
+    pending: String,
+}
+
+impl Osc7Tracker {
+    /// Returns the last directory reported in `output`, if any.
+    pub fn scan(&mut self, output: &str) -> Option<String> {
+        self.pending.push_str(output);
+        let mut cwd = None;
+        let mut offset = 0;
+        let keep_from = loop {
+            let Some(start) = self.pending[offset..].find(OSC7_PREFIX) else {
+                break offset + partial_prefix_start(&self.pending[offset..], OSC7_PREFIX);
+            };
+            let body_start = offset + start + OSC7_PREFIX.len();
+            // Either BEL or the ESC of an ST ends the sequence.
+            let Some(end) = self.pending[body_start..].find(['\x07', '\x1b']) else {
+                break offset + start;
+            };
+            if let Some(path) = parse_osc7_url(&self.pending[body_start..body_start + end]) {
+                cwd = Some(path);
+            }
+            offset = body_start + end + 1;
+        };
+
+        self.pending.drain(..keep_from);
+        if self.pending.len() > MAX_PENDING_BYTES {
+            self.pending.clear();
+        }
+        cwd
+    }
+}
+
+/// Where a trailing, possibly incomplete, `prefix` starts.
+pub(crate) fn partial_prefix_start(text: &str, prefix: &str) -> usize {
+    (1..prefix.len())
+        .rev()
+        .find(|&len| text.ends_with(&prefix[..len]))
+        .map_or(text.len(), |len| text.len() - len)
+}
+
+fn parse_osc7_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    Some(percent_decode(path))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Applies `update` to a live shell and tells the UI when anything changed.
+pub(crate) async fn update_shell(
+    app: &AppHandle,
+    shell_id: &str,
+    update: impl FnOnce(&mut PtyShell),
+) -> Option<ShellInfo> {
+    let (info, changed) = {
+        let state = app.state::<AppState>();
+        let mut shells = state.shells.lock().await;
+        let shell = shells.get_mut(shell_id)?;
+        let before = ShellInfo::from(&*shell);
+        update(shell);
+        let after = ShellInfo::from(&*shell);
+        let changed = after != before;
+        (after, changed)
+    };
+
+    if changed {
+        let _ = app.emit("shell-metadata", info.clone());
+    }
+    Some(info)
+}
+
+#[tauri::command]
+pub async fn rename_shell(
+    app: AppHandle,
+    shell_id: String,
+    label: Option<String>,
+) -> Result<ShellInfo, String> {
+    let label = label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+    let info = update_shell(&app, &shell_id, |shell| shell.label = label.clone())
+        .await
+        .ok_or_else(|| format!("Shell with id {} not found", shell_id))?;
+    restore::set_saved_label(&app, &shell_id, label);
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn get_active_sessions(app: AppHandle) -> Result<Vec<ShellInfo>, String> {
+    let state = app.state::<AppState>();
+    let shells = state.shells.lock().await;
+    let mut sessions: Vec<ShellInfo> = shells.values().map(ShellInfo::from).collect();
+    sessions.sort_by(|a, b| (&a.connection_id, &a.shell_id).cmp(&(&b.connection_id, &b.shell_id)));
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc7_tracker_reads_split_sequences() {
+        let mut tracker = Osc7Tracker::default();
+
+        assert_eq!(
+            tracker.scan("$ cd docs\r\n\x1b]7;file://box/home/me/My%20Docs\x07$ "),
+            Some("/home/me/My Docs".to_string())
+        );
+        assert_eq!(tracker.scan("ls\r\n\x1b"), None);
+        assert_eq!(tracker.scan("]7;file://box/tmp"), None);
+        assert_eq!(tracker.scan("\x1b\\$ "), Some("/tmp".to_string()));
+        assert_eq!(tracker.scan("\x1b]0;title\x07"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+// Events about a shell that its viewers get a copy of.
+const MIRRORED_EVENTS: [&str; 4] = [
+    "terminal-output",
+    "shell-metadata",
+    "shell-exited",
+    "shell-closed",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellViewer {
+    pub viewer_id: String,
+    pub shell_id: String,
+    /// Output so far, to paint before mirrored output arrives.
+    pub scrollback: String,
+}
+
+/// Read-only views of shells. A viewer gets a copy of its shell's events
+/// under its own id, and input sent to that id is refused.
+#[derive(Default)]
+pub struct ShellMirrors {
+    /// Viewer ids by the shell they watch.
+    viewers: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl ShellMirrors {
+    fn viewers(&self) -> MutexGuard<'_, HashMap<String, Vec<String>>> {
+        self.viewers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_viewer(&self, id: &str) -> bool {
+        self.viewers()
+            .values()
+            .any(|viewers| viewers.iter().any(|viewer| viewer == id))
+    }
+
+    /// Copies of `payload` for each viewer of the shell it belongs to, with
+    /// `shell_id` swapped for the viewer's id and `mirror_of` set to the
+    /// shell's.
+    pub(crate) fn mirror(&self, event: &str, payload: &Value) -> Vec<Value> {
+        if !MIRRORED_EVENTS.contains(&event) {
+            return Vec::new();
+        }
+        let Some(shell_id) = payload.get("shell_id").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        let viewers = self.viewers();
+        let Some(viewer_ids) = viewers.get(shell_id) else {
+            return Vec::new();
+        };
+        viewer_ids
+            .iter()
+            .map(|viewer_id| {
+                let mut copy = payload.clone();
+                if let Value::Object(fields) = &mut copy {
+                    fields.insert("shell_id".to_string(), Value::from(viewer_id.as_str()));
+                    fields.insert("mirror_of".to_string(), Value::from(shell_id));
+                }
+                copy
+            })
+            .collect()
+    }
+}
+
+/// Refuses input for viewer ids.
+pub(crate) fn ensure_writable(app: &AppHandle, shell_id: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if state.shell_mirrors.is_viewer(shell_id) {
+        return Err(format!("Shell {} is a read-only view", shell_id));
+    }
+    Ok(())
+}
+
+/// Drops the viewers of shells that are gone for good.
+pub(crate) fn forget_shells(app: &AppHandle, shell_ids: &[String]) {
+    let state = app.state::<AppState>();
+    let mut viewers = state.shell_mirrors.viewers();
+    for shell_id in shell_ids {
+        viewers.remove(shell_id);
+    }
+}
+
+/// Opens a read-only view of a shell. The viewer keeps following the shell
+/// across reconnects until it is closed or the connection is disconnected.
+#[tauri::command]
+pub async fn open_shell_viewer(app: AppHandle, shell_id: String) -> Result<ShellViewer, String> {
+    let state = app.state::<AppState>();
+    if !state.shells.lock().await.contains_key(&shell_id) {
+        return Err(format!("Shell with id {} not found", shell_id));
+    }
+    let scrollback = {
+        let scrollback = state.scrollback.lock().await;
+        match scrollback.get(&shell_id) {
+            Some(buffer) => buffer
+                .lock()
+                .map_err(|_| "Scrollback buffer is unavailable".to_string())?
+                .contents(),
+            None => String::new(),
+        }
+    };
+
+    let viewer_id = uuid::Uuid::new_v4().to_string();
+    state
+        .shell_mirrors
+        .viewers()
+        .entry(shell_id.clone())
+        .or_default()
+        .push(viewer_id.clone());
+    Ok(ShellViewer {
+        viewer_id,
+        shell_id,
+        scrollback,
+    })
+}
+
+#[tauri::command]
+pub async fn close_shell_viewer(app: AppHandle, viewer_id: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut viewers = state.shell_mirrors.viewers();
+    let mut found = false;
+    viewers.retain(|_, viewer_ids| {
+        let before = viewer_ids.len();
+        viewer_ids.retain(|id| *id != viewer_id);
+        found |= viewer_ids.len() != before;
+        !viewer_ids.is_empty()
+    });
+    if !found {
+        return Err(format!("Viewer with id {} not found", viewer_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_copies_shell_events_to_viewers() {
+        let mirrors = ShellMirrors::default();
+        mirrors
+            .viewers()
+            .insert("shell-1".to_string(), vec!["viewer-1".to_string()]);
+        let output = serde_json::json!({ "shell_id": "shell-1", "output": "ls\r\n" });
+
+        let copies = mirrors.mirror("terminal-output", &output);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0]["shell_id"], "viewer-1");
+        assert_eq!(copies[0]["mirror_of"], "shell-1");
+        assert_eq!(copies[0]["output"], "ls\r\n");
+
+        assert!(mirrors.mirror("paste-warning", &output).is_empty());
+        assert!(mirrors
+            .mirror(
+                "terminal-output",
+                &serde_json::json!({ "shell_id": "shell-2" })
+            )
+            .is_empty());
+        assert!(mirrors.is_viewer("viewer-1"));
+        assert!(!mirrors.is_viewer("shell-1"));
+    }
+}
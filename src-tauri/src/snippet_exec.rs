@@ -0,0 +1,168 @@
+use russh::ChannelMsg;
+use std::time::Instant;
+use tauri::AppHandle;
+use tokio::time::{timeout, Duration};
+
+use crate::remote::{shell_quote, RemoteCommandOutput};
+use crate::{
+    get_app_dir, get_secret, identities, load_snippets, open_server_channel, saved_server,
+    AuthMethod, SecretKind,
+};
+
+// Set as SUDO_PROMPT so the prompt can be told apart from command output.
+const SUDO_PROMPT_MARKER: &str = "[ssh-thing:sudo-password]";
+const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+// Long enough for a package upgrade; a sudo snippet has no terminal to
+// interrupt it from.
+const SNIPPET_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+fn sudo_command(command: &str) -> String {
+    format!(
+        "export SUDO_PROMPT={}; {}",
+        shell_quote(SUDO_PROMPT_MARKER),
+        command
+    )
+}
+
+/// Whether the output so far ends in a sudo prompt, given the next chunk.
+/// `tail` keeps the end of the output between chunks so a prompt split
+/// across them is still seen. Only a prompt at the very end counts, since
+/// sudo then waits for input; the marker elsewhere is just output, such as
+/// from `env`.
+fn ends_at_prompt(tail: &mut String, chunk: &str) -> bool {
+    tail.push_str(chunk);
+    let at_prompt = tail.ends_with(SUDO_PROMPT_MARKER);
+    let mut start = tail.len().saturating_sub(SUDO_PROMPT_MARKER.len());
+    while !tail.is_char_boundary(start) {
+        start -= 1;
+    }
+    tail.drain(..start);
+    at_prompt
+}
+
+fn sudo_password(app: &AppHandle, server_id: &str) -> Result<String, String> {
+    let server = saved_server(app, server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    let server = identities::with_identity(app, &server)?;
+    match &server.auth {
+        AuthMethod::SecretRef {
+            secret_id,
+            kind: SecretKind::Password,
+        } => get_secret(app, secret_id),
+        AuthMethod::Password { password } => Ok(password.clone()),
+        _ => Err("Running snippets with sudo needs a saved password for this server".to_string()),
+    }
+}
+
+/// Runs a snippet over the server's active session. Snippets marked
+/// `requires_sudo` get a terminal so sudo can ask, and the first sudo prompt
+/// is answered with the server's saved password. The password is written to
+/// the channel only; it never appears in the returned output or the logs.
+#[tauri::command]
+pub async fn run_snippet(
+    app: AppHandle,
+    server_id: String,
+    snippet_id: String,
+) -> Result<RemoteCommandOutput, String> {
+    let snippet = load_snippets(&get_app_dir(&app)?)?
+        .into_iter()
+        .find(|snippet| snippet.id == snippet_id)
+        .ok_or_else(|| format!("Snippet with id {} not found", snippet_id))?;
+    if !snippet.requires_sudo {
+        return crate::remote::run_remote_command(&app, &server_id, &snippet.command).await;
+    }
+
+    let password = sudo_password(&app, &server_id)?;
+    let mut channel = open_server_channel(&app, &server_id).await?;
+    let started = Instant::now();
+    channel
+        .request_pty(false, "dumb", 200, 50, 0, 0, &[])
+        .await
+        .map_err(|e| format!("Failed to allocate a terminal for sudo: {}", e))?;
+    channel
+        .exec(true, sudo_command(&snippet.command))
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let mut stdout = String::new();
+    let mut tail = String::new();
+    let mut exit_code = None;
+    let mut answered = false;
+    let run = async {
+        while let Some(message) = channel.wait().await {
+            match message {
+                ChannelMsg::Data { data } => {
+                    let chunk = String::from_utf8_lossy(&data);
+                    // Kept scanning past the output cap so a late prompt is
+                    // still answered.
+                    if stdout.len() < MAX_OUTPUT_BYTES {
+                        stdout.push_str(&chunk);
+                    }
+                    if !ends_at_prompt(&mut tail, &chunk) {
+                        continue;
+                    }
+                    if let Some(len) = stdout
+                        .strip_suffix(SUDO_PROMPT_MARKER)
+                        .map(|output| output.len())
+                    {
+                        stdout.truncate(len);
+                    }
+                    // A second prompt means the saved password was rejected;
+                    // answering again would only burn another attempt.
+                    if answered {
+                        return Err("sudo did not accept the server's saved password".to_string());
+                    }
+                    answered = true;
+                    channel
+                        .data(format!("{}\n", password).as_bytes())
+                        .await
+                        .map_err(|_| "Failed to send the sudo password".to_string())?;
+                }
+                ChannelMsg::ExitStatus { exit_status } => exit_code = Some(exit_status),
+                ChannelMsg::Failure => {
+                    return Err("Remote command request failed".to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    };
+    let result = timeout(SNIPPET_TIMEOUT, run).await.unwrap_or_else(|_| {
+        Err(format!(
+            "Snippet did not finish within {} minutes",
+            SNIPPET_TIMEOUT.as_secs() / 60
+        ))
+    });
+    if let Err(e) = result {
+        let _ = channel.close().await;
+        return Err(e);
+    }
+
+    // With a terminal, stderr arrives interleaved with stdout.
+    Ok(RemoteCommandOutput {
+        stdout,
+        stderr: String::new(),
+        exit_code,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ends_at_prompt_only_for_a_trailing_prompt() {
+        let mut tail = String::new();
+        assert!(!ends_at_prompt(&mut tail, "updating\n[ssh-thing:sudo"));
+        assert!(ends_at_prompt(&mut tail, "-password]"));
+
+        let mut tail = String::new();
+        assert!(!ends_at_prompt(
+            &mut tail,
+            "SUDO_PROMPT=[ssh-thing:sudo-password]\nHOME=/root\n"
+        ));
+        assert!(tail.len() <= SUDO_PROMPT_MARKER.len());
+        assert!(ends_at_prompt(&mut tail, "é[ssh-thing:sudo-password]"));
+    }
+}
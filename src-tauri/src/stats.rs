@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::net::TrafficCounters;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::time::{interval, timeout, Duration, MissedTickBehavior};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{AppState, SshSession};
+
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+const RTT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// Cancelling a forward that was never requested costs the server nothing and
+// always gets a reply, which makes it a cheap round-trip probe.
+const RTT_PROBE_ADDRESS: &str = "ssh-thing-rtt-probe";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionStats {
+    pub connection_id: String,
+    pub server_id: String,
+    pub connected_at: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub bytes_in_per_sec: u64,
+    pub bytes_out_per_sec: u64,
+    #[serde(default)]
+    pub rtt_ms: Option<u64>,
+}
+
+struct TrackedSession {
+    counters: Arc<TrafficCounters>,
+    stats: SessionStats,
+}
+
+#[derive(Default)]
+pub struct SessionStatsRegistry {
+    sessions: Mutex<HashMap<String, TrackedSession>>,
+}
+
+impl SessionStatsRegistry {
+    fn is_current(&self, connection_id: &str, counters: &Arc<TrafficCounters>) -> bool {
+        self.sessions.lock().is_ok_and(|sessions| {
+            sessions
+                .get(connection_id)
+                .is_some_and(|tracked| Arc::ptr_eq(&tracked.counters, counters))
+        })
+    }
+
+    fn remove(&self, connection_id: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(connection_id);
+        }
+    }
+}
+
+fn per_second(current: u64, previous: u64, elapsed: Duration) -> u64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds <= 0.0 {
+        return 0;
+    }
+    (current.saturating_sub(previous) as f64 / seconds).round() as u64
+}
+
+/// Milliseconds for a request to reach the server and come back, or `None`
+/// when it gets no reply in time.
+pub(crate) async fn round_trip(handle: &SshSession) -> Option<u64> {
+    let started = Instant::now();
+    match timeout(
+        RTT_PROBE_TIMEOUT,
+        handle.cancel_tcpip_forward(RTT_PROBE_ADDRESS, 0),
+    )
+    .await
+    {
+        Ok(Ok(())) | Ok(Err(russh::Error::RequestDenied)) => {
+            Some(started.elapsed().as_millis() as u64)
+        }
+        _ => None,
+    }
+}
+
+// Returns None when the session is not registered yet or already closed.
+async fn measure_rtt(app: &AppHandle, connection_id: &str) -> Option<Option<u64>> {
+    let handle = {
+        let state = app.state::<AppState>();
+        let sessions = state.sessions.lock().await;
+        sessions.get(connection_id)?.handle.clone()
+    };
+    if handle.is_closed() {
+        return None;
+    }
+    Some(round_trip(&handle).await)
+}
+
+async fn monitor_session(app: AppHandle, connection_id: String, counters: Arc<TrafficCounters>) {
+    let mut ticker = interval(STATS_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker.tick().await;
+
+    let mut last_tick = Instant::now();
+    let mut last_in = counters.bytes_in();
+    let mut last_out = counters.bytes_out();
+
+    loop {
+        ticker.tick().await;
+        // A reconnect under the same connection id registers new counters;
+        // this monitor then belongs to a session that is gone.
+        if !app
+            .state::<AppState>()
+            .session_stats
+            .is_current(&connection_id, &counters)
+        {
+            break;
+        }
+
+        let rtt_ms = match measure_rtt(&app, &connection_id).await {
+            Some(rtt_ms) => rtt_ms,
+            // The session was not inserted yet or has already closed.
+            None => {
+                let state = app.state::<AppState>();
+                let sessions = state.sessions.lock().await;
+                if sessions
+                    .get(&connection_id)
+                    .is_some_and(|session| session.handle.is_closed())
+                {
+                    drop(sessions);
+                    untrack_session(&app, &connection_id);
+                    break;
+                }
+                None
+            }
+        };
+
+        let now = Instant::now();
+        let bytes_in = counters.bytes_in();
+        let bytes_out = counters.bytes_out();
+        let elapsed = now.duration_since(last_tick);
+
+        let stats = {
+            let state = app.state::<AppState>();
+            let Ok(mut sessions) = state.session_stats.sessions.lock() else {
+                break;
+            };
+            let Some(tracked) = sessions.get_mut(&connection_id) else {
+                break;
+            };
+            tracked.stats.bytes_in = bytes_in;
+            tracked.stats.bytes_out = bytes_out;
+            tracked.stats.bytes_in_per_sec = per_second(bytes_in, last_in, elapsed);
+            tracked.stats.bytes_out_per_sec = per_second(bytes_out, last_out, elapsed);
+            tracked.stats.rtt_ms = rtt_ms;
+            tracked.stats.clone()
+        };
+        let _ = app.emit("session-stats", stats);
+
+        last_tick = now;
+        last_in = bytes_in;
+        last_out = bytes_out;
+    }
+
+    debug!(connection_id, "Session stats monitor stopped");
+}
+
+pub fn track_session(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: Option<&str>,
+    counters: Arc<TrafficCounters>,
+) {
+    let connected_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let tracked = TrackedSession {
+        counters: counters.clone(),
+        stats: SessionStats {
+            connection_id: connection_id.to_string(),
+            server_id: server_id.unwrap_or_default().to_string(),
+            connected_at,
+            bytes_in: counters.bytes_in(),
+            bytes_out: counters.bytes_out(),
+            bytes_in_per_sec: 0,
+            bytes_out_per_sec: 0,
+            rtt_ms: None,
+        },
+    };
+
+    {
+        let state = app.state::<AppState>();
+        let Ok(mut sessions) = state.session_stats.sessions.lock() else {
+            return;
+        };
+        sessions.insert(connection_id.to_string(), tracked);
+    }
+
+    tokio::spawn(monitor_session(
+        app.clone(),
+        connection_id.to_string(),
+        counters,
+    ));
+}
+
+/// The address a tracked session's TCP connection went to.
+pub fn peer_addr(app: &AppHandle, connection_id: &str) -> Option<SocketAddr> {
+    let state = app.state::<AppState>();
+    let sessions = state.session_stats.sessions.lock().ok()?;
+    sessions.get(connection_id)?.counters.peer_addr()
+}
+
+pub fn untrack_session(app: &AppHandle, connection_id: &str) {
+    app.state::<AppState>().session_stats.remove(connection_id);
+}
+
+#[tauri::command]
+pub async fn get_session_stats(
+    app: AppHandle,
+    server_id: String,
+) -> Result<Vec<SessionStats>, String> {
+    let state = app.state::<AppState>();
+    let sessions = state
+        .session_stats
+        .sessions
+        .lock()
+        .map_err(|_| "Session stats are unavailable".to_string())?;
+    Ok(sessions
+        .values()
+        .filter(|tracked| tracked.stats.server_id == server_id)
+        .map(|tracked| {
+            let mut stats = tracked.stats.clone();
+            stats.bytes_in = tracked.counters.bytes_in();
+            stats.bytes_out = tracked.counters.bytes_out();
+            stats
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_second_rate() {
+        assert_eq!(per_second(3000, 1000, Duration::from_secs(2)), 1000);
+        assert_eq!(per_second(1000, 1000, Duration::from_secs(5)), 0);
+        assert_eq!(per_second(10, 0, Duration::ZERO), 0);
+    }
+}
@@ -0,0 +1,264 @@
+use std::io::{self, Read};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{mpsc, oneshot};
+
+#[cfg(debug_assertions)]
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::history::InputLineTracker;
+use crate::inline_images::InlineImageDetector;
+use crate::output_decoder::OutputDecoder;
+use crate::paste::BracketedPasteTracker;
+use crate::resize::{ResizeDebouncer, TerminalSize};
+use crate::shell_integration::{self, CommandTracker};
+use crate::shell_metadata::{self, Osc7Tracker};
+use crate::{
+    cleanup_shell, emit_connection_state, record_shell_exit, scrollback, settings, AppState,
+    ConnectionState, PtyShell, ShellCommand, TerminalOutput,
+};
+
+/// The write side of a non-SSH terminal (local PTY, serial port, ...).
+pub(crate) trait ShellBackend: Send + 'static {
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+
+    fn resize(&mut self, _size: TerminalSize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self);
+
+    /// Printed once the output side has ended.
+    fn ended_message(&self, exit_status: Option<u32>) -> String;
+}
+
+/// Reads `reader` on its own thread, since these reads block, and hands the
+/// chunks to the shell task. The channel closes on EOF or the first error
+/// other than a timeout.
+pub(crate) fn spawn_reader(mut reader: Box<dyn Read + Send>) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel(64);
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    if tx.is_closed() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// Pumps `output` as `terminal-output` events until it closes or the shell is
+/// told to close. The caller puts the returned shell in the `shells` map.
+pub(crate) async fn spawn_stream_shell<B: ShellBackend>(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: &str,
+    shell_id: Option<&str>,
+    mut output: mpsc::Receiver<Vec<u8>>,
+    exit: Option<oneshot::Receiver<u32>>,
+    mut backend: B,
+) -> Result<PtyShell, String> {
+    let (cmd_tx, mut cmd_rx) = mpsc::channel::<ShellCommand>(100);
+    let superseded = Arc::new(AtomicBool::new(false));
+    let shell_id = shell_id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let defaults = settings::connection_defaults(app);
+    let scrollback_for_task = {
+        let state = app.state::<AppState>();
+        let mut scrollback = state.scrollback.lock().await;
+        scrollback
+            .entry(shell_id.clone())
+            .or_insert_with(|| {
+                scrollback::shared(defaults.scrollback_bytes, defaults.scrollback_spill_bytes)
+            })
+            .clone()
+    };
+    let app_for_task = app.clone();
+    let connection_id_for_task = connection_id.to_string();
+    let server_id_for_task = server_id.to_string();
+    let shell_id_for_task = shell_id.clone();
+    let superseded_for_task = superseded.clone();
+
+    emit_connection_state(
+        app,
+        Some(connection_id),
+        Some(server_id),
+        Some(&shell_id),
+        ConnectionState::Connected,
+    )?;
+
+    tokio::spawn(async move {
+        let emit_output = |output: String| {
+            let payload = TerminalOutput {
+                connection_id: Some(connection_id_for_task.clone()),
+                server_id: Some(server_id_for_task.clone()),
+                shell_id: shell_id_for_task.clone(),
+                output,
+            };
+            scrollback::push_output(&scrollback_for_task, &payload.output);
+            let _ = app_for_task.emit("terminal-output", payload);
+        };
+
+        let mut osc7_tracker = Osc7Tracker::default();
+        let mut input_tracker = InputLineTracker::default();
+        let mut command_tracker = CommandTracker::default();
+        let mut resize_debouncer = ResizeDebouncer::new(None);
+        let mut output_decoder = OutputDecoder::default();
+        let mut image_detector = InlineImageDetector::default();
+        let mut paste_tracker = BracketedPasteTracker::default();
+
+        loop {
+            tokio::select! {
+                chunk = output.recv() => {
+                    let Some(chunk) = chunk else {
+                        let exit_status = match exit {
+                            Some(exit) => exit.await.ok(),
+                            None => None,
+                        };
+                        let rest = output_decoder.finish(&[]);
+                        if !rest.is_empty() {
+                            emit_output(rest);
+                        }
+                        emit_output(backend.ended_message(exit_status));
+                        if let Some(exit_status) = exit_status {
+                            record_shell_exit(
+                                &app_for_task,
+                                &connection_id_for_task,
+                                &server_id_for_task,
+                                &shell_id_for_task,
+                                exit_status,
+                            )
+                            .await;
+                        }
+                        break;
+                    };
+                    let output = output_decoder.decode(&chunk);
+                    if output.is_empty() {
+                        continue;
+                    }
+                    if let Some(cwd) = osc7_tracker.scan(&output) {
+                        shell_metadata::update_shell(&app_for_task, &shell_id_for_task, |shell| {
+                            shell.cwd = Some(cwd)
+                        })
+                        .await;
+                    }
+                    let protocols = image_detector.scan(&output);
+                    if !protocols.is_empty() {
+                        shell_metadata::update_shell(&app_for_task, &shell_id_for_task, |shell| {
+                            shell.image_protocols.extend(protocols)
+                        })
+                        .await;
+                    }
+                    if let Some(enabled) = paste_tracker.scan(&output) {
+                        shell_metadata::update_shell(&app_for_task, &shell_id_for_task, |shell| {
+                            shell.bracketed_paste = enabled
+                        })
+                        .await;
+                    }
+                    let activity = command_tracker.scan(&output);
+                    emit_output(output);
+                    for activity in activity {
+                        shell_integration::emit_command_activity(
+                            &app_for_task,
+                            &connection_id_for_task,
+                            &server_id_for_task,
+                            &shell_id_for_task,
+                            activity,
+                        );
+                    }
+                }
+                _ = resize_debouncer.due(), if resize_debouncer.is_pending() => {
+                    let Some(size) = resize_debouncer.take() else {
+                        continue;
+                    };
+                    if let Err(_e) = backend.resize(size) {
+                        #[cfg(debug_assertions)]
+                        debug!(
+                            shell_id = %shell_id_for_task,
+                            width = size.width,
+                            height = size.height,
+                            error = %_e,
+                            "Failed to resize shell"
+                        );
+                    }
+                }
+                cmd = cmd_rx.recv() => {
+                    match cmd {
+                        Some(ShellCommand::SendInput(input)) => {
+                            if let Some(command) = input_tracker.push(&input).pop() {
+                                command_tracker.note_command(command);
+                            }
+                            if let Err(e) = backend.write(input.as_bytes()) {
+                                emit_output(format!("\r\nFailed to send input: {}\r\n", e));
+                            }
+                        }
+                        Some(ShellCommand::Inject(input)) => {
+                            let _ = backend.write(input.as_bytes());
+                        }
+                        Some(ShellCommand::Resize(size)) => {
+                            resize_debouncer.request(size);
+                        }
+                        // Transfers and triggers are tied to SSH shells.
+                        Some(ShellCommand::ZmodemSend(_))
+                        | Some(ShellCommand::ZmodemCancel)
+                        | Some(ShellCommand::SetTriggers(_)) => {}
+                        Some(ShellCommand::Close) | None => {
+                            backend.close();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        drop(cmd_rx);
+        if !cleanup_shell(
+            &app_for_task,
+            &connection_id_for_task,
+            &server_id_for_task,
+            &shell_id_for_task,
+            &superseded_for_task,
+        )
+        .await
+        {
+            return;
+        }
+        let _ = emit_connection_state(
+            &app_for_task,
+            Some(connection_id_for_task.as_str()),
+            Some(server_id_for_task.as_str()),
+            Some(shell_id_for_task.as_str()),
+            ConnectionState::Disconnected,
+        );
+    });
+
+    Ok(PtyShell {
+        id: shell_id,
+        connection_id: connection_id.to_string(),
+        server_id: server_id.to_string(),
+        exit_status: None,
+        label: None,
+        cwd: None,
+        last_command: None,
+        size: None,
+        image_protocols: Vec::new(),
+        bracketed_paste: false,
+        os: None,
+        cmd_tx,
+        superseded,
+    })
+}
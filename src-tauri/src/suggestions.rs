@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+use crate::actions::{load_action_history, ActionHistoryEntry};
+use crate::history::{load_command_history, CommandHistoryEntry};
+use crate::{get_app_dir, load_snippets, Snippet};
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionSource {
+    Snippet,
+    History,
+    Action,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandSuggestion {
+    pub command: String,
+    /// The snippet name, for snippet suggestions.
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub snippet_id: Option<String>,
+    pub source: SuggestionSource,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+}
+
+/// How well `text` matches `prefix`: a prefix of the whole text beats a
+/// prefix of one of its words, which beats a plain substring.
+fn match_rank(text: &str, prefix: &str) -> Option<u8> {
+    if prefix.is_empty() {
+        return Some(0);
+    }
+    let text = text.to_lowercase();
+    if text.starts_with(prefix) {
+        return Some(3);
+    }
+    let word_start = text
+        .match_indices(prefix)
+        .any(|(index, _)| text[..index].ends_with([' ', '/', '-', '_', '.']));
+    if word_start {
+        Some(2)
+    } else if text.contains(prefix) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Merges snippets with the server's typed and executed commands, best
+/// match first. Snippets win ties; commands are otherwise ordered by how
+/// recently they ran, and each command appears once.
+pub fn rank_suggestions(
+    snippets: &[Snippet],
+    history: &[CommandHistoryEntry],
+    actions: &[ActionHistoryEntry],
+    server_id: &str,
+    prefix: &str,
+    limit: usize,
+) -> Vec<CommandSuggestion> {
+    let prefix = prefix.trim().to_lowercase();
+    let mut ranked: Vec<(u8, CommandSuggestion)> = Vec::new();
+
+    for snippet in snippets {
+        let rank = match_rank(&snippet.name, &prefix).max(match_rank(&snippet.command, &prefix));
+        if let Some(rank) = rank {
+            ranked.push((
+                rank,
+                CommandSuggestion {
+                    command: snippet.command.clone(),
+                    label: Some(snippet.name.clone()),
+                    snippet_id: Some(snippet.id.clone()),
+                    source: SuggestionSource::Snippet,
+                    last_used_at: None,
+                },
+            ));
+        }
+    }
+
+    let typed = history
+        .iter()
+        .filter(|entry| entry.server_id == server_id)
+        .map(|entry| (&entry.command, entry.executed_at, SuggestionSource::History));
+    let executed = actions
+        .iter()
+        .filter(|entry| entry.server_id == server_id)
+        .map(|entry| (&entry.command, entry.completed_at, SuggestionSource::Action));
+    for (command, used_at, source) in typed.chain(executed) {
+        if let Some(rank) = match_rank(command, &prefix) {
+            ranked.push((
+                rank,
+                CommandSuggestion {
+                    command: command.clone(),
+                    label: None,
+                    snippet_id: None,
+                    source,
+                    last_used_at: Some(used_at),
+                },
+            ));
+        }
+    }
+
+    ranked.sort_by_key(|(rank, suggestion)| {
+        (
+            Reverse(*rank),
+            suggestion.source != SuggestionSource::Snippet,
+            Reverse(suggestion.last_used_at),
+        )
+    });
+
+    let mut seen = HashSet::new();
+    ranked
+        .into_iter()
+        .map(|(_, suggestion)| suggestion)
+        .filter(|suggestion| seen.insert(suggestion.command.clone()))
+        .take(limit)
+        .collect()
+}
+
+/// Top command palette matches for `prefix` on one server, so the full
+/// history never has to be sent to the frontend.
+#[tauri::command]
+pub async fn suggest_commands(
+    app: AppHandle,
+    server_id: String,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<CommandSuggestion>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let snippets = load_snippets(&app_dir)?;
+    let history = load_command_history(&app_dir)?;
+    let actions = load_action_history(&app_dir)?;
+    Ok(rank_suggestions(
+        &snippets,
+        &history,
+        &actions,
+        &server_id,
+        &prefix,
+        limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(server_id: &str, command: &str, executed_at: u64) -> CommandHistoryEntry {
+        CommandHistoryEntry {
+            server_id: server_id.to_string(),
+            command: command.to_string(),
+            executed_at,
+        }
+    }
+
+    #[test]
+    fn test_rank_suggestions_orders_by_match_then_source_then_recency() {
+        let snippets = vec![Snippet {
+            id: "snippet-1".to_string(),
+            name: "Restart nginx".to_string(),
+            command: "sudo systemctl restart nginx".to_string(),
+            description: None,
+            requires_sudo: true,
+        }];
+        let history = vec![
+            typed("a", "systemctl status nginx", 1),
+            typed("a", "tail -f /var/log/nginx/error.log", 2),
+            typed("a", "systemctl status nginx", 3),
+            typed("b", "nginx -t", 4),
+        ];
+
+        let found = rank_suggestions(&snippets, &history, &[], "a", "nginx", 10);
+        let commands: Vec<&str> = found.iter().map(|s| s.command.as_str()).collect();
+
+        assert_eq!(
+            commands,
+            vec![
+                "sudo systemctl restart nginx",
+                "systemctl status nginx",
+                "tail -f /var/log/nginx/error.log",
+            ]
+        );
+        assert_eq!(found[1].last_used_at, Some(3));
+        assert!(rank_suggestions(&snippets, &history, &[], "a", "docker", 10).is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use ssh_thing_core::net::tailscale_cli;
+use std::collections::HashMap;
+use std::io;
+use std::net::Ipv4Addr;
+use tauri::AppHandle;
+
+use crate::cloud::{
+    apply_discovery, CloudSource, CloudSyncResult, DiscoveredServer, DEFAULT_SSH_PORT,
+};
+
+pub const TAILSCALE_PROVIDER: &str = "tailscale";
+const DEFAULT_TAILSCALE_USER: &str = "root";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StatusJson {
+    #[serde(default)]
+    backend_state: String,
+    #[serde(default, rename = "Self")]
+    self_node: Option<PeerJson>,
+    #[serde(default)]
+    peer: HashMap<String, PeerJson>,
+    #[serde(default)]
+    current_tailnet: Option<TailnetJson>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PeerJson {
+    #[serde(default, rename = "ID")]
+    id: String,
+    #[serde(default)]
+    host_name: String,
+    #[serde(default, rename = "DNSName")]
+    dns_name: String,
+    #[serde(default, rename = "TailscaleIPs")]
+    tailscale_ips: Vec<String>,
+    #[serde(default, rename = "OS")]
+    os: String,
+    #[serde(default)]
+    online: bool,
+    /// Only present for nodes running Tailscale SSH.
+    #[serde(default, rename = "sshHostKeys")]
+    ssh_host_keys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TailnetJson {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailscaleStatus {
+    pub installed: bool,
+    pub running: bool,
+    #[serde(default)]
+    pub tailnet: Option<String>,
+    #[serde(default)]
+    pub self_ips: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TailscalePeer {
+    pub id: String,
+    pub host_name: String,
+    pub dns_name: String,
+    pub ips: Vec<String>,
+    pub os: String,
+    pub online: bool,
+    pub ssh_enabled: bool,
+}
+
+async fn load_status() -> io::Result<StatusJson> {
+    let stdout = tailscale_cli(&["status", "--json"]).await?;
+    serde_json::from_slice(&stdout).map_err(io::Error::other)
+}
+
+fn peer_from(peer: &PeerJson) -> TailscalePeer {
+    TailscalePeer {
+        id: peer.id.clone(),
+        host_name: peer.host_name.clone(),
+        dns_name: peer.dns_name.trim_end_matches('.').to_string(),
+        ips: peer.tailscale_ips.clone(),
+        os: peer.os.clone(),
+        online: peer.online,
+        ssh_enabled: peer
+            .ssh_host_keys
+            .as_ref()
+            .is_some_and(|keys| !keys.is_empty()),
+    }
+}
+
+fn list_peers(status: &StatusJson, ssh_only: bool) -> Vec<TailscalePeer> {
+    let mut peers: Vec<TailscalePeer> = status
+        .peer
+        .values()
+        .map(peer_from)
+        .filter(|peer| !ssh_only || peer.ssh_enabled)
+        .collect();
+    peers.sort_by(|a, b| a.host_name.cmp(&b.host_name));
+    peers
+}
+
+fn discovered_from(peer: &TailscalePeer, user: &str) -> Option<DiscoveredServer> {
+    let host = peer
+        .ips
+        .iter()
+        .find(|ip| ip.parse::<Ipv4Addr>().is_ok())
+        .or(peer.ips.first())?;
+    Some(DiscoveredServer {
+        source: CloudSource {
+            provider: TAILSCALE_PROVIDER.to_string(),
+            resource_id: peer.id.clone(),
+            region: None,
+        },
+        name: peer.host_name.clone(),
+        host: host.clone(),
+        port: DEFAULT_SSH_PORT,
+        user: user.to_string(),
+        key_secret_id: None,
+    })
+}
+
+#[tauri::command]
+pub async fn get_tailscale_status() -> Result<TailscaleStatus, String> {
+    match load_status().await {
+        Ok(status) => Ok(TailscaleStatus {
+            installed: true,
+            running: status.backend_state == "Running",
+            tailnet: status.current_tailnet.map(|tailnet| tailnet.name),
+            self_ips: status
+                .self_node
+                .map(|node| node.tailscale_ips)
+                .unwrap_or_default(),
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(TailscaleStatus {
+            installed: false,
+            running: false,
+            tailnet: None,
+            self_ips: Vec::new(),
+        }),
+        Err(e) => Err(format!("Failed to read Tailscale status: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub async fn list_tailscale_peers(ssh_only: Option<bool>) -> Result<Vec<TailscalePeer>, String> {
+    let status = load_status()
+        .await
+        .map_err(|e| format!("Failed to read Tailscale status: {}", e))?;
+    Ok(list_peers(&status, ssh_only.unwrap_or(true)))
+}
+
+#[tauri::command]
+pub async fn sync_tailscale_servers(
+    app: AppHandle,
+    ssh_only: Option<bool>,
+    default_user: Option<String>,
+    remove_missing: Option<bool>,
+) -> Result<CloudSyncResult, String> {
+    let status = load_status()
+        .await
+        .map_err(|e| format!("Failed to read Tailscale status: {}", e))?;
+    let user = default_user.as_deref().unwrap_or(DEFAULT_TAILSCALE_USER);
+    let discovered: Vec<DiscoveredServer> = list_peers(&status, ssh_only.unwrap_or(true))
+        .iter()
+        .filter_map(|peer| discovered_from(peer, user))
+        .collect();
+    apply_discovery(
+        &app,
+        TAILSCALE_PROVIDER,
+        &discovered,
+        remove_missing.unwrap_or(false),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = r#"{
+        "BackendState": "Running",
+        "Self": {"ID": "n0", "HostName": "laptop", "TailscaleIPs": ["100.64.0.1"]},
+        "CurrentTailnet": {"Name": "example.com"},
+        "Peer": {
+            "nodekey:b": {
+                "ID": "n2",
+                "HostName": "nas",
+                "DNSName": "nas.example.ts.net.",
+                "TailscaleIPs": ["100.101.1.2", "fd7a:115c:a1e0::2"],
+                "OS": "linux",
+                "Online": true,
+                "sshHostKeys": ["ssh-ed25519 AAAA"]
+            },
+            "nodekey:a": {
+                "ID": "n1",
+                "HostName": "phone",
+                "TailscaleIPs": ["100.101.1.3"],
+                "OS": "iOS",
+                "Online": false
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_list_peers_filters_ssh() {
+        let status: StatusJson = serde_json::from_str(STATUS).expect("status");
+        assert_eq!(status.backend_state, "Running");
+
+        let all = list_peers(&status, false);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].host_name, "nas");
+
+        let ssh = list_peers(&status, true);
+        assert_eq!(ssh.len(), 1);
+        assert!(ssh[0].ssh_enabled);
+        assert_eq!(ssh[0].dns_name, "nas.example.ts.net");
+
+        let discovered = discovered_from(&ssh[0], "admin").expect("discovered");
+        assert_eq!(discovered.host, "100.101.1.2");
+        assert_eq!(discovered.source.resource_id, "n2");
+        assert_eq!(discovered.user, "admin");
+    }
+}
@@ -0,0 +1,423 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::time::Duration;
+
+#[cfg(debug_assertions)]
+use tracing::debug;
+
+use crate::resize::TerminalSize;
+use crate::settings::load_settings;
+use crate::stream_shell::{spawn_reader, spawn_stream_shell, ShellBackend};
+use crate::{emit_connection_state, get_app_dir, AppState, ConnectionState, PtyConfig};
+
+/// Telnet shells use `telnet:<host>:<port>` as their server id.
+pub const TELNET_SERVER_PREFIX: &str = "telnet:";
+const DEFAULT_TELNET_PORT: u16 = 23;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const INSECURE_BANNER: &str = "\x1b[1;31m*** Telnet is not encrypted: everything typed here, including passwords, can be read on the network. ***\x1b[0m\r\n";
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const OPT_ECHO: u8 = 1;
+const OPT_SUPPRESS_GO_AHEAD: u8 = 3;
+const OPT_TERMINAL_TYPE: u8 = 24;
+const OPT_WINDOW_SIZE: u8 = 31;
+const TERMINAL_TYPE_IS: u8 = 0;
+const TERMINAL_TYPE_SEND: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseState {
+    Data,
+    Iac,
+    Negotiate(u8),
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TelnetOutput {
+    data: Vec<u8>,
+    replies: Vec<u8>,
+    /// The server asked for window size reports.
+    window_size_requested: bool,
+}
+
+/// Strips telnet commands out of the server's byte stream and answers option
+/// negotiation: we let the server echo and suppress go-ahead, and report our
+/// terminal type and window size. Everything else is refused.
+struct TelnetParser {
+    state: ParseState,
+    subnegotiation: Vec<u8>,
+    terminal_type: String,
+}
+
+impl TelnetParser {
+    fn new(terminal_type: &str) -> Self {
+        Self {
+            state: ParseState::Data,
+            subnegotiation: Vec::new(),
+            terminal_type: terminal_type.to_ascii_uppercase(),
+        }
+    }
+
+    fn feed(&mut self, input: &[u8]) -> TelnetOutput {
+        let mut output = TelnetOutput::default();
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (ParseState::Data, IAC) => ParseState::Iac,
+                (ParseState::Data, _) => {
+                    output.data.push(byte);
+                    ParseState::Data
+                }
+                (ParseState::Iac, IAC) => {
+                    output.data.push(IAC);
+                    ParseState::Data
+                }
+                (ParseState::Iac, DO | DONT | WILL | WONT) => ParseState::Negotiate(byte),
+                (ParseState::Iac, SB) => {
+                    self.subnegotiation.clear();
+                    ParseState::Subnegotiation
+                }
+                // NOP, go-ahead and the other bare commands carry no data.
+                (ParseState::Iac, _) => ParseState::Data,
+                (ParseState::Negotiate(verb), option) => {
+                    self.negotiate(verb, option, &mut output);
+                    ParseState::Data
+                }
+                (ParseState::Subnegotiation, IAC) => ParseState::SubnegotiationIac,
+                (ParseState::Subnegotiation, _) => {
+                    self.subnegotiation.push(byte);
+                    ParseState::Subnegotiation
+                }
+                (ParseState::SubnegotiationIac, SE) => {
+                    self.subnegotiate(&mut output);
+                    ParseState::Data
+                }
+                (ParseState::SubnegotiationIac, _) => {
+                    self.subnegotiation.push(byte);
+                    ParseState::Subnegotiation
+                }
+            };
+        }
+        output
+    }
+
+    fn negotiate(&self, verb: u8, option: u8, output: &mut TelnetOutput) {
+        let reply = match (verb, option) {
+            (WILL, OPT_ECHO | OPT_SUPPRESS_GO_AHEAD) => DO,
+            (WILL, _) => DONT,
+            (DO, OPT_TERMINAL_TYPE) => WILL,
+            (DO, OPT_WINDOW_SIZE) => {
+                output.window_size_requested = true;
+                WILL
+            }
+            (DO, _) => WONT,
+            // Refusals need no answer from us; we never enabled anything
+            // the server did not ask for.
+            _ => return,
+        };
+        output.replies.extend_from_slice(&[IAC, reply, option]);
+    }
+
+    fn subnegotiate(&self, output: &mut TelnetOutput) {
+        if self.subnegotiation == [OPT_TERMINAL_TYPE, TERMINAL_TYPE_SEND] {
+            output
+                .replies
+                .extend_from_slice(&[IAC, SB, OPT_TERMINAL_TYPE, TERMINAL_TYPE_IS]);
+            output
+                .replies
+                .extend_from_slice(self.terminal_type.as_bytes());
+            output.replies.extend_from_slice(&[IAC, SE]);
+        }
+    }
+}
+
+fn push_escaped(target: &mut Vec<u8>, byte: u8) {
+    target.push(byte);
+    if byte == IAC {
+        target.push(IAC);
+    }
+}
+
+fn window_size_message(width: u32, height: u32) -> Vec<u8> {
+    let width = width.min(u16::MAX as u32) as u16;
+    let height = height.min(u16::MAX as u32) as u16;
+    let mut message = vec![IAC, SB, OPT_WINDOW_SIZE];
+    for byte in width.to_be_bytes().into_iter().chain(height.to_be_bytes()) {
+        push_escaped(&mut message, byte);
+    }
+    message.extend_from_slice(&[IAC, SE]);
+    message
+}
+
+/// Escapes IAC bytes and turns a bare carriage return into CR NUL, which is
+/// what the telnet spec expects for the Enter key.
+fn encode_input(input: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(input.len());
+    for (index, &byte) in input.iter().enumerate() {
+        push_escaped(&mut encoded, byte);
+        if byte == b'\r' && input.get(index + 1) != Some(&b'\n') {
+            encoded.push(0);
+        }
+    }
+    encoded
+}
+
+struct TelnetWriter {
+    stream: TcpStream,
+    window_size_enabled: bool,
+    size: (u32, u32),
+}
+
+type SharedWriter = Arc<Mutex<TelnetWriter>>;
+
+fn lock(writer: &SharedWriter) -> io::Result<std::sync::MutexGuard<'_, TelnetWriter>> {
+    writer
+        .lock()
+        .map_err(|_| io::Error::other("telnet writer is unavailable"))
+}
+
+/// Hands the terminal only the data bytes, answering negotiation on the way.
+struct TelnetReader {
+    stream: TcpStream,
+    parser: TelnetParser,
+    writer: SharedWriter,
+    pending: Vec<u8>,
+}
+
+impl Read for TelnetReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.pending.is_empty() {
+                let n = buf.len().min(self.pending.len());
+                buf[..n].copy_from_slice(&self.pending[..n]);
+                self.pending.drain(..n);
+                return Ok(n);
+            }
+
+            let mut raw = vec![0u8; buf.len()];
+            let n = self.stream.read(&mut raw)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let output = self.parser.feed(&raw[..n]);
+            if !output.replies.is_empty() || output.window_size_requested {
+                let mut writer = lock(&self.writer)?;
+                writer.stream.write_all(&output.replies)?;
+                if output.window_size_requested {
+                    writer.window_size_enabled = true;
+                    let (width, height) = writer.size;
+                    writer
+                        .stream
+                        .write_all(&window_size_message(width, height))?;
+                }
+            }
+            self.pending = output.data;
+        }
+    }
+}
+
+struct TelnetConsole {
+    writer: SharedWriter,
+}
+
+impl ShellBackend for TelnetConsole {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        lock(&self.writer)?.stream.write_all(&encode_input(data))
+    }
+
+    // NAWS has no room for pixel sizes.
+    fn resize(&mut self, size: TerminalSize) -> io::Result<()> {
+        let (width, height) = (size.width, size.height);
+        let mut writer = lock(&self.writer)?;
+        writer.size = (width, height);
+        if writer.window_size_enabled {
+            writer
+                .stream
+                .write_all(&window_size_message(width, height))?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Ok(writer) = lock(&self.writer) {
+            let _ = writer.stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn ended_message(&self, _exit_status: Option<u32>) -> String {
+        "\r\n\r\nConnection closed\r\n".to_string()
+    }
+}
+
+fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => format!("Failed to connect: {}", e),
+        None => format!("Failed to resolve {}", host),
+    })
+}
+
+/// Opens a telnet session for devices that speak nothing else. Refused unless
+/// telnet has been enabled in settings.
+#[tauri::command]
+pub async fn open_telnet_shell(
+    app: AppHandle,
+    connection_id: String,
+    host: String,
+    port: Option<u16>,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> Result<String, String> {
+    let settings = load_settings(&get_app_dir(&app)?)?;
+    if !settings.allow_telnet {
+        return Err(
+            "Telnet is disabled because it is unencrypted. Enable it in settings to use it."
+                .to_string(),
+        );
+    }
+
+    let port = port.unwrap_or(DEFAULT_TELNET_PORT);
+    let server_id = format!("{}{}:{}", TELNET_SERVER_PREFIX, host, port);
+    let config = PtyConfig {
+        term: settings.defaults.term,
+        width: width.unwrap_or(80),
+        height: height.unwrap_or(24),
+        pixel_width: 0,
+        pixel_height: 0,
+        command: None,
+    };
+
+    #[cfg(debug_assertions)]
+    debug!(host = %host, port, "Opening telnet session");
+
+    emit_connection_state(
+        &app,
+        Some(connection_id.as_str()),
+        Some(server_id.as_str()),
+        None,
+        ConnectionState::Connecting,
+    )?;
+    let connect_host = host.clone();
+    let stream = tokio::task::spawn_blocking(move || connect_tcp(&connect_host, port))
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))?
+        .inspect_err(|e| {
+            let _ = emit_connection_state(
+                &app,
+                Some(connection_id.as_str()),
+                Some(server_id.as_str()),
+                None,
+                ConnectionState::Error(e.clone()),
+            );
+        })?;
+
+    let read_stream = stream
+        .try_clone()
+        .map_err(|e| format!("Failed to read from {}: {}", host, e))?;
+    let writer = Arc::new(Mutex::new(TelnetWriter {
+        stream,
+        window_size_enabled: false,
+        size: (config.width, config.height),
+    }));
+    let reader = TelnetReader {
+        stream: read_stream,
+        parser: TelnetParser::new(&config.term),
+        writer: writer.clone(),
+        pending: INSECURE_BANNER.as_bytes().to_vec(),
+    };
+
+    let shell = spawn_stream_shell(
+        &app,
+        &connection_id,
+        &server_id,
+        None,
+        spawn_reader(Box::new(reader)),
+        None,
+        TelnetConsole { writer },
+    )
+    .await?;
+    let shell_id = shell.id.clone();
+
+    let state = app.state::<AppState>();
+    let mut shells = state.shells.lock().await;
+    shells.insert(shell_id.clone(), shell);
+    Ok(shell_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_strips_negotiation_and_answers_it() {
+        let mut parser = TelnetParser::new("xterm-256color");
+        let output = parser.feed(&[
+            b'h',
+            IAC,
+            WILL,
+            OPT_ECHO,
+            IAC,
+            DO,
+            OPT_WINDOW_SIZE,
+            IAC,
+            DO,
+            42,
+            b'i',
+            IAC,
+            IAC,
+        ]);
+
+        assert_eq!(output.data, vec![b'h', b'i', IAC]);
+        assert_eq!(
+            output.replies,
+            vec![IAC, DO, OPT_ECHO, IAC, WILL, OPT_WINDOW_SIZE, IAC, WONT, 42]
+        );
+        assert!(output.window_size_requested);
+    }
+
+    #[test]
+    fn test_parser_answers_terminal_type_across_reads() {
+        let mut parser = TelnetParser::new("xterm");
+        let first = parser.feed(&[IAC, SB, OPT_TERMINAL_TYPE]);
+        assert!(first.replies.is_empty());
+
+        let second = parser.feed(&[TERMINAL_TYPE_SEND, IAC, SE, b'$']);
+        let mut expected = vec![IAC, SB, OPT_TERMINAL_TYPE, TERMINAL_TYPE_IS];
+        expected.extend_from_slice(b"XTERM");
+        expected.extend_from_slice(&[IAC, SE]);
+        assert_eq!(second.replies, expected);
+        assert_eq!(second.data, vec![b'$']);
+    }
+
+    #[test]
+    fn test_encode_input_escapes_iac_and_bare_cr() {
+        assert_eq!(encode_input(b"ls\r"), b"ls\r\0".to_vec());
+        assert_eq!(encode_input(b"a\r\nb"), b"a\r\nb".to_vec());
+        assert_eq!(encode_input(&[IAC]), vec![IAC, IAC]);
+    }
+
+    #[test]
+    fn test_window_size_message_escapes_iac() {
+        assert_eq!(
+            window_size_message(255, 24),
+            vec![IAC, SB, OPT_WINDOW_SIZE, 0, IAC, IAC, 0, 24, IAC, SE]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tokio::time::{timeout, Duration};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::remote::{run_on_channel, shell_quote};
+use crate::{settings, AppState, SshSession};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// Tried in order when the requested TERM has no terminfo entry remotely.
+const FALLBACK_TERMS: [&str; 3] = ["xterm-256color", "xterm", "vt100"];
+// 24-bit color terminfo entries report 2^24 colors.
+const DIRECT_COLORS: u32 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProbedTerm {
+    name: String,
+    colors: Option<u32>,
+    /// The entry has the `RGB` or `Tc` capability.
+    direct_color: bool,
+}
+
+/// What a server's terminfo database and locale support, probed once per
+/// connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TermProbe {
+    /// False when the server has no `infocmp`, so installed entries are
+    /// unknown rather than missing.
+    infocmp: bool,
+    terms: Vec<ProbedTerm>,
+    charmap: Option<String>,
+}
+
+/// Payload of `shell-capabilities`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellCapabilities {
+    pub connection_id: String,
+    pub server_id: String,
+    pub shell_id: String,
+    /// The TERM the shell was opened with.
+    pub term: String,
+    /// Differs from `term` when the requested entry is not installed.
+    pub requested_term: String,
+    pub colors: Option<u32>,
+    pub truecolor: bool,
+    /// Whether the remote locale is UTF-8, which decides whether wide and
+    /// combining characters take the cells the frontend expects.
+    pub utf8_locale: Option<bool>,
+    /// The remote locale's character set. Output in anything other than
+    /// UTF-8 is transcoded before it is emitted.
+    pub charset: Option<String>,
+}
+
+/// Probe results by connection id.
+#[derive(Default)]
+pub struct TermProbes {
+    probes: Mutex<HashMap<String, TermProbe>>,
+}
+
+fn probe_script(requested: &str) -> String {
+    let candidates: Vec<String> = std::iter::once(requested)
+        .chain(FALLBACK_TERMS)
+        .map(shell_quote)
+        .collect();
+    format!(
+        "command -v infocmp >/dev/null 2>&1 && echo infocmp\n\
+         for t in {}; do\n\
+         infocmp \"$t\" >/dev/null 2>&1 && printf 'term %s %s %s\\n' \"$t\" \
+         \"$(tput -T \"$t\" colors 2>/dev/null)\" \
+         \"$(infocmp -x \"$t\" 2>/dev/null | grep -cE '(^|[[:space:]])(RGB|Tc)[,=]')\"\n\
+         done\n\
+         printf 'charmap %s\\n' \"$(locale charmap 2>/dev/null)\"",
+        candidates.join(" ")
+    )
+}
+
+pub(crate) fn parse_probe(output: &str) -> TermProbe {
+    let mut probe = TermProbe::default();
+    for line in output.lines() {
+        // Split on single spaces: an empty field means `tput` printed
+        // nothing.
+        let mut fields = line.trim_end().split(' ');
+        match fields.next() {
+            Some("infocmp") => probe.infocmp = true,
+            Some("term") => {
+                let Some(name) = fields.next() else {
+                    continue;
+                };
+                let colors = fields.next().and_then(|colors| colors.parse::<u32>().ok());
+                let rgb = fields.next().and_then(|count| count.parse::<u32>().ok());
+                probe.terms.push(ProbedTerm {
+                    name: name.to_string(),
+                    colors,
+                    direct_color: rgb.is_some_and(|count| count > 0),
+                });
+            }
+            Some("charmap") => {
+                probe.charmap = fields
+                    .next()
+                    .filter(|charmap| !charmap.is_empty())
+                    .map(str::to_string)
+            }
+            _ => {}
+        }
+    }
+    probe
+}
+
+impl TermProbe {
+    /// `requested` when it is installed or nothing is known, otherwise the
+    /// first installed fallback.
+    pub(crate) fn choose_term(&self, requested: &str) -> String {
+        if !self.infocmp || self.installed(requested).is_some() {
+            return requested.to_string();
+        }
+        FALLBACK_TERMS
+            .iter()
+            .find(|term| self.installed(term).is_some())
+            .map_or_else(|| requested.to_string(), |term| term.to_string())
+    }
+
+    /// The character set `locale charmap` reported.
+    pub(crate) fn charmap(&self) -> Option<&str> {
+        self.charmap.as_deref()
+    }
+
+    fn installed(&self, term: &str) -> Option<&ProbedTerm> {
+        self.terms.iter().find(|probed| probed.name == term)
+    }
+}
+
+/// Probes the server behind `session` unless a probe for the connection is
+/// cached or probing is turned off. Failures are logged and give `None`, so
+/// the shell opens with the requested TERM.
+pub(crate) async fn probe_connection(
+    app: &AppHandle,
+    session: &SshSession,
+    connection_id: &str,
+    requested: &str,
+) -> Option<TermProbe> {
+    if !settings::connection_defaults(app).probe_terminal {
+        return None;
+    }
+    let state = app.state::<AppState>();
+    if let Some(probe) = state.term_probes.probes.lock().await.get(connection_id) {
+        return Some(probe.clone());
+    }
+
+    let probe = timeout(PROBE_TIMEOUT, async {
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Failed to open channel: {}", e))?;
+        run_on_channel(channel, &probe_script(requested)).await
+    })
+    .await
+    .map_err(|_| "Terminal probe timed out".to_string())
+    .and_then(|output| output);
+    let probe = match probe {
+        Ok(output) => parse_probe(&output.stdout),
+        Err(e) => {
+            debug!(connection_id, error = %e, "Terminal probe failed");
+            return None;
+        }
+    };
+    state
+        .term_probes
+        .probes
+        .lock()
+        .await
+        .insert(connection_id.to_string(), probe.clone());
+    Some(probe)
+}
+
+pub(crate) fn emit_capabilities(
+    app: &AppHandle,
+    probe: &TermProbe,
+    requested_term: &str,
+    term: &str,
+    connection_id: &str,
+    server_id: &str,
+    shell_id: &str,
+) {
+    let probed = probe.installed(term);
+    let payload = ShellCapabilities {
+        connection_id: connection_id.to_string(),
+        server_id: server_id.to_string(),
+        shell_id: shell_id.to_string(),
+        term: term.to_string(),
+        requested_term: requested_term.to_string(),
+        colors: probed.and_then(|probed| probed.colors),
+        truecolor: probed.is_some_and(|probed| {
+            probed.direct_color || probed.colors.is_some_and(|colors| colors >= DIRECT_COLORS)
+        }),
+        utf8_locale: probe.charmap.as_ref().map(|charmap| {
+            let charmap = charmap.to_ascii_uppercase();
+            charmap == "UTF-8" || charmap == "UTF8"
+        }),
+        charset: probe.charmap.clone(),
+    };
+    let _ = app.emit("shell-capabilities", payload);
+}
+
+pub(crate) async fn forget_connection(app: &AppHandle, connection_id: &str) {
+    let state = app.state::<AppState>();
+    state.term_probes.probes.lock().await.remove(connection_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_and_fall_back_when_term_is_missing() {
+        let probe = parse_probe("infocmp\nterm xterm 8 0\nterm vt100  0\ncharmap UTF-8\n");
+
+        assert_eq!(probe.choose_term("xterm-256color"), "xterm");
+        assert_eq!(probe.choose_term("xterm"), "xterm");
+        assert_eq!(probe.installed("vt100").and_then(|t| t.colors), None);
+        assert_eq!(probe.charmap.as_deref(), Some("UTF-8"));
+
+        // Without infocmp nothing is known to be missing.
+        let unknown = parse_probe("charmap ANSI_X3.4-1968\n");
+        assert_eq!(unknown.choose_term("xterm-kitty"), "xterm-kitty");
+
+        let direct = parse_probe("infocmp\nterm xterm-256color 256 1\n");
+        assert!(direct.installed("xterm-256color").unwrap().direct_color);
+    }
+}
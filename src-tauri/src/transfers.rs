@@ -0,0 +1,646 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tokio::sync::{Mutex, Notify};
+use tokio::time::Duration;
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::sftp::{
+    run_download_directory, run_sync_directory, run_upload_directory, DirectoryTransferOptions,
+    SyncOptions, TransferDirection,
+};
+use crate::AppState;
+
+const DEFAULT_MAX_CONCURRENT_PER_SERVER: usize = 2;
+const MAX_FINISHED_TRANSFERS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TransferLimits {
+    #[serde(default = "default_max_concurrent_per_server")]
+    pub max_concurrent_per_server: usize,
+    #[serde(default)]
+    pub bandwidth_limit_bytes_per_sec: Option<u64>,
+}
+
+fn default_max_concurrent_per_server() -> usize {
+    DEFAULT_MAX_CONCURRENT_PER_SERVER
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_per_server: DEFAULT_MAX_CONCURRENT_PER_SERVER,
+            bandwidth_limit_bytes_per_sec: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TransferJob {
+    Upload {
+        server_id: String,
+        local_path: String,
+        remote_path: String,
+        #[serde(default)]
+        options: DirectoryTransferOptions,
+    },
+    Download {
+        server_id: String,
+        remote_path: String,
+        local_path: String,
+        #[serde(default)]
+        options: DirectoryTransferOptions,
+    },
+    Sync {
+        server_id: String,
+        local_path: String,
+        remote_path: String,
+        direction: TransferDirection,
+        #[serde(default)]
+        delete: bool,
+        #[serde(default)]
+        options: SyncOptions,
+    },
+}
+
+impl TransferJob {
+    fn server_id(&self) -> &str {
+        match self {
+            TransferJob::Upload { server_id, .. }
+            | TransferJob::Download { server_id, .. }
+            | TransferJob::Sync { server_id, .. } => server_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TransferStatus {
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            TransferStatus::Completed | TransferStatus::Failed | TransferStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferInfo {
+    pub id: String,
+    pub server_id: String,
+    pub job: TransferJob,
+    pub status: TransferStatus,
+    pub queued_at: u64,
+    #[serde(default)]
+    pub started_at: Option<u64>,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(default)]
+    pub files_transferred: usize,
+    #[serde(default)]
+    pub bytes_transferred: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Token bucket shared by every queued transfer so the configured cap applies
+/// to their combined throughput rather than to each one individually.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    state: std::sync::Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    bytes_per_sec: Option<u64>,
+    available: f64,
+    updated_at: Instant,
+}
+
+impl BandwidthLimiter {
+    fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            state: std::sync::Mutex::new(LimiterState {
+                bytes_per_sec,
+                available: bytes_per_sec.unwrap_or(0) as f64,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn set_limit(&self, bytes_per_sec: Option<u64>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.bytes_per_sec = bytes_per_sec.filter(|rate| *rate > 0);
+        state.available = state.bytes_per_sec.unwrap_or(0) as f64;
+        state.updated_at = Instant::now();
+    }
+
+    /// Takes `bytes` from the bucket and returns how long the caller must wait
+    /// before sending them. Bursts are capped at one second worth of data.
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(rate) = state.bytes_per_sec else {
+            return Duration::ZERO;
+        };
+        let rate = rate as f64;
+        let elapsed = now
+            .saturating_duration_since(state.updated_at)
+            .as_secs_f64();
+        state.available = (state.available + elapsed * rate).min(rate) - bytes as f64;
+        state.updated_at = now;
+        if state.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.available / rate)
+        }
+    }
+
+    async fn throttle(&self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Pause/cancel switches checked by the copy loop between chunks.
+#[derive(Debug)]
+pub struct TransferControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    changed: Notify,
+    limiter: Arc<BandwidthLimiter>,
+}
+
+impl TransferControl {
+    fn new(limiter: Arc<BandwidthLimiter>) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            changed: Notify::new(),
+            limiter,
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        self.changed.notify_waiters();
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.changed.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub(crate) async fn checkpoint(&self, bytes: usize) -> Result<(), String> {
+        loop {
+            // Register interest before checking the flags so a resume that
+            // lands in between is not missed.
+            let changed = self.changed.notified();
+            if self.is_cancelled() {
+                return Err("Transfer cancelled".to_string());
+            }
+            if !self.paused.load(Ordering::SeqCst) {
+                break;
+            }
+            changed.await;
+        }
+        self.limiter.throttle(bytes).await;
+        Ok(())
+    }
+}
+
+struct QueuedTransfer {
+    info: TransferInfo,
+    control: Arc<TransferControl>,
+    started: bool,
+}
+
+pub struct TransferManager {
+    limits: Mutex<TransferLimits>,
+    transfers: Mutex<Vec<QueuedTransfer>>,
+    limiter: Arc<BandwidthLimiter>,
+}
+
+impl Default for TransferManager {
+    fn default() -> Self {
+        let limits = TransferLimits::default();
+        Self {
+            limiter: Arc::new(BandwidthLimiter::new(limits.bandwidth_limit_bytes_per_sec)),
+            limits: Mutex::new(limits),
+            transfers: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+fn unix_timestamp_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn emit_transfer_update(app: &AppHandle, info: &TransferInfo) {
+    let _ = app.emit("transfer-queue", info.clone());
+}
+
+// Picks queued transfers in FIFO order while their server has a free slot.
+// Paused transfers that already started keep holding their slot.
+fn next_runnable(transfers: &[QueuedTransfer], max_per_server: usize) -> Vec<usize> {
+    let mut active: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for transfer in transfers {
+        if transfer.started && !transfer.info.status.is_finished() {
+            *active.entry(transfer.info.server_id.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut runnable = Vec::new();
+    for (index, transfer) in transfers.iter().enumerate() {
+        if transfer.info.status != TransferStatus::Queued {
+            continue;
+        }
+        let count = active.entry(transfer.info.server_id.as_str()).or_default();
+        if *count < max_per_server.max(1) {
+            *count += 1;
+            runnable.push(index);
+        }
+    }
+    runnable
+}
+
+fn prune_finished(transfers: &mut Vec<QueuedTransfer>) {
+    let finished = transfers
+        .iter()
+        .filter(|transfer| transfer.info.status.is_finished())
+        .count();
+    let mut excess = finished.saturating_sub(MAX_FINISHED_TRANSFERS);
+    transfers.retain(|transfer| {
+        if excess > 0 && transfer.info.status.is_finished() {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+// Boxed because finished transfers reschedule from inside the spawned task;
+// the explicit `Send` bound breaks the otherwise recursive future type.
+fn schedule(app: &AppHandle) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+    Box::pin(async move {
+        let state = app.state::<AppState>();
+        let manager = &state.transfers;
+        let max_per_server = manager.limits.lock().await.max_concurrent_per_server;
+
+        let mut transfers = manager.transfers.lock().await;
+        for index in next_runnable(&transfers, max_per_server) {
+            let transfer = &mut transfers[index];
+            transfer.started = true;
+            transfer.info.status = TransferStatus::Running;
+            transfer.info.started_at = Some(unix_timestamp_now());
+            emit_transfer_update(app, &transfer.info);
+
+            let app = app.clone();
+            let info = transfer.info.clone();
+            let control = transfer.control.clone();
+            tauri::async_runtime::spawn(async move {
+                let outcome = run_job(&app, &info, &control).await;
+                finish_transfer(&app, &info.id, outcome).await;
+            });
+        }
+    })
+}
+
+async fn run_job(
+    app: &AppHandle,
+    info: &TransferInfo,
+    control: &TransferControl,
+) -> Result<(usize, u64), String> {
+    debug!(transfer_id = %info.id, server_id = %info.server_id, "Starting queued transfer");
+    match &info.job {
+        TransferJob::Upload {
+            server_id,
+            local_path,
+            remote_path,
+            options,
+        } => run_upload_directory(
+            app,
+            server_id,
+            &info.id,
+            local_path,
+            remote_path,
+            options,
+            Some(control),
+        )
+        .await
+        .map(|result| (result.files_transferred, result.bytes_transferred))
+        .map_err(|e| e.to_string()),
+        TransferJob::Download {
+            server_id,
+            remote_path,
+            local_path,
+            options,
+        } => run_download_directory(
+            app,
+            server_id,
+            &info.id,
+            remote_path,
+            local_path,
+            options,
+            Some(control),
+        )
+        .await
+        .map(|result| (result.files_transferred, result.bytes_transferred))
+        .map_err(|e| e.to_string()),
+        TransferJob::Sync {
+            server_id,
+            local_path,
+            remote_path,
+            direction,
+            delete,
+            options,
+        } => run_sync_directory(
+            app,
+            server_id,
+            &info.id,
+            local_path,
+            remote_path,
+            *direction,
+            *delete,
+            options,
+            Some(control),
+        )
+        .await
+        .map(|result| (result.files_transferred, result.bytes_transferred))
+        .map_err(|e| e.to_string()),
+    }
+}
+
+async fn finish_transfer(app: &AppHandle, id: &str, outcome: Result<(usize, u64), String>) {
+    {
+        let state = app.state::<AppState>();
+        let mut transfers = state.transfers.transfers.lock().await;
+        if let Some(transfer) = transfers.iter_mut().find(|t| t.info.id == id) {
+            transfer.info.finished_at = Some(unix_timestamp_now());
+            match outcome {
+                Ok((files, bytes)) => {
+                    transfer.info.status = TransferStatus::Completed;
+                    transfer.info.files_transferred = files;
+                    transfer.info.bytes_transferred = bytes;
+                }
+                Err(_) if transfer.control.is_cancelled() => {
+                    transfer.info.status = TransferStatus::Cancelled;
+                }
+                Err(e) => {
+                    transfer.info.status = TransferStatus::Failed;
+                    transfer.info.error = Some(e);
+                }
+            }
+            emit_transfer_update(app, &transfer.info);
+        }
+        prune_finished(&mut transfers);
+    }
+    schedule(app).await;
+}
+
+async fn update_transfer<F>(app: &AppHandle, id: &str, update: F) -> Result<TransferInfo, String>
+where
+    F: FnOnce(&mut QueuedTransfer) -> Result<(), String>,
+{
+    let info = {
+        let state = app.state::<AppState>();
+        let mut transfers = state.transfers.transfers.lock().await;
+        let transfer = transfers
+            .iter_mut()
+            .find(|t| t.info.id == id)
+            .ok_or_else(|| format!("Transfer {} not found", id))?;
+        update(transfer)?;
+        emit_transfer_update(app, &transfer.info);
+        transfer.info.clone()
+    };
+    schedule(app).await;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn queue_transfer(app: AppHandle, job: TransferJob) -> Result<TransferInfo, String> {
+    let info = TransferInfo {
+        id: uuid::Uuid::new_v4().to_string(),
+        server_id: job.server_id().to_string(),
+        job,
+        status: TransferStatus::Queued,
+        queued_at: unix_timestamp_now(),
+        started_at: None,
+        finished_at: None,
+        files_transferred: 0,
+        bytes_transferred: 0,
+        error: None,
+    };
+
+    {
+        let state = app.state::<AppState>();
+        let control = Arc::new(TransferControl::new(state.transfers.limiter.clone()));
+        let mut transfers = state.transfers.transfers.lock().await;
+        transfers.push(QueuedTransfer {
+            info: info.clone(),
+            control,
+            started: false,
+        });
+    }
+    emit_transfer_update(&app, &info);
+    schedule(&app).await;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn list_transfers(app: AppHandle) -> Result<Vec<TransferInfo>, String> {
+    let state = app.state::<AppState>();
+    let transfers = state.transfers.transfers.lock().await;
+    Ok(transfers.iter().map(|t| t.info.clone()).collect())
+}
+
+#[tauri::command]
+pub async fn pause_transfer(app: AppHandle, id: String) -> Result<TransferInfo, String> {
+    update_transfer(&app, &id, |transfer| match transfer.info.status {
+        TransferStatus::Queued | TransferStatus::Running => {
+            transfer.control.set_paused(true);
+            transfer.info.status = TransferStatus::Paused;
+            Ok(())
+        }
+        TransferStatus::Paused => Ok(()),
+        _ => Err(format!(
+            "Transfer {} has already finished",
+            transfer.info.id
+        )),
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn resume_transfer(app: AppHandle, id: String) -> Result<TransferInfo, String> {
+    update_transfer(&app, &id, |transfer| match transfer.info.status {
+        TransferStatus::Paused => {
+            transfer.control.set_paused(false);
+            transfer.info.status = if transfer.started {
+                TransferStatus::Running
+            } else {
+                TransferStatus::Queued
+            };
+            Ok(())
+        }
+        TransferStatus::Queued | TransferStatus::Running => Ok(()),
+        _ => Err(format!(
+            "Transfer {} has already finished",
+            transfer.info.id
+        )),
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn cancel_transfer(app: AppHandle, id: String) -> Result<TransferInfo, String> {
+    update_transfer(&app, &id, |transfer| {
+        if transfer.info.status.is_finished() {
+            return Err(format!(
+                "Transfer {} has already finished",
+                transfer.info.id
+            ));
+        }
+        transfer.control.cancel();
+        // Running transfers are marked cancelled once their task unwinds.
+        if !transfer.started {
+            transfer.info.status = TransferStatus::Cancelled;
+            transfer.info.finished_at = Some(unix_timestamp_now());
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_transfer_limits(app: AppHandle) -> Result<TransferLimits, String> {
+    let state = app.state::<AppState>();
+    let limits = state.transfers.limits.lock().await;
+    Ok(limits.clone())
+}
+
+#[tauri::command]
+pub async fn set_transfer_limits(
+    app: AppHandle,
+    limits: TransferLimits,
+) -> Result<TransferLimits, String> {
+    if limits.max_concurrent_per_server == 0 {
+        return Err("At least one concurrent transfer per server is required".to_string());
+    }
+
+    {
+        let state = app.state::<AppState>();
+        state
+            .transfers
+            .limiter
+            .set_limit(limits.bandwidth_limit_bytes_per_sec);
+        *state.transfers.limits.lock().await = limits.clone();
+    }
+    schedule(&app).await;
+    Ok(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(server_id: &str, status: TransferStatus, started: bool) -> QueuedTransfer {
+        QueuedTransfer {
+            info: TransferInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                server_id: server_id.to_string(),
+                job: TransferJob::Upload {
+                    server_id: server_id.to_string(),
+                    local_path: "/tmp/site".to_string(),
+                    remote_path: "/var/www".to_string(),
+                    options: DirectoryTransferOptions::default(),
+                },
+                status,
+                queued_at: 0,
+                started_at: None,
+                finished_at: None,
+                files_transferred: 0,
+                bytes_transferred: 0,
+                error: None,
+            },
+            control: Arc::new(TransferControl::new(Arc::new(BandwidthLimiter::new(None)))),
+            started,
+        }
+    }
+
+    #[test]
+    fn test_next_runnable_respects_per_server_limit() {
+        let transfers = vec![
+            queued("a", TransferStatus::Running, true),
+            queued("a", TransferStatus::Queued, false),
+            queued("a", TransferStatus::Queued, false),
+            queued("b", TransferStatus::Paused, false),
+            queued("b", TransferStatus::Queued, false),
+            queued("a", TransferStatus::Completed, true),
+        ];
+
+        assert_eq!(next_runnable(&transfers, 2), vec![1, 4]);
+        assert_eq!(next_runnable(&transfers, 1), vec![4]);
+    }
+
+    #[test]
+    fn test_paused_running_transfer_keeps_its_slot() {
+        let transfers = vec![
+            queued("a", TransferStatus::Paused, true),
+            queued("a", TransferStatus::Queued, false),
+        ];
+
+        assert!(next_runnable(&transfers, 1).is_empty());
+    }
+
+    #[test]
+    fn test_bandwidth_limiter_delays_after_burst() {
+        let limiter = BandwidthLimiter::new(Some(1000));
+        let start = Instant::now();
+
+        assert_eq!(limiter.reserve(1000, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(500, start), Duration::from_millis(500));
+        assert_eq!(
+            limiter.reserve(0, start + Duration::from_secs(2)),
+            Duration::ZERO
+        );
+
+        limiter.set_limit(None);
+        assert_eq!(limiter.reserve(1_000_000, start), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_reports_cancellation() {
+        let control = TransferControl::new(Arc::new(BandwidthLimiter::new(None)));
+        control.set_paused(true);
+        control.cancel();
+
+        assert_eq!(
+            control.checkpoint(1).await,
+            Err("Transfer cancelled".to_string())
+        );
+    }
+}
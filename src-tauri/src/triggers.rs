@@ -0,0 +1,407 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use tokio::time::Duration;
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{get_app_dir, load_snippets, parse_json_array_lenient, AppState, ShellCommand};
+
+const TRIGGERS_FILE: &str = "triggers.json";
+const MAX_SCAN_BUFFER_BYTES: usize = 4 * 1024;
+const DEFAULT_COOLDOWN_MS: u64 = 1000;
+
+static ANSI_ESCAPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]")
+        .expect("ANSI escape pattern should compile")
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum TriggerAction {
+    SendInput {
+        input: String,
+    },
+    Notify {
+        #[serde(default)]
+        message: Option<String>,
+    },
+    RunSnippet {
+        snippet_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub id: String,
+    pub name: String,
+    /// Applies to every server when unset.
+    #[serde(default)]
+    pub server_id: Option<String>,
+    pub pattern: String,
+    pub action: TriggerAction,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub cooldown_ms: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerFiredEvent {
+    pub trigger_id: String,
+    pub trigger_name: String,
+    pub connection_id: String,
+    pub server_id: String,
+    pub shell_id: String,
+    pub matched: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggerHit {
+    pub trigger: Trigger,
+    pub matched: String,
+}
+
+struct CompiledTrigger {
+    trigger: Trigger,
+    regex: Regex,
+    last_fired: Option<Instant>,
+}
+
+/// Matches trigger patterns against a shell's output. Output is scanned with
+/// escape sequences removed and kept in a short rolling buffer so prompts
+/// split across reads still match.
+#[derive(Default)]
+pub struct TriggerEngine {
+    rules: Vec<CompiledTrigger>,
+    buffer: String,
+}
+
+impl TriggerEngine {
+    pub fn new(triggers: Vec<Trigger>) -> Self {
+        let rules = triggers
+            .into_iter()
+            .filter(|trigger| trigger.enabled)
+            .filter_map(|trigger| match Regex::new(&trigger.pattern) {
+                Ok(regex) => Some(CompiledTrigger {
+                    trigger,
+                    regex,
+                    last_fired: None,
+                }),
+                Err(e) => {
+                    debug!(trigger_id = %trigger.id, error = %e, "Skipping trigger with invalid pattern");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            rules,
+            buffer: String::new(),
+        }
+    }
+
+    pub fn scan(&mut self, output: &str) -> Vec<TriggerHit> {
+        self.scan_at(output, Instant::now())
+    }
+
+    fn scan_at(&mut self, output: &str, now: Instant) -> Vec<TriggerHit> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+
+        self.buffer.push_str(&ANSI_ESCAPE.replace_all(output, ""));
+        if self.buffer.len() > MAX_SCAN_BUFFER_BYTES {
+            let mut cut = self.buffer.len() - MAX_SCAN_BUFFER_BYTES;
+            while !self.buffer.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.buffer.drain(..cut);
+        }
+
+        let mut hits = Vec::new();
+        let mut consumed = 0;
+        for rule in &mut self.rules {
+            let Some(found) = rule.regex.find(&self.buffer) else {
+                continue;
+            };
+            consumed = consumed.max(found.end());
+
+            // Matches during the cooldown are dropped rather than deferred,
+            // so an echoed response cannot retrigger the rule later.
+            let cooldown =
+                Duration::from_millis(rule.trigger.cooldown_ms.unwrap_or(DEFAULT_COOLDOWN_MS));
+            if rule
+                .last_fired
+                .is_some_and(|fired| now.saturating_duration_since(fired) < cooldown)
+            {
+                continue;
+            }
+            rule.last_fired = Some(now);
+            hits.push(TriggerHit {
+                trigger: rule.trigger.clone(),
+                matched: found.as_str().to_string(),
+            });
+        }
+
+        // Matched text must not fire again on the next read.
+        self.buffer.drain(..consumed);
+        hits
+    }
+}
+
+fn get_triggers_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(TRIGGERS_FILE)
+}
+
+pub fn load_triggers(app_dir: &Path) -> Result<Vec<Trigger>, String> {
+    let path = get_triggers_path(app_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read triggers file: {}", e))?;
+    parse_json_array_lenient(&data, "triggers")
+}
+
+fn save_triggers(app_dir: &Path, triggers: &[Trigger]) -> Result<(), String> {
+    let path = get_triggers_path(app_dir);
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Invalid path for triggers file".to_string())?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let content = serde_json::to_string_pretty(triggers)
+        .map_err(|e| format!("Failed to serialize triggers: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write triggers file: {}", e))?;
+    Ok(())
+}
+
+pub fn triggers_for_server(triggers: &[Trigger], server_id: &str) -> Vec<Trigger> {
+    triggers
+        .iter()
+        .filter(|trigger| {
+            trigger
+                .server_id
+                .as_deref()
+                .is_none_or(|id| id == server_id)
+        })
+        .cloned()
+        .collect()
+}
+
+fn validate_trigger(trigger: &Trigger) -> Result<(), String> {
+    Regex::new(&trigger.pattern)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid trigger pattern: {}", e))
+}
+
+/// Input to write back to the shell for a hit, if its action sends any.
+pub fn trigger_input(app: &AppHandle, hit: &TriggerHit) -> Option<String> {
+    match &hit.trigger.action {
+        TriggerAction::SendInput { input } => Some(input.clone()),
+        TriggerAction::RunSnippet { snippet_id } => {
+            let snippets = get_app_dir(app).and_then(|dir| load_snippets(&dir));
+            match snippets {
+                Ok(snippets) => snippets
+                    .into_iter()
+                    .find(|snippet| &snippet.id == snippet_id)
+                    .map(|snippet| format!("{}\r", snippet.command)),
+                Err(e) => {
+                    debug!(snippet_id, error = %e, "Failed to load snippet for trigger");
+                    None
+                }
+            }
+        }
+        TriggerAction::Notify { .. } => None,
+    }
+}
+
+pub fn emit_trigger_fired(
+    app: &AppHandle,
+    connection_id: &str,
+    server_id: &str,
+    shell_id: &str,
+    hit: &TriggerHit,
+) {
+    let message = match &hit.trigger.action {
+        TriggerAction::Notify { message } => message.clone(),
+        _ => None,
+    };
+    let payload = TriggerFiredEvent {
+        trigger_id: hit.trigger.id.clone(),
+        trigger_name: hit.trigger.name.clone(),
+        connection_id: connection_id.to_string(),
+        server_id: server_id.to_string(),
+        shell_id: shell_id.to_string(),
+        matched: hit.matched.clone(),
+        message,
+    };
+    let _ = app.emit("trigger-fired", payload);
+}
+
+// Open shells keep their own compiled copy; push edits to them right away.
+async fn refresh_shell_triggers(app: &AppHandle, triggers: &[Trigger]) {
+    let state = app.state::<AppState>();
+    let shells: Vec<_> = {
+        let shells = state.shells.lock().await;
+        shells
+            .values()
+            .map(|shell| (shell.server_id.clone(), shell.cmd_tx.clone()))
+            .collect()
+    };
+
+    for (server_id, cmd_tx) in shells {
+        let _ = cmd_tx
+            .send(ShellCommand::SetTriggers(triggers_for_server(
+                triggers, &server_id,
+            )))
+            .await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_triggers(app: AppHandle) -> Result<Vec<Trigger>, String> {
+    let app_dir = get_app_dir(&app)?;
+    load_triggers(&app_dir)
+}
+
+#[tauri::command]
+pub async fn add_trigger(app: AppHandle, trigger: Trigger) -> Result<Vec<Trigger>, String> {
+    validate_trigger(&trigger)?;
+    let app_dir = get_app_dir(&app)?;
+    let mut triggers = load_triggers(&app_dir)?;
+    triggers.push(trigger);
+    save_triggers(&app_dir, &triggers)?;
+    refresh_shell_triggers(&app, &triggers).await;
+    Ok(triggers)
+}
+
+#[tauri::command]
+pub async fn update_trigger(
+    app: AppHandle,
+    id: String,
+    trigger: Trigger,
+) -> Result<Vec<Trigger>, String> {
+    validate_trigger(&trigger)?;
+    let app_dir = get_app_dir(&app)?;
+    let mut triggers = load_triggers(&app_dir)?;
+    let index = triggers
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Trigger with id {} not found", id))?;
+    triggers[index] = trigger;
+    save_triggers(&app_dir, &triggers)?;
+    refresh_shell_triggers(&app, &triggers).await;
+    Ok(triggers)
+}
+
+#[tauri::command]
+pub async fn delete_trigger(app: AppHandle, id: String) -> Result<Vec<Trigger>, String> {
+    let app_dir = get_app_dir(&app)?;
+    let mut triggers = load_triggers(&app_dir)?;
+    let index = triggers
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Trigger with id {} not found", id))?;
+    triggers.remove(index);
+    save_triggers(&app_dir, &triggers)?;
+    refresh_shell_triggers(&app, &triggers).await;
+    Ok(triggers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(id: &str, server_id: Option<&str>, pattern: &str) -> Trigger {
+        Trigger {
+            id: id.to_string(),
+            name: id.to_string(),
+            server_id: server_id.map(|id| id.to_string()),
+            pattern: pattern.to_string(),
+            action: TriggerAction::SendInput {
+                input: "yes\n".to_string(),
+            },
+            enabled: true,
+            cooldown_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_trigger_deserializes_with_defaults() {
+        let json = r#"{
+            "id": "t1",
+            "name": "Confirm host",
+            "pattern": "\\(yes/no\\)\\?",
+            "action": { "type": "Notify" }
+        }"#;
+        let trigger: Trigger = serde_json::from_str(json).expect("deserialize trigger");
+
+        assert!(trigger.enabled);
+        assert_eq!(trigger.server_id, None);
+        assert_eq!(trigger.action, TriggerAction::Notify { message: None });
+    }
+
+    #[test]
+    fn test_engine_matches_across_chunks_and_ignores_colors() {
+        let mut engine = TriggerEngine::new(vec![trigger("t1", None, r"\(yes/no\)\?")]);
+        let now = Instant::now();
+
+        assert!(engine
+            .scan_at("Continue connecting \x1b[1m(yes/", now)
+            .is_empty());
+        let hits = engine.scan_at("no)?\x1b[0m ", now);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matched, "(yes/no)?");
+    }
+
+    #[test]
+    fn test_engine_respects_cooldown_and_consumes_matches() {
+        let mut engine = TriggerEngine::new(vec![trigger("t1", None, "password:")]);
+        let now = Instant::now();
+
+        assert_eq!(engine.scan_at("password:", now).len(), 1);
+        assert!(engine.scan_at("password:", now).is_empty());
+        let later = now + Duration::from_millis(DEFAULT_COOLDOWN_MS);
+        // The occurrence seen during the cooldown was dropped.
+        assert!(engine.scan_at("", later).is_empty());
+        assert_eq!(engine.scan_at("password:", later).len(), 1);
+    }
+
+    #[test]
+    fn test_triggers_for_server_includes_global_rules() {
+        let triggers = vec![
+            trigger("global", None, "a"),
+            trigger("mine", Some("server-1"), "b"),
+            trigger("other", Some("server-2"), "c"),
+        ];
+        let ids: Vec<String> = triggers_for_server(&triggers, "server-1")
+            .into_iter()
+            .map(|trigger| trigger.id)
+            .collect();
+
+        assert_eq!(ids, vec!["global", "mine"]);
+    }
+
+    #[test]
+    fn test_engine_skips_invalid_and_disabled_triggers() {
+        let mut disabled = trigger("off", None, "prompt");
+        disabled.enabled = false;
+        let mut engine = TriggerEngine::new(vec![disabled, trigger("bad", None, "(")]);
+
+        assert!(engine.scan("prompt").is_empty());
+        assert!(validate_trigger(&trigger("bad", None, "(")).is_err());
+    }
+}
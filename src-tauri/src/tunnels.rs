@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::debug;
+
+use crate::event_bus::Emitter;
+use crate::{
+    connect_server, disconnect_ssh, forwarding, get_app_dir, load_servers, saved_server,
+    session_watch, AppState, ManagedSession,
+};
+
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_DELAY_SECONDS: u64 = 15;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TunnelState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    Stopped,
+}
+
+/// A shell-less connection that exists only to carry one forward profile.
+/// Also the payload of `persistent-tunnel`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PersistentTunnel {
+    pub server_id: String,
+    pub profile: String,
+    pub connection_id: String,
+    pub state: TunnelState,
+    /// Failed connection attempts since the last successful one.
+    pub attempt: u32,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct TunnelSupervisor {
+    tunnels: Mutex<HashMap<String, (PersistentTunnel, JoinHandle<()>)>>,
+}
+
+fn tunnel_connection_id(server_id: &str, profile: &str) -> String {
+    format!("tunnel:{}:{}", server_id, profile)
+}
+
+/// 1, 2, 4, 8 seconds and so on, capped so a tunnel comes back quickly once
+/// the server is reachable again.
+fn reconnect_delay(attempt: u32) -> Duration {
+    let seconds = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX)
+        .min(MAX_RECONNECT_DELAY_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+async fn set_state(app: &AppHandle, tunnel: &mut PersistentTunnel, state: TunnelState) {
+    tunnel.state = state;
+    let supervisor = &app.state::<AppState>().tunnels;
+    if let Some((stored, _)) = supervisor
+        .tunnels
+        .lock()
+        .await
+        .get_mut(&tunnel.connection_id)
+    {
+        *stored = tunnel.clone();
+    }
+    let _ = app.emit("persistent-tunnel", tunnel.clone());
+}
+
+async fn session_open(app: &AppHandle, connection_id: &str) -> bool {
+    let state = app.state::<AppState>();
+    let sessions = state.sessions.lock().await;
+    sessions
+        .get(connection_id)
+        .is_some_and(|session| !session.handle.is_closed())
+}
+
+async fn connect_once(app: &AppHandle, tunnel: &PersistentTunnel) -> Result<(), String> {
+    let server = saved_server(app, &tunnel.server_id)
+        .ok_or_else(|| format!("Server with id {} not found", tunnel.server_id))?;
+    let profile = server
+        .forward_profiles
+        .iter()
+        .find(|profile| profile.name == tunnel.profile)
+        .ok_or_else(|| format!("Forward profile {} not found", tunnel.profile))?;
+
+    let session = connect_server(app, &server, &tunnel.connection_id).await?;
+    let state = app.state::<AppState>();
+    let replaced = state.sessions.lock().await.insert(
+        tunnel.connection_id.clone(),
+        ManagedSession {
+            connection_id: tunnel.connection_id.clone(),
+            server_id: server.id.clone(),
+            handle: Arc::new(session),
+        },
+    );
+    if let Some(old) = replaced {
+        let _ = disconnect_ssh(app, Some(&old.handle), None, None).await;
+    }
+    session_watch::watch(app, &tunnel.connection_id);
+
+    // Forwards find the session by connection id, so ones left running
+    // from before a reconnect carry on over the new session.
+    if !forwarding::profile_active(app, &tunnel.connection_id, &profile.name).await {
+        forwarding::start_profile(app, &tunnel.connection_id, &server.id, profile).await?;
+    }
+    Ok(())
+}
+
+async fn supervise(app: AppHandle, mut tunnel: PersistentTunnel) {
+    loop {
+        match connect_once(&app, &tunnel).await {
+            Ok(()) => {
+                tunnel.attempt = 0;
+                tunnel.error = None;
+                set_state(&app, &mut tunnel, TunnelState::Connected).await;
+                while session_open(&app, &tunnel.connection_id).await {
+                    sleep(SESSION_CHECK_INTERVAL).await;
+                }
+            }
+            Err(e) => {
+                debug!(connection_id = %tunnel.connection_id, error = %e, "Tunnel connection failed");
+                tunnel.attempt += 1;
+                tunnel.error = Some(e);
+            }
+        }
+        set_state(&app, &mut tunnel, TunnelState::Reconnecting).await;
+        sleep(reconnect_delay(tunnel.attempt)).await;
+    }
+}
+
+async fn start_tunnel(
+    app: &AppHandle,
+    server_id: &str,
+    profile: &str,
+) -> Result<PersistentTunnel, String> {
+    let tunnel = PersistentTunnel {
+        server_id: server_id.to_string(),
+        profile: profile.to_string(),
+        connection_id: tunnel_connection_id(server_id, profile),
+        state: TunnelState::Connecting,
+        attempt: 0,
+        error: None,
+    };
+    let supervisor = &app.state::<AppState>().tunnels;
+    let mut tunnels = supervisor.tunnels.lock().await;
+    if tunnels.contains_key(&tunnel.connection_id) {
+        return Err(format!(
+            "A tunnel for profile {} is already running",
+            profile
+        ));
+    }
+    // Sent while holding the lock so it cannot land after the task's own
+    // first update.
+    let _ = app.emit("persistent-tunnel", tunnel.clone());
+    let task = tokio::spawn(supervise(app.clone(), tunnel.clone()));
+    tunnels.insert(tunnel.connection_id.clone(), (tunnel.clone(), task));
+    Ok(tunnel)
+}
+
+/// Opens a tunnel for every forward profile marked to start on launch.
+pub fn start_launch_tunnels(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let servers = match get_app_dir(&app).and_then(|dir| load_servers(&dir, &app)) {
+            Ok(servers) => servers,
+            Err(e) => {
+                debug!(error = %e, "Failed to load servers for launch tunnels");
+                return;
+            }
+        };
+        for server in &servers {
+            for profile in server.forward_profiles.iter().filter(|p| p.start_on_launch) {
+                if let Err(e) = start_tunnel(&app, &server.id, &profile.name).await {
+                    debug!(server_id = %server.id, error = %e, "Failed to start launch tunnel");
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_persistent_tunnels(app: AppHandle) -> Result<Vec<PersistentTunnel>, String> {
+    let supervisor = &app.state::<AppState>().tunnels;
+    let tunnels = supervisor.tunnels.lock().await;
+    let mut listed: Vec<PersistentTunnel> =
+        tunnels.values().map(|(tunnel, _)| tunnel.clone()).collect();
+    listed.sort_by(|a, b| a.connection_id.cmp(&b.connection_id));
+    Ok(listed)
+}
+
+/// Keeps the profile's forwards up on a connection of their own, without a
+/// shell, reconnecting whenever it drops until stopped.
+#[tauri::command]
+pub async fn start_persistent_tunnel(
+    app: AppHandle,
+    server_id: String,
+    profile: String,
+) -> Result<PersistentTunnel, String> {
+    start_tunnel(&app, &server_id, &profile).await
+}
+
+#[tauri::command]
+pub async fn stop_persistent_tunnel(
+    app: AppHandle,
+    server_id: String,
+    profile: String,
+) -> Result<(), String> {
+    let connection_id = tunnel_connection_id(&server_id, &profile);
+    let state = app.state::<AppState>();
+    let (mut tunnel, task) = state
+        .tunnels
+        .tunnels
+        .lock()
+        .await
+        .remove(&connection_id)
+        .ok_or_else(|| format!("No tunnel is running for profile {}", profile))?;
+    task.abort();
+
+    forwarding::stop_connection(&app, &connection_id).await;
+    let session = state.sessions.lock().await.remove(&connection_id);
+    if let Some(session) = session {
+        let _ = disconnect_ssh(&app, Some(&session.handle), None, None).await;
+    }
+    set_state(&app, &mut tunnel, TunnelState::Stopped).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_backs_off_to_cap() {
+        assert_eq!(reconnect_delay(0), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(4));
+        assert_eq!(reconnect_delay(10), Duration::from_secs(15));
+        assert_eq!(reconnect_delay(200), Duration::from_secs(15));
+    }
+}
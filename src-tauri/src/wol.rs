@@ -0,0 +1,17 @@
+use ssh_thing_core::wol::send_magic_packet;
+use tauri::AppHandle;
+
+use crate::{get_app_dir, load_servers};
+
+#[tauri::command]
+pub async fn wake_server(app: AppHandle, server_id: String) -> Result<(), String> {
+    let app_dir = get_app_dir(&app)?;
+    let server = load_servers(&app_dir, &app)?
+        .into_iter()
+        .find(|server| server.id == server_id)
+        .ok_or_else(|| format!("Server with id {} not found", server_id))?;
+    let config = server
+        .wake_on_lan
+        .ok_or_else(|| "Wake-on-LAN is not configured for this server".to_string())?;
+    send_magic_packet(&config).await
+}
@@ -0,0 +1,1202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::debug;
+
+const ZPAD: u8 = b'*';
+const ZDLE: u8 = 0x18;
+const ZHEX: u8 = b'B';
+const ZBIN: u8 = b'A';
+const ZBIN32: u8 = b'C';
+const XON: u8 = 0x11;
+
+const ZCRCE: u8 = b'h';
+const ZCRCG: u8 = b'i';
+const ZCRCQ: u8 = b'j';
+const ZCRCW: u8 = b'k';
+const ZRUB0: u8 = b'l';
+const ZRUB1: u8 = b'm';
+
+const ZRQINIT: u8 = 0;
+const ZRINIT: u8 = 1;
+const ZSINIT: u8 = 2;
+const ZACK: u8 = 3;
+const ZFILE: u8 = 4;
+const ZSKIP: u8 = 5;
+const ZABORT: u8 = 7;
+const ZFIN: u8 = 8;
+const ZRPOS: u8 = 9;
+const ZDATA: u8 = 10;
+const ZEOF: u8 = 11;
+const ZFERR: u8 = 12;
+const ZCOMPL: u8 = 15;
+const ZCAN: u8 = 16;
+const ZCOMMAND: u8 = 18;
+
+const CANFDX: u8 = 0x01;
+const CANOVIO: u8 = 0x02;
+const ZCBIN: u8 = 1;
+
+const SUBPACKET_BYTES: usize = 1024;
+const SUBPACKETS_PER_POLL: usize = 16;
+const MAX_SUBPACKET_BYTES: usize = 8 * 1024;
+const MAX_PENDING_BYTES: usize = 64 * 1024;
+const PROGRESS_INTERVAL_BYTES: u64 = 256 * 1024;
+const CANCEL_SEQUENCE: &[u8] = &[
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08,
+    0x08, 0x08,
+];
+
+// What sz/rz print when they start: a hex ZRQINIT from a sender, a hex
+// ZRINIT from a receiver. The final byte tells them apart.
+const START_PREFIX: &[u8] = b"**\x18B0";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ZmodemStatus {
+    SendRequested,
+    ReceiveStarted,
+    FileStarted {
+        name: String,
+        size: Option<u64>,
+    },
+    Progress {
+        name: String,
+        bytes: u64,
+        total: Option<u64>,
+    },
+    FileCompleted {
+        name: String,
+        path: String,
+    },
+    FileSkipped {
+        name: String,
+    },
+    Finished,
+    Failed {
+        message: String,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct ZmodemOutput {
+    pub terminal: Vec<u8>,
+    pub outgoing: Vec<u8>,
+    pub events: Vec<ZmodemStatus>,
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn needs_escape(byte: u8) -> bool {
+    matches!(byte, ZDLE | 0x10 | 0x90 | 0x11 | 0x91 | 0x13 | 0x93)
+}
+
+fn escape_into(out: &mut Vec<u8>, data: &[u8]) {
+    for &byte in data {
+        if needs_escape(byte) {
+            out.push(ZDLE);
+            out.push(byte ^ 0x40);
+        } else {
+            out.push(byte);
+        }
+    }
+}
+
+fn position_bytes(position: u64) -> [u8; 4] {
+    (position as u32).to_le_bytes()
+}
+
+fn flag_bytes(zf0: u8) -> [u8; 4] {
+    [0, 0, 0, zf0]
+}
+
+fn hex_header(frame_type: u8, data: [u8; 4]) -> Vec<u8> {
+    let mut raw = vec![frame_type];
+    raw.extend_from_slice(&data);
+    raw.extend_from_slice(&crc16(&raw).to_be_bytes());
+
+    let mut out = vec![ZPAD, ZPAD, ZDLE, ZHEX];
+    for byte in raw {
+        out.extend_from_slice(format!("{:02x}", byte).as_bytes());
+    }
+    out.extend_from_slice(b"\r\n");
+    if frame_type != ZACK && frame_type != ZFIN {
+        out.push(XON);
+    }
+    out
+}
+
+fn binary_header(frame_type: u8, data: [u8; 4]) -> Vec<u8> {
+    let mut raw = vec![frame_type];
+    raw.extend_from_slice(&data);
+    raw.extend_from_slice(&crc16(&raw).to_be_bytes());
+
+    let mut out = vec![ZPAD, ZDLE, ZBIN];
+    escape_into(&mut out, &raw);
+    out
+}
+
+fn data_subpacket(data: &[u8], end: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 8);
+    escape_into(&mut out, data);
+    out.push(ZDLE);
+    out.push(end);
+
+    let mut checked = data.to_vec();
+    checked.push(end);
+    escape_into(&mut out, &crc16(&checked).to_be_bytes());
+    if end == ZCRCW {
+        out.push(XON);
+    }
+    out
+}
+
+#[derive(Debug, PartialEq)]
+enum Frame {
+    Header { frame_type: u8, data: [u8; 4] },
+    Data { payload: Vec<u8>, end: u8 },
+    BadData,
+    Cancel,
+}
+
+fn is_flow_control(byte: u8) -> bool {
+    matches!(byte, 0x11 | 0x13 | 0x91 | 0x93)
+}
+
+fn starts_with_cancel(bytes: &[u8]) -> bool {
+    bytes.len() >= 5 && bytes[..5].iter().all(|byte| *byte == ZDLE)
+}
+
+fn unescape_byte(byte: u8) -> u8 {
+    match byte {
+        ZRUB0 => 0x7f,
+        ZRUB1 => 0xff,
+        other => other ^ 0x40,
+    }
+}
+
+/// Reads `want` ZDLE-decoded bytes from `input`, returning them and how many
+/// raw bytes were consumed, or `None` if more input is needed.
+fn unescape(input: &[u8], want: usize) -> Option<(Vec<u8>, usize)> {
+    let mut out = Vec::with_capacity(want);
+    let mut index = 0;
+    while out.len() < want {
+        let byte = *input.get(index)?;
+        if is_flow_control(byte) {
+            index += 1;
+        } else if byte == ZDLE {
+            out.push(unescape_byte(*input.get(index + 1)?));
+            index += 2;
+        } else {
+            out.push(byte);
+            index += 1;
+        }
+    }
+    Some((out, index))
+}
+
+fn decode_hex(input: &[u8]) -> Option<Vec<u8>> {
+    input
+        .chunks(2)
+        .map(|pair| {
+            let text = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(text, 16).ok()
+        })
+        .collect()
+}
+
+fn checksum_matches(data: &[u8], crc: &[u8], crc32_mode: bool) -> bool {
+    if crc32_mode {
+        crc.len() == 4 && crc32(data).to_le_bytes() == crc[..4]
+    } else {
+        crc.len() == 2 && crc16(data).to_be_bytes() == crc[..2]
+    }
+}
+
+/// Incremental ZMODEM frame decoder. Bytes are buffered until a complete
+/// header or data subpacket is available.
+#[derive(Default)]
+struct FrameParser {
+    buffer: Vec<u8>,
+    // Set while data subpackets are expected; the flag is the CRC-32 mode of
+    // the header that introduced them.
+    data_crc32: Option<bool>,
+}
+
+impl FrameParser {
+    fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn take_remaining(&mut self) -> Vec<u8> {
+        self.data_crc32 = None;
+        std::mem::take(&mut self.buffer)
+    }
+
+    fn next_frame(&mut self) -> Option<Frame> {
+        match self.data_crc32 {
+            Some(crc32_mode) => self.next_data(crc32_mode),
+            None => self.next_header(),
+        }
+    }
+
+    fn next_header(&mut self) -> Option<Frame> {
+        loop {
+            if self.buffer.windows(5).any(starts_with_cancel) {
+                self.buffer.clear();
+                return Some(Frame::Cancel);
+            }
+
+            let Some(start) = self.buffer.windows(2).position(|pair| pair == [ZPAD, ZDLE]) else {
+                // Keep a short tail in case a header is split across reads.
+                let keep = self.buffer.len().min(4);
+                self.buffer.drain(..self.buffer.len() - keep);
+                return None;
+            };
+            self.buffer.drain(..start);
+            if self.buffer.len() < 3 {
+                return None;
+            }
+
+            let frame = match self.buffer[2] {
+                ZHEX => {
+                    if self.buffer.len() < 17 {
+                        return None;
+                    }
+                    let decoded = decode_hex(&self.buffer[3..17]);
+                    // Hex headers end with CR LF (LF may have its high bit
+                    // set) and usually an XON.
+                    let trailer = self.buffer[17..]
+                        .iter()
+                        .take_while(|byte| matches!(**byte, 0x0d | 0x8d | 0x0a | 0x8a | XON))
+                        .count();
+                    self.buffer.drain(..17 + trailer);
+                    decoded.and_then(|raw| {
+                        checksum_matches(&raw[..5], &raw[5..], false).then_some((raw, false))
+                    })
+                }
+                kind @ (ZBIN | ZBIN32) => {
+                    let crc32_mode = kind == ZBIN32;
+                    let want = if crc32_mode { 9 } else { 7 };
+                    let (raw, used) = unescape(&self.buffer[3..], want)?;
+                    self.buffer.drain(..3 + used);
+                    checksum_matches(&raw[..5], &raw[5..], crc32_mode).then_some((raw, crc32_mode))
+                }
+                _ => {
+                    self.buffer.drain(..2);
+                    None
+                }
+            };
+
+            let Some((raw, crc32_mode)) = frame else {
+                continue;
+            };
+            let frame_type = raw[0];
+            if matches!(frame_type, ZFILE | ZDATA | ZSINIT | ZCOMMAND) {
+                self.data_crc32 = Some(crc32_mode);
+            }
+            return Some(Frame::Header {
+                frame_type,
+                data: [raw[1], raw[2], raw[3], raw[4]],
+            });
+        }
+    }
+
+    fn next_data(&mut self, crc32_mode: bool) -> Option<Frame> {
+        let mut payload = Vec::new();
+        let mut index = 0;
+        loop {
+            if payload.len() > MAX_SUBPACKET_BYTES {
+                self.buffer.drain(..index);
+                self.data_crc32 = None;
+                return Some(Frame::BadData);
+            }
+            let byte = *self.buffer.get(index)?;
+            if is_flow_control(byte) {
+                index += 1;
+                continue;
+            }
+            if byte != ZDLE {
+                payload.push(byte);
+                index += 1;
+                continue;
+            }
+            if starts_with_cancel(&self.buffer[index..]) {
+                self.buffer.clear();
+                self.data_crc32 = None;
+                return Some(Frame::Cancel);
+            }
+
+            let marker = *self.buffer.get(index + 1)?;
+            if !matches!(marker, ZCRCE | ZCRCG | ZCRCQ | ZCRCW) {
+                payload.push(unescape_byte(marker));
+                index += 2;
+                continue;
+            }
+
+            let crc_len = if crc32_mode { 4 } else { 2 };
+            let (crc, used) = unescape(&self.buffer[index + 2..], crc_len)?;
+            self.buffer.drain(..index + 2 + used);
+
+            let mut checked = payload.clone();
+            checked.push(marker);
+            if !checksum_matches(&checked, &crc, crc32_mode) {
+                self.data_crc32 = None;
+                return Some(Frame::BadData);
+            }
+            if matches!(marker, ZCRCE | ZCRCW) {
+                self.data_crc32 = None;
+            }
+            return Some(Frame::Data {
+                payload,
+                end: marker,
+            });
+        }
+    }
+}
+
+fn header_position(data: [u8; 4]) -> u64 {
+    u32::from_le_bytes(data) as u64
+}
+
+fn sanitize_file_name(raw: &str) -> Option<String> {
+    let name = raw.rsplit(['/', '\\']).next()?.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => (&name[..index], &name[index..]),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+struct IncomingFile {
+    name: String,
+    path: PathBuf,
+    handle: fs::File,
+    size: Option<u64>,
+    offset: u64,
+    last_reported: u64,
+}
+
+struct Receiver {
+    download_dir: PathBuf,
+    // Header whose data subpacket is still to come.
+    pending: Option<u8>,
+    file: Option<IncomingFile>,
+    // After a CRC error, data is ignored until the sender repositions.
+    discarding: bool,
+}
+
+struct OutgoingFile {
+    name: String,
+    handle: fs::File,
+    size: u64,
+    offset: u64,
+    last_reported: u64,
+    streaming: bool,
+    eof_sent: bool,
+}
+
+struct Sender {
+    queue: VecDeque<PathBuf>,
+    file: Option<OutgoingFile>,
+    finishing: bool,
+}
+
+enum Mode {
+    Receive(Receiver),
+    Send(Sender),
+}
+
+struct Session {
+    parser: FrameParser,
+    mode: Mode,
+}
+
+enum Flow {
+    Continue,
+    Done,
+}
+
+impl Receiver {
+    fn handle(&mut self, frame: Frame, out: &mut ZmodemOutput) -> Flow {
+        match frame {
+            Frame::Header { frame_type, data } => self.handle_header(frame_type, data, out),
+            Frame::Data { payload, end } => self.handle_data(payload, end, out),
+            Frame::BadData => {
+                self.discarding = true;
+                let offset = self.file.as_ref().map_or(0, |file| file.offset);
+                out.outgoing
+                    .extend(hex_header(ZRPOS, position_bytes(offset)));
+                Flow::Continue
+            }
+            Frame::Cancel => self.fail("Transfer cancelled by remote", out),
+        }
+    }
+
+    fn handle_header(&mut self, frame_type: u8, data: [u8; 4], out: &mut ZmodemOutput) -> Flow {
+        match frame_type {
+            ZRQINIT => {
+                out.outgoing
+                    .extend(hex_header(ZRINIT, flag_bytes(CANFDX | CANOVIO)));
+            }
+            ZSINIT | ZFILE | ZCOMMAND => self.pending = Some(frame_type),
+            ZDATA => {
+                let offset = self.file.as_ref().map_or(0, |file| file.offset);
+                if self.file.is_none() {
+                    return self.fail("Received data before a file header", out);
+                }
+                if header_position(data) == offset {
+                    self.pending = Some(ZDATA);
+                    self.discarding = false;
+                } else {
+                    self.discarding = true;
+                    out.outgoing
+                        .extend(hex_header(ZRPOS, position_bytes(offset)));
+                }
+            }
+            ZEOF => {
+                let matches_offset = self
+                    .file
+                    .as_ref()
+                    .is_some_and(|file| file.offset == header_position(data));
+                // A ZEOF that does not match what we have is stale; the
+                // sender repeats it after resending the missing data.
+                if matches_offset {
+                    if let Some(file) = self.file.take() {
+                        let _ = file.handle.sync_all();
+                        out.events.push(ZmodemStatus::FileCompleted {
+                            name: file.name,
+                            path: file.path.display().to_string(),
+                        });
+                    }
+                    out.outgoing
+                        .extend(hex_header(ZRINIT, flag_bytes(CANFDX | CANOVIO)));
+                }
+            }
+            ZFIN => {
+                out.outgoing.extend(hex_header(ZFIN, [0; 4]));
+                out.events.push(ZmodemStatus::Finished);
+                return Flow::Done;
+            }
+            ZABORT | ZCAN | ZFERR => return self.fail("Transfer aborted by remote", out),
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn handle_data(&mut self, payload: Vec<u8>, end: u8, out: &mut ZmodemOutput) -> Flow {
+        match self.pending {
+            Some(ZFILE) => {
+                self.pending = None;
+                self.open_file(&payload, out)
+            }
+            Some(ZSINIT) => {
+                self.pending = None;
+                out.outgoing.extend(hex_header(ZACK, [0; 4]));
+                Flow::Continue
+            }
+            Some(ZCOMMAND) => {
+                // Never run commands on behalf of the remote side; a non-zero
+                // ZCOMPL status reports the command as failed.
+                self.pending = None;
+                out.outgoing.extend(hex_header(ZCOMPL, position_bytes(1)));
+                Flow::Continue
+            }
+            Some(ZDATA) if !self.discarding => self.write_data(&payload, end, out),
+            _ => Flow::Continue,
+        }
+    }
+
+    fn open_file(&mut self, payload: &[u8], out: &mut ZmodemOutput) -> Flow {
+        let mut parts = payload.splitn(2, |byte| *byte == 0);
+        let raw_name = String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+        let size = parts
+            .next()
+            .map(|info| String::from_utf8_lossy(info).into_owned())
+            .and_then(|info| info.split_whitespace().next()?.parse::<u64>().ok());
+
+        let Some(name) = sanitize_file_name(&raw_name) else {
+            out.outgoing.extend(hex_header(ZSKIP, [0; 4]));
+            out.events
+                .push(ZmodemStatus::FileSkipped { name: raw_name });
+            return Flow::Continue;
+        };
+
+        if let Err(e) = fs::create_dir_all(&self.download_dir) {
+            return self.fail(&format!("Failed to create download directory: {}", e), out);
+        }
+        let path = unique_path(&self.download_dir, &name);
+        let handle = match fs::File::create(&path) {
+            Ok(handle) => handle,
+            Err(e) => {
+                return self.fail(&format!("Failed to create {}: {}", path.display(), e), out)
+            }
+        };
+
+        debug!(name = %name, path = %path.display(), "Receiving ZMODEM file");
+        out.events.push(ZmodemStatus::FileStarted {
+            name: name.clone(),
+            size,
+        });
+        self.file = Some(IncomingFile {
+            name,
+            path,
+            handle,
+            size,
+            offset: 0,
+            last_reported: 0,
+        });
+        out.outgoing.extend(hex_header(ZRPOS, position_bytes(0)));
+        Flow::Continue
+    }
+
+    fn write_data(&mut self, payload: &[u8], end: u8, out: &mut ZmodemOutput) -> Flow {
+        let Some(file) = self.file.as_mut() else {
+            return Flow::Continue;
+        };
+        if let Err(e) = file.handle.write_all(payload) {
+            let message = format!("Failed to write {}: {}", file.path.display(), e);
+            return self.fail(&message, out);
+        }
+        file.offset += payload.len() as u64;
+
+        if file.offset - file.last_reported >= PROGRESS_INTERVAL_BYTES {
+            file.last_reported = file.offset;
+            out.events.push(ZmodemStatus::Progress {
+                name: file.name.clone(),
+                bytes: file.offset,
+                total: file.size,
+            });
+        }
+        if matches!(end, ZCRCQ | ZCRCW) {
+            out.outgoing
+                .extend(hex_header(ZACK, position_bytes(file.offset)));
+        }
+        if matches!(end, ZCRCE | ZCRCW) {
+            self.pending = None;
+        }
+        Flow::Continue
+    }
+
+    fn fail(&mut self, message: &str, out: &mut ZmodemOutput) -> Flow {
+        self.file = None;
+        out.outgoing.extend_from_slice(CANCEL_SEQUENCE);
+        out.events.push(ZmodemStatus::Failed {
+            message: message.to_string(),
+        });
+        Flow::Done
+    }
+}
+
+impl Sender {
+    fn start_next_file(&mut self, out: &mut ZmodemOutput) -> Flow {
+        self.file = None;
+        while let Some(path) = self.queue.pop_front() {
+            match self.open_file(&path, out) {
+                Ok(()) => return Flow::Continue,
+                Err(e) => {
+                    debug!(path = %path.display(), error = %e, "Skipping ZMODEM upload");
+                    out.events.push(ZmodemStatus::Failed { message: e });
+                }
+            }
+        }
+        self.finishing = true;
+        out.outgoing.extend(hex_header(ZFIN, [0; 4]));
+        Flow::Continue
+    }
+
+    fn open_file(&mut self, path: &Path, out: &mut ZmodemOutput) -> Result<(), String> {
+        let handle = fs::File::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let metadata = handle
+            .metadata()
+            .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+        if !metadata.is_file() {
+            return Err(format!("{} is not a regular file", path.display()));
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| format!("Invalid file name: {}", path.display()))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+
+        let mut info = name.clone().into_bytes();
+        info.push(0);
+        info.extend_from_slice(
+            format!(
+                "{} {:o} 100644 0 {} 0",
+                metadata.len(),
+                modified,
+                self.queue.len() + 1
+            )
+            .as_bytes(),
+        );
+        info.push(0);
+
+        out.outgoing.extend(binary_header(ZFILE, flag_bytes(ZCBIN)));
+        out.outgoing.extend(data_subpacket(&info, ZCRCW));
+        out.events.push(ZmodemStatus::FileStarted {
+            name: name.clone(),
+            size: Some(metadata.len()),
+        });
+        self.file = Some(OutgoingFile {
+            name,
+            handle,
+            size: metadata.len(),
+            offset: 0,
+            last_reported: 0,
+            streaming: false,
+            eof_sent: false,
+        });
+        Ok(())
+    }
+
+    fn handle(&mut self, frame: Frame, out: &mut ZmodemOutput) -> Flow {
+        let (frame_type, data) = match frame {
+            Frame::Header { frame_type, data } => (frame_type, data),
+            Frame::Cancel => return self.fail("Transfer cancelled by remote", out),
+            Frame::Data { .. } | Frame::BadData => return Flow::Continue,
+        };
+
+        match frame_type {
+            ZRPOS => {
+                let Some(file) = self.file.as_mut() else {
+                    return Flow::Continue;
+                };
+                let position = header_position(data).min(file.size);
+                if let Err(e) = file.handle.seek(SeekFrom::Start(position)) {
+                    let message = format!("Failed to seek {}: {}", file.name, e);
+                    return self.fail(&message, out);
+                }
+                file.offset = position;
+                file.streaming = true;
+                out.outgoing
+                    .extend(binary_header(ZDATA, position_bytes(position)));
+            }
+            ZSKIP => {
+                if let Some(file) = self.file.take() {
+                    out.events
+                        .push(ZmodemStatus::FileSkipped { name: file.name });
+                }
+                return self.start_next_file(out);
+            }
+            ZRINIT => {
+                if self.finishing {
+                    return Flow::Continue;
+                }
+                // rz repeats ZRINIT while it waits; only the one that follows
+                // our ZEOF moves on to the next file.
+                let previous_done = self.file.as_ref().is_none_or(|file| file.eof_sent);
+                if previous_done {
+                    if let Some(file) = self.file.take() {
+                        out.events.push(ZmodemStatus::FileCompleted {
+                            name: file.name.clone(),
+                            path: file.name,
+                        });
+                    }
+                    return self.start_next_file(out);
+                }
+            }
+            ZFIN => {
+                out.outgoing.extend_from_slice(b"OO");
+                out.events.push(ZmodemStatus::Finished);
+                return Flow::Done;
+            }
+            ZABORT | ZCAN | ZFERR => return self.fail("Transfer aborted by remote", out),
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    /// Produces the next batch of file data while a file is being streamed.
+    fn poll(&mut self, out: &mut ZmodemOutput) -> Result<bool, String> {
+        let Some(file) = self.file.as_mut().filter(|file| file.streaming) else {
+            return Ok(false);
+        };
+
+        let mut buffer = vec![0u8; SUBPACKET_BYTES];
+        for _ in 0..SUBPACKETS_PER_POLL {
+            let read = file
+                .handle
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to read {}: {}", file.name, e))?;
+            file.offset += read as u64;
+            let last = read == 0 || file.offset >= file.size;
+            out.outgoing.extend(data_subpacket(
+                &buffer[..read],
+                if last { ZCRCE } else { ZCRCG },
+            ));
+
+            if file.offset - file.last_reported >= PROGRESS_INTERVAL_BYTES {
+                file.last_reported = file.offset;
+                out.events.push(ZmodemStatus::Progress {
+                    name: file.name.clone(),
+                    bytes: file.offset,
+                    total: Some(file.size),
+                });
+            }
+            if last {
+                file.streaming = false;
+                file.eof_sent = true;
+                out.outgoing
+                    .extend(binary_header(ZEOF, position_bytes(file.offset)));
+                break;
+            }
+        }
+        Ok(true)
+    }
+
+    fn fail(&mut self, message: &str, out: &mut ZmodemOutput) -> Flow {
+        self.file = None;
+        self.queue.clear();
+        out.outgoing.extend_from_slice(CANCEL_SEQUENCE);
+        out.events.push(ZmodemStatus::Failed {
+            message: message.to_string(),
+        });
+        Flow::Done
+    }
+}
+
+/// Sits in front of the terminal output of a PTY shell and takes over the
+/// byte stream while an sz/rz transfer is running.
+pub struct ZmodemProcessor {
+    download_dir: PathBuf,
+    detect_buffer: Vec<u8>,
+    session: Option<Session>,
+    send_requested: bool,
+    // sz writes "OO" after the final ZFIN; it should not reach the terminal.
+    swallow_over_and_out: usize,
+}
+
+impl ZmodemProcessor {
+    pub fn new(download_dir: PathBuf) -> Self {
+        Self {
+            download_dir,
+            detect_buffer: Vec::new(),
+            session: None,
+            send_requested: false,
+            swallow_over_and_out: 0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn process(&mut self, data: &[u8]) -> ZmodemOutput {
+        let mut out = ZmodemOutput::default();
+        let mut input = data;
+
+        while self.swallow_over_and_out > 0 && input.first() == Some(&b'O') {
+            self.swallow_over_and_out -= 1;
+            input = &input[1..];
+        }
+        if !input.is_empty() {
+            self.swallow_over_and_out = 0;
+        }
+
+        if self.session.is_some() {
+            self.feed_session(input, &mut out);
+        } else {
+            self.detect(input, &mut out);
+        }
+        out
+    }
+
+    pub fn send_files(&mut self, paths: Vec<PathBuf>) -> Result<ZmodemOutput, String> {
+        if !self.send_requested {
+            return Err("The remote side is not waiting to receive files".to_string());
+        }
+        if paths.is_empty() {
+            return Err("No files selected".to_string());
+        }
+
+        self.send_requested = false;
+        let mut out = ZmodemOutput::default();
+        let mut sender = Sender {
+            queue: paths.into(),
+            file: None,
+            finishing: false,
+        };
+        sender.start_next_file(&mut out);
+        self.session = Some(Session {
+            parser: FrameParser::default(),
+            mode: Mode::Send(sender),
+        });
+        Ok(out)
+    }
+
+    /// Whether an upload has file data waiting for `poll`.
+    pub fn is_sending(&self) -> bool {
+        matches!(
+            &self.session,
+            Some(Session { mode: Mode::Send(sender), .. })
+                if sender.file.as_ref().is_some_and(|file| file.streaming)
+        )
+    }
+
+    /// Returns the next batch of file data for an upload in progress, if
+    /// any. Called once per batch so the caller can handle channel input,
+    /// such as ZRPOS or a cancel, in between.
+    pub fn poll(&mut self) -> Option<ZmodemOutput> {
+        let session = self.session.as_mut()?;
+        let Mode::Send(sender) = &mut session.mode else {
+            return None;
+        };
+
+        let mut out = ZmodemOutput::default();
+        match sender.poll(&mut out) {
+            Ok(true) => Some(out),
+            Ok(false) => None,
+            Err(e) => {
+                sender.fail(&e, &mut out);
+                self.session = None;
+                Some(out)
+            }
+        }
+    }
+
+    pub fn cancel(&mut self) -> ZmodemOutput {
+        let mut out = ZmodemOutput::default();
+        if self.session.take().is_some() || self.send_requested {
+            out.outgoing.extend_from_slice(CANCEL_SEQUENCE);
+            out.events.push(ZmodemStatus::Failed {
+                message: "Transfer cancelled".to_string(),
+            });
+        }
+        self.send_requested = false;
+        out
+    }
+
+    fn detect(&mut self, input: &[u8], out: &mut ZmodemOutput) {
+        let mut buffer = std::mem::take(&mut self.detect_buffer);
+        buffer.extend_from_slice(input);
+
+        let mut offset = 0;
+        loop {
+            let found = buffer[offset..]
+                .windows(START_PREFIX.len() + 1)
+                .position(|window| window.starts_with(START_PREFIX))
+                .map(|index| offset + index);
+            let Some(start) = found else {
+                // Hold back a possible partial start sequence for the next read.
+                let pending = &buffer[offset..];
+                let keep = (1..=START_PREFIX.len().min(pending.len()))
+                    .rev()
+                    .find(|len| START_PREFIX.starts_with(&pending[pending.len() - len..]))
+                    .unwrap_or(0);
+                let held = buffer.len() - keep;
+                out.terminal.extend_from_slice(&buffer[offset..held]);
+                self.detect_buffer = buffer.split_off(held);
+                return;
+            };
+
+            out.terminal.extend_from_slice(&buffer[offset..start]);
+            let rest = &buffer[start..];
+            match rest[START_PREFIX.len()] {
+                b'0' => {
+                    debug!("Detected ZMODEM download");
+                    self.send_requested = false;
+                    out.events.push(ZmodemStatus::ReceiveStarted);
+                    self.session = Some(Session {
+                        parser: FrameParser::default(),
+                        mode: Mode::Receive(Receiver {
+                            download_dir: self.download_dir.clone(),
+                            pending: None,
+                            file: None,
+                            discarding: false,
+                        }),
+                    });
+                    self.feed_session(rest, out);
+                    return;
+                }
+                b'1' => {
+                    // rz repeats its ZRINIT until it gets an answer; only the
+                    // first one is surfaced while the user picks files.
+                    if !self.send_requested {
+                        debug!("Detected ZMODEM upload request");
+                        self.send_requested = true;
+                        out.events.push(ZmodemStatus::SendRequested);
+                    }
+                    offset = rest
+                        .iter()
+                        .position(|byte| *byte == b'\n' || *byte == 0x8a)
+                        .map_or(buffer.len(), |index| start + index + 1);
+                    while buffer.get(offset) == Some(&XON) {
+                        offset += 1;
+                    }
+                }
+                _ => {
+                    out.terminal.push(rest[0]);
+                    offset = start + 1;
+                }
+            }
+        }
+    }
+
+    fn feed_session(&mut self, input: &[u8], out: &mut ZmodemOutput) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+        session.parser.push(input);
+
+        while let Some(frame) = session.parser.next_frame() {
+            let flow = match &mut session.mode {
+                Mode::Receive(receiver) => receiver.handle(frame, out),
+                Mode::Send(sender) => sender.handle(frame, out),
+            };
+            if let Flow::Done = flow {
+                let receiving = matches!(session.mode, Mode::Receive(_));
+                let leftover = session.parser.take_remaining();
+                self.session = None;
+                if receiving {
+                    self.swallow_over_and_out = 2;
+                }
+                let mut rest = self.process(&leftover);
+                out.terminal.append(&mut rest.terminal);
+                out.outgoing.append(&mut rest.outgoing);
+                out.events.append(&mut rest.events);
+                return;
+            }
+        }
+
+        if session.parser.buffer.len() > MAX_PENDING_BYTES && session.parser.data_crc32.is_none() {
+            let keep = session.parser.buffer.len() - 4;
+            session.parser.buffer.drain(..keep);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ssh-thing-zmodem-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn test_crc_vectors() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_hex_header_matches_rz_output() {
+        assert_eq!(
+            hex_header(ZRINIT, flag_bytes(0x23)),
+            b"**\x18B0100000023be50\r\n\x11".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_parser_round_trips_binary_frames() {
+        let mut parser = FrameParser::default();
+        let payload = vec![0x18, 0x11, 0x7f, b'a', 0xff];
+        let mut stream = b"noise".to_vec();
+        stream.extend(binary_header(ZDATA, position_bytes(42)));
+        stream.extend(data_subpacket(&payload, ZCRCE));
+
+        // Feed byte by byte to exercise partial reads.
+        let mut frames = Vec::new();
+        for byte in stream {
+            parser.push(&[byte]);
+            while let Some(frame) = parser.next_frame() {
+                frames.push(frame);
+            }
+        }
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame::Header {
+                    frame_type: ZDATA,
+                    data: position_bytes(42),
+                },
+                Frame::Data {
+                    payload,
+                    end: ZCRCE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_reports_corrupt_data() {
+        let mut parser = FrameParser::default();
+        parser.push(&binary_header(ZDATA, position_bytes(0)));
+        let mut packet = data_subpacket(b"hello", ZCRCG);
+        packet[0] = b'j';
+        parser.push(&packet);
+
+        assert!(matches!(parser.next_frame(), Some(Frame::Header { .. })));
+        assert_eq!(parser.next_frame(), Some(Frame::BadData));
+    }
+
+    #[test]
+    fn test_processor_passes_plain_output_through() {
+        let mut processor = ZmodemProcessor::new(temp_dir());
+        let first = processor.process(b"hello *");
+        let second = processor.process(b"* world");
+
+        assert_eq!(first.terminal, b"hello ");
+        assert_eq!(second.terminal, b"** world");
+        assert!(!processor.is_active());
+
+        // Binary output full of near-miss start sequences passes through
+        // in one pass rather than one level of work per hit.
+        let noise = b"**\x18B09".repeat(100_000);
+        let output = processor.process(&noise);
+        assert_eq!(output.terminal, noise);
+        assert!(!processor.is_active());
+    }
+
+    #[test]
+    fn test_processor_receives_file_from_sz() {
+        let dir = temp_dir();
+        let mut processor = ZmodemProcessor::new(dir.clone());
+
+        let mut stream = b"rz\r".to_vec();
+        stream.extend(hex_header(ZRQINIT, [0; 4]));
+        let out = processor.process(&stream);
+        assert_eq!(out.terminal, b"rz\r");
+        assert_eq!(
+            out.outgoing,
+            hex_header(ZRINIT, flag_bytes(CANFDX | CANOVIO))
+        );
+        assert_eq!(out.events, vec![ZmodemStatus::ReceiveStarted]);
+
+        let mut stream = binary_header(ZFILE, flag_bytes(ZCBIN));
+        stream.extend(data_subpacket(b"../notes.txt\x0011 0 100644\x00", ZCRCW));
+        let out = processor.process(&stream);
+        assert_eq!(out.outgoing, hex_header(ZRPOS, position_bytes(0)));
+
+        let mut stream = binary_header(ZDATA, position_bytes(0));
+        stream.extend(data_subpacket(b"hello ", ZCRCG));
+        stream.extend(data_subpacket(b"world", ZCRCE));
+        stream.extend(binary_header(ZEOF, position_bytes(11)));
+        stream.extend(hex_header(ZFIN, [0; 4]));
+        stream.extend(b"OO$ ");
+        let out = processor.process(&stream);
+
+        assert_eq!(out.terminal, b"$ ");
+        assert!(!processor.is_active());
+        assert!(out.events.contains(&ZmodemStatus::Finished));
+        assert_eq!(
+            fs::read_to_string(dir.join("notes.txt")).expect("read file"),
+            "hello world"
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_processor_sends_file_to_rz() {
+        let dir = temp_dir();
+        let path = dir.join("upload.bin");
+        fs::write(&path, vec![0x18u8; 3000]).expect("write file");
+        let mut processor = ZmodemProcessor::new(dir.clone());
+
+        let mut stream = b"rz waiting to receive.".to_vec();
+        stream.extend(hex_header(ZRINIT, flag_bytes(CANFDX | CANOVIO)));
+        let out = processor.process(&stream);
+        assert_eq!(out.terminal, b"rz waiting to receive.");
+        assert_eq!(out.events, vec![ZmodemStatus::SendRequested]);
+
+        let out = processor.send_files(vec![path]).expect("start upload");
+        let mut receiver = FrameParser::default();
+        receiver.push(&out.outgoing);
+        assert!(matches!(
+            receiver.next_frame(),
+            Some(Frame::Header {
+                frame_type: ZFILE,
+                ..
+            })
+        ));
+
+        assert!(!processor.is_sending());
+        let out = processor.process(&hex_header(ZRPOS, position_bytes(0)));
+        receiver.push(&out.outgoing);
+        assert!(processor.is_sending());
+        let mut received = Vec::new();
+        while let Some(out) = processor.poll() {
+            receiver.push(&out.outgoing);
+        }
+        assert!(!processor.is_sending());
+        let mut saw_eof = false;
+        while let Some(frame) = receiver.next_frame() {
+            match frame {
+                Frame::Data { payload, .. } => received.extend(payload),
+                Frame::Header {
+                    frame_type: ZEOF,
+                    data,
+                } => {
+                    saw_eof = true;
+                    assert_eq!(header_position(data), 3000);
+                }
+                _ => {}
+            }
+        }
+        // The ZFILE info subpacket precedes the file contents.
+        assert!(saw_eof);
+        assert!(received.ends_with(&[0x18u8; 3000]));
+
+        let out = processor.process(&hex_header(ZRINIT, flag_bytes(CANFDX)));
+        assert_eq!(out.outgoing, hex_header(ZFIN, [0; 4]));
+        let out = processor.process(&hex_header(ZFIN, [0; 4]));
+        assert_eq!(out.outgoing, b"OO");
+        assert!(!processor.is_active());
+        let _ = fs::remove_dir_all(dir);
+    }
+}